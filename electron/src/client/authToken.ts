@@ -0,0 +1,16 @@
+// Token the panel authenticates with at the server.
+// The main process reads it from QITECH_AUTH_TOKEN, see docs/rest-api.md.
+let authToken: Promise<string | undefined> | undefined;
+
+export function getAuthToken(): Promise<string | undefined> {
+  authToken ??= window.environment
+    .getInfo()
+    .then((info) => info.qitechAuthToken);
+  return authToken;
+}
+
+// Headers for fetch requests to the server, empty without a token
+export async function getAuthHeaders(): Promise<Record<string, string>> {
+  const token = await getAuthToken();
+  return token ? { Authorization: `Bearer ${token}` } : {};
+}
@@ -87,11 +87,222 @@ export type EthercatInterfaceDiscoveryEvent = z.infer<
   typeof ethercatInterfaceDiscoveryEventSchema
 >;
 
+// The EtherCAT loop failed, the server stopped all machines and exits
+export const ethercatFaultEventDataSchema = z.object({
+  error: z.string(),
+  machines: z.array(machineIdentificationUnique),
+  outputs_written: z.boolean(),
+});
+
+export type EthercatFaultEventData = z.infer<
+  typeof ethercatFaultEventDataSchema
+>;
+
+export const ethercatFaultEventSchema = eventSchema(
+  ethercatFaultEventDataSchema,
+);
+
+export type EthercatFaultEvent = z.infer<typeof ethercatFaultEventSchema>;
+
+// SubDevices left OP or the bus is initialized again, the server keeps running
+export const faultedSubDeviceSchema = z.object({
+  subdevice_index: z.number().int(),
+  configured_address: z.number().int(),
+  name: z.string(),
+  state: z.string(),
+});
+
+export type FaultedSubDevice = z.infer<typeof faultedSubDeviceSchema>;
+
+export const ethercatBusEventDataSchema = z
+  .object({
+    Ok: z.boolean(),
+    Degraded: z.array(faultedSubDeviceSchema),
+    Recovering: z.array(faultedSubDeviceSchema),
+  })
+  .check(rustEnum);
+
+export type EthercatBusEventData = z.infer<typeof ethercatBusEventDataSchema>;
+
+export const ethercatBusEventSchema = eventSchema(ethercatBusEventDataSchema);
+
+export type EthercatBusEvent = z.infer<typeof ethercatBusEventSchema>;
+
+// EthercatLinkQualityEvent
+export const portErrorCountersSchema = z.object({
+  invalid_frames: z.number().int(),
+  rx_errors: z.number().int(),
+  forwarded_rx_errors: z.number().int(),
+  lost_links: z.number().int(),
+});
+
+export type PortErrorCounters = z.infer<typeof portErrorCountersSchema>;
+
+export const subDeviceLinkQualitySchema = z.object({
+  subdevice_index: z.number().int(),
+  configured_address: z.number().int(),
+  name: z.string(),
+  al_state: z.string().nullable(),
+  al_status_code: z.string().nullable(),
+  ports: z.array(portErrorCountersSchema),
+  processing_unit_errors: z.number().int(),
+  pdi_errors: z.number().int(),
+  last_read_ms: z.number().int().nullable(),
+});
+
+export type SubDeviceLinkQuality = z.infer<typeof subDeviceLinkQualitySchema>;
+
+export const ethercatLinkQualityEventDataSchema = z.object({
+  cycles: z.number().int(),
+  expected_working_counter: z.number().int(),
+  last_working_counter: z.number().int(),
+  working_counter_mismatches: z.number().int(),
+  timed_out_frames: z.number().int(),
+  lost_frames: z.number().int(),
+  subdevices: z.array(subDeviceLinkQualitySchema),
+});
+
+export type EthercatLinkQualityEventData = z.infer<
+  typeof ethercatLinkQualityEventDataSchema
+>;
+
+export const ethercatLinkQualityEventSchema = eventSchema(
+  ethercatLinkQualityEventDataSchema,
+);
+
+export type EthercatLinkQualityEvent = z.infer<
+  typeof ethercatLinkQualityEventSchema
+>;
+
+// EthercatDiagnosisEvent
+export const diagnosisSeveritySchema = z.enum(["info", "warning", "error"]);
+
+export type DiagnosisSeverity = z.infer<typeof diagnosisSeveritySchema>;
+
+export const diagnosisLogEntrySchema = z.object({
+  id: z.number().int(),
+  subdevice_index: z.number().int(),
+  configured_address: z.number().int(),
+  subdevice_name: z.string(),
+  severity: diagnosisSeveritySchema,
+  diag_code: z.number().int(),
+  text_id: z.number().int(),
+  message: z.string(),
+  timestamp_ns: z.number(),
+  local_timestamp: z.boolean(),
+  received_ms: z.number().int(),
+});
+
+export type DiagnosisLogEntry = z.infer<typeof diagnosisLogEntrySchema>;
+
+export const ethercatDiagnosisEventDataSchema = z.object({
+  entries: z.array(diagnosisLogEntrySchema),
+});
+
+export type EthercatDiagnosisEventData = z.infer<
+  typeof ethercatDiagnosisEventDataSchema
+>;
+
+export const ethercatDiagnosisEventSchema = eventSchema(
+  ethercatDiagnosisEventDataSchema,
+);
+
+export type EthercatDiagnosisEvent = z.infer<
+  typeof ethercatDiagnosisEventSchema
+>;
+
+// EthercatSdoEvent
+export const sdoValueSchema = z.object({
+  type: z.enum([
+    "bool",
+    "u8",
+    "i8",
+    "u16",
+    "i16",
+    "u32",
+    "i32",
+    "u64",
+    "i64",
+    "f32",
+    "f64",
+    "string",
+    "bytes",
+  ]),
+  value: z.union([z.boolean(), z.number(), z.string(), z.array(z.number())]),
+});
+
+export type SdoValue = z.infer<typeof sdoValueSchema>;
+
+export const sdoEntrySchema = z.object({
+  sub_index: z.number(),
+  name: z.string(),
+  data_type: z.number(),
+  data_type_name: z.string().nullable(),
+  bit_length: z.number(),
+  readable: z.boolean(),
+  writable_in_op: z.boolean(),
+});
+
+export const sdoObjectSchema = z.object({
+  index: z.number(),
+  name: z.string(),
+  object_code: z.number(),
+  max_sub_index: z.number(),
+  entries: z.array(sdoEntrySchema),
+});
+
+export type SdoObject = z.infer<typeof sdoObjectSchema>;
+
+export const sdoResultSchema = z.discriminatedUnion("type", [
+  z.object({ type: z.literal("uploaded"), value: sdoValueSchema }),
+  z.object({ type: z.literal("downloaded") }),
+  z.object({ type: z.literal("objects"), indices: z.array(z.number()) }),
+  z.object({ type: z.literal("object"), object: sdoObjectSchema }),
+]);
+
+export const sdoRequestSchema = z.object({
+  id: z.number(),
+  subdevice_index: z.number(),
+  operation: z.enum(["upload", "download", "list_objects", "describe_object"]),
+  index: z.number().optional(),
+  sub_index: z.number().optional(),
+  value_type: sdoValueSchema.shape.type.optional(),
+  value: sdoValueSchema.optional(),
+  client: z.string(),
+  requested_ms: z.number(),
+  finished_ms: z.number().nullable(),
+  status: z.enum(["pending", "done", "failed"]),
+  result: sdoResultSchema.optional(),
+  error: z.string().optional(),
+});
+
+export type SdoRequest = z.infer<typeof sdoRequestSchema>;
+
+export const ethercatSdoEventDataSchema = z.object({
+  request: sdoRequestSchema,
+});
+
+export const ethercatSdoEventSchema = eventSchema(ethercatSdoEventDataSchema);
+
+export type EthercatSdoEvent = z.infer<typeof ethercatSdoEventSchema>;
+
+// SDO requests kept in the store, same as the server keeps
+const MAX_SDO_REQUESTS = 100;
+
+// Diagnosis entries kept in the store, the full log is served by the REST API
+const MAX_DIAGNOSIS_ENTRIES = 500;
+
 // Update the main namespace store schema
 export const mainNamespaceStoreSchema = z.object({
   ethercatDevices: ethercatDevicesEventSchema.nullable(),
   machines: machinesEventSchema.nullable(),
   ethercatInterfaceDiscovery: ethercatInterfaceDiscoveryEventSchema.nullable(),
+  ethercatFault: ethercatFaultEventSchema.nullable(),
+  ethercatBus: ethercatBusEventSchema.nullable(),
+  ethercatLinkQuality: ethercatLinkQualityEventSchema.nullable(),
+  ethercatDiagnosis: z.array(diagnosisLogEntrySchema),
+  // newest first
+  ethercatSdoRequests: z.array(sdoRequestSchema),
 });
 
 export type MainNamespaceStore = z.infer<typeof mainNamespaceStoreSchema>;
@@ -101,12 +312,22 @@ export const createMainNamespaceStore = (): StoreApi<MainNamespaceStore> => {
     ethercatDevices: null,
     machines: null,
     ethercatInterfaceDiscovery: null,
+    ethercatFault: null,
+    ethercatBus: null,
+    ethercatLinkQuality: null,
+    ethercatDiagnosis: [],
+    ethercatSdoRequests: [],
   }));
 };
 
 export const eventSchemaMap = {
   EthercatDevicesEvent: ethercatDevicesEventSchema,
   MachinesEvent: machinesEventSchema,
+  EthercatFaultEvent: ethercatFaultEventSchema,
+  EthercatBusEvent: ethercatBusEventSchema,
+  EthercatLinkQualityEvent: ethercatLinkQualityEventSchema,
+  EthercatDiagnosisEvent: ethercatDiagnosisEventSchema,
+  EthercatSdoEvent: ethercatSdoEventSchema,
 };
 
 export function mainMessageHandler(
@@ -135,6 +356,49 @@ export function mainMessageHandler(
           ...state,
           ethercatInterfaceDiscovery: validatedEvent,
         }));
+      } else if (eventName === "EthercatFaultEvent") {
+        const validatedEvent = ethercatFaultEventSchema.parse(event);
+        store.setState((state) => ({
+          ...state,
+          ethercatFault: validatedEvent,
+        }));
+      } else if (eventName === "EthercatBusEvent") {
+        const validatedEvent = ethercatBusEventSchema.parse(event);
+        store.setState((state) => ({
+          ...state,
+          ethercatBus: validatedEvent,
+        }));
+      } else if (eventName === "EthercatLinkQualityEvent") {
+        const validatedEvent = ethercatLinkQualityEventSchema.parse(event);
+        store.setState((state) => ({
+          ...state,
+          ethercatLinkQuality: validatedEvent,
+        }));
+      } else if (eventName === "EthercatDiagnosisEvent") {
+        const validatedEvent = ethercatDiagnosisEventSchema.parse(event);
+        store.setState((state) => {
+          // cached events are replayed on connect, skip entries we already have
+          const lastId = state.ethercatDiagnosis.at(-1)?.id ?? -1;
+          const entries = validatedEvent.data.entries.filter(
+            (entry) => entry.id > lastId,
+          );
+          return {
+            ...state,
+            ethercatDiagnosis: [...state.ethercatDiagnosis, ...entries].slice(
+              -MAX_DIAGNOSIS_ENTRIES,
+            ),
+          };
+        });
+      } else if (eventName === "EthercatSdoEvent") {
+        const validatedEvent = ethercatSdoEventSchema.parse(event);
+        const request = validatedEvent.data.request;
+        store.setState((state) => ({
+          ...state,
+          ethercatSdoRequests: [
+            request,
+            ...state.ethercatSdoRequests.filter((r) => r.id !== request.id),
+          ].slice(0, MAX_SDO_REQUESTS),
+        }));
       } else {
         handleUnhandledEventError(eventName);
       }
@@ -3,6 +3,7 @@ import { create, StoreApi } from "zustand";
 import { produce } from "immer";
 import { io, Socket } from "socket.io-client";
 import MsgPackParser from "socket.io-msgpack-parser";
+import { getAuthToken } from "./authToken";
 import { useSyncExternalStore } from "react";
 import { z } from "zod";
 import { toastError, toastZodError } from "@/components/Toast";
@@ -287,6 +288,9 @@ const useSocketioStore = create<SocketioStore>()((set, get) => ({
     const socket = io(get().baseUrl + namespace_path, {
       autoConnect: false,
       parser: MsgPackParser,
+      auth: (cb) => {
+        getAuthToken().then((token) => cb(token ? { token } : {}));
+      },
     });
 
     // create function to reset the store
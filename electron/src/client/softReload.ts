@@ -7,6 +7,11 @@ export function softReloadMainNamespaceStore() {
     ethercatDevices: null,
     machines: null,
     ethercatInterfaceDiscovery: null,
+    ethercatFault: null,
+    ethercatBus: null,
+    ethercatLinkQuality: null,
+    ethercatDiagnosis: [],
+    ethercatSdoRequests: [],
   });
 
   // 2️⃣ Re-attach the message handler if needed
@@ -5,6 +5,7 @@ import {
   machineIdentificationUnique,
 } from "@/machines/types";
 import { useState } from "react";
+import { getAuthHeaders } from "./authToken";
 import { z } from "zod";
 
 const mutationResponseSchema = z.discriminatedUnion("success", [
@@ -102,6 +103,7 @@ export const getClient = () => {
         method,
         headers: {
           "Content-Type": "application/json",
+          ...(await getAuthHeaders()),
           ...headers,
         },
         body: JSON.stringify(bodyParsed.data),
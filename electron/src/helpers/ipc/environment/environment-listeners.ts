@@ -12,6 +12,7 @@ export const environmentInfo = {
   qitechOsGitCommit: process.env.QITECH_OS_GIT_COMMIT,
   qitechOsGitAbbreviation: process.env.QITECH_OS_GIT_ABBREVIATION,
   qitechOsGitUrl: process.env.QITECH_OS_GIT_URL,
+  qitechAuthToken: process.env.QITECH_AUTH_TOKEN,
 };
 
 export function addEnvironmentEventListeners() {
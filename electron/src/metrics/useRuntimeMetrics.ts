@@ -1,5 +1,6 @@
 import { useEffect, useState } from "react";
 import { pushRuntimeSample } from "./runtimeSeries";
+import { getAuthHeaders } from "@/client/authToken";
 
 // Talk directly to the Rust API on port 3001.
 // If you later proxy via Vite/Electron, make this configurable.
@@ -49,7 +50,7 @@ function notifySubscribers() {
 
 async function fetchOnce() {
   try {
-    const res = await fetch(RUNTIME_URL);
+    const res = await fetch(RUNTIME_URL, { headers: await getAuthHeaders() });
     if (!res.ok) {
       console.error(
         "Runtime metrics fetch failed:",
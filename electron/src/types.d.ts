@@ -26,6 +26,7 @@ interface EnvironmentInfo {
   qitechOsGitCommit?: string;
   qitechOsGitAbbreviation?: string;
   qitechOsGitUrl?: string;
+  qitechAuthToken?: string;
 }
 
 interface EnvironmentContext {
@@ -0,0 +1,29 @@
+use clap::{Command, arg};
+
+pub fn cli() -> Command {
+    Command::new("ethercat-esi-codegen")
+        .about("Generate ethercat-hal devices from EtherCAT Slave Information (ESI) files")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("ls")
+                .about("List all devices of an ESI file")
+                .arg(arg!(<ESI> "ESI file to read").help("ESI file to read")),
+        )
+        .subcommand(
+            Command::new("generate")
+                .about("Generate the ethercat-hal module of a device")
+                .arg(arg!(<ESI> "ESI file to read").help("ESI file to read"))
+                .arg(
+                    arg!(--device <TYPE> "Device type, e.g. EL3001 or EL3062-0030")
+                        .required(true)
+                        .short('d')
+                        .help("Device type, e.g. EL3001 or EL3062-0030"),
+                )
+                .arg(
+                    arg!(--out <DIR> "Directory to write <module>.rs to, stdout if not set")
+                        .required(false)
+                        .short('o')
+                        .help("Directory to write <module>.rs to, stdout if not set"),
+                ),
+        )
+}
@@ -0,0 +1,1120 @@
+//! Generates an `ethercat_hal` device module from the ESI description of a device
+//!
+//! The output follows the layout of the handwritten devices in `ethercat-hal/src/devices`:
+//! identity constants, PDO objects, `TxPdo`/`RxPdo` structs, the predefined PDO assignments
+//! and a configuration with every writable object in `0x8000..=0x8FFF`.
+//! IO traits (`DigitalInputDevice`, `AnalogInputDevice`, ...) and ports are device specific
+//! and are left to the developer.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use anyhow::bail;
+
+use crate::esi::{Device, EsiFile, Object, ObjectItem, Pdo, PdoEntry};
+
+/// Rust type of a PDO entry or configuration value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RustType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl RustType {
+    /// Maps an ESI base type, unknown types are read as unsigned integers of their size
+    fn from_esi(data_type: Option<&str>, bits: usize) -> Option<Self> {
+        let by_name = match data_type.unwrap_or_default() {
+            "BOOL" | "BIT" => Some(Self::Bool),
+            "SINT" => Some(Self::I8),
+            "INT" => Some(Self::I16),
+            "DINT" => Some(Self::I32),
+            "LINT" => Some(Self::I64),
+            "USINT" | "BYTE" => Some(Self::U8),
+            "UINT" | "WORD" => Some(Self::U16),
+            "UDINT" | "DWORD" => Some(Self::U32),
+            "ULINT" | "LWORD" => Some(Self::U64),
+            "REAL" => Some(Self::F32),
+            "LREAL" => Some(Self::F64),
+            name if name.starts_with("STRING") || name.starts_with("ARRAY") => return None,
+            _ => None,
+        };
+        by_name.or(match bits {
+            1 => Some(Self::Bool),
+            2..=8 => Some(Self::U8),
+            9..=16 => Some(Self::U16),
+            17..=32 => Some(Self::U32),
+            33..=64 => Some(Self::U64),
+            _ => None,
+        })
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+
+    const fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// Integer type the bits of a float are loaded as
+    const fn bits_type(self) -> Self {
+        match self {
+            Self::F32 => Self::U32,
+            Self::F64 => Self::U64,
+            other => other,
+        }
+    }
+
+    /// Rust literal of a raw little endian value
+    fn literal(self, raw: u64, bits: usize) -> String {
+        let sign_extended = || {
+            let shift = 64 - bits.clamp(1, 64);
+            #[allow(clippy::cast_possible_wrap)]
+            let value = ((raw << shift) as i64) >> shift;
+            value
+        };
+        match self {
+            Self::Bool => (raw != 0).to_string(),
+            Self::I8 | Self::I16 | Self::I32 | Self::I64 => sign_extended().to_string(),
+            Self::F32 => {
+                #[allow(clippy::cast_possible_truncation)]
+                let value = f32::from_bits(raw as u32);
+                if value.is_finite() {
+                    format!("{value:?}")
+                } else {
+                    "0.0".to_string()
+                }
+            }
+            Self::F64 => {
+                let value = f64::from_bits(raw);
+                if value.is_finite() {
+                    format!("{value:?}")
+                } else {
+                    "0.0".to_string()
+                }
+            }
+            _ => raw.to_string(),
+        }
+    }
+}
+
+/// Generates the module of a device type from all of its revisions in the ESI
+///
+/// The PDOs and objects of the newest revision are used.
+pub fn generate(esi: &EsiFile, type_name: &str, source: &str) -> Result<String, anyhow::Error> {
+    let revisions = esi.revisions(type_name);
+    let Some(device) = revisions.first() else {
+        bail!("Device {} not found in the ESI", type_name);
+    };
+    let generator = Generator::new(device);
+    Ok(generator.module(esi, &revisions, source))
+}
+
+/// Module name of a device type, e.g. `el3062_0030` for `EL3062-0030`
+pub fn module_name(type_name: &str) -> String {
+    type_name_prefix(type_name).to_lowercase()
+}
+
+/// Prefix of all generated items, e.g. `EL3062_0030` for `EL3062-0030`
+fn type_name_prefix(type_name: &str) -> String {
+    type_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Line to add to `device_from_subdevice_identity_tuple`
+pub fn registration(type_name: &str, revisions: usize) -> String {
+    let prefix = type_name_prefix(type_name);
+    let identities = (0..revisions)
+        .map(|revision| format!("{}_IDENTITY_{}", prefix, revision_letter(revision)))
+        .collect::<Vec<_>>()
+        .join(" | ");
+    format!(
+        "{} => Ok(Arc::new(RwLock::new({}::{}::new()))),",
+        identities,
+        module_name(type_name),
+        prefix
+    )
+}
+
+fn revision_letter(revision: usize) -> String {
+    let letter = u8::try_from(revision % 26).unwrap_or_default();
+    let mut name = char::from(b'A' + letter).to_string();
+    if revision >= 26 {
+        name.push_str(&(revision / 26).to_string());
+    }
+    name
+}
+
+/// A PDO object struct, shared by all PDOs with the same layout
+struct PdoObjectStruct {
+    name: String,
+    /// PDOs using this struct
+    pdos: Vec<u16>,
+    pdo_name: String,
+    tx: bool,
+    bits: usize,
+    fields: Vec<PdoField>,
+}
+
+struct PdoField {
+    name: String,
+    entry: PdoEntry,
+    offset: usize,
+    /// `None` if the entry can't be decoded
+    rust_type: Option<RustType>,
+}
+
+/// A configuration struct, shared by all objects with the same data type
+struct ConfigStruct {
+    name: String,
+    /// Objects of this type and the name of their field in the configuration
+    objects: Vec<(String, u16)>,
+    fields: Vec<ConfigField>,
+}
+
+struct ConfigField {
+    name: String,
+    item: ObjectItem,
+    rust_type: RustType,
+    /// Name of the generated enum, if the item is an enumeration
+    enum_name: Option<String>,
+}
+
+struct ConfigEnum {
+    name: String,
+    repr: RustType,
+    variants: Vec<(String, i64)>,
+}
+
+struct Generator<'a> {
+    device: &'a Device,
+    prefix: String,
+    pdo_structs: Vec<PdoObjectStruct>,
+    /// PDO index to the field name in the `TxPdo`/`RxPdo` struct and the name of the PDO object struct
+    pdo_fields: HashMap<u16, (String, String)>,
+    config_structs: Vec<ConfigStruct>,
+    config_enums: Vec<ConfigEnum>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(device: &'a Device) -> Self {
+        let mut generator = Self {
+            device,
+            prefix: type_name_prefix(&device.type_name),
+            pdo_structs: vec![],
+            pdo_fields: HashMap::new(),
+            config_structs: vec![],
+            config_enums: vec![],
+        };
+        generator.collect_pdo_objects();
+        generator.collect_configuration();
+        generator
+    }
+
+    fn collect_pdo_objects(&mut self) {
+        let mut struct_names = HashSet::new();
+        let mut pdo_field_names = HashSet::new();
+        let pdos = self
+            .device
+            .txpdos
+            .iter()
+            .map(|pdo| (true, pdo))
+            .chain(self.device.rxpdos.iter().map(|pdo| (false, pdo)));
+
+        for (tx, pdo) in pdos {
+            let fields = pdo_fields(pdo);
+            let existing = self.pdo_structs.iter_mut().find(|existing| {
+                existing.tx == tx
+                    && existing.fields.len() == fields.len()
+                    && existing.fields.iter().zip(&fields).all(|(a, b)| {
+                        a.name == b.name && a.offset == b.offset && a.rust_type == b.rust_type
+                    })
+                    && existing.bits == pdo.bit_len()
+            });
+            let struct_name = if let Some(existing) = existing {
+                existing.pdos.push(pdo.index);
+                existing.name.clone()
+            } else {
+                let mut name = pascal_case(&strip_channel(&words(&pdo.name)));
+                if name.is_empty() || !is_identifier_start(&name) {
+                    name = format!("Pdo{}", name);
+                }
+                if !struct_names.insert(name.clone()) {
+                    name = format!("{}{:04X}", name, pdo.index);
+                    struct_names.insert(name.clone());
+                }
+                self.pdo_structs.push(PdoObjectStruct {
+                    name: name.clone(),
+                    pdos: vec![pdo.index],
+                    pdo_name: pdo.name.clone(),
+                    tx,
+                    bits: pdo.bit_len(),
+                    fields,
+                });
+                name
+            };
+
+            let field_name = unique(
+                &mut pdo_field_names,
+                snake_case(&words(&pdo.name)),
+                &format!("pdo_{:04x}", pdo.index),
+            );
+            self.pdo_fields
+                .insert(pdo.index, (field_name, struct_name.clone()));
+        }
+    }
+
+    fn collect_configuration(&mut self) {
+        let mut struct_names = HashSet::new();
+        let mut object_field_names = HashSet::new();
+        let mut enums_by_type: HashMap<String, String> = HashMap::new();
+        let mut enum_names = HashSet::new();
+
+        for object in &self.device.objects {
+            let object_field = unique(
+                &mut object_field_names,
+                snake_case(&words(&object.name)),
+                &format!("object_{:04x}", object.index),
+            );
+            // channels share the struct of the first object with the same data type
+            if let Some(index) = self.config_structs.iter().position(|existing| {
+                self.device
+                    .objects
+                    .iter()
+                    .find(|o| o.index == existing.objects[0].1)
+                    .is_some_and(|o| o.data_type == object.data_type && !o.data_type.is_empty())
+            }) {
+                self.config_structs[index]
+                    .objects
+                    .push((object_field, object.index));
+                continue;
+            }
+
+            let base_name = pascal_case(&strip_channel(&words(&object.name)));
+            let mut name = format!("{}{}", self.prefix, base_name);
+            if !struct_names.insert(name.clone()) || name == format!("{}Configuration", self.prefix)
+            {
+                name = format!("{}{}{:04X}", self.prefix, base_name, object.index);
+                struct_names.insert(name.clone());
+            }
+
+            let fields = self.config_fields(object, &mut enums_by_type, &mut enum_names);
+            if fields.is_empty() {
+                continue;
+            }
+            self.config_structs.push(ConfigStruct {
+                name,
+                objects: vec![(object_field, object.index)],
+                fields,
+            });
+        }
+    }
+
+    fn config_fields(
+        &mut self,
+        object: &Object,
+        enums_by_type: &mut HashMap<String, String>,
+        enum_names: &mut HashSet<String>,
+    ) -> Vec<ConfigField> {
+        let mut field_names = HashSet::new();
+        let mut fields = vec![];
+        for item in &object.items {
+            let Some(rust_type) = RustType::from_esi(Some(&item.base_type), item.bit_size) else {
+                continue;
+            };
+            let name = unique(
+                &mut field_names,
+                snake_case(&words(&item.name)),
+                &format!("subindex_{:02x}", item.sub_index),
+            );
+
+            let enum_name = match &item.enum_type {
+                Some(enum_type)
+                    if !item.enum_values.is_empty()
+                        && rust_type != RustType::Bool
+                        && !rust_type.is_float() =>
+                {
+                    if let Some(name) = enums_by_type.get(enum_type) {
+                        Some(name.clone())
+                    } else {
+                        let mut enum_name =
+                            format!("{}{}", self.prefix, pascal_case(&words(&item.name)));
+                        if !enum_names.insert(enum_name.clone()) {
+                            enum_name = format!("{}{}", enum_name, pascal_case(&words(enum_type)));
+                            enum_names.insert(enum_name.clone());
+                        }
+                        self.config_enums.push(ConfigEnum {
+                            name: enum_name.clone(),
+                            repr: rust_type,
+                            variants: enum_variants(&item.enum_values),
+                        });
+                        enums_by_type.insert(enum_type.to_string(), enum_name.clone());
+                        Some(enum_name)
+                    }
+                }
+                _ => None,
+            };
+
+            fields.push(ConfigField {
+                name,
+                item: item.clone(),
+                rust_type,
+                enum_name,
+            });
+        }
+        fields
+    }
+
+    fn module(&self, esi: &EsiFile, revisions: &[&Device], source: &str) -> String {
+        let mut code = Code::default();
+        code.line(format!(
+            "//! {} generated by ethercat-esi-codegen from {}",
+            self.device.name, source
+        ));
+        code.line("//!");
+        code.line("//! Add the ports and IO traits of the device before using it in a machine.");
+        code.line("");
+        self.imports(&mut code);
+        self.device_struct(&mut code);
+        self.pdo_assignment_structs(&mut code);
+        self.configuration(&mut code);
+        self.predefined_pdo_assignment(&mut code);
+        self.pdo_objects(&mut code);
+        self.identity(&mut code, esi, revisions);
+        code.0
+    }
+
+    const fn has_txpdo(&self) -> bool {
+        !self.device.txpdos.is_empty()
+    }
+
+    const fn has_rxpdo(&self) -> bool {
+        !self.device.rxpdos.is_empty()
+    }
+
+    fn imports(&self, code: &mut Code) {
+        let mut pdo_imports = vec!["PredefinedPdoAssignment"];
+        if self.has_rxpdo() {
+            pdo_imports.push("RxPdo");
+        }
+        if self.pdo_structs.iter().any(|s| !s.tx) {
+            pdo_imports.push("RxPdoObject");
+        }
+        if self.has_txpdo() {
+            pdo_imports.push("TxPdo");
+        }
+        if self.pdo_structs.iter().any(|s| s.tx) {
+            pdo_imports.push("TxPdoObject");
+        }
+        let mut derives = vec!["EthercatDevice"];
+        if !self.pdo_structs.is_empty() {
+            derives.push("PdoObject");
+        }
+        derives.extend(["RxPdo", "TxPdo"]);
+
+        code.line(
+            "use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};",
+        );
+        code.line("use crate::coe::{ConfigurableDevice, Configuration};");
+        code.line("use crate::helpers::ethercrab_types::EthercrabSubDevicePreoperational;");
+        code.line(format!("use crate::pdo::{{{}}};", pdo_imports.join(", ")));
+        if !self.pdo_structs.is_empty() {
+            code.line("use bitvec::prelude::*;");
+        }
+        code.line(format!(
+            "use ethercat_hal_derive::{{{}}};",
+            derives.join(", ")
+        ));
+        code.line("");
+    }
+
+    fn device_struct(&self, code: &mut Code) {
+        let p = &self.prefix;
+        code.line("#[derive(EthercatDevice)]");
+        code.line(format!("pub struct {p} {{"));
+        if self.has_txpdo() {
+            code.line(format!("    pub txpdo: {p}TxPdo,"));
+        }
+        if self.has_rxpdo() {
+            code.line(format!("    pub rxpdo: {p}RxPdo,"));
+        }
+        code.line(format!("    pub configuration: {p}Configuration,"));
+        code.line("    is_used: bool,");
+        code.line("}");
+        code.line("");
+        code.line(format!("impl EthercatDeviceProcessing for {p} {{}}"));
+        code.line("");
+        code.line(format!("impl std::fmt::Debug for {p} {{"));
+        code.line("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {");
+        code.line(format!("        write!(f, \"{p}\")"));
+        code.line("    }");
+        code.line("}");
+        code.line("");
+        code.line(format!("impl NewEthercatDevice for {p} {{"));
+        code.line("    fn new() -> Self {");
+        code.line(format!(
+            "        let configuration = {p}Configuration::default();"
+        ));
+        code.line("        Self {");
+        if self.has_txpdo() {
+            code.line("            txpdo: configuration.pdo_assignment.txpdo_assignment(),");
+        }
+        if self.has_rxpdo() {
+            code.line("            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),");
+        }
+        code.line("            configuration,");
+        code.line("            is_used: false,");
+        code.line("        }");
+        code.line("    }");
+        code.line("}");
+        code.line("");
+        code.line(format!(
+            "impl ConfigurableDevice<{p}Configuration> for {p} {{"
+        ));
+        code.line("    async fn write_config<'maindevice>(");
+        code.line("        &mut self,");
+        code.line("        device: &EthercrabSubDevicePreoperational<'maindevice>,");
+        code.line(format!("        config: &{p}Configuration,"));
+        code.line("    ) -> Result<(), anyhow::Error> {");
+        code.line("        config.write_config(device).await?;");
+        code.line("        self.configuration = config.clone();");
+        if self.has_txpdo() {
+            code.line("        self.txpdo = config.pdo_assignment.txpdo_assignment();");
+        }
+        if self.has_rxpdo() {
+            code.line("        self.rxpdo = config.pdo_assignment.rxpdo_assignment();");
+        }
+        code.line("        Ok(())");
+        code.line("    }");
+        code.line("");
+        code.line(format!("    fn get_config(&self) -> {p}Configuration {{"));
+        code.line("        self.configuration.clone()");
+        code.line("    }");
+        code.line("}");
+        code.line("");
+    }
+
+    fn pdo_assignment_structs(&self, code: &mut Code) {
+        for (tx, pdos) in [(true, &self.device.txpdos), (false, &self.device.rxpdos)] {
+            let kind = if tx { "TxPdo" } else { "RxPdo" };
+            if pdos.is_empty() {
+                code.line("#[derive(Debug, Clone, ".to_string() + kind + ")]");
+                code.line(format!("pub struct {}{} {{}}", self.prefix, kind));
+                code.line("");
+                continue;
+            }
+            code.line("#[derive(Debug, Clone, ".to_string() + kind + ")]");
+            code.line(format!("pub struct {}{} {{", self.prefix, kind));
+            for pdo in pdos {
+                let (field, struct_name) = &self.pdo_fields[&pdo.index];
+                code.line(format!("    #[pdo_object_index(0x{:04X})]", pdo.index));
+                code.line(format!("    pub {field}: Option<{struct_name}>,"));
+            }
+            code.line("}");
+            code.line("");
+        }
+    }
+
+    fn configuration(&self, code: &mut Code) {
+        let p = &self.prefix;
+        code.line("#[derive(Debug, Clone, Default)]");
+        code.line(format!("pub struct {p}Configuration {{"));
+        code.line(format!(
+            "    pub pdo_assignment: {p}PredefinedPdoAssignment,"
+        ));
+        for config_struct in &self.config_structs {
+            for (field, index) in &config_struct.objects {
+                code.line(format!("    /// 0x{index:04X}"));
+                code.line(format!("    pub {}: {},", field, config_struct.name));
+            }
+        }
+        code.line("}");
+        code.line("");
+        code.line(format!("impl Configuration for {p}Configuration {{"));
+        code.line("    async fn write_config<'a>(");
+        code.line("        &self,");
+        code.line("        device: &EthercrabSubDevicePreoperational<'a>,");
+        code.line("    ) -> Result<(), anyhow::Error> {");
+        for config_struct in &self.config_structs {
+            for (field, index) in &config_struct.objects {
+                code.line(format!(
+                    "        self.{field}.write_config(device, 0x{index:04X}).await?;"
+                ));
+            }
+        }
+        code.line("        self.pdo_assignment");
+        code.line("            .txpdo_assignment()");
+        code.line("            .write_config(device)");
+        code.line("            .await?;");
+        code.line("        self.pdo_assignment");
+        code.line("            .rxpdo_assignment()");
+        code.line("            .write_config(device)");
+        code.line("            .await?;");
+        code.line("        Ok(())");
+        code.line("    }");
+        code.line("}");
+        code.line("");
+
+        for config_struct in &self.config_structs {
+            self.config_struct(code, config_struct);
+        }
+        for config_enum in &self.config_enums {
+            config_enum_code(code, config_enum);
+        }
+    }
+
+    fn config_struct(&self, code: &mut Code, config_struct: &ConfigStruct) {
+        let name = &config_struct.name;
+        let object = self
+            .device
+            .objects
+            .iter()
+            .find(|object| object.index == config_struct.objects[0].1);
+        if let Some(object) = object {
+            code.line(format!("/// {}", object.name));
+        }
+        if config_struct.objects.len() > 1 {
+            let indices = config_struct
+                .objects
+                .iter()
+                .map(|(_, index)| format!("0x{index:04X}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            code.line("///");
+            code.line(format!("/// Used for {indices}"));
+        }
+        code.line("#[derive(Debug, Clone)]");
+        code.line(format!("pub struct {name} {{"));
+        for field in &config_struct.fields {
+            code.line(format!(
+                "    /// Subindex 0x{:02X} {}",
+                field.item.sub_index, field.item.name
+            ));
+            let rust_type = field
+                .enum_name
+                .as_deref()
+                .unwrap_or_else(|| field.rust_type.name());
+            code.line(format!("    pub {}: {},", field.name, rust_type));
+        }
+        code.line("}");
+        code.line("");
+
+        code.line(format!("impl Default for {name} {{"));
+        code.line("    fn default() -> Self {");
+        code.line("        Self {");
+        for field in &config_struct.fields {
+            let value = self.default_value(field);
+            code.line(format!("            {}: {},", field.name, value));
+        }
+        code.line("        }");
+        code.line("    }");
+        code.line("}");
+        code.line("");
+
+        code.line(format!("impl {name} {{"));
+        code.line("    pub async fn write_config<'a>(");
+        code.line("        &self,");
+        code.line("        device: &EthercrabSubDevicePreoperational<'a>,");
+        code.line("        index: u16,");
+        code.line("    ) -> Result<(), anyhow::Error> {");
+        for field in &config_struct.fields {
+            let value = match &field.enum_name {
+                Some(_) => format!("{}::from(self.{})", field.rust_type.name(), field.name),
+                None => format!("self.{}", field.name),
+            };
+            code.line(format!(
+                "        device.sdo_write(index, 0x{:02X}, {}).await?;",
+                field.item.sub_index, value
+            ));
+        }
+        code.line("        Ok(())");
+        code.line("    }");
+        code.line("}");
+        code.line("");
+    }
+
+    /// Default of the ESI, or the first variant of an enumeration if the default has no name
+    fn default_value(&self, field: &ConfigField) -> String {
+        let raw = field.item.default.unwrap_or_default();
+        let literal = field.rust_type.literal(raw, field.item.bit_size);
+        let Some(enum_name) = &field.enum_name else {
+            return literal;
+        };
+        self.config_enums
+            .iter()
+            .find(|config_enum| &config_enum.name == enum_name)
+            .and_then(|config_enum| {
+                config_enum
+                    .variants
+                    .iter()
+                    .find(|(_, value)| value.to_string() == literal)
+                    .or_else(|| config_enum.variants.first())
+            })
+            .map_or(literal, |(variant, _)| format!("{enum_name}::{variant}"))
+    }
+
+    fn predefined_pdo_assignment(&self, code: &mut Code) {
+        let p = &self.prefix;
+        let assignments = self.device.assignments();
+        let mut variant_names = HashSet::new();
+        let variants: Vec<(String, &crate::esi::SmMapping)> = assignments
+            .iter()
+            .enumerate()
+            .map(|(i, mapping)| {
+                let mut name = pascal_case(&words(&mapping.name));
+                if !is_identifier_start(&name) {
+                    name = format!("Mapping{name}");
+                }
+                (
+                    unique(&mut variant_names, name, &format!("Mapping{i}")),
+                    mapping,
+                )
+            })
+            .collect();
+        let default = variants
+            .iter()
+            .find(|(_, mapping)| mapping.default)
+            .or_else(|| variants.first())
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+
+        code.line("#[derive(Debug, Clone, Default)]");
+        code.line(format!("pub enum {p}PredefinedPdoAssignment {{"));
+        for (name, _) in &variants {
+            if *name == default {
+                code.line("    #[default]");
+            }
+            code.line(format!("    {name},"));
+        }
+        code.line("}");
+        code.line("");
+        code.line(format!(
+            "impl PredefinedPdoAssignment<{p}TxPdo, {p}RxPdo> for {p}PredefinedPdoAssignment {{"
+        ));
+        for (tx, pdos) in [(true, &self.device.txpdos), (false, &self.device.rxpdos)] {
+            let (method, kind) = if tx {
+                ("txpdo_assignment", "TxPdo")
+            } else {
+                ("rxpdo_assignment", "RxPdo")
+            };
+            code.line(format!("    fn {method}(&self) -> {p}{kind} {{"));
+            code.line("        match self {");
+            for (name, mapping) in &variants {
+                if pdos.is_empty() {
+                    code.line(format!("            Self::{name} => {p}{kind} {{}},"));
+                    continue;
+                }
+                code.line(format!("            Self::{name} => {p}{kind} {{"));
+                for pdo in pdos.iter() {
+                    let (field, struct_name) = &self.pdo_fields[&pdo.index];
+                    // mandatory PDOs are always assigned
+                    if mapping.pdos.contains(&pdo.index) || (pdo.mandatory && pdo.fixed) {
+                        code.line(format!(
+                            "                {field}: Some({struct_name}::default()),"
+                        ));
+                    } else {
+                        code.line(format!("                {field}: None,"));
+                    }
+                }
+                code.line("            },");
+            }
+            code.line("        }");
+            code.line("    }");
+            if tx {
+                code.line("");
+            }
+        }
+        code.line("}");
+        code.line("");
+    }
+
+    fn pdo_objects(&self, code: &mut Code) {
+        for pdo_struct in &self.pdo_structs {
+            pdo_object_code(code, pdo_struct);
+        }
+    }
+
+    fn identity(&self, code: &mut Code, esi: &EsiFile, revisions: &[&Device]) {
+        let p = &self.prefix;
+        code.line(format!(
+            "pub const {p}_VENDOR_ID: u32 = 0x{:x};",
+            esi.vendor_id
+        ));
+        code.line(format!(
+            "pub const {p}_PRODUCT_ID: u32 = 0x{:08x};",
+            self.device.product_code
+        ));
+        let revisions: Vec<&&Device> = revisions
+            .iter()
+            .filter(|revision| revision.product_code == self.device.product_code)
+            .collect();
+        for (i, revision) in revisions.iter().enumerate() {
+            code.line(format!(
+                "pub const {p}_REVISION_{}: u32 = 0x{:08x};",
+                revision_letter(i),
+                revision.revision
+            ));
+        }
+        for i in 0..revisions.len() {
+            let letter = revision_letter(i);
+            code.line(format!(
+                "pub const {p}_IDENTITY_{letter}: SubDeviceIdentityTuple = ({p}_VENDOR_ID, {p}_PRODUCT_ID, {p}_REVISION_{letter});"
+            ));
+        }
+    }
+}
+
+fn pdo_fields(pdo: &Pdo) -> Vec<PdoField> {
+    let mut names = HashSet::new();
+    let mut offset = 0;
+    let mut fields = vec![];
+    for entry in &pdo.entries {
+        if !entry.is_padding() {
+            // `Status__Underrange` is `Underrange` in the group `Status`
+            let short_name = entry.name.rsplit("__").next().unwrap_or_default();
+            fields.push(PdoField {
+                name: unique(
+                    &mut names,
+                    snake_case(&words(short_name)),
+                    &format!("entry_{:02x}", entry.sub_index),
+                ),
+                entry: entry.clone(),
+                offset,
+                rust_type: RustType::from_esi(entry.data_type.as_deref(), entry.bit_len),
+            });
+        }
+        offset += entry.bit_len;
+    }
+    fields
+}
+
+fn pdo_object_code(code: &mut Code, pdo_struct: &PdoObjectStruct) {
+    let name = &pdo_struct.name;
+    let has_float = pdo_struct
+        .fields
+        .iter()
+        .any(|field| field.rust_type.is_some_and(RustType::is_float));
+
+    code.line(format!(
+        "/// PDO 0x{:04X} {}",
+        pdo_struct.pdos[0], pdo_struct.pdo_name
+    ));
+    if pdo_struct.pdos.len() > 1 {
+        let others = pdo_struct.pdos[1..]
+            .iter()
+            .map(|index| format!("0x{index:04X}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        code.line("///");
+        code.line(format!("/// Also used by {others}"));
+    }
+    if has_float {
+        code.line("#[derive(Debug, Clone, Default, PdoObject, PartialEq)]");
+    } else {
+        code.line("#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]");
+    }
+    code.line(format!("#[pdo_object(bits = {})]", pdo_struct.bits));
+    code.line(format!("pub struct {name} {{"));
+    for field in &pdo_struct.fields {
+        let entry = &field.entry;
+        let description = entry.name.replace("__", " ");
+        match field.rust_type {
+            Some(rust_type) => {
+                code.line(format!(
+                    "    /// 0x{:04X}:{:02X} {}",
+                    entry.index, entry.sub_index, description
+                ));
+                code.line(format!("    pub {}: {},", field.name, rust_type.name()));
+            }
+            None => code.line(format!(
+                "    // 0x{:04X}:{:02X} {} ({} bits) is not decoded",
+                entry.index, entry.sub_index, description, entry.bit_len
+            )),
+        }
+    }
+    code.line("}");
+    code.line("");
+
+    let decoded: Vec<(&PdoField, RustType)> = pdo_struct
+        .fields
+        .iter()
+        .filter_map(|field| Some((field, field.rust_type?)))
+        .collect();
+    let bits_name = if decoded.is_empty() { "_bits" } else { "bits" };
+
+    if pdo_struct.tx {
+        code.line(format!("impl TxPdoObject for {name} {{"));
+        code.line(format!(
+            "    fn read(&mut self, {bits_name}: &BitSlice<u8, Lsb0>) {{"
+        ));
+        for (field, rust_type) in decoded {
+            let start = field.offset;
+            let end = start + field.entry.bit_len;
+            let value = match rust_type {
+                RustType::Bool => format!("bits[{start}]"),
+                RustType::F32 | RustType::F64 => format!(
+                    "{}::from_bits(bits[{start}..{end}].load_le::<{}>())",
+                    rust_type.name(),
+                    rust_type.bits_type().name()
+                ),
+                _ => format!("bits[{start}..{end}].load_le::<{}>()", rust_type.name()),
+            };
+            code.line(format!("        self.{} = {};", field.name, value));
+        }
+    } else {
+        code.line(format!("impl RxPdoObject for {name} {{"));
+        code.line(format!(
+            "    fn write(&self, {bits_name}: &mut BitSlice<u8, Lsb0>) {{"
+        ));
+        for (field, rust_type) in decoded {
+            let start = field.offset;
+            let end = start + field.entry.bit_len;
+            let line = match rust_type {
+                RustType::Bool => format!("bits.set({start}, self.{});", field.name),
+                RustType::F32 | RustType::F64 => {
+                    format!(
+                        "bits[{start}..{end}].store_le(self.{}.to_bits());",
+                        field.name
+                    )
+                }
+                _ => format!("bits[{start}..{end}].store_le(self.{});", field.name),
+            };
+            code.line(format!("        {line}"));
+        }
+    }
+    code.line("    }");
+    code.line("}");
+    code.line("");
+}
+
+fn config_enum_code(code: &mut Code, config_enum: &ConfigEnum) {
+    let name = &config_enum.name;
+    let repr = config_enum.repr.name();
+    code.line("#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+    code.line(format!("pub enum {name} {{"));
+    for (variant, _) in &config_enum.variants {
+        code.line(format!("    {variant},"));
+    }
+    code.line("}");
+    code.line("");
+    code.line(format!("impl From<{name}> for {repr} {{"));
+    code.line(format!("    fn from(value: {name}) -> Self {{"));
+    code.line("        match value {");
+    for (variant, value) in &config_enum.variants {
+        code.line(format!("            {name}::{variant} => {value},"));
+    }
+    code.line("        }");
+    code.line("    }");
+    code.line("}");
+    code.line("");
+}
+
+fn enum_variants(values: &[(String, i64)]) -> Vec<(String, i64)> {
+    let mut names = HashSet::new();
+    values
+        .iter()
+        .map(|(text, value)| {
+            let mut name = pascal_case(&words(text));
+            if !is_identifier_start(&name) {
+                name = format!("Value{name}");
+            }
+            let fallback = format!("{name}{}", value.unsigned_abs());
+            (unique(&mut names, name, &fallback), *value)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Code(String);
+
+impl Code {
+    fn line(&mut self, line: impl AsRef<str>) {
+        self.0.push_str(line.as_ref());
+        self.0.push('\n');
+    }
+}
+
+/// Lowercase alphanumeric words of a name, `AI Settings Ch.1` becomes `["ai", "settings", "ch", "1"]`
+fn words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+/// Removes a trailing channel number, `AI Standard Channel 1` and `AI Settings Ch.1` become `AI Standard` and `AI Settings`
+fn strip_channel(words: &[String]) -> Vec<String> {
+    let mut stripped = words.to_vec();
+    if stripped
+        .last()
+        .is_some_and(|word| word.chars().all(|c| c.is_ascii_digit()))
+    {
+        stripped.pop();
+        if stripped
+            .last()
+            .is_some_and(|word| word == "ch" || word == "channel")
+        {
+            stripped.pop();
+        }
+    }
+    if stripped.is_empty() {
+        return words.to_vec();
+    }
+    stripped
+}
+
+fn pascal_case(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+fn snake_case(words: &[String]) -> String {
+    let name = words.join("_");
+    if name.is_empty() || !is_identifier_start(&name) {
+        return format!("_{name}");
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        return format!("{name}_");
+    }
+    name
+}
+
+fn is_identifier_start(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+/// Uses `name` if it is not taken yet, `fallback` otherwise
+fn unique(taken: &mut HashSet<String>, name: String, fallback: &str) -> String {
+    // names starting with `_` are invalid for fields which are used
+    let name = if name.starts_with('_') {
+        fallback.to_string()
+    } else {
+        name
+    };
+    if taken.insert(name.clone()) {
+        return name;
+    }
+    let mut candidate = fallback.to_string();
+    let mut counter = 1;
+    while !taken.insert(candidate.clone()) {
+        counter += 1;
+        candidate = format!("{fallback}_{counter}");
+    }
+    candidate
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::esi::tests::esi;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(module_name("EL3062-0030"), "el3062_0030");
+        assert_eq!(
+            pascal_case(&strip_channel(&words("AI Standard Channel 1"))),
+            "AiStandard"
+        );
+        assert_eq!(snake_case(&words("AI Settings Ch.1")), "ai_settings_ch_1");
+        assert_eq!(snake_case(&words("Type")), "type_");
+        assert_eq!(revision_letter(1), "B");
+        assert_eq!(
+            registration("EL3002", 2),
+            "EL3002_IDENTITY_A | EL3002_IDENTITY_B => Ok(Arc::new(RwLock::new(el3002::EL3002::new()))),"
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(RustType::I16.literal(0xff9c, 16), "-100");
+        assert_eq!(RustType::U16.literal(1000, 16), "1000");
+        assert_eq!(RustType::Bool.literal(1, 1), "true");
+        assert_eq!(
+            RustType::F32.literal(u64::from(1.5f32.to_bits()), 32),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let code = generate(&esi(), "EL3002", "EL3002.xml").unwrap();
+
+        // identity of both revisions, newest first
+        assert!(code.contains("pub const EL3002_PRODUCT_ID: u32 = 0x0bba3052;"));
+        assert!(code.contains("pub const EL3002_REVISION_A: u32 = 0x00160000;"));
+        assert!(code.contains("pub const EL3002_REVISION_B: u32 = 0x00150000;"));
+
+        // the PDO objects of both channels share a struct
+        assert!(code.contains("pub struct AiStandard {"));
+        assert!(code.contains("/// Also used by 0x1A02"));
+        assert!(code.contains("#[pdo_object(bits = 32)]"));
+        assert!(code.contains("self.limit_1 = bits[2..4].load_le::<u8>();"));
+        assert!(code.contains("self.value = bits[16..32].load_le::<i16>();"));
+        assert!(code.contains("bits[0..32].store_le(self.gain.to_bits());"));
+        assert!(code.contains("#[pdo_object_index(0x1A03)]"));
+        assert!(code.contains("pub ai_compact_channel_2: Option<AiCompact>,"));
+
+        // alternative mappings become the predefined assignments
+        assert!(code.contains("Self::Compact => EL3002TxPdo {"));
+        assert!(code.contains("ai_standard_channel_1: None,"));
+        assert!(code.contains("#[default]\n    Standard,"));
+
+        // both channels share the configuration struct
+        assert!(code.contains("pub ai_settings_ch_1: EL3002AiSettings,"));
+        assert!(code.contains("pub ai_settings_ch_2: EL3002AiSettings,"));
+        assert!(code.contains("self.ai_settings_ch_2.write_config(device, 0x8010).await?;"));
+        assert!(code.contains("user_scale_offset: -100,"));
+        assert!(code.contains("presentation: EL3002Presentation::SignedPresentation,"));
+        assert!(
+            code.contains("device.sdo_write(index, 0x02, u8::from(self.presentation)).await?;")
+        );
+        assert!(code.contains("EL3002Presentation::UnsignedPresentation => 1,"));
+        assert!(code.contains("pub filter_time: EL3002FilterTime,"));
+        assert!(code.contains("filter_time: 1000,"));
+
+        assert!(generate(&esi(), "EL3004", "EL3002.xml").is_err());
+    }
+}
@@ -0,0 +1,685 @@
+//! The parts of an EtherCAT Slave Information (ESI) file which are needed to generate a driver
+//!
+//! See ETG.2000 for the format. Only `Descriptions/Devices/Device` is read, modular devices
+//! (`Modules`/`Slots`) are not supported.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+
+use crate::xml::Element;
+
+/// Configuration objects of ETG.5001 devices live in `0x8000..=0x8FFF`
+pub const CONFIGURATION_OBJECTS: std::ops::RangeInclusive<u16> = 0x8000..=0x8FFF;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsiFile {
+    pub vendor_id: u32,
+    pub vendor_name: String,
+    pub devices: Vec<Device>,
+}
+
+/// One revision of a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// e.g. `EL3001` or `EL3062-0030`
+    pub type_name: String,
+    pub product_code: u32,
+    pub revision: u32,
+    pub name: String,
+    pub txpdos: Vec<Pdo>,
+    pub rxpdos: Vec<Pdo>,
+    /// Alternative PDO assignments offered by the configuration tool
+    pub mappings: Vec<SmMapping>,
+    /// Writable configuration objects, see [`CONFIGURATION_OBJECTS`]
+    pub objects: Vec<Object>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pdo {
+    pub index: u16,
+    pub name: String,
+    /// Sync manager of the default assignment, `None` if the PDO is not assigned by default
+    pub sm: Option<u8>,
+    pub fixed: bool,
+    pub mandatory: bool,
+    /// PDOs which can't be assigned together with this one
+    pub excludes: Vec<u16>,
+    pub entries: Vec<PdoEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdoEntry {
+    /// `0` for padding
+    pub index: u16,
+    pub sub_index: u8,
+    pub bit_len: usize,
+    pub name: String,
+    pub data_type: Option<String>,
+}
+
+impl PdoEntry {
+    pub const fn is_padding(&self) -> bool {
+        self.index == 0
+    }
+}
+
+/// A named set of PDOs which are assigned together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmMapping {
+    pub name: String,
+    pub default: bool,
+    pub pdos: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Object {
+    pub index: u16,
+    pub name: String,
+    /// Name of the data type, objects of the same type are channels of the same function
+    pub data_type: String,
+    /// Writable subindices, a simple object has a single item with subindex `0`
+    pub items: Vec<ObjectItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectItem {
+    pub sub_index: u8,
+    pub name: String,
+    /// Base type like `BOOL`, `UINT` or `BIT3`
+    pub base_type: String,
+    pub bit_size: usize,
+    /// Little endian raw value
+    pub default: Option<u64>,
+    /// Named values of enumeration types
+    pub enum_values: Vec<(String, i64)>,
+    /// Name of the enumeration type
+    pub enum_type: Option<String>,
+}
+
+impl EsiFile {
+    pub fn from_xml(root: &Element) -> Result<Self, anyhow::Error> {
+        if root.name != "EtherCATInfo" {
+            bail!("Expected <EtherCATInfo> but found <{}>", root.name);
+        }
+        let vendor = root
+            .child("Vendor")
+            .ok_or_else(|| anyhow!("Missing <Vendor>"))?;
+        let vendor_id = parse_number(
+            vendor
+                .child_text("Id")
+                .ok_or_else(|| anyhow!("Missing <Vendor><Id>"))?,
+        )?;
+        let vendor_id = u32::try_from(vendor_id)?;
+        let vendor_name = vendor.child_text("Name").unwrap_or_default().to_string();
+
+        let devices = root
+            .path(&["Descriptions", "Devices"])
+            .ok_or_else(|| anyhow!("Missing <Descriptions><Devices>"))?
+            .children("Device")
+            .map(Device::from_xml)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            vendor_id,
+            vendor_name,
+            devices,
+        })
+    }
+
+    /// All revisions of a device type, newest first
+    pub fn revisions(&self, type_name: &str) -> Vec<&Device> {
+        let mut revisions: Vec<&Device> = self
+            .devices
+            .iter()
+            .filter(|device| device.type_name.eq_ignore_ascii_case(type_name))
+            .collect();
+        revisions.sort_by_key(|device| std::cmp::Reverse(device.revision));
+        revisions
+    }
+}
+
+impl Device {
+    fn from_xml(device: &Element) -> Result<Self, anyhow::Error> {
+        let type_element = device
+            .child("Type")
+            .ok_or_else(|| anyhow!("Missing <Type> in <Device>"))?;
+        let type_name = type_element.text.clone();
+        let context = |e: anyhow::Error| anyhow!("{}: {}", type_name, e);
+
+        let product_code = type_element
+            .attribute("ProductCode")
+            .map(parse_number)
+            .transpose()
+            .map_err(context)?
+            .unwrap_or_default();
+        let revision = type_element
+            .attribute("RevisionNo")
+            .map(parse_number)
+            .transpose()
+            .map_err(context)?
+            .unwrap_or_default();
+
+        let txpdos = device
+            .children("TxPdo")
+            .map(Pdo::from_xml)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(context)?;
+        let rxpdos = device
+            .children("RxPdo")
+            .map(Pdo::from_xml)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(context)?;
+
+        let mappings = device
+            .path(&["VendorSpecific", "TwinCAT"])
+            .map(|twincat| {
+                twincat
+                    .children("AlternativeSmMapping")
+                    .map(SmMapping::from_xml)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()
+            .map_err(context)?
+            .unwrap_or_default();
+
+        let objects = device
+            .path(&["Profile", "Dictionary"])
+            .map(parse_dictionary)
+            .transpose()
+            .map_err(context)?
+            .unwrap_or_default();
+
+        Ok(Self {
+            product_code: u32::try_from(product_code).map_err(|e| context(e.into()))?,
+            revision: u32::try_from(revision).map_err(|e| context(e.into()))?,
+            name: localized_name(device).unwrap_or_default().to_string(),
+            type_name,
+            txpdos,
+            rxpdos,
+            mappings,
+            objects,
+        })
+    }
+
+    /// The PDOs a variant of the predefined PDO assignment consists of
+    ///
+    /// Uses the alternative mappings of the ESI, or the PDOs with a default sync manager if there are none.
+    pub fn assignments(&self) -> Vec<SmMapping> {
+        if !self.mappings.is_empty() {
+            return self.mappings.clone();
+        }
+        vec![SmMapping {
+            name: "Standard".to_string(),
+            default: true,
+            pdos: self
+                .txpdos
+                .iter()
+                .chain(&self.rxpdos)
+                .filter(|pdo| pdo.sm.is_some() || pdo.mandatory)
+                .map(|pdo| pdo.index)
+                .collect(),
+        }]
+    }
+}
+
+impl Pdo {
+    fn from_xml(pdo: &Element) -> Result<Self, anyhow::Error> {
+        let index = parse_index(
+            pdo.child_text("Index")
+                .ok_or_else(|| anyhow!("Missing <Index> in <{}>", pdo.name))?,
+        )?;
+        let entries = pdo
+            .children("Entry")
+            .map(|entry| {
+                let index = parse_index(entry.child_text("Index").unwrap_or("0"))?;
+                let sub_index = entry
+                    .child_text("SubIndex")
+                    .map(parse_number)
+                    .transpose()?
+                    .unwrap_or_default();
+                let bit_len = parse_number(
+                    entry
+                        .child_text("BitLen")
+                        .ok_or_else(|| anyhow!("Missing <BitLen> in PDO {:#06x}", index))?,
+                )?;
+                Ok(PdoEntry {
+                    index,
+                    sub_index: u8::try_from(sub_index)?,
+                    bit_len: usize::try_from(bit_len)?,
+                    name: entry.child_text("Name").unwrap_or_default().to_string(),
+                    data_type: entry.child_text("DataType").map(str::to_string),
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(Self {
+            index,
+            name: localized_name(pdo).unwrap_or_default().to_string(),
+            sm: pdo
+                .attribute("Sm")
+                .map(parse_number)
+                .transpose()?
+                .map(u8::try_from)
+                .transpose()?,
+            fixed: pdo.attribute("Fixed").is_some_and(parse_bool),
+            mandatory: pdo.attribute("Mandatory").is_some_and(parse_bool),
+            excludes: pdo
+                .children("Exclude")
+                .map(|exclude| parse_index(&exclude.text))
+                .collect::<Result<Vec<_>, _>>()?,
+            entries,
+        })
+    }
+
+    /// Size of all entries including padding
+    pub fn bit_len(&self) -> usize {
+        self.entries.iter().map(|entry| entry.bit_len).sum()
+    }
+}
+
+impl SmMapping {
+    fn from_xml(mapping: &Element) -> Result<Self, anyhow::Error> {
+        let pdos = mapping
+            .children("Sm")
+            .flat_map(|sm| sm.children("Pdo"))
+            .map(|pdo| parse_index(&pdo.text))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            name: localized_name(mapping).unwrap_or_default().to_string(),
+            default: mapping.attribute("Default").is_some_and(parse_bool),
+            pdos,
+        })
+    }
+}
+
+/// Reads the writable configuration objects from `<Dictionary>`
+fn parse_dictionary(dictionary: &Element) -> Result<Vec<Object>, anyhow::Error> {
+    let data_types: HashMap<&str, &Element> = dictionary
+        .child("DataTypes")
+        .map(|data_types| {
+            data_types
+                .children("DataType")
+                .filter_map(|data_type| Some((data_type.child_text("Name")?, data_type)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let Some(objects) = dictionary.child("Objects") else {
+        return Ok(vec![]);
+    };
+
+    let mut result = vec![];
+    for object in objects.children("Object") {
+        let index = parse_index(object.child_text("Index").unwrap_or("0"))?;
+        if !CONFIGURATION_OBJECTS.contains(&index) {
+            continue;
+        }
+        let type_name = object.child_text("Type").unwrap_or_default();
+        let name = localized_name(object).unwrap_or_default().to_string();
+
+        // default values are listed by subitem name in the object, the types by subindex in the data type
+        let defaults: HashMap<&str, &str> = object
+            .child("Info")
+            .map(|info| {
+                info.children("SubItem")
+                    .filter_map(|item| {
+                        Some((
+                            item.child_text("Name")?,
+                            item.path(&["Info", "DefaultData"])?.text.as_str(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let items = match data_types.get(type_name) {
+            Some(data_type) if data_type.child("SubItem").is_some() => {
+                let mut items = vec![];
+                for sub_item in data_type.children("SubItem") {
+                    let sub_index = parse_number(sub_item.child_text("SubIdx").unwrap_or("0"))?;
+                    if sub_index == 0 || !is_writable(sub_item) {
+                        continue;
+                    }
+                    let item_name = sub_item.child_text("Name").unwrap_or_default();
+                    let item_type = sub_item.child_text("Type").unwrap_or_default();
+                    let bit_size = parse_number(sub_item.child_text("BitSize").unwrap_or("0"))?;
+                    items.push(object_item(
+                        &data_types,
+                        u8::try_from(sub_index)?,
+                        item_name,
+                        item_type,
+                        usize::try_from(bit_size)?,
+                        defaults.get(item_name).copied(),
+                    )?);
+                }
+                items
+            }
+            // arrays aren't configuration
+            Some(data_type) if data_type.child("ArrayInfo").is_some() => continue,
+            _ => {
+                if !is_writable(object) {
+                    continue;
+                }
+                let bit_size = parse_number(object.child_text("BitSize").unwrap_or("0"))?;
+                let default = object
+                    .path(&["Info", "DefaultData"])
+                    .map(|d| d.text.as_str());
+                vec![object_item(
+                    &data_types,
+                    0,
+                    &name,
+                    type_name,
+                    usize::try_from(bit_size)?,
+                    default,
+                )?]
+            }
+        };
+        if items.is_empty() {
+            continue;
+        }
+        result.push(Object {
+            index,
+            name,
+            data_type: type_name.to_string(),
+            items,
+        });
+    }
+    Ok(result)
+}
+
+fn object_item(
+    data_types: &HashMap<&str, &Element>,
+    sub_index: u8,
+    name: &str,
+    type_name: &str,
+    bit_size: usize,
+    default: Option<&str>,
+) -> Result<ObjectItem, anyhow::Error> {
+    let mut base_type = type_name;
+    let mut enum_values = vec![];
+    let mut enum_type = None;
+    // follow aliases like `DT0800EN03` with base type `BIT3` until a basic type is reached
+    while let Some(data_type) = data_types.get(base_type) {
+        if data_type.child("EnumInfo").is_some() && enum_type.is_none() {
+            enum_type = Some(base_type.to_string());
+            enum_values = data_type
+                .children("EnumInfo")
+                .map(|info| {
+                    let text = info.child_text("Text").unwrap_or_default().to_string();
+                    let value = parse_signed(info.child_text("Enum").unwrap_or("0"))?;
+                    Ok((text, value))
+                })
+                .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        }
+        match data_type.child_text("BaseType") {
+            Some(next) if next != base_type => base_type = next,
+            _ => break,
+        }
+    }
+    Ok(ObjectItem {
+        sub_index,
+        name: name.to_string(),
+        base_type: base_type.to_string(),
+        bit_size,
+        default: default.map(parse_default_data).transpose()?,
+        enum_values,
+        enum_type,
+    })
+}
+
+fn is_writable(element: &Element) -> bool {
+    element
+        .path(&["Flags", "Access"])
+        .is_some_and(|access| access.text.contains('w'))
+}
+
+/// English name if there are several, the first one otherwise
+fn localized_name(element: &Element) -> Option<&str> {
+    element
+        .children("Name")
+        .find(|name| name.attribute("LcId") == Some("1033"))
+        .or_else(|| element.child("Name"))
+        .map(|name| name.text.as_str())
+}
+
+fn parse_bool(value: &str) -> bool {
+    matches!(value, "1" | "true")
+}
+
+/// Numbers are decimal or hex with a `#x` prefix
+pub fn parse_number(value: &str) -> Result<u64, anyhow::Error> {
+    let value = value.trim();
+    let parsed = value
+        .strip_prefix("#x")
+        .or_else(|| value.strip_prefix("0x"))
+        .map_or_else(|| value.parse(), |hex| u64::from_str_radix(hex, 16));
+    parsed.map_err(|e| anyhow!("Invalid number {:?}: {}", value, e))
+}
+
+fn parse_signed(value: &str) -> Result<i64, anyhow::Error> {
+    match value.trim().strip_prefix('-') {
+        Some(magnitude) => Ok(-i64::try_from(parse_number(magnitude)?)?),
+        None => Ok(i64::try_from(parse_number(value)?)?),
+    }
+}
+
+fn parse_index(value: &str) -> Result<u16, anyhow::Error> {
+    Ok(u16::try_from(parse_number(value)?)?)
+}
+
+/// `<DefaultData>` holds the bytes of the value as hex in little endian
+fn parse_default_data(value: &str) -> Result<u64, anyhow::Error> {
+    let value = value.trim();
+    if value.len() > 16 {
+        bail!("Invalid default data {:?}", value);
+    }
+    value
+        .as_bytes()
+        .chunks(2)
+        .rev()
+        .try_fold(0u64, |acc, byte| {
+            let byte = std::str::from_utf8(byte)
+                .ok()
+                .filter(|byte| byte.len() == 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid default data {:?}", value))?;
+            Ok((acc << 8) | u64::from(byte))
+        })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::xml::parse;
+
+    /// Trimmed down ESI of an EL3002 with two revisions
+    pub const ESI: &str = r##"<?xml version="1.0" encoding="ISO8859-1"?>
+<EtherCATInfo Version="1.6">
+  <Vendor>
+    <Id>2</Id>
+    <Name>Beckhoff Automation GmbH &amp; Co. KG</Name>
+  </Vendor>
+  <Descriptions>
+    <Devices>
+      <Device Physics="YY">
+        <Type ProductCode="#x0bba3052" RevisionNo="#x00150000">EL3002</Type>
+        <Name LcId="1033"><![CDATA[EL3002 2Ch. Ana. Input +/-10V]]></Name>
+      </Device>
+      <Device Physics="YY">
+        <Type ProductCode="#x0bba3052" RevisionNo="#x00160000">EL3002</Type>
+        <Name LcId="1031"><![CDATA[EL3002 2K. Ana. Eingang +/-10V]]></Name>
+        <Name LcId="1033"><![CDATA[EL3002 2Ch. Ana. Input +/-10V]]></Name>
+        <Profile>
+          <Dictionary>
+            <DataTypes>
+              <DataType>
+                <Name>DT0800EN03</Name>
+                <BaseType>BIT3</BaseType>
+                <BitSize>3</BitSize>
+                <EnumInfo><Text>Signed presentation</Text><Enum>0</Enum></EnumInfo>
+                <EnumInfo><Text>Unsigned presentation</Text><Enum>1</Enum></EnumInfo>
+              </DataType>
+              <DataType>
+                <Name>DT8000</Name>
+                <BitSize>48</BitSize>
+                <SubItem>
+                  <SubIdx>0</SubIdx><Name>SubIndex 000</Name><Type>USINT</Type><BitSize>8</BitSize>
+                  <Flags><Access>ro</Access></Flags>
+                </SubItem>
+                <SubItem>
+                  <SubIdx>1</SubIdx><Name>Enable user scale</Name><Type>BOOL</Type><BitSize>1</BitSize>
+                  <Flags><Access>rw</Access></Flags>
+                </SubItem>
+                <SubItem>
+                  <SubIdx>2</SubIdx><Name>Presentation</Name><Type>DT0800EN03</Type><BitSize>3</BitSize>
+                  <Flags><Access>rw</Access></Flags>
+                </SubItem>
+                <SubItem>
+                  <SubIdx>17</SubIdx><Name>User scale offset</Name><Type>INT</Type><BitSize>16</BitSize>
+                  <Flags><Access>rw</Access></Flags>
+                </SubItem>
+              </DataType>
+            </DataTypes>
+            <Objects>
+              <Object>
+                <Index>#x6000</Index><Name>AI Inputs Ch.1</Name><Type>DT6000</Type>
+              </Object>
+              <Object>
+                <Index>#x8000</Index><Name>AI Settings Ch.1</Name><Type>DT8000</Type>
+                <Info>
+                  <SubItem><Name>Enable user scale</Name><Info><DefaultData>00</DefaultData></Info></SubItem>
+                  <SubItem><Name>User scale offset</Name><Info><DefaultData>9cff</DefaultData></Info></SubItem>
+                </Info>
+              </Object>
+              <Object>
+                <Index>#x8010</Index><Name>AI Settings Ch.2</Name><Type>DT8000</Type>
+              </Object>
+              <Object>
+                <Index>#x8020</Index><Name>Filter time</Name><Type>UINT</Type><BitSize>16</BitSize>
+                <Info><DefaultData>e803</DefaultData></Info>
+                <Flags><Access>rw</Access></Flags>
+              </Object>
+            </Objects>
+          </Dictionary>
+        </Profile>
+        <TxPdo Fixed="1" Sm="3">
+          <Index>#x1a00</Index>
+          <Name>AI Standard Channel 1</Name>
+          <Exclude>#x1a01</Exclude>
+          <Entry><Index>#x6000</Index><SubIndex>1</SubIndex><BitLen>1</BitLen><Name>Status__Underrange</Name><DataType>BOOL</DataType></Entry>
+          <Entry><Index>#x6000</Index><SubIndex>2</SubIndex><BitLen>1</BitLen><Name>Status__Overrange</Name><DataType>BOOL</DataType></Entry>
+          <Entry><Index>#x6000</Index><SubIndex>3</SubIndex><BitLen>2</BitLen><Name>Status__Limit 1</Name><DataType>BIT2</DataType></Entry>
+          <Entry><Index>#x0</Index><BitLen>12</BitLen></Entry>
+          <Entry><Index>#x6000</Index><SubIndex>17</SubIndex><BitLen>16</BitLen><Name>Value</Name><DataType>INT</DataType></Entry>
+        </TxPdo>
+        <TxPdo Fixed="1">
+          <Index>#x1a01</Index>
+          <Name>AI Compact Channel 1</Name>
+          <Exclude>#x1a00</Exclude>
+          <Entry><Index>#x6000</Index><SubIndex>17</SubIndex><BitLen>16</BitLen><Name>Value</Name><DataType>INT</DataType></Entry>
+        </TxPdo>
+        <TxPdo Fixed="1" Sm="3">
+          <Index>#x1a02</Index>
+          <Name>AI Standard Channel 2</Name>
+          <Exclude>#x1a03</Exclude>
+          <Entry><Index>#x6010</Index><SubIndex>1</SubIndex><BitLen>1</BitLen><Name>Status__Underrange</Name><DataType>BOOL</DataType></Entry>
+          <Entry><Index>#x6010</Index><SubIndex>2</SubIndex><BitLen>1</BitLen><Name>Status__Overrange</Name><DataType>BOOL</DataType></Entry>
+          <Entry><Index>#x6010</Index><SubIndex>3</SubIndex><BitLen>2</BitLen><Name>Status__Limit 1</Name><DataType>BIT2</DataType></Entry>
+          <Entry><Index>#x0</Index><BitLen>12</BitLen></Entry>
+          <Entry><Index>#x6010</Index><SubIndex>17</SubIndex><BitLen>16</BitLen><Name>Value</Name><DataType>INT</DataType></Entry>
+        </TxPdo>
+        <TxPdo Fixed="1">
+          <Index>#x1a03</Index>
+          <Name>AI Compact Channel 2</Name>
+          <Exclude>#x1a02</Exclude>
+          <Entry><Index>#x6010</Index><SubIndex>17</SubIndex><BitLen>16</BitLen><Name>Value</Name><DataType>INT</DataType></Entry>
+        </TxPdo>
+        <RxPdo Sm="2">
+          <Index>#x1600</Index>
+          <Name>Gain</Name>
+          <Entry><Index>#x7000</Index><SubIndex>1</SubIndex><BitLen>32</BitLen><Name>Gain</Name><DataType>REAL</DataType></Entry>
+        </RxPdo>
+        <VendorSpecific>
+          <TwinCAT>
+            <AlternativeSmMapping Default="1">
+              <Name>Standard</Name>
+              <Sm No="2"><Pdo>#x1600</Pdo></Sm>
+              <Sm No="3"><Pdo>#x1a00</Pdo><Pdo>#x1a02</Pdo></Sm>
+            </AlternativeSmMapping>
+            <AlternativeSmMapping>
+              <Name>Compact</Name>
+              <Sm No="2"><Pdo>#x1600</Pdo></Sm>
+              <Sm No="3"><Pdo>#x1a01</Pdo><Pdo>#x1a03</Pdo></Sm>
+            </AlternativeSmMapping>
+          </TwinCAT>
+        </VendorSpecific>
+      </Device>
+    </Devices>
+  </Descriptions>
+</EtherCATInfo>"##;
+
+    pub fn esi() -> EsiFile {
+        EsiFile::from_xml(&parse(ESI).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_device() {
+        let esi = esi();
+        assert_eq!(esi.vendor_id, 2);
+        assert_eq!(esi.devices.len(), 2);
+
+        let revisions = esi.revisions("el3002");
+        assert_eq!(revisions.len(), 2);
+        let device = revisions[0];
+        assert_eq!(device.revision, 0x0016_0000);
+        assert_eq!(device.product_code, 0x0bba_3052);
+        assert_eq!(device.name, "EL3002 2Ch. Ana. Input +/-10V");
+
+        assert_eq!(device.txpdos.len(), 4);
+        assert_eq!(device.rxpdos.len(), 1);
+        let pdo = &device.txpdos[0];
+        assert_eq!(pdo.index, 0x1A00);
+        assert_eq!(pdo.sm, Some(3));
+        assert_eq!(pdo.excludes, vec![0x1A01]);
+        assert_eq!(pdo.bit_len(), 32);
+        assert!(pdo.entries[3].is_padding());
+
+        assert_eq!(device.mappings.len(), 2);
+        assert!(device.mappings[0].default);
+        assert_eq!(device.mappings[1].pdos, vec![0x1600, 0x1A01, 0x1A03]);
+    }
+
+    #[test]
+    fn test_objects() {
+        let esi = esi();
+        let objects = &esi.revisions("EL3002")[0].objects;
+        // the input object is not configuration
+        assert_eq!(objects.len(), 3);
+        assert_eq!(objects[0].index, 0x8000);
+        assert_eq!(objects[0].data_type, "DT8000");
+
+        let items = &objects[0].items;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].default, Some(0));
+        assert_eq!(items[1].base_type, "BIT3");
+        assert_eq!(items[1].enum_type.as_deref(), Some("DT0800EN03"));
+        assert_eq!(items[1].enum_values.len(), 2);
+        assert_eq!(items[2].sub_index, 0x11);
+        assert_eq!(items[2].default, Some(0xff9c));
+
+        let filter_time = &objects[2].items[0];
+        assert_eq!(filter_time.sub_index, 0);
+        assert_eq!(filter_time.base_type, "UINT");
+        assert_eq!(filter_time.default, Some(1000));
+    }
+
+    #[test]
+    fn test_assignments_without_mappings() {
+        let mut device = esi().revisions("EL3002")[0].clone();
+        device.mappings.clear();
+        let assignments = device.assignments();
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].pdos, vec![0x1A00, 0x1A02, 0x1600]);
+    }
+}
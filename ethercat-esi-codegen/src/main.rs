@@ -0,0 +1,110 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::anyhow;
+use esi::EsiFile;
+
+pub mod cli;
+pub mod codegen;
+pub mod esi;
+pub mod xml;
+
+fn main() {
+    let matches = cli::cli().get_matches();
+    let result = match matches.subcommand() {
+        Some(("ls", sub_matches)) => {
+            let file = sub_matches
+                .get_one::<String>("ESI")
+                .expect("ESI is required");
+            ls(file)
+        }
+        Some(("generate", sub_matches)) => {
+            let file = sub_matches
+                .get_one::<String>("ESI")
+                .expect("ESI is required");
+            let device = sub_matches
+                .get_one::<String>("device")
+                .expect("device is required");
+            let out = sub_matches.get_one::<String>("out");
+            generate(file, device, out.map(Path::new))
+        }
+        _ => unreachable!("subcommand is required"),
+    };
+    if let Err(e) = result {
+        eprintln!("{e:?}");
+        std::process::exit(1);
+    }
+}
+
+fn read_esi(file: &str) -> Result<EsiFile, anyhow::Error> {
+    let bytes = std::fs::read(file).map_err(|e| anyhow!("Failed to read {}: {}", file, e))?;
+    // most ESI files are ISO 8859-1, whose bytes are the first 256 code points
+    let content = String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect());
+    let root = xml::parse(&content).map_err(|e| anyhow!("Failed to parse {}: {}", file, e))?;
+    EsiFile::from_xml(&root)
+}
+
+fn ls(file: &str) -> Result<(), anyhow::Error> {
+    let esi = read_esi(file)?;
+    println!("Vendor {:#x} {}", esi.vendor_id, esi.vendor_name);
+    for device in &esi.devices {
+        println!(
+            "{:<16} product {:#010x} revision {:#010x} {}",
+            device.type_name, device.product_code, device.revision, device.name
+        );
+    }
+    Ok(())
+}
+
+fn generate(file: &str, type_name: &str, out: Option<&Path>) -> Result<(), anyhow::Error> {
+    let esi = read_esi(file)?;
+    let source = Path::new(file).file_name().map_or_else(
+        || file.to_string(),
+        |name| name.to_string_lossy().to_string(),
+    );
+    let code = format_code(codegen::generate(&esi, type_name, &source)?);
+
+    match out {
+        Some(dir) => {
+            let path = dir.join(format!("{}.rs", codegen::module_name(type_name)));
+            std::fs::write(&path, code)
+                .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{code}"),
+    }
+    eprintln!("Register the device in `device_from_subdevice_identity_tuple`:");
+    eprintln!(
+        "    {}",
+        codegen::registration(type_name, esi.revisions(type_name).len())
+    );
+    Ok(())
+}
+
+/// Runs the code through rustfmt, returns it unformatted if rustfmt is not available
+fn format_code(code: String) -> String {
+    let child = Command::new("rustfmt")
+        .args(["--edition", "2024", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        eprintln!("rustfmt not found, the code is not formatted");
+        return code;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        if stdin.write_all(code.as_bytes()).is_err() {
+            return code;
+        }
+    }
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => String::from_utf8(output.stdout).unwrap_or(code),
+        _ => {
+            eprintln!("rustfmt failed, the code is not formatted");
+            code
+        }
+    }
+}
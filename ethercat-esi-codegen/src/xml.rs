@@ -0,0 +1,268 @@
+//! Minimal XML reader for ESI files
+//!
+//! ESI files only use a small part of XML: elements, attributes, text, comments,
+//! CDATA sections and the XML declaration. DTDs and processing instructions are skipped.
+//! Namespace prefixes are kept in the names.
+
+use anyhow::{anyhow, bail};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Self>,
+    /// Concatenated text content, trimmed
+    pub text: String,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// First child with the given name
+    pub fn child(&self, name: &str) -> Option<&Self> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// All children with the given name
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Self> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Text of the first child with the given name
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.as_str())
+    }
+
+    /// Follows a path of child names, e.g. `["Descriptions", "Devices"]`
+    pub fn path(&self, path: &[&str]) -> Option<&Self> {
+        path.iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+}
+
+/// Parses a document and returns its root element
+pub fn parse(input: &str) -> Result<Element, anyhow::Error> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if parser.pos < input.len() {
+        bail!(
+            "Unexpected content after the root element at byte {}",
+            parser.pos
+        );
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips until after `end`
+    fn skip_past(&mut self, end: &str) -> Result<&str, anyhow::Error> {
+        let start = self.pos;
+        let offset = self
+            .rest()
+            .find(end)
+            .ok_or_else(|| anyhow!("Missing {:?} for the block at byte {}", end, start))?;
+        self.pos += offset + end.len();
+        Ok(&self.input[start..start + offset])
+    }
+
+    /// Skips whitespace, comments, the declaration, processing instructions and DTDs
+    fn skip_misc(&mut self) -> Result<(), anyhow::Error> {
+        // byte order mark
+        if self.rest().starts_with('\u{feff}') {
+            self.pos += '\u{feff}'.len_utf8();
+        }
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&str, anyhow::Error> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or_else(|| self.rest().len());
+        if len == 0 {
+            bail!("Expected a name at byte {}", start);
+        }
+        self.pos += len;
+        Ok(&self.input[start..start + len])
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), anyhow::Error> {
+        if !self.rest().starts_with(token) {
+            bail!("Expected {:?} at byte {}", token, self.pos);
+        }
+        self.pos += token.len();
+        Ok(())
+    }
+
+    fn element(&mut self) -> Result<Element, anyhow::Error> {
+        self.expect("<")?;
+        let mut element = Element {
+            name: self.name()?.to_string(),
+            ..Element::default()
+        };
+
+        // attributes
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = if self.rest().starts_with('"') {
+                "\""
+            } else {
+                "'"
+            };
+            self.expect(quote)?;
+            let value = unescape(self.skip_past(quote)?)?;
+            element.attributes.push((key, value));
+        }
+
+        // content
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                bail!("Element <{}> is not closed", element.name);
+            } else if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    bail!("Expected </{}> but found </{}>", element.name, name);
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                break;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                text.push_str(self.skip_past("]]>")?);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                element.children.push(self.element()?);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                text.push_str(&unescape(&rest[..len])?);
+                self.pos += len;
+            }
+        }
+        element.text = text.trim().to_string();
+        Ok(element)
+    }
+}
+
+/// Replaces the predefined entities and character references
+fn unescape(raw: &str) -> Result<String, anyhow::Error> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| anyhow!("Unterminated entity in {:?}", raw))?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = entity.strip_prefix("#x").map_or_else(
+                    || {
+                        entity
+                            .strip_prefix('#')
+                            .and_then(|decimal| decimal.parse().ok())
+                    },
+                    |hex| u32::from_str_radix(hex, 16).ok(),
+                );
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| anyhow!("Unknown entity &{};", entity))?
+            }
+        };
+        out.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let root = parse(
+            r#"<?xml version="1.0" encoding="ISO8859-1"?>
+            <!-- comment -->
+            <Root Version='1.2'>
+                <Name LcId="1033">A &amp; B &#x41;</Name>
+                <!-- comment -->
+                <Empty/>
+                <Data><![CDATA[<raw>]]></Data>
+                <Name LcId="1031">Zweiter</Name>
+            </Root>"#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "Root");
+        assert_eq!(root.attribute("Version"), Some("1.2"));
+        assert_eq!(root.children.len(), 4);
+        assert_eq!(root.child_text("Name"), Some("A & B A"));
+        assert_eq!(root.children("Name").count(), 2);
+        assert_eq!(root.child("Empty").unwrap().children.len(), 0);
+        assert_eq!(root.child_text("Data"), Some("<raw>"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("<Root><Child></Root>").is_err());
+        assert!(parse("<Root>").is_err());
+        assert!(parse("<Root/><Second/>").is_err());
+        assert!(parse("<Root>&unknown;</Root>").is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Distributed clocks (DC) synchronisation
+//!
+//! Devices which support it latch their process data on the SYNC0 pulse of their DC unit
+//! instead of whenever the frame passes by, so cycle jitter of the MainDevice doesn't reach the IO.
+//! See ETG.1000.4 (DC registers) and ETG.1020 (sync manager parameters `0x1C32`/`0x1C33`).
+
+use std::ops::Deref;
+use std::time::Duration;
+
+use ethercrab::{SubDevice, SubDeviceRef};
+
+use crate::devices::SubDeviceIdentityTuple;
+use crate::devices::el2521::{
+    EL2521_IDENTITY_0000_A, EL2521_IDENTITY_0000_B, EL2521_IDENTITY_0024_A,
+};
+use crate::devices::el5152::EL5152_IDENTITY_A;
+use crate::devices::el7031::{EL7031_IDENTITY_A, EL7031_IDENTITY_B};
+use crate::devices::el7031_0030::EL7031_0030_IDENTITY_A;
+use crate::devices::el7041_0052::EL7041_0052_IDENTITY_A;
+
+/// Local copy of the system time, 64 bit
+pub const DC_SYSTEM_TIME: u16 = 0x0910;
+/// Deviation of the local copy of the system time from the reference clock
+pub const DC_SYSTEM_TIME_DIFFERENCE: u16 = 0x092C;
+/// Bit 0: write access to the cyclic unit by EtherCAT (0) or PDI (1)
+const DC_CYCLIC_UNIT_CONTROL: u16 = 0x0980;
+/// Bit 0: cyclic operation, bit 1: SYNC0, bit 2: SYNC1
+const DC_SYNC_ACTIVE: u16 = 0x0981;
+/// System time of the first SYNC0 pulse, 64 bit
+const DC_SYNC_START_TIME: u16 = 0x0990;
+/// SYNC0 cycle time in nanoseconds, 32 bit
+const DC_SYNC0_CYCLE_TIME: u16 = 0x09A0;
+
+const CYCLIC_OP_ENABLE: u8 = 0b0000_0001;
+const SYNC0_ACTIVATE: u8 = 0b0000_0010;
+
+/// Output sync manager parameters
+const SM_OUTPUT_PARAMETER: u16 = 0x1C32;
+/// Input sync manager parameters
+const SM_INPUT_PARAMETER: u16 = 0x1C33;
+/// Subindex of the synchronisation type in the sync manager parameters
+const SM_SYNC_MODE: u8 = 0x01;
+
+/// How a device synchronises its process data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcSyncMode {
+    /// Process data is latched when the frame passes by
+    FreeRun,
+    /// Process data is latched on the SYNC0 pulse
+    Sync0,
+}
+
+impl DcSyncMode {
+    /// Value of the synchronisation type in `0x1C32:01` and `0x1C33:01`
+    const fn sync_manager_mode(self) -> u16 {
+        match self {
+            Self::FreeRun => 0x00,
+            Self::Sync0 => 0x02,
+        }
+    }
+}
+
+/// Mode a device type runs in when DC is enabled
+///
+/// Only devices whose timing matters (motion, encoders, pulse outputs) are synchronised,
+/// everything else keeps running in free run.
+pub const fn dc_sync_mode_from_subdevice_identity_tuple(
+    subdevice_identity_tuple: SubDeviceIdentityTuple,
+) -> DcSyncMode {
+    match subdevice_identity_tuple {
+        EL7031_IDENTITY_A | EL7031_IDENTITY_B => DcSyncMode::Sync0,
+        EL7031_0030_IDENTITY_A => DcSyncMode::Sync0,
+        EL7041_0052_IDENTITY_A => DcSyncMode::Sync0,
+        EL5152_IDENTITY_A => DcSyncMode::Sync0,
+        EL2521_IDENTITY_0000_A | EL2521_IDENTITY_0000_B | EL2521_IDENTITY_0024_A => {
+            DcSyncMode::Sync0
+        }
+        _ => DcSyncMode::FreeRun,
+    }
+}
+
+/// Selects the synchronisation type of the sync managers through CoE, has to be done in PRE-OP
+pub async fn set_sync_manager_mode<S>(
+    subdevice: &SubDeviceRef<'_, S>,
+    mode: DcSyncMode,
+) -> Result<(), anyhow::Error>
+where
+    S: Deref<Target = SubDevice> + Sync,
+{
+    let value = mode.sync_manager_mode();
+    for index in [SM_OUTPUT_PARAMETER, SM_INPUT_PARAMETER] {
+        subdevice
+            .sdo_write(index, SM_SYNC_MODE, value)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "[{}::set_sync_manager_mode] Failed to write {:#06x}:{:02x}: {}",
+                    module_path!(),
+                    index,
+                    SM_SYNC_MODE,
+                    e
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// Starts the SYNC0 pulse generation of the DC unit, has to be done in PRE-OP
+///
+/// The first pulse is `start_delay` after the current system time of the device,
+/// rounded to a whole number of periods so all devices pulse at the same instant.
+pub async fn configure_sync0<S>(
+    subdevice: &SubDeviceRef<'_, S>,
+    start_delay: Duration,
+    period: Duration,
+) -> Result<(), anyhow::Error>
+where
+    S: Sync,
+{
+    let period_ns = u32::try_from(period.as_nanos())?;
+
+    // stop the cyclic unit while it is configured
+    subdevice.register_write(DC_SYNC_ACTIVE, 0u8).await?;
+    subdevice
+        .register_write(DC_CYCLIC_UNIT_CONTROL, 0u8)
+        .await?;
+
+    let device_time: u64 = subdevice.register_read(DC_SYSTEM_TIME).await?;
+    let start_time = sync0_start_time(device_time, start_delay, period);
+
+    subdevice
+        .register_write(DC_SYNC_START_TIME, start_time)
+        .await?;
+    subdevice
+        .register_write(DC_SYNC0_CYCLE_TIME, period_ns)
+        .await?;
+    subdevice
+        .register_write(DC_SYNC_ACTIVE, SYNC0_ACTIVATE | CYCLIC_OP_ENABLE)
+        .await?;
+    Ok(())
+}
+
+/// Deviation of the local system time from the reference clock in nanoseconds
+///
+/// Positive if the local clock is ahead.
+pub async fn read_system_time_difference<S>(
+    subdevice: &SubDeviceRef<'_, S>,
+) -> Result<i64, anyhow::Error>
+where
+    S: Sync,
+{
+    let raw: u32 = subdevice.register_read(DC_SYSTEM_TIME_DIFFERENCE).await?;
+    Ok(decode_system_time_difference(raw))
+}
+
+/// `0x092C` is sign and magnitude, bit 31 is set if the local copy is smaller than the received time
+pub const fn decode_system_time_difference(raw: u32) -> i64 {
+    let magnitude = (raw & 0x7FFF_FFFF) as i64;
+    if raw & 0x8000_0000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// System time of the first SYNC0 pulse
+pub fn sync0_start_time(device_time: u64, start_delay: Duration, period: Duration) -> u64 {
+    let period_ns = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX).max(1);
+    let start_delay_ns = u64::try_from(start_delay.as_nanos()).unwrap_or(u64::MAX);
+    device_time.saturating_add(start_delay_ns) / period_ns * period_ns
+}
+
+/// Time between the last SYNC0 pulse and `dc_system_time`
+///
+/// SYNC0 pulses are at whole multiples of `period`, see [`sync0_start_time`].
+pub fn cycle_start_offset(dc_system_time: u64, period: Duration) -> Duration {
+    let period_ns = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX).max(1);
+    Duration::from_nanos(dc_system_time % period_ns)
+}
+
+/// Time from `dc_system_time` until the next cycle should start, `shift` after the next SYNC0 pulse
+pub fn next_cycle_wait(dc_system_time: u64, period: Duration, shift: Duration) -> Duration {
+    period - cycle_start_offset(dc_system_time, period) + shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_system_time_difference() {
+        assert_eq!(decode_system_time_difference(0x0000_0064), 100);
+        assert_eq!(decode_system_time_difference(0x8000_0064), -100);
+        assert_eq!(decode_system_time_difference(0x8000_0000), 0);
+    }
+
+    #[test]
+    fn test_cycle_timing() {
+        let period = Duration::from_micros(700);
+        let start = sync0_start_time(1_000_123_456, Duration::from_millis(100), period);
+        assert_eq!(start % 700_000, 0);
+        assert!(start <= 1_100_123_456 && start > 1_100_123_456 - 700_000);
+
+        // frame passed the reference 100µs after SYNC0
+        let time = 7_000_000 + 100_000;
+        assert_eq!(cycle_start_offset(time, period), Duration::from_micros(100));
+        assert_eq!(
+            next_cycle_wait(time, period, Duration::from_micros(50)),
+            Duration::from_micros(650)
+        );
+    }
+
+    #[test]
+    fn test_sync_modes() {
+        assert_eq!(
+            dc_sync_mode_from_subdevice_identity_tuple(EL7031_IDENTITY_A),
+            DcSyncMode::Sync0
+        );
+        assert_eq!(
+            dc_sync_mode_from_subdevice_identity_tuple((0x2, 0x1, 0x0)),
+            DcSyncMode::FreeRun
+        );
+        assert_eq!(DcSyncMode::Sync0.sync_manager_mode(), 2);
+    }
+}
@@ -1,161 +1,9 @@
 use ethercrab::{SubDevice, SubDeviceRef};
-use std::fmt;
+use std::ops::Deref;
 
 const DIAGNOSIS_HISTORY_INDEX: u16 = 0x10f3;
 const NEWEST_MESSAGE: u8 = 02;
 const NEW_MESSAGE_AVAILABLE: u8 = 04;
-const DIAG_MESSAGE_LENGTH: usize = 26;
-
-pub struct SubdeviceDiagnosisEntry {
-    pub diag_code: u32,
-    pub flags: u16,
-    pub text_id: u16,
-    pub timestamp: u64,
-    pub data_type_p1: u16,
-    pub p1: u8,
-    pub flags_p2: u16,
-    pub p2: [u8; 5],
-}
-
-impl fmt::Display for SubdeviceDiagnosisEntry {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "SubdeviceDiagnosisEntry {{ diag_code: {}, flags: {}, text_id: {}, timestamp: {}, data_type_p1: {}, p1: {}, flags_p2: {}, p2: {:?} }}",
-            self.diag_code,
-            self.flags,
-            self.text_id,
-            self.timestamp,
-            self.data_type_p1,
-            self.p1,
-            self.flags_p2,
-            self.p2
-        )
-    }
-}
-
-impl SubdeviceDiagnosisEntry {
-    pub fn to_pretty_string(&self) -> String {
-        // Decode message type
-        let msg_type = match self.flags {
-            0x0000 => "Info",
-            0x0001 => "Warning",
-            0x0002 => "Error",
-            _ => "Unknown",
-        };
-
-        // Decode data type of P1
-        let p1_type = match self.data_type_p1 {
-            0x0005 => "UINT8",
-            _ => "Unknown",
-        };
-
-        // Decode data type of P2
-        let p2_type = match self.flags_p2 {
-            0x0005 => "BYTE[5]",
-            _ => "Unknown",
-        };
-
-        // Format P2 bytes as hex list
-        let p2_hex: Vec<String> = self.p2.iter().map(|b| format!("0x{:02X}", b)).collect();
-
-        format!(
-            "Diagnosis Entry:\n\
-             ├─ diag_code:     0x{:08X}\n\
-             ├─ message type:  {} (0x{:04X})\n\
-             ├─ text_id:       {}{}\n\
-             ├─ timestamp:     {} (64-bit)\n\
-             ├─ P1 type:       {} (0x{:04X})\n\
-             ├─ P1 value:      {}\n\
-             ├─ P2 type:       {} (0x{:04X})\n\
-             └─ P2 data:       [{}]",
-            self.diag_code,
-            msg_type,
-            self.flags,
-            self.text_id,
-            if self.text_id == 0 { " (no text)" } else { "" },
-            self.timestamp,
-            // P1
-            p1_type,
-            self.data_type_p1,
-            self.p1,
-            // P2
-            p2_type,
-            self.flags_p2,
-            p2_hex.join(", ")
-        )
-    }
-}
-
-/*
-    Could be tested with this:
-        00 E8 00 A0 02 00 00 00 5F FB D5 A5 A6 0B 00 00 05 00 11 05 10 08 2A 00 2A 00
-*/
-
-pub fn convert_raw_diagnosis_bytes(
-    message: [u8; DIAG_MESSAGE_LENGTH],
-) -> Result<SubdeviceDiagnosisEntry, anyhow::Error> {
-    let diag_code_bytes = [message[0], message[1], message[2], message[3]];
-    let diag_code = u32::from_le_bytes(diag_code_bytes);
-
-    let flags_code_bytes = [message[4], message[5]];
-    let flags = u16::from_le_bytes(flags_code_bytes);
-
-    let text_id_bytes = [message[6], message[7]];
-    let text_id = u16::from_le_bytes(text_id_bytes);
-
-    let timestamp_bytes = [
-        message[8],
-        message[9],
-        message[10],
-        message[11],
-        message[12],
-        message[13],
-        message[14],
-        message[15],
-    ];
-    let timestamp = u64::from_le_bytes(timestamp_bytes);
-
-    let data_type_p1_bytes = [message[16], message[17]];
-    let data_type_p1 = u16::from_le_bytes(data_type_p1_bytes);
-
-    // TODO: Find the Constants
-    // 0x0005 Apparently Means p1 variable is u8
-    // For now we only handle u8
-    let p1 = match data_type_p1 {
-        0x0005 => message[18],
-        _ => 0,
-    };
-
-    if p1 == 0 {
-        return Err(anyhow::anyhow!(
-            "convert_raw_diagnosis_bytes: Datatype_p1 is unknown {:?}",
-            data_type_p1
-        ));
-    }
-
-    // flags_p2 is packed, whatever thats supposed to mean
-    let flags_p2_bytes = [message[19], message[20]];
-    let flags_p2 = u16::from_le_bytes(flags_p2_bytes);
-
-    let p2 = [
-        message[21],
-        message[22],
-        message[23],
-        message[24],
-        message[25],
-    ];
-    return Ok(SubdeviceDiagnosisEntry {
-        diag_code,
-        flags,
-        text_id,
-        timestamp,
-        data_type_p1,
-        p1,
-        flags_p2,
-        p2,
-    });
-}
 
 /// Expects the SubdeviceRef to be the Coupler or any other device with a diag history index
 pub async fn get_most_recent_diagnosis_message(
@@ -181,10 +29,7 @@ pub async fn get_most_recent_diagnosis_message(
         return None;
     }
 
-    let newest_message_index = device
-        .sdo_read::<u8>(DIAGNOSIS_HISTORY_INDEX, NEWEST_MESSAGE)
-        .await;
-    let newest_message_index = match newest_message_index {
+    let newest_message_index = match read_newest_message_index(device).await {
         Ok(m) => m,
         Err(e) => {
             tracing::error!(
@@ -199,31 +44,530 @@ pub async fn get_most_recent_diagnosis_message(
         "get_most_recent_diagnosis_message: Reading newest message at index: {}",
         newest_message_index
     );
-    let res = device
-        .sdo_read::<[u8; DIAG_MESSAGE_LENGTH]>(DIAGNOSIS_HISTORY_INDEX, newest_message_index)
-        .await;
-    let message: [u8; DIAG_MESSAGE_LENGTH] = match res {
-        Ok(res) => res,
-        Err(_) => {
-            tracing::error!("get_most_recent_diagnosis_message: Failed to read Diagnosis Message");
-            return None;
-        }
-    };
-    let message = convert_raw_diagnosis_bytes(message);
-    let message = match message {
-        Ok(msg) => msg,
+    let message = match read_diagnosis_message(device, newest_message_index).await {
+        Ok(message) => message,
         Err(e) => {
             tracing::error!(
-                "get_most_recent_diagnosis_message: Failed to convert Diagnosis Message bytes to struct {:?}",
+                "get_most_recent_diagnosis_message: Failed to read Diagnosis Message {:?}",
                 e
             );
             return None;
         }
     };
 
-    tracing::info!(
-        "get_most_recent_diagnosis_message: {}",
-        message.to_pretty_string()
+    let text = format!(
+        "{:?}: {}",
+        message.severity(),
+        message.text(device.identity().vendor_id)
     );
-    return Some(message.to_pretty_string());
+    tracing::info!("get_most_recent_diagnosis_message: {}", text);
+    Some(text)
+}
+
+/// Subindex of the oldest message slot, the ring buffer goes up to `5 + maximum messages`
+const FIRST_MESSAGE: u8 = 6;
+/// Size of a ring buffer which fills every subindex up to `0xFF`
+const MAX_RING_BUFFER_SIZE: u8 = u8::MAX - FIRST_MESSAGE + 1;
+const MAXIMUM_MESSAGES: u8 = 1;
+
+/// Upper bound of a message including its parameters, Beckhoff messages are around 30 bytes
+const MAX_DIAG_MESSAGE_LENGTH: usize = 256;
+
+/// Beckhoff text IDs are only resolved for subdevices of this vendor
+const BECKHOFF_VENDOR_ID: u32 = 0x2;
+
+/// Header of the diagnosis history object `0x10F3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosisHistoryInfo {
+    /// Size of the ring buffer
+    pub maximum_messages: u8,
+    /// Subindex of the newest message, `0` if there is none
+    pub newest_message: u8,
+}
+
+impl DiagnosisHistoryInfo {
+    /// `None` if the subdevice has no ring buffer
+    ///
+    /// Sizes beyond the last subindex are clamped to [`MAX_RING_BUFFER_SIZE`].
+    pub fn new(maximum_messages: u8, newest_message: u8) -> Option<Self> {
+        if maximum_messages == 0 {
+            return None;
+        }
+        Some(Self {
+            maximum_messages: maximum_messages.min(MAX_RING_BUFFER_SIZE),
+            newest_message,
+        })
+    }
+
+    /// Subindex of the message written before the one at `sub_index`
+    pub fn previous_message(&self, sub_index: u8) -> u8 {
+        if sub_index <= FIRST_MESSAGE {
+            let last = u16::from(FIRST_MESSAGE) + u16::from(self.maximum_messages.max(1)) - 1;
+            u8::try_from(last).unwrap_or(u8::MAX)
+        } else {
+            sub_index - 1
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosisSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Parameter of a diagnosis message, see ETG.1020 "Diagnosis History Object"
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosisParameter {
+    Bool(bool),
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Text(String),
+    /// Another text ID which is inserted into the message
+    TextId(u16),
+}
+
+impl DiagnosisParameter {
+    /// Formats the parameter for a `printf` like conversion of a text ID
+    fn format(&self, conversion: char, width: usize, zero_pad: bool) -> String {
+        let value = match (self, conversion) {
+            (Self::Signed(v), 'x' | 'X') => format!("{:X}", *v as u64),
+            (Self::Unsigned(v), 'x' | 'X') => format!("{v:X}"),
+            (Self::Bool(v), _) => u8::from(*v).to_string(),
+            (Self::Signed(v), _) => v.to_string(),
+            (Self::Unsigned(v), _) => v.to_string(),
+            (Self::Float(v), _) => v.to_string(),
+            (Self::Bytes(bytes), _) => bytes
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            (Self::Text(text), _) => text.clone(),
+            (Self::TextId(id), _) => beckhoff_text(*id)
+                .map_or_else(|| format!("Text ID 0x{id:04X}"), |text| text.to_string()),
+        };
+        let value = match conversion {
+            'x' => value.to_lowercase(),
+            _ => value,
+        };
+        match zero_pad {
+            true => format!("{value:0>width$}"),
+            false => format!("{value:>width$}"),
+        }
+    }
+}
+
+/// One message of the diagnosis history object `0x10F3`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosisMessage {
+    pub diag_code: u32,
+    /// Bits 0-3 are the severity, bit 4 marks a local timestamp, bits 8-15 are the number of parameters
+    pub flags: u16,
+    pub text_id: u16,
+    /// Nanoseconds of the DC system time, or of the local clock of the subdevice
+    pub timestamp: u64,
+    pub parameters: Vec<DiagnosisParameter>,
+}
+
+impl DiagnosisMessage {
+    pub fn parse(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        if bytes.len() < 16 {
+            return Err(anyhow::anyhow!(
+                "DiagnosisMessage::parse: Message is too short ({} bytes)",
+                bytes.len()
+            ));
+        }
+        let flags = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let parameter_count = usize::from(flags >> 8);
+
+        let mut parameters = vec![];
+        let mut rest = &bytes[16..];
+        // some subdevices don't fill in the parameter count, then everything left is parsed
+        while rest.len() >= 2 && (parameter_count == 0 || parameters.len() < parameter_count) {
+            let parameter_flags = u16::from_le_bytes([rest[0], rest[1]]);
+            rest = &rest[2..];
+            match parse_parameter(parameter_flags, rest) {
+                Some((parameter, length)) => {
+                    parameters.push(parameter);
+                    rest = &rest[length..];
+                }
+                // unknown parameter, the rest of the message can't be interpreted
+                None => break,
+            }
+        }
+
+        Ok(Self {
+            diag_code: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            flags,
+            text_id: u16::from_le_bytes([bytes[6], bytes[7]]),
+            timestamp: u64::from_le_bytes([
+                bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14],
+                bytes[15],
+            ]),
+            parameters,
+        })
+    }
+
+    pub const fn severity(&self) -> DiagnosisSeverity {
+        match self.flags & 0x000F {
+            0x0001 => DiagnosisSeverity::Warning,
+            0x0002 => DiagnosisSeverity::Error,
+            _ => DiagnosisSeverity::Info,
+        }
+    }
+
+    /// The timestamp is from the local clock of the subdevice instead of the DC system time
+    pub const fn has_local_timestamp(&self) -> bool {
+        self.flags & 0x0010 != 0
+    }
+
+    /// Slots of the ring buffer which were never written are zeroed
+    pub const fn is_empty(&self) -> bool {
+        self.diag_code == 0 && self.text_id == 0 && self.timestamp == 0
+    }
+
+    /// Readable message with the parameters filled in
+    ///
+    /// Text IDs are only known for Beckhoff subdevices, other messages are described by their diag code.
+    pub fn text(&self, vendor_id: u32) -> String {
+        let template = match vendor_id {
+            BECKHOFF_VENDOR_ID => beckhoff_text(self.text_id),
+            _ => None,
+        };
+        let (mut text, used) = match template {
+            Some(template) => format_template(template, &self.parameters),
+            None if self.text_id != 0 => (format!("Text ID 0x{:04X}", self.text_id), 0),
+            None => (describe_diag_code(self.diag_code), 0),
+        };
+        let unused: Vec<String> = self.parameters[used..]
+            .iter()
+            .map(|parameter| parameter.format('s', 0, false))
+            .collect();
+        if !unused.is_empty() {
+            text.push_str(&format!(" ({})", unused.join(", ")));
+        }
+        text
+    }
+}
+
+/// Parses the value of a parameter with its flags, returns the parameter and its length in bytes
+fn parse_parameter(flags: u16, bytes: &[u8]) -> Option<(DiagnosisParameter, usize)> {
+    let length_or_type = usize::from(flags & 0x0FFF);
+    let (parameter, length) = match flags >> 12 {
+        // CoE data type
+        0x0 => match length_or_type {
+            0x0001 => (DiagnosisParameter::Bool(*bytes.first()? != 0), 1),
+            0x0002 => (
+                DiagnosisParameter::Signed(i8::from_le_bytes(le(bytes)?).into()),
+                1,
+            ),
+            0x0003 => (
+                DiagnosisParameter::Signed(i16::from_le_bytes(le(bytes)?).into()),
+                2,
+            ),
+            0x0004 => (
+                DiagnosisParameter::Signed(i32::from_le_bytes(le(bytes)?).into()),
+                4,
+            ),
+            0x0005 => (
+                DiagnosisParameter::Unsigned(u8::from_le_bytes(le(bytes)?).into()),
+                1,
+            ),
+            0x0006 => (
+                DiagnosisParameter::Unsigned(u16::from_le_bytes(le(bytes)?).into()),
+                2,
+            ),
+            0x0007 => (
+                DiagnosisParameter::Unsigned(u32::from_le_bytes(le(bytes)?).into()),
+                4,
+            ),
+            0x0008 => (
+                DiagnosisParameter::Float(f32::from_le_bytes(le(bytes)?).into()),
+                4,
+            ),
+            0x0011 => (DiagnosisParameter::Float(f64::from_le_bytes(le(bytes)?)), 8),
+            0x0015 => (
+                DiagnosisParameter::Signed(i64::from_le_bytes(le(bytes)?)),
+                8,
+            ),
+            0x001B => (
+                DiagnosisParameter::Unsigned(u64::from_le_bytes(le(bytes)?)),
+                8,
+            ),
+            _ => return None,
+        },
+        // byte array
+        0x1 => (
+            DiagnosisParameter::Bytes(bytes.get(..length_or_type)?.to_vec()),
+            length_or_type,
+        ),
+        // ASCII string
+        0x2 => (
+            DiagnosisParameter::Text(
+                String::from_utf8_lossy(bytes.get(..length_or_type)?)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            ),
+            length_or_type,
+        ),
+        // Unicode string, length in bytes
+        0x3 => {
+            let units: Vec<u16> = bytes
+                .get(..length_or_type)?
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            (
+                DiagnosisParameter::Text(
+                    String::from_utf16_lossy(&units)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                ),
+                length_or_type,
+            )
+        }
+        0x4 => (
+            DiagnosisParameter::TextId(u16::from_le_bytes(le(bytes)?)),
+            2,
+        ),
+        _ => return None,
+    };
+    Some((parameter, length))
+}
+
+fn le<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+    bytes.get(..N)?.try_into().ok()
+}
+
+/// Replaces the `printf` like conversions of `template` with the parameters in order
+///
+/// Returns the text and the number of parameters which were used.
+fn format_template(template: &str, parameters: &[DiagnosisParameter]) -> (String, usize) {
+    let mut text = String::new();
+    let mut used = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            text.push('%');
+            continue;
+        }
+        let zero_pad = chars.next_if_eq(&'0').is_some();
+        let mut width = 0;
+        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            chars.next();
+        }
+        let conversion = chars.next().unwrap_or('s');
+        match parameters.get(used) {
+            Some(parameter) => {
+                text.push_str(&parameter.format(conversion, width, zero_pad));
+                used += 1;
+            }
+            None => text.push('?'),
+        }
+    }
+    (text, used)
+}
+
+/// Messages without text ID are described by the class of their diag code
+///
+/// Diag codes `0x1000` to `0xFFFF` are the CoE emergency error codes.
+fn describe_diag_code(diag_code: u32) -> String {
+    let class = match diag_code {
+        0x0000_0000 => "No error",
+        0x0000_1000..=0x0000_1FFF => "Generic error",
+        0x0000_2000..=0x0000_2FFF => "Current",
+        0x0000_3000..=0x0000_3FFF => "Voltage",
+        0x0000_4000..=0x0000_4FFF => "Temperature",
+        0x0000_5000..=0x0000_5FFF => "Device hardware",
+        0x0000_6000..=0x0000_6FFF => "Device software",
+        0x0000_7000..=0x0000_7FFF => "Additional modules",
+        0x0000_8000..=0x0000_8FFF => "Monitoring",
+        0x0000_9000..=0x0000_9FFF => "External error",
+        0x0000_F000..=0x0000_FEFF => "Additional functions",
+        0x0000_FF00..=0x0000_FFFF => "Device specific",
+        _ => "Manufacturer specific",
+    };
+    format!("{class} (diag code 0x{diag_code:08X})")
+}
+
+/// Texts of the Beckhoff text IDs, see the "Diagnostics" chapter of the terminal documentation
+const fn beckhoff_text(text_id: u16) -> Option<&'static str> {
+    let text = match text_id {
+        0x0001 => "No error",
+        0x0002 => "Communication established",
+        0x0003 => "Initialization: 0x%X, 0x%X, 0x%X",
+        0x1000 => "Information: 0x%X, 0x%X, 0x%X",
+        0x1012 => "EtherCAT state change Init - PreOp",
+        0x1021 => "EtherCAT state change PreOp - Init",
+        0x1024 => "EtherCAT state change PreOp - Safe-Op",
+        0x1042 => "EtherCAT state change SafeOp - PreOp",
+        0x1048 => "EtherCAT state change SafeOp - Op",
+        0x1084 => "EtherCAT state change Op - SafeOp",
+        0x1100 => "Detection of operation mode completed: 0x%X, %d",
+        0x1135 => "Cycle time o.k.: %d",
+        0x1157 => "Data manually saved (Idx: 0x%X, Subidx: 0x%X)",
+        0x1158 => "Data automatically saved (Idx: 0x%X, Subidx: 0x%X)",
+        0x1201 => "Communication re-established",
+        0x1300 => "Position set: %d, %d",
+        0x4000 => "Warning: 0x%X, 0x%X, 0x%X",
+        0x4001 => "Warning: 0x%X, 0x%X, 0x%X",
+        0x4101 => "Terminal-Overtemperature",
+        0x4102 => "Discrepancy in the PDO-Configuration",
+        0x8000 => "%s",
+        0x8001 => "Error: 0x%X, 0x%X, 0x%X",
+        0x8002 => "Communication aborted",
+        0x8003 => "Configuration error: 0x%X, 0x%X, 0x%X",
+        0x8100 => "Status word set: 0x%X, %d",
+        0x8101 => "Operation mode incompatible to PDO interface: 0x%X, %d",
+        0x8102 => "Invalid combination of Inputs and Outputs PDOs",
+        0x8103 => "No variable linkage",
+        0x8104 => "Terminal-Overtemperature",
+        0x8105 => "PD-Watchdog",
+        0x8135 => "Cycle time has to be a multiple of 125 µs",
+        0x8136 => "Configuration error: invalid sampling rate",
+        0x8137 => "Electronic type plate: CRC error",
+        0x8140 => "Sync error",
+        0x8141 => "Sync%X Interrupt lost",
+        0x8142 => "Sync Interrupt asynchronous",
+        0x8143 => "Jitter too big",
+        0x8200 => "Write access error: %d, %d",
+        0x8201 => "No communication to field-side (Auxiliary voltage missing)",
+        0x8300 => "Set position error: 0x%X, %d",
+        0x8301 => "Encoder increments not configured: 0x%X, %d",
+        0x8302 => "Amplifier off",
+        0x8303 => "Encoder power missing",
+        0x8304 => "Encoder communication error, channel: %X",
+        0x8400 => "Incorrect drive configuration: 0x%X, %d",
+        0x8401 => "Limiting of calibration velocity: %d, %d",
+        0x8402 => "Emergency ramp active: %d, %d",
+        0x8403 => "ADC Error",
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Reads the size of the ring buffer and the subindex of the newest message
+///
+/// Fails with a mailbox error if the subdevice has no diagnosis history,
+/// `None` if it reports a ring buffer without messages.
+pub async fn read_diagnosis_history_info<S: Deref<Target = SubDevice> + Sync>(
+    device: &SubDeviceRef<'_, S>,
+) -> Result<Option<DiagnosisHistoryInfo>, ethercrab::error::Error> {
+    let maximum_messages = device
+        .sdo_read::<u8>(DIAGNOSIS_HISTORY_INDEX, MAXIMUM_MESSAGES)
+        .await?;
+    let newest_message = read_newest_message_index(device).await?;
+    Ok(DiagnosisHistoryInfo::new(maximum_messages, newest_message))
+}
+
+/// Subindex of the newest message, `0` if there is none
+pub async fn read_newest_message_index<S: Deref<Target = SubDevice> + Sync>(
+    device: &SubDeviceRef<'_, S>,
+) -> Result<u8, ethercrab::error::Error> {
+    device
+        .sdo_read::<u8>(DIAGNOSIS_HISTORY_INDEX, NEWEST_MESSAGE)
+        .await
+}
+
+/// Reads and parses the message at `sub_index`, see [`DiagnosisHistoryInfo::previous_message`] to walk the history
+pub async fn read_diagnosis_message<S: Deref<Target = SubDevice> + Sync>(
+    device: &SubDeviceRef<'_, S>,
+    sub_index: u8,
+) -> Result<DiagnosisMessage, anyhow::Error> {
+    let bytes = device
+        .sdo_read::<heapless::Vec<u8, MAX_DIAG_MESSAGE_LENGTH>>(DIAGNOSIS_HISTORY_INDEX, sub_index)
+        .await?;
+    DiagnosisMessage::parse(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: [u8; 26] = [
+        0x00, 0xE8, 0x00, 0xA0, 0x02, 0x00, 0x00, 0x00, 0x5F, 0xFB, 0xD5, 0xA5, 0xA6, 0x0B, 0x00,
+        0x00, 0x05, 0x00, 0x11, 0x05, 0x10, 0x08, 0x2A, 0x00, 0x2A, 0x00,
+    ];
+
+    #[test]
+    fn test_parse_message() {
+        let message = DiagnosisMessage::parse(&MESSAGE).unwrap();
+        assert_eq!(message.diag_code, 0xA000_E800);
+        assert_eq!(message.severity(), DiagnosisSeverity::Error);
+        assert!(!message.has_local_timestamp());
+        assert_eq!(message.timestamp, 0x0000_0BA6_A5D5_FB5F);
+        assert_eq!(
+            message.parameters,
+            vec![
+                DiagnosisParameter::Unsigned(0x11),
+                DiagnosisParameter::Bytes(vec![0x08, 0x2A, 0x00, 0x2A, 0x00]),
+            ]
+        );
+        assert_eq!(
+            message.text(BECKHOFF_VENDOR_ID),
+            "Manufacturer specific (diag code 0xA000E800) (17, 08 2A 00 2A 00)"
+        );
+    }
+
+    #[test]
+    fn test_text_id() {
+        // Beckhoff "Data manually saved" with two UINT16 parameters and padding
+        let mut bytes = vec![0x00, 0xE8, 0x00, 0x00, 0x00, 0x02, 0x57, 0x11];
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&[0x06, 0x00, 0x00, 0x80, 0x06, 0x00, 0x11, 0x00, 0x00, 0x00]);
+        let message = DiagnosisMessage::parse(&bytes).unwrap();
+        assert_eq!(message.severity(), DiagnosisSeverity::Info);
+        assert_eq!(message.parameters.len(), 2);
+        assert_eq!(
+            message.text(BECKHOFF_VENDOR_ID),
+            "Data manually saved (Idx: 0x8000, Subidx: 0x11)"
+        );
+        assert_eq!(message.text(0x539), "Text ID 0x1157 (32768, 17)");
+    }
+
+    #[test]
+    fn test_previous_message() {
+        let info = DiagnosisHistoryInfo {
+            maximum_messages: 50,
+            newest_message: 6,
+        };
+        assert_eq!(info.previous_message(7), 6);
+        assert_eq!(info.previous_message(6), 55);
+    }
+
+    #[test]
+    fn test_previous_message_full_ring_buffer() {
+        let info = DiagnosisHistoryInfo::new(255, 6).unwrap();
+        assert_eq!(info.maximum_messages, 250);
+        assert_eq!(info.previous_message(6), 255);
+        assert_eq!(info.previous_message(255), 254);
+
+        // constructed without `new`
+        let info = DiagnosisHistoryInfo {
+            maximum_messages: 255,
+            newest_message: 6,
+        };
+        assert_eq!(info.previous_message(6), 255);
+    }
+
+    #[test]
+    fn test_empty_ring_buffer() {
+        assert_eq!(DiagnosisHistoryInfo::new(0, 0), None);
+
+        // constructed without `new`
+        let info = DiagnosisHistoryInfo {
+            maximum_messages: 0,
+            newest_message: 0,
+        };
+        assert_eq!(info.previous_message(6), 6);
+    }
 }
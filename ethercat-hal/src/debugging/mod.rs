@@ -1 +1,2 @@
 pub mod diagnosis_history;
+pub mod sdo_info;
@@ -0,0 +1,405 @@
+//! SDO information service (CoE service 8) to browse the object dictionary of a subdevice
+//!
+//! ethercrab only implements SDO upload and download, so the requests are written to the
+//! mailbox sync managers directly. Must not run concurrently with other mailbox requests
+//! to the same subdevice.
+
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use ethercrab::error::CoeAbortCode;
+use ethercrab::{Command, EtherCrabWireRead, MainDevice, SubDevice, SubDeviceRef};
+
+/// Configuration of sync manager 0, the next ones follow every 8 bytes
+const SYNC_MANAGER_CONFIG: u16 = 0x0800;
+const SYNC_MANAGER_COUNT: usize = 4;
+
+const MAILBOX_HEADER_LENGTH: usize = 6;
+const MAILBOX_TYPE_COE: u8 = 0x03;
+const COE_SERVICE_SDO_INFO: u16 = 0x08;
+
+const GET_OD_LIST: u8 = 0x01;
+const GET_OBJECT_DESCRIPTION: u8 = 0x03;
+const GET_ENTRY_DESCRIPTION: u8 = 0x05;
+const SDO_INFO_ERROR: u8 = 0x07;
+
+/// List type of [`GET_OD_LIST`] for all objects
+const ALL_OBJECTS: u16 = 0x0001;
+
+/// How long a subdevice may take to answer one fragment
+const MAILBOX_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Description of an object of the object dictionary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectDescription {
+    pub index: u16,
+    pub data_type: u16,
+    pub max_sub_index: u8,
+    /// `0x07` variable, `0x08` array, `0x09` record
+    pub object_code: u8,
+    pub name: String,
+}
+
+/// Description of one subindex of an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryDescription {
+    pub index: u16,
+    pub sub_index: u8,
+    pub data_type: u16,
+    pub bit_length: u16,
+    /// Bits 0-2 read access in PreOp, SafeOp, Op, bits 3-5 write access in PreOp, SafeOp, Op
+    pub access: u16,
+    pub name: String,
+}
+
+impl EntryDescription {
+    pub const fn readable(&self) -> bool {
+        self.access & 0b000_111 != 0
+    }
+
+    /// Writable in OP, where the runtime requests are done
+    pub const fn writable_in_op(&self) -> bool {
+        self.access & 0b100_000 != 0
+    }
+}
+
+/// Name of a CoE base data type
+pub const fn data_type_name(data_type: u16) -> Option<&'static str> {
+    let name = match data_type {
+        0x0001 => "BOOL",
+        0x0002 => "SINT",
+        0x0003 => "INT",
+        0x0004 => "DINT",
+        0x0005 => "USINT",
+        0x0006 => "UINT",
+        0x0007 => "UDINT",
+        0x0008 => "REAL",
+        0x0009 => "STRING",
+        0x000A => "ARRAY OF BYTE",
+        0x000B => "ARRAY OF UINT",
+        0x0011 => "LREAL",
+        0x0015 => "LINT",
+        0x001B => "ULINT",
+        0x0030..=0x0037 => "BIT",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Indices of all objects in the object dictionary
+pub async fn read_object_list<S: Deref<Target = SubDevice> + Sync>(
+    maindevice: &MainDevice<'_>,
+    device: &SubDeviceRef<'_, S>,
+) -> Result<Vec<u16>, anyhow::Error> {
+    let response = sdo_info(
+        maindevice,
+        device.configured_address(),
+        GET_OD_LIST,
+        &ALL_OBJECTS.to_le_bytes(),
+    )
+    .await?;
+    // the response starts with the list type
+    Ok(response
+        .get(2..)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|index| u16::from_le_bytes([index[0], index[1]]))
+        .collect())
+}
+
+pub async fn read_object_description<S: Deref<Target = SubDevice> + Sync>(
+    maindevice: &MainDevice<'_>,
+    device: &SubDeviceRef<'_, S>,
+    index: u16,
+) -> Result<ObjectDescription, anyhow::Error> {
+    let response = sdo_info(
+        maindevice,
+        device.configured_address(),
+        GET_OBJECT_DESCRIPTION,
+        &index.to_le_bytes(),
+    )
+    .await?;
+    parse_object_description(&response)
+}
+
+pub async fn read_entry_description<S: Deref<Target = SubDevice> + Sync>(
+    maindevice: &MainDevice<'_>,
+    device: &SubDeviceRef<'_, S>,
+    index: u16,
+    sub_index: u8,
+) -> Result<EntryDescription, anyhow::Error> {
+    let [index_low, index_high] = index.to_le_bytes();
+    // value info 0: only the description, no unit, default, minimum or maximum
+    let response = sdo_info(
+        maindevice,
+        device.configured_address(),
+        GET_ENTRY_DESCRIPTION,
+        &[index_low, index_high, sub_index, 0],
+    )
+    .await?;
+    parse_entry_description(&response)
+}
+
+fn parse_object_description(data: &[u8]) -> Result<ObjectDescription, anyhow::Error> {
+    if data.len() < 6 {
+        return Err(anyhow::anyhow!(
+            "parse_object_description: Response is too short ({} bytes)",
+            data.len()
+        ));
+    }
+    Ok(ObjectDescription {
+        index: u16::from_le_bytes([data[0], data[1]]),
+        data_type: u16::from_le_bytes([data[2], data[3]]),
+        max_sub_index: data[4],
+        object_code: data[5],
+        name: parse_name(&data[6..]),
+    })
+}
+
+fn parse_entry_description(data: &[u8]) -> Result<EntryDescription, anyhow::Error> {
+    if data.len() < 10 {
+        return Err(anyhow::anyhow!(
+            "parse_entry_description: Response is too short ({} bytes)",
+            data.len()
+        ));
+    }
+    Ok(EntryDescription {
+        index: u16::from_le_bytes([data[0], data[1]]),
+        sub_index: data[2],
+        // data[3] is the value info
+        data_type: u16::from_le_bytes([data[4], data[5]]),
+        bit_length: u16::from_le_bytes([data[6], data[7]]),
+        access: u16::from_le_bytes([data[8], data[9]]),
+        name: parse_name(&data[10..]),
+    })
+}
+
+fn parse_name(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Mailbox {
+    sync_manager: u16,
+    address: u16,
+    length: u16,
+}
+
+impl Mailbox {
+    const fn status_register(&self) -> u16 {
+        SYNC_MANAGER_CONFIG + self.sync_manager * 8 + 5
+    }
+}
+
+/// Finds the enabled mailbox sync managers, `(write, read)` as seen from the maindevice
+async fn mailboxes(
+    maindevice: &MainDevice<'_>,
+    address: u16,
+) -> Result<(Mailbox, Mailbox), anyhow::Error> {
+    let config = Command::fprd(address, SYNC_MANAGER_CONFIG)
+        .receive::<[u8; SYNC_MANAGER_COUNT * 8]>(maindevice)
+        .await?;
+    let (mut write, mut read) = (None, None);
+    for (sync_manager, config) in config.chunks_exact(8).enumerate() {
+        let control = config[4];
+        let enabled = config[6] & 0x01 != 0;
+        // operation mode 0b10 is mailbox
+        if !enabled || control & 0b11 != 0b10 {
+            continue;
+        }
+        let mailbox = Mailbox {
+            sync_manager: sync_manager as u16,
+            address: u16::from_le_bytes([config[0], config[1]]),
+            length: u16::from_le_bytes([config[2], config[3]]),
+        };
+        match (control >> 2) & 0b11 {
+            0b01 => write = write.or(Some(mailbox)),
+            0b00 => read = read.or(Some(mailbox)),
+            _ => (),
+        }
+    }
+    write
+        .zip(read)
+        .ok_or_else(|| anyhow::anyhow!("SubDevice {:#06x} has no mailbox", address))
+}
+
+async fn mailbox_full(
+    maindevice: &MainDevice<'_>,
+    address: u16,
+    mailbox: &Mailbox,
+) -> Result<bool, anyhow::Error> {
+    let status = Command::fprd(address, mailbox.status_register())
+        .receive::<u8>(maindevice)
+        .await?;
+    Ok(status & 0x08 != 0)
+}
+
+async fn read_mailbox(
+    maindevice: &MainDevice<'_>,
+    address: u16,
+    mailbox: &Mailbox,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let response = Command::fprd(address, mailbox.address)
+        .receive_slice(maindevice, mailbox.length)
+        .await?;
+    Ok(response.to_vec())
+}
+
+/// Sends one SDO information request and collects the data of all response fragments
+async fn sdo_info(
+    maindevice: &MainDevice<'_>,
+    address: u16,
+    opcode: u8,
+    data: &[u8],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let (write, read) = mailboxes(maindevice, address).await?;
+
+    // a stale response would be taken as ours
+    if mailbox_full(maindevice, address, &read).await? {
+        read_mailbox(maindevice, address, &read).await?;
+    }
+
+    let request = request_frame(opcode, data);
+    if request.len() > usize::from(write.length) {
+        return Err(anyhow::anyhow!(
+            "sdo_info: Request doesn't fit into the mailbox of SubDevice {:#06x}",
+            address
+        ));
+    }
+    Command::fpwr(address, write.address)
+        .with_len(write.length)
+        .send(maindevice, request.as_slice())
+        .await?;
+
+    let mut response = vec![];
+    loop {
+        let deadline = Instant::now() + MAILBOX_TIMEOUT;
+        while !mailbox_full(maindevice, address, &read).await? {
+            if Instant::now() > deadline {
+                return Err(anyhow::anyhow!(
+                    "sdo_info: SubDevice {:#06x} didn't respond",
+                    address
+                ));
+            }
+            smol::Timer::after(Duration::from_micros(200)).await;
+        }
+        let frame = read_mailbox(maindevice, address, &read).await?;
+        match parse_response_frame(&frame, opcode + 1)? {
+            Some((fragment, fragments_left)) => {
+                response.extend_from_slice(fragment);
+                if fragments_left == 0 {
+                    return Ok(response);
+                }
+            }
+            // e.g. an emergency message in between
+            None => continue,
+        }
+    }
+}
+
+fn request_frame(opcode: u8, data: &[u8]) -> Vec<u8> {
+    // CoE header and SDO information header
+    let length = 2 + 4 + data.len() as u16;
+    let mut frame = Vec::with_capacity(MAILBOX_HEADER_LENGTH + usize::from(length));
+    frame.extend_from_slice(&length.to_le_bytes());
+    // address and channel/priority
+    frame.extend_from_slice(&[0, 0, 0]);
+    // mailbox counter 0 is never used by ethercrab, so it doesn't collide with its requests
+    frame.push(MAILBOX_TYPE_COE);
+    frame.extend_from_slice(&(COE_SERVICE_SDO_INFO << 12).to_le_bytes());
+    frame.extend_from_slice(&[opcode, 0, 0, 0]);
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Data and remaining fragments of an SDO information response, `None` for other mailbox messages
+fn parse_response_frame(
+    frame: &[u8],
+    expected_opcode: u8,
+) -> Result<Option<(&[u8], u16)>, anyhow::Error> {
+    if frame.len() < MAILBOX_HEADER_LENGTH + 6 {
+        return Err(anyhow::anyhow!("sdo_info: Response is too short"));
+    }
+    let length = usize::from(u16::from_le_bytes([frame[0], frame[1]]));
+    let mailbox_type = frame[5] & 0x0F;
+    let service = u16::from_le_bytes([frame[6], frame[7]]) >> 12;
+    if mailbox_type != MAILBOX_TYPE_COE || service != COE_SERVICE_SDO_INFO {
+        return Ok(None);
+    }
+
+    let opcode = frame[8] & 0x7F;
+    let fragments_left = u16::from_le_bytes([frame[10], frame[11]]);
+    let data = frame
+        .get(MAILBOX_HEADER_LENGTH + 6..MAILBOX_HEADER_LENGTH + length)
+        .ok_or_else(|| anyhow::anyhow!("sdo_info: Response length {} is invalid", length))?;
+
+    match opcode {
+        SDO_INFO_ERROR => {
+            let code = data.get(..4).unwrap_or_default();
+            Err(CoeAbortCode::unpack_from_slice(code).map_or_else(
+                |_| anyhow::anyhow!("sdo_info: Aborted with code {:02X?}", code),
+                |code| anyhow::anyhow!("sdo_info: Aborted: {}", code),
+            ))
+        }
+        opcode if opcode == expected_opcode => Ok(Some((data, fragments_left))),
+        opcode => Err(anyhow::anyhow!(
+            "sdo_info: Expected opcode {:#04x}, got {:#04x}",
+            expected_opcode,
+            opcode
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_frame() {
+        assert_eq!(
+            request_frame(GET_OBJECT_DESCRIPTION, &0x8000u16.to_le_bytes()),
+            vec![
+                0x08, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x80, 0x03, 0x00, 0x00, 0x00, 0x00, 0x80
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_response_frame() {
+        // object description of 0x8000 "SMB Settings Ch.1", with padding of the mailbox
+        let mut frame = vec![0x1D, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x80];
+        frame.extend_from_slice(&[0x04, 0x00, 0x00, 0x00]);
+        frame.extend_from_slice(&[0x00, 0x80, 0x2A, 0x00, 0x11, 0x09]);
+        frame.extend_from_slice(b"SMB Settings Ch.1");
+        frame.extend_from_slice(&[0; 16]);
+
+        let (data, fragments_left) = parse_response_frame(&frame, 0x04).unwrap().unwrap();
+        assert_eq!(fragments_left, 0);
+        let description = parse_object_description(data).unwrap();
+        assert_eq!(
+            description,
+            ObjectDescription {
+                index: 0x8000,
+                data_type: 0x002A,
+                max_sub_index: 0x11,
+                object_code: 0x09,
+                name: "SMB Settings Ch.1".to_string(),
+            }
+        );
+
+        // emergency messages are skipped
+        frame[7] = 0x10;
+        assert!(parse_response_frame(&frame, 0x04).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let mut frame = vec![0x0A, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x80];
+        frame.extend_from_slice(&[0x07, 0x00, 0x00, 0x00]);
+        frame.extend_from_slice(&0x0602_0000u32.to_le_bytes());
+        let error = parse_response_frame(&frame, 0x04).unwrap_err();
+        assert!(error.to_string().starts_with("sdo_info: Aborted"));
+    }
+}
@@ -0,0 +1,239 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::{
+        cia402::{Cia402Axis, Cia402Mode},
+        ethercrab_types::EthercrabSubDevicePreoperational,
+    },
+    io::servo_drive::{ServoDriveDevice, ServoDriveInput, ServoDriveOutput},
+    pdo::{
+        PredefinedPdoAssignment,
+        cia402::{CIA402_INPUTS_MAPPING, CIA402_OUTPUTS_MAPPING, Cia402Inputs, Cia402Outputs},
+    },
+};
+use anyhow::anyhow;
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// Device type (0x1000) of drives implementing the CiA 402 profile, the low word is the profile number
+const DEVICE_TYPE_CIA402: u32 = 402;
+
+/// Generic servo drive of any vendor which implements the CiA 402 profile
+///
+/// The default PDO mappings differ between vendors, so [`Cia402Configuration`] maps
+/// 0x1600/0x1A00 to the standard objects of the profile.
+#[derive(EthercatDevice)]
+pub struct Cia402Drive {
+    pub configuration: Cia402Configuration,
+    pub txpdo: Cia402TxPdo,
+    pub rxpdo: Cia402RxPdo,
+    pub axis: Cia402Axis,
+    is_used: bool,
+}
+
+/// Reads the device type (0x1000) to check if a SubDevice without a driver is a CiA 402 drive
+pub async fn is_cia402_subdevice(subdevice: &EthercrabSubDevicePreoperational<'_>) -> bool {
+    subdevice
+        .sdo_read::<u32>(0x1000, 0)
+        .await
+        .is_ok_and(|device_type| device_type & 0xFFFF == DEVICE_TYPE_CIA402)
+}
+
+impl EthercatDeviceProcessing for Cia402Drive {
+    fn output_pre_process(&mut self) -> Result<(), anyhow::Error> {
+        let inputs = match &self.txpdo.inputs {
+            Some(value) => value,
+            None => return Err(anyhow!("inputs is None")),
+        };
+        let controlword = self
+            .axis
+            .update(inputs.statusword, inputs.position_actual_value);
+
+        let outputs = match &mut self.rxpdo.outputs {
+            Some(value) => value,
+            None => return Err(anyhow!("outputs is None")),
+        };
+        outputs.controlword = controlword;
+        outputs.target_position = self.axis.output.target_position;
+        outputs.target_velocity = self.axis.output.target_velocity;
+        outputs.modes_of_operation = u8::from(self.configuration.mode) as i8;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Cia402Drive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cia402Drive")
+    }
+}
+
+impl NewEthercatDevice for Cia402Drive {
+    fn new() -> Self {
+        let configuration = Cia402Configuration::default();
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            axis: Cia402Axis::new(),
+            is_used: false,
+        }
+    }
+}
+
+impl ServoDriveDevice<Cia402Port> for Cia402Drive {
+    fn set_output(
+        &mut self,
+        port: Cia402Port,
+        value: ServoDriveOutput,
+    ) -> Result<(), anyhow::Error> {
+        match port {
+            Cia402Port::SRV1 => self.axis.output = value,
+        }
+        Ok(())
+    }
+
+    fn get_output(&self, port: Cia402Port) -> Result<ServoDriveOutput, anyhow::Error> {
+        match port {
+            Cia402Port::SRV1 => Ok(self.axis.output.clone()),
+        }
+    }
+
+    fn get_input(&self, port: Cia402Port) -> Result<ServoDriveInput, anyhow::Error> {
+        match port {
+            Cia402Port::SRV1 => {
+                let inputs = match &self.txpdo.inputs {
+                    Some(value) => value,
+                    None => return Err(anyhow!("inputs is None")),
+                };
+                let status = self.axis.status();
+                Ok(ServoDriveInput {
+                    state: status.state,
+                    warning: status.warning,
+                    target_reached: status.target_reached,
+                    following_error: status.following_error,
+                    position: inputs.position_actual_value,
+                    velocity: inputs.velocity_actual_value,
+                })
+            }
+        }
+    }
+
+    fn get_mode(&self, _port: Cia402Port) -> Cia402Mode {
+        self.configuration.mode
+    }
+}
+
+impl ConfigurableDevice<Cia402Configuration> for Cia402Drive {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &Cia402Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> Cia402Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cia402Configuration {
+    pub pdo_assignment: Cia402PredefinedPdoAssignment,
+
+    // 6060:00
+    // Also written cyclically through the RxPDO
+    pub mode: Cia402Mode,
+}
+
+impl Default for Cia402Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: Cia402PredefinedPdoAssignment::Standard,
+            mode: Cia402Mode::CyclicSynchronousVelocity,
+        }
+    }
+}
+
+/// Writes a PDO mapping object, the mapping has to be cleared before the entries can be changed
+async fn write_mapping(
+    device: &EthercrabSubDevicePreoperational<'_>,
+    index: u16,
+    entries: &[u32],
+) -> Result<(), anyhow::Error> {
+    device.sdo_write(index, 0, 0u8).await?;
+    for (sub_index, entry) in entries.iter().enumerate() {
+        device.sdo_write(index, sub_index as u8 + 1, *entry).await?;
+    }
+    device.sdo_write(index, 0, entries.len() as u8).await?;
+    Ok(())
+}
+
+impl Configuration for Cia402Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        write_mapping(device, 0x1600, &CIA402_OUTPUTS_MAPPING).await?;
+        write_mapping(device, 0x1A00, &CIA402_INPUTS_MAPPING).await?;
+        device
+            .sdo_write(0x6060, 0, u8::from(self.mode) as i8)
+            .await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cia402Port {
+    SRV1,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct Cia402TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub inputs: Option<Cia402Inputs>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct Cia402RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub outputs: Option<Cia402Outputs>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Cia402PredefinedPdoAssignment {
+    /// Statusword, actual position, actual velocity and mode display in,
+    /// controlword, target position, target velocity and mode out
+    Standard,
+}
+
+impl PredefinedPdoAssignment<Cia402TxPdo, Cia402RxPdo> for Cia402PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> Cia402TxPdo {
+        match self {
+            Self::Standard => Cia402TxPdo {
+                inputs: Some(Cia402Inputs::default()),
+            },
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> Cia402RxPdo {
+        match self {
+            Self::Standard => Cia402RxPdo {
+                outputs: Some(Cia402Outputs::default()),
+            },
+        }
+    }
+}
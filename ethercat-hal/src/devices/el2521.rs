@@ -1,16 +1,22 @@
 use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
 use crate::{
     coe::{ConfigurableDevice, Configuration},
-    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
-    io::pulse_train_output::{
-        PulseTrainOutputDevice, PulseTrainOutputInput, PulseTrainOutputOutput,
+    helpers::{
+        el252x_pwm::{pwm_output, write_pwm_output},
+        ethercrab_types::EthercrabSubDevicePreoperational,
+    },
+    io::{
+        pulse_train_output::{
+            PulseTrainOutputDevice, PulseTrainOutputInput, PulseTrainOutputOutput,
+        },
+        pwm_output::{PwmOutputDevice, PwmOutputInput, PwmOutputOutput},
     },
     pdo::{
         PredefinedPdoAssignment, RxPdo, TxPdo,
         el252x::{EncControl, EncStatus, PtoControl, PtoStatus, PtoTarget},
     },
 };
-use anyhow::Ok;
+use anyhow::{Ok, anyhow};
 use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
 
 /// EL2521 1-channel pulse train output terminal
@@ -80,6 +86,65 @@ impl PulseTrainOutputDevice<EL2521Port> for EL2521 {
     }
 }
 
+impl EL2521 {
+    fn check_pwm_mode(&self) -> Result<(), anyhow::Error> {
+        match self.configuration.operating_mode {
+            EL2521OperatingMode::PulseWidthModulation => Ok(()),
+            mode => Err(anyhow!(
+                "EL2521 is in {:?} operating mode, PWM output needs PulseWidthModulation",
+                mode
+            )),
+        }
+    }
+}
+
+impl PwmOutputDevice<EL2521Port> for EL2521 {
+    fn set_output(
+        &mut self,
+        _port: EL2521Port,
+        value: PwmOutputOutput,
+    ) -> Result<(), anyhow::Error> {
+        self.check_pwm_mode()?;
+        let pto_control = self
+            .rxpdo
+            .pto_control
+            .as_mut()
+            .ok_or_else(|| anyhow!("pto_control is None"))?;
+        write_pwm_output(
+            pto_control,
+            &value,
+            self.configuration.base_frequency_1,
+            self.configuration.base_frequency_2,
+        )
+    }
+
+    fn get_output(&self, _port: EL2521Port) -> Result<PwmOutputOutput, anyhow::Error> {
+        self.check_pwm_mode()?;
+        let pto_control = self
+            .rxpdo
+            .pto_control
+            .as_ref()
+            .ok_or_else(|| anyhow!("pto_control is None"))?;
+        Ok(pwm_output(
+            pto_control,
+            self.configuration.base_frequency_1,
+            self.configuration.base_frequency_2,
+        ))
+    }
+
+    fn get_input(&self, _port: EL2521Port) -> Result<PwmOutputInput, anyhow::Error> {
+        let pto_status = self
+            .txpdo
+            .pto_status
+            .as_ref()
+            .ok_or_else(|| anyhow!("pto_status is None"))?;
+        Ok(PwmOutputInput {
+            error: pto_status.error,
+            warning: false,
+        })
+    }
+}
+
 impl ConfigurableDevice<EL2521Configuration> for EL2521 {
     async fn write_config<'maindevice>(
         &mut self,
@@ -1,16 +1,22 @@
 use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
 use crate::{
     coe::{ConfigurableDevice, Configuration},
-    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
-    io::pulse_train_output::{
-        PulseTrainOutputDevice, PulseTrainOutputInput, PulseTrainOutputOutput,
+    helpers::{
+        el252x_pwm::{pwm_output, write_pwm_output},
+        ethercrab_types::EthercrabSubDevicePreoperational,
+    },
+    io::{
+        pulse_train_output::{
+            PulseTrainOutputDevice, PulseTrainOutputInput, PulseTrainOutputOutput,
+        },
+        pwm_output::{PwmOutputDevice, PwmOutputInput, PwmOutputOutput},
     },
     pdo::{
         PredefinedPdoAssignment, RxPdo, TxPdo,
         el252x::{EncControl, EncStatus, PtoControl, PtoStatus, PtoTarget},
     },
 };
-use anyhow::Ok;
+use anyhow::{Ok, anyhow};
 use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
 
 /// EL2521 2-channel pulse train output terminal
@@ -103,7 +109,58 @@ impl PulseTrainOutputDevice<EL2522Port> for EL2522 {
     }
 }
 
+impl PwmOutputDevice<EL2522Port> for EL2522 {
+    fn set_output(
+        &mut self,
+        port: EL2522Port,
+        value: PwmOutputOutput,
+    ) -> Result<(), anyhow::Error> {
+        let config = self.get_pwm_channel_configuration(port)?;
+        let (base_frequency_1, base_frequency_2) =
+            (config.base_frequency_1, config.base_frequency_2);
+        let (pto_control, _, _) = self.get_rxpdo_mut(port);
+        write_pwm_output(pto_control, &value, base_frequency_1, base_frequency_2)
+    }
+
+    fn get_output(&self, port: EL2522Port) -> Result<PwmOutputOutput, anyhow::Error> {
+        let config = self.get_pwm_channel_configuration(port)?;
+        let (pto_control, _, _) = self.get_rxpdo(port);
+        Ok(pwm_output(
+            pto_control,
+            config.base_frequency_1,
+            config.base_frequency_2,
+        ))
+    }
+
+    fn get_input(&self, port: EL2522Port) -> Result<PwmOutputInput, anyhow::Error> {
+        let (pto_status, _) = self.get_txpdo(port);
+        Ok(PwmOutputInput {
+            error: pto_status.error,
+            warning: false,
+        })
+    }
+}
+
 impl EL2522 {
+    /// Channel configuration of `port`, fails if the channel is not in PWM operating mode
+    fn get_pwm_channel_configuration(
+        &self,
+        port: EL2522Port,
+    ) -> Result<&EL2522ChannelConfiguration, anyhow::Error> {
+        let config = match port {
+            EL2522Port::PTO1 => &self.configuration.channel1_configuration,
+            EL2522Port::PTO2 => &self.configuration.channel2_configuration,
+        };
+        match config.operating_mode {
+            EL2522OperatingMode::PulseWidthModulation => Ok(config),
+            mode => Err(anyhow!(
+                "EL2522 {:?} is in {:?} operating mode, PWM output needs PulseWidthModulation",
+                port,
+                mode
+            )),
+        }
+    }
+
     const fn get_txpdo(&self, port: EL2522Port) -> (&PtoStatus, &EncStatus) {
         match port {
             EL2522Port::PTO1 => (
@@ -605,6 +662,26 @@ mod tests {
             10002
         );
     }
+
+    #[test]
+    fn test_pwm_output() {
+        let mut device = EL2522::new();
+        let output = PwmOutputOutput {
+            duty_cycle: 1.0,
+            frequency: units::f64::Frequency::new::<units::frequency::hertz>(50_000.0),
+        };
+        // frequency modulation by default
+        assert!(
+            PwmOutputDevice::set_output(&mut device, EL2522Port::PTO2, output.clone()).is_err()
+        );
+
+        device.configuration.channel2_configuration.operating_mode =
+            EL2522OperatingMode::PulseWidthModulation;
+        PwmOutputDevice::set_output(&mut device, EL2522Port::PTO2, output).unwrap();
+        let pto_control = device.rxpdo.pto_control_channel2.as_ref().unwrap();
+        assert!(!pto_control.frequency_select);
+        assert_eq!(pto_control.frequency_value, 0x7FFF);
+    }
 }
 
 pub const EL2522_VENDOR_ID: u32 = 0x2;
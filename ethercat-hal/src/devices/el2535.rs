@@ -0,0 +1,310 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::pwm_output::{PwmOutputDevice, PwmOutputInput, PwmOutputOutput},
+    pdo::{
+        PredefinedPdoAssignment,
+        el2535::{PwmControl, PwmStatus},
+    },
+};
+use anyhow::anyhow;
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+use units::{f64::Frequency, frequency::hertz};
+
+/// `output` of [`PwmControl`] for the maximum current
+const OUTPUT_MAX: i16 = 0x7FFF;
+
+/// EL2535 2-channel current-controlled PWM output terminal
+///
+/// The duty cycle of [`PwmOutputDevice`] is the fraction of the configured maximum current,
+/// the PWM frequency is fixed by the CoE configuration.
+#[derive(EthercatDevice)]
+pub struct EL2535 {
+    pub configuration: EL2535Configuration,
+    pub txpdo: EL2535TxPdo,
+    pub rxpdo: EL2535RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL2535 {}
+
+impl std::fmt::Debug for EL2535 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EL2535")
+    }
+}
+
+impl NewEthercatDevice for EL2535 {
+    fn new() -> Self {
+        let configuration = EL2535Configuration::default();
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            is_used: false,
+        }
+    }
+}
+
+impl EL2535 {
+    const fn channel_configuration(&self, port: EL2535Port) -> &EL2535ChannelConfiguration {
+        match port {
+            EL2535Port::PWM1 => &self.configuration.channel1_configuration,
+            EL2535Port::PWM2 => &self.configuration.channel2_configuration,
+        }
+    }
+
+    fn status(&self, port: EL2535Port) -> Result<&PwmStatus, anyhow::Error> {
+        let status = match port {
+            EL2535Port::PWM1 => &self.txpdo.pwm_status_channel1,
+            EL2535Port::PWM2 => &self.txpdo.pwm_status_channel2,
+        };
+        status
+            .as_ref()
+            .ok_or_else(|| anyhow!("pwm_status of {:?} is None", port))
+    }
+
+    fn control(&self, port: EL2535Port) -> Result<&PwmControl, anyhow::Error> {
+        let control = match port {
+            EL2535Port::PWM1 => &self.rxpdo.pwm_control_channel1,
+            EL2535Port::PWM2 => &self.rxpdo.pwm_control_channel2,
+        };
+        control
+            .as_ref()
+            .ok_or_else(|| anyhow!("pwm_control of {:?} is None", port))
+    }
+
+    fn control_mut(&mut self, port: EL2535Port) -> Result<&mut PwmControl, anyhow::Error> {
+        let control = match port {
+            EL2535Port::PWM1 => &mut self.rxpdo.pwm_control_channel1,
+            EL2535Port::PWM2 => &mut self.rxpdo.pwm_control_channel2,
+        };
+        control
+            .as_mut()
+            .ok_or_else(|| anyhow!("pwm_control of {:?} is None", port))
+    }
+
+    /// Acknowledges an error of the output stage on the rising edge
+    pub fn set_reset(&mut self, port: EL2535Port, reset: bool) -> Result<(), anyhow::Error> {
+        self.control_mut(port)?.reset = reset;
+        Ok(())
+    }
+}
+
+impl PwmOutputDevice<EL2535Port> for EL2535 {
+    fn set_output(
+        &mut self,
+        port: EL2535Port,
+        value: PwmOutputOutput,
+    ) -> Result<(), anyhow::Error> {
+        let pwm_frequency = self.channel_configuration(port).pwm_frequency;
+        let frequency = value.frequency.get::<hertz>().round();
+        if frequency != f64::from(pwm_frequency) {
+            return Err(anyhow!(
+                "EL2535 {:?} runs at the configured PWM frequency of {} Hz, not {} Hz",
+                port,
+                pwm_frequency,
+                frequency
+            ));
+        }
+        let output = (value.duty_cycle.clamp(0.0, 1.0) * f64::from(OUTPUT_MAX)).round() as i16;
+        let control = self.control_mut(port)?;
+        control.enable = output > 0;
+        control.output = output;
+        Ok(())
+    }
+
+    fn get_output(&self, port: EL2535Port) -> Result<PwmOutputOutput, anyhow::Error> {
+        let control = self.control(port)?;
+        Ok(PwmOutputOutput {
+            duty_cycle: f64::from(control.output.max(0)) / f64::from(OUTPUT_MAX),
+            frequency: Frequency::new::<hertz>(f64::from(
+                self.channel_configuration(port).pwm_frequency,
+            )),
+        })
+    }
+
+    fn get_input(&self, port: EL2535Port) -> Result<PwmOutputInput, anyhow::Error> {
+        let status = self.status(port)?;
+        Ok(PwmOutputInput {
+            error: status.error,
+            warning: status.warning,
+        })
+    }
+}
+
+impl ConfigurableDevice<EL2535Configuration> for EL2535 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL2535Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL2535Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL2535Configuration {
+    pub pdo_assignment: EL2535PredefinedPdoAssignment,
+    pub channel1_configuration: EL2535ChannelConfiguration,
+    pub channel2_configuration: EL2535ChannelConfiguration,
+}
+
+impl Default for EL2535Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL2535PredefinedPdoAssignment::Standard,
+            channel1_configuration: EL2535ChannelConfiguration::default(),
+            channel2_configuration: EL2535ChannelConfiguration::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL2535ChannelConfiguration {
+    /// # 0x8000:11 (Ch.1) / 0x8010:11 (Ch.2)
+    /// Maximum current in mA, the current at a duty cycle of 100%
+    ///
+    /// default: `1000`
+    pub max_current: u16,
+
+    /// # 0x8000:14 (Ch.1) / 0x8010:14 (Ch.2)
+    /// PWM frequency in Hz
+    ///
+    /// default: `100`
+    pub pwm_frequency: u16,
+}
+
+impl Default for EL2535ChannelConfiguration {
+    fn default() -> Self {
+        Self {
+            max_current: 1000,
+            pwm_frequency: 100,
+        }
+    }
+}
+
+impl Configuration for EL2535Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        for (index, config) in [
+            (0x8000, &self.channel1_configuration),
+            (0x8010, &self.channel2_configuration),
+        ] {
+            device.sdo_write(index, 0x11, config.max_current).await?;
+            device.sdo_write(index, 0x14, config.pwm_frequency).await?;
+        }
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL2535Port {
+    PWM1,
+    PWM2,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL2535TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub pwm_status_channel1: Option<PwmStatus>,
+    #[pdo_object_index(0x1A01)]
+    pub pwm_status_channel2: Option<PwmStatus>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL2535RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub pwm_control_channel1: Option<PwmControl>,
+    #[pdo_object_index(0x1601)]
+    pub pwm_control_channel2: Option<PwmControl>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EL2535PredefinedPdoAssignment {
+    Standard,
+}
+
+impl PredefinedPdoAssignment<EL2535TxPdo, EL2535RxPdo> for EL2535PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL2535TxPdo {
+        match self {
+            Self::Standard => EL2535TxPdo {
+                pwm_status_channel1: Some(PwmStatus::default()),
+                pwm_status_channel2: Some(PwmStatus::default()),
+            },
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL2535RxPdo {
+        match self {
+            Self::Standard => EL2535RxPdo {
+                pwm_control_channel1: Some(PwmControl::default()),
+                pwm_control_channel2: Some(PwmControl::default()),
+            },
+        }
+    }
+}
+
+pub const EL2535_VENDOR_ID: u32 = 0x2;
+pub const EL2535_PRODUCT_ID: u32 = 0x09e73052;
+pub const EL2535_REVISION_A: u32 = 0x00100000;
+
+pub const EL2535_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL2535_VENDOR_ID, EL2535_PRODUCT_ID, EL2535_REVISION_A);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pwm_output() {
+        let mut device = EL2535::new();
+        device
+            .set_output(
+                EL2535Port::PWM2,
+                PwmOutputOutput {
+                    duty_cycle: 0.25,
+                    frequency: Frequency::new::<hertz>(100.0),
+                },
+            )
+            .unwrap();
+        let control = device.rxpdo.pwm_control_channel2.as_ref().unwrap();
+        assert!(control.enable);
+        assert_eq!(control.output, 0x2000);
+        assert!(!device.rxpdo.pwm_control_channel1.as_ref().unwrap().enable);
+
+        // only the configured frequency
+        assert!(
+            device
+                .set_output(
+                    EL2535Port::PWM1,
+                    PwmOutputOutput {
+                        duty_cycle: 0.25,
+                        frequency: Frequency::new::<hertz>(200.0),
+                    },
+                )
+                .is_err()
+        );
+    }
+}
@@ -76,6 +76,7 @@ impl AnalogInputDevice<EL3001Port> for EL3001 {
         AnalogInputInput {
             normalized,
             wiring_error: false,
+            ..Default::default()
         }
     }
 
@@ -101,6 +101,7 @@ impl AnalogInputDevice<EL3021Port> for EL3021 {
         AnalogInputInput {
             normalized,
             wiring_error: false,
+            ..Default::default()
         }
     }
 
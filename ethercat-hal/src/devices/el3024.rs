@@ -146,6 +146,7 @@ impl AnalogInputDevice<EL3024Port> for EL3024 {
         AnalogInputInput {
             normalized,
             wiring_error: false,
+            ..Default::default()
         }
     }
 
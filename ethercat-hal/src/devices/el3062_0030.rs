@@ -104,6 +104,7 @@ impl AnalogInputDevice<EL3062_0030Port> for EL3062_0030 {
         AnalogInputInput {
             normalized,
             wiring_error: false,
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,345 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::io::analog_input::physical::{AnalogInputRange, AnalogInputValue, AnalogSignalRange};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::{
+        ethercrab_types::EthercrabSubDevicePreoperational,
+        signing_converter_u16::U16SigningConverter,
+    },
+    io::analog_input::{AnalogInputDevice, AnalogInputInput},
+    pdo::{
+        PredefinedPdoAssignment,
+        analog_input::{AiCompact, AiStandard},
+    },
+    shared_config::el30xx::{EL30XXChannelConfiguration, EL30XXPresentation},
+};
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// EL3054/EL3064/EL3104/EL3164 4-channel analog input terminals
+///
+/// The terminals share the object dictionary and differ in signal range and resolution,
+/// see [`EL30x4Variant`]. Underrange, overrange and error are only reported with the standard PDOs.
+#[derive(EthercatDevice)]
+pub struct EL30x4 {
+    pub variant: EL30x4Variant,
+    pub configuration: EL30x4Configuration,
+    pub txpdo: EL30x4TxPdo,
+    pub rxpdo: EL30x4RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL30x4 {}
+
+impl std::fmt::Debug for EL30x4 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.variant)
+    }
+}
+
+impl NewEthercatDevice for EL30x4 {
+    /// An EL3064, use [`EL30x4::from_variant`] for the other terminals
+    fn new() -> Self {
+        Self::from_variant(EL30x4Variant::EL3064)
+    }
+}
+
+impl EL30x4 {
+    pub fn from_variant(variant: EL30x4Variant) -> Self {
+        let configuration = EL30x4Configuration::default();
+        Self {
+            variant,
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            is_used: false,
+        }
+    }
+
+    /// Smallest step of the measured value
+    pub fn resolution(&self) -> AnalogInputValue {
+        self.variant
+            .signal_range()
+            .range()
+            .resolution(self.variant.resolution_bits())
+    }
+
+    const fn channel(
+        &self,
+        port: EL30x4Port,
+    ) -> (
+        &Option<AiStandard>,
+        &Option<AiCompact>,
+        &EL30XXChannelConfiguration,
+    ) {
+        match port {
+            EL30x4Port::AI1 => (
+                &self.txpdo.ai_standard_channel1,
+                &self.txpdo.ai_compact_channel1,
+                &self.configuration.channel1,
+            ),
+            EL30x4Port::AI2 => (
+                &self.txpdo.ai_standard_channel2,
+                &self.txpdo.ai_compact_channel2,
+                &self.configuration.channel2,
+            ),
+            EL30x4Port::AI3 => (
+                &self.txpdo.ai_standard_channel3,
+                &self.txpdo.ai_compact_channel3,
+                &self.configuration.channel3,
+            ),
+            EL30x4Port::AI4 => (
+                &self.txpdo.ai_standard_channel4,
+                &self.txpdo.ai_compact_channel4,
+                &self.configuration.channel4,
+            ),
+        }
+    }
+}
+
+impl AnalogInputDevice<EL30x4Port> for EL30x4 {
+    fn get_input(&self, port: EL30x4Port) -> AnalogInputInput {
+        let (ai_standard, ai_compact, channel_config) = self.channel(port);
+        let raw_value = match (ai_standard, ai_compact) {
+            (Some(ai_standard), _) => ai_standard.value,
+            (None, Some(ai_compact)) => ai_compact.value,
+            _ => panic!("Invalid TxPdo assignment"),
+        };
+        let raw_value = U16SigningConverter::load_raw(raw_value);
+        let value: i16 = match channel_config.presentation {
+            EL30XXPresentation::Unsigned => raw_value.as_unsigned() as i16,
+            EL30XXPresentation::Signed => raw_value.as_signed(),
+            EL30XXPresentation::SignedMagnitude => raw_value.as_signed_magnitude(),
+        };
+        let value = channel_config.revert_user_scale(value);
+
+        let normalized = self.analog_input_range().raw_to_normalized(value) as f32;
+        // only the standard PDO reports errors
+        ai_standard.as_ref().map_or_else(
+            || AnalogInputInput {
+                normalized,
+                ..Default::default()
+            },
+            |ai_standard| AnalogInputInput {
+                normalized,
+                wiring_error: ai_standard.error,
+                underrange: ai_standard.undervoltage,
+                overrange: ai_standard.overvoltage,
+            },
+        )
+    }
+
+    fn analog_input_range(&self) -> AnalogInputRange {
+        self.variant.signal_range().range()
+    }
+}
+
+impl ConfigurableDevice<EL30x4Configuration> for EL30x4 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL30x4Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL30x4Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL30x4Variant {
+    /// 4-20mA, 12 bit
+    EL3054,
+    /// 0-10V, 12 bit
+    EL3064,
+    /// -10-10V, 16 bit
+    EL3104,
+    /// 0-10V, 16 bit
+    EL3164,
+}
+
+impl EL30x4Variant {
+    pub const fn signal_range(&self) -> AnalogSignalRange {
+        match self {
+            Self::EL3054 => AnalogSignalRange::Current4To20mA,
+            Self::EL3064 | Self::EL3164 => AnalogSignalRange::Potential0To10V,
+            Self::EL3104 => AnalogSignalRange::PotentialPlusMinus10V,
+        }
+    }
+
+    /// Resolution including the sign bit
+    pub const fn resolution_bits(&self) -> u32 {
+        match self {
+            Self::EL3054 | Self::EL3064 => 12,
+            Self::EL3104 | Self::EL3164 => 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL30x4Configuration {
+    pub pdo_assignment: EL30x4PredefinedPdoAssignment,
+    // 0x8000
+    pub channel1: EL30XXChannelConfiguration,
+    // 0x8010
+    pub channel2: EL30XXChannelConfiguration,
+    // 0x8020
+    pub channel3: EL30XXChannelConfiguration,
+    // 0x8030
+    pub channel4: EL30XXChannelConfiguration,
+}
+
+impl Default for EL30x4Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL30x4PredefinedPdoAssignment::Standard,
+            channel1: EL30XXChannelConfiguration::default(),
+            channel2: EL30XXChannelConfiguration::default(),
+            channel3: EL30XXChannelConfiguration::default(),
+            channel4: EL30XXChannelConfiguration::default(),
+        }
+    }
+}
+
+impl Configuration for EL30x4Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        self.channel1.write_channel_config(device, 0x8000).await?;
+        self.channel2.write_channel_config(device, 0x8010).await?;
+        self.channel3.write_channel_config(device, 0x8020).await?;
+        self.channel4.write_channel_config(device, 0x8030).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL30x4Port {
+    AI1,
+    AI2,
+    AI3,
+    AI4,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL30x4TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub ai_standard_channel1: Option<AiStandard>,
+    #[pdo_object_index(0x1A01)]
+    pub ai_compact_channel1: Option<AiCompact>,
+
+    #[pdo_object_index(0x1A02)]
+    pub ai_standard_channel2: Option<AiStandard>,
+    #[pdo_object_index(0x1A03)]
+    pub ai_compact_channel2: Option<AiCompact>,
+
+    #[pdo_object_index(0x1A04)]
+    pub ai_standard_channel3: Option<AiStandard>,
+    #[pdo_object_index(0x1A05)]
+    pub ai_compact_channel3: Option<AiCompact>,
+
+    #[pdo_object_index(0x1A06)]
+    pub ai_standard_channel4: Option<AiStandard>,
+    #[pdo_object_index(0x1A07)]
+    pub ai_compact_channel4: Option<AiCompact>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL30x4RxPdo {}
+
+#[derive(Debug, Clone)]
+pub enum EL30x4PredefinedPdoAssignment {
+    /// Value with status, reports the diagnostics
+    Standard,
+    /// Value only
+    Compact,
+}
+
+impl PredefinedPdoAssignment<EL30x4TxPdo, EL30x4RxPdo> for EL30x4PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL30x4TxPdo {
+        match self {
+            Self::Standard => EL30x4TxPdo {
+                ai_standard_channel1: Some(AiStandard::default()),
+                ai_compact_channel1: None,
+                ai_standard_channel2: Some(AiStandard::default()),
+                ai_compact_channel2: None,
+                ai_standard_channel3: Some(AiStandard::default()),
+                ai_compact_channel3: None,
+                ai_standard_channel4: Some(AiStandard::default()),
+                ai_compact_channel4: None,
+            },
+            Self::Compact => EL30x4TxPdo {
+                ai_standard_channel1: None,
+                ai_compact_channel1: Some(AiCompact::default()),
+                ai_standard_channel2: None,
+                ai_compact_channel2: Some(AiCompact::default()),
+                ai_standard_channel3: None,
+                ai_compact_channel3: Some(AiCompact::default()),
+                ai_standard_channel4: None,
+                ai_compact_channel4: Some(AiCompact::default()),
+            },
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL30x4RxPdo {
+        EL30x4RxPdo {}
+    }
+}
+
+pub const EL30X4_VENDOR_ID: u32 = 0x2;
+pub const EL3054_PRODUCT_ID: u32 = 0x0bee3052;
+pub const EL3064_PRODUCT_ID: u32 = 0x0bf83052;
+pub const EL3104_PRODUCT_ID: u32 = 0x0c203052;
+pub const EL3164_PRODUCT_ID: u32 = 0x0c5c3052;
+pub const EL30X4_REVISION_A: u32 = 0x00140000;
+
+pub const EL3054_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL30X4_VENDOR_ID, EL3054_PRODUCT_ID, EL30X4_REVISION_A);
+pub const EL3064_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL30X4_VENDOR_ID, EL3064_PRODUCT_ID, EL30X4_REVISION_A);
+pub const EL3104_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL30X4_VENDOR_ID, EL3104_PRODUCT_ID, EL30X4_REVISION_A);
+pub const EL3164_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL30X4_VENDOR_ID, EL3164_PRODUCT_ID, EL30X4_REVISION_A);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use units::electric_current::milliampere;
+
+    #[test]
+    fn test_user_scaled_current_input() {
+        let mut device = EL30x4::from_variant(EL30x4Variant::EL3054);
+        device.configuration.channel2.set_user_scale(0.5, 100);
+
+        // 12mA scaled by the terminal
+        let standard = device.txpdo.ai_standard_channel2.as_mut().unwrap();
+        standard.value = (16384 / 2 + 100) as u16;
+        standard.undervoltage = true;
+
+        let input = device.get_input(EL30x4Port::AI2);
+        assert!(input.underrange);
+        match input.get_physical(&device.analog_input_range()) {
+            AnalogInputValue::Current(current) => {
+                assert!((current.get::<milliampere>() - 12.0).abs() < 0.01);
+            }
+            _ => panic!("Expected a current value"),
+        }
+    }
+}
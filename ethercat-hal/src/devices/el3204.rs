@@ -47,6 +47,7 @@ impl TemperatureInputDevice<EL3204Port> for EL3204 {
             temperature: channel.temperature,
             undervoltage: channel.undervoltage,
             overvoltage: channel.overvoltage,
+            open_circuit: false,
             limit1: channel.limit1,
             limit2: channel.limit2,
             error: channel.error,
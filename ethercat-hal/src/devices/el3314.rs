@@ -0,0 +1,233 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::temperature_input::{TemperatureInputDevice, TemperatureInputInput},
+    pdo::{PredefinedPdoAssignment, analog_input::AiStandard, el331x::TcOutput},
+    shared_config::el331x::EL331XChannelConfiguration,
+};
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// EL3314 4-channel thermocouple input device
+///
+/// Types J, K, L, E, T, N, U, B, R, S, C and ±30/60/75mV
+#[derive(EthercatDevice)]
+pub struct EL3314 {
+    pub configuration: EL3314Configuration,
+    pub txpdo: EL3314TxPdo,
+    pub rxpdo: EL3314RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL3314 {}
+
+impl std::fmt::Debug for EL3314 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EL3314")
+    }
+}
+
+impl NewEthercatDevice for EL3314 {
+    fn new() -> Self {
+        let configuration = EL3314Configuration::default();
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            is_used: false,
+        }
+    }
+}
+
+impl EL3314 {
+    const fn channel_configuration(&self, port: &EL3314Port) -> &EL331XChannelConfiguration {
+        match port {
+            EL3314Port::T1 => &self.configuration.channel1,
+            EL3314Port::T2 => &self.configuration.channel2,
+            EL3314Port::T3 => &self.configuration.channel3,
+            EL3314Port::T4 => &self.configuration.channel4,
+        }
+    }
+
+    /// Sets the cold junction temperature in degrees Celsius
+    ///
+    /// Needs [`EL3314PredefinedPdoAssignment::ProcessDataColdJunction`] and
+    /// [`crate::shared_config::el331x::EL331XColdJunctionCompensation::ProcessData`] on the channel.
+    pub fn set_cold_junction_temperature(
+        &mut self,
+        port: EL3314Port,
+        temperature: f32,
+    ) -> Result<(), anyhow::Error> {
+        let value = self
+            .channel_configuration(&port)
+            .cold_junction_raw(temperature);
+        let output = match port {
+            EL3314Port::T1 => self.rxpdo.channel1.as_mut(),
+            EL3314Port::T2 => self.rxpdo.channel2.as_mut(),
+            EL3314Port::T3 => self.rxpdo.channel3.as_mut(),
+            EL3314Port::T4 => self.rxpdo.channel4.as_mut(),
+        }
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "[{}::EL3314::set_cold_junction_temperature] TC Outputs are not assigned",
+                module_path!()
+            )
+        })?;
+        output.cold_junction_compensation = value;
+        Ok(())
+    }
+}
+
+impl TemperatureInputDevice<EL3314Port> for EL3314 {
+    fn get_input(&self, port: EL3314Port) -> TemperatureInputInput {
+        let expect_text = "All channels should be Some(_)";
+        let channel = match port {
+            EL3314Port::T1 => self.txpdo.channel1.as_ref().expect(expect_text),
+            EL3314Port::T2 => self.txpdo.channel2.as_ref().expect(expect_text),
+            EL3314Port::T3 => self.txpdo.channel3.as_ref().expect(expect_text),
+            EL3314Port::T4 => self.txpdo.channel4.as_ref().expect(expect_text),
+        };
+        self.channel_configuration(&port).temperature_input(channel)
+    }
+}
+
+impl ConfigurableDevice<EL3314Configuration> for EL3314 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL3314Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL3314Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL3314Configuration {
+    pub pdo_assignment: EL3314PredefinedPdoAssignment,
+    pub channel1: EL331XChannelConfiguration,
+    pub channel2: EL331XChannelConfiguration,
+    pub channel3: EL331XChannelConfiguration,
+    pub channel4: EL331XChannelConfiguration,
+}
+
+impl Default for EL3314Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL3314PredefinedPdoAssignment::Standard,
+            channel1: EL331XChannelConfiguration::default(),
+            channel2: EL331XChannelConfiguration::default(),
+            channel3: EL331XChannelConfiguration::default(),
+            channel4: EL331XChannelConfiguration::default(),
+        }
+    }
+}
+
+impl Configuration for EL3314Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        self.channel1.write_channel_config(device, 0x8000).await?;
+        self.channel2.write_channel_config(device, 0x8010).await?;
+        self.channel3.write_channel_config(device, 0x8020).await?;
+        self.channel4.write_channel_config(device, 0x8030).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL3314Port {
+    T1,
+    T2,
+    T3,
+    T4,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL3314TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub channel1: Option<AiStandard>,
+    #[pdo_object_index(0x1A01)]
+    pub channel2: Option<AiStandard>,
+    #[pdo_object_index(0x1A02)]
+    pub channel3: Option<AiStandard>,
+    #[pdo_object_index(0x1A03)]
+    pub channel4: Option<AiStandard>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL3314RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub channel1: Option<TcOutput>,
+    #[pdo_object_index(0x1601)]
+    pub channel2: Option<TcOutput>,
+    #[pdo_object_index(0x1602)]
+    pub channel3: Option<TcOutput>,
+    #[pdo_object_index(0x1603)]
+    pub channel4: Option<TcOutput>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EL3314PredefinedPdoAssignment {
+    /// TC Inputs only
+    Standard,
+    /// TC Inputs and the cold junction temperature as TC Outputs
+    ProcessDataColdJunction,
+}
+
+impl PredefinedPdoAssignment<EL3314TxPdo, EL3314RxPdo> for EL3314PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL3314TxPdo {
+        EL3314TxPdo {
+            channel1: Some(AiStandard::default()),
+            channel2: Some(AiStandard::default()),
+            channel3: Some(AiStandard::default()),
+            channel4: Some(AiStandard::default()),
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL3314RxPdo {
+        match self {
+            Self::Standard => EL3314RxPdo {
+                channel1: None,
+                channel2: None,
+                channel3: None,
+                channel4: None,
+            },
+            Self::ProcessDataColdJunction => EL3314RxPdo {
+                channel1: Some(TcOutput::default()),
+                channel2: Some(TcOutput::default()),
+                channel3: Some(TcOutput::default()),
+                channel4: Some(TcOutput::default()),
+            },
+        }
+    }
+}
+
+pub const EL3314_VENDOR_ID: u32 = 0x2;
+pub const EL3314_PRODUCT_ID: u32 = 0x0cf23052;
+pub const EL3314_REVISION_A: u32 = 0x00120000;
+pub const EL3314_REVISION_B: u32 = 0x00130000;
+
+pub const EL3314_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL3314_VENDOR_ID, EL3314_PRODUCT_ID, EL3314_REVISION_A);
+
+pub const EL3314_IDENTITY_B: SubDeviceIdentityTuple =
+    (EL3314_VENDOR_ID, EL3314_PRODUCT_ID, EL3314_REVISION_B);
@@ -0,0 +1,285 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::temperature_input::{TemperatureInputDevice, TemperatureInputInput},
+    pdo::{PredefinedPdoAssignment, analog_input::AiStandard, el331x::TcOutput},
+    shared_config::el331x::EL331XChannelConfiguration,
+};
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// EL3318 8-channel thermocouple input device
+///
+/// Types J, K, L, E, T, N, U, B, R, S, C and ±30/60/75mV
+#[derive(EthercatDevice)]
+pub struct EL3318 {
+    pub configuration: EL3318Configuration,
+    pub txpdo: EL3318TxPdo,
+    pub rxpdo: EL3318RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL3318 {}
+
+impl std::fmt::Debug for EL3318 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EL3318")
+    }
+}
+
+impl NewEthercatDevice for EL3318 {
+    fn new() -> Self {
+        let configuration = EL3318Configuration::default();
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            is_used: false,
+        }
+    }
+}
+
+impl EL3318 {
+    const fn channel_configuration(&self, port: &EL3318Port) -> &EL331XChannelConfiguration {
+        match port {
+            EL3318Port::T1 => &self.configuration.channel1,
+            EL3318Port::T2 => &self.configuration.channel2,
+            EL3318Port::T3 => &self.configuration.channel3,
+            EL3318Port::T4 => &self.configuration.channel4,
+            EL3318Port::T5 => &self.configuration.channel5,
+            EL3318Port::T6 => &self.configuration.channel6,
+            EL3318Port::T7 => &self.configuration.channel7,
+            EL3318Port::T8 => &self.configuration.channel8,
+        }
+    }
+
+    /// Sets the cold junction temperature in degrees Celsius
+    ///
+    /// Needs [`EL3318PredefinedPdoAssignment::ProcessDataColdJunction`] and
+    /// [`crate::shared_config::el331x::EL331XColdJunctionCompensation::ProcessData`] on the channel.
+    pub fn set_cold_junction_temperature(
+        &mut self,
+        port: EL3318Port,
+        temperature: f32,
+    ) -> Result<(), anyhow::Error> {
+        let value = self
+            .channel_configuration(&port)
+            .cold_junction_raw(temperature);
+        let output = match port {
+            EL3318Port::T1 => self.rxpdo.channel1.as_mut(),
+            EL3318Port::T2 => self.rxpdo.channel2.as_mut(),
+            EL3318Port::T3 => self.rxpdo.channel3.as_mut(),
+            EL3318Port::T4 => self.rxpdo.channel4.as_mut(),
+            EL3318Port::T5 => self.rxpdo.channel5.as_mut(),
+            EL3318Port::T6 => self.rxpdo.channel6.as_mut(),
+            EL3318Port::T7 => self.rxpdo.channel7.as_mut(),
+            EL3318Port::T8 => self.rxpdo.channel8.as_mut(),
+        }
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "[{}::EL3318::set_cold_junction_temperature] TC Outputs are not assigned",
+                module_path!()
+            )
+        })?;
+        output.cold_junction_compensation = value;
+        Ok(())
+    }
+}
+
+impl TemperatureInputDevice<EL3318Port> for EL3318 {
+    fn get_input(&self, port: EL3318Port) -> TemperatureInputInput {
+        let expect_text = "All channels should be Some(_)";
+        let channel = match port {
+            EL3318Port::T1 => self.txpdo.channel1.as_ref().expect(expect_text),
+            EL3318Port::T2 => self.txpdo.channel2.as_ref().expect(expect_text),
+            EL3318Port::T3 => self.txpdo.channel3.as_ref().expect(expect_text),
+            EL3318Port::T4 => self.txpdo.channel4.as_ref().expect(expect_text),
+            EL3318Port::T5 => self.txpdo.channel5.as_ref().expect(expect_text),
+            EL3318Port::T6 => self.txpdo.channel6.as_ref().expect(expect_text),
+            EL3318Port::T7 => self.txpdo.channel7.as_ref().expect(expect_text),
+            EL3318Port::T8 => self.txpdo.channel8.as_ref().expect(expect_text),
+        };
+        self.channel_configuration(&port).temperature_input(channel)
+    }
+}
+
+impl ConfigurableDevice<EL3318Configuration> for EL3318 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL3318Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL3318Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL3318Configuration {
+    pub pdo_assignment: EL3318PredefinedPdoAssignment,
+    pub channel1: EL331XChannelConfiguration,
+    pub channel2: EL331XChannelConfiguration,
+    pub channel3: EL331XChannelConfiguration,
+    pub channel4: EL331XChannelConfiguration,
+    pub channel5: EL331XChannelConfiguration,
+    pub channel6: EL331XChannelConfiguration,
+    pub channel7: EL331XChannelConfiguration,
+    pub channel8: EL331XChannelConfiguration,
+}
+
+impl Default for EL3318Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL3318PredefinedPdoAssignment::Standard,
+            channel1: EL331XChannelConfiguration::default(),
+            channel2: EL331XChannelConfiguration::default(),
+            channel3: EL331XChannelConfiguration::default(),
+            channel4: EL331XChannelConfiguration::default(),
+            channel5: EL331XChannelConfiguration::default(),
+            channel6: EL331XChannelConfiguration::default(),
+            channel7: EL331XChannelConfiguration::default(),
+            channel8: EL331XChannelConfiguration::default(),
+        }
+    }
+}
+
+impl Configuration for EL3318Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        self.channel1.write_channel_config(device, 0x8000).await?;
+        self.channel2.write_channel_config(device, 0x8010).await?;
+        self.channel3.write_channel_config(device, 0x8020).await?;
+        self.channel4.write_channel_config(device, 0x8030).await?;
+        self.channel5.write_channel_config(device, 0x8040).await?;
+        self.channel6.write_channel_config(device, 0x8050).await?;
+        self.channel7.write_channel_config(device, 0x8060).await?;
+        self.channel8.write_channel_config(device, 0x8070).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL3318Port {
+    T1,
+    T2,
+    T3,
+    T4,
+    T5,
+    T6,
+    T7,
+    T8,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL3318TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub channel1: Option<AiStandard>,
+    #[pdo_object_index(0x1A01)]
+    pub channel2: Option<AiStandard>,
+    #[pdo_object_index(0x1A02)]
+    pub channel3: Option<AiStandard>,
+    #[pdo_object_index(0x1A03)]
+    pub channel4: Option<AiStandard>,
+    #[pdo_object_index(0x1A04)]
+    pub channel5: Option<AiStandard>,
+    #[pdo_object_index(0x1A05)]
+    pub channel6: Option<AiStandard>,
+    #[pdo_object_index(0x1A06)]
+    pub channel7: Option<AiStandard>,
+    #[pdo_object_index(0x1A07)]
+    pub channel8: Option<AiStandard>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL3318RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub channel1: Option<TcOutput>,
+    #[pdo_object_index(0x1601)]
+    pub channel2: Option<TcOutput>,
+    #[pdo_object_index(0x1602)]
+    pub channel3: Option<TcOutput>,
+    #[pdo_object_index(0x1603)]
+    pub channel4: Option<TcOutput>,
+    #[pdo_object_index(0x1604)]
+    pub channel5: Option<TcOutput>,
+    #[pdo_object_index(0x1605)]
+    pub channel6: Option<TcOutput>,
+    #[pdo_object_index(0x1606)]
+    pub channel7: Option<TcOutput>,
+    #[pdo_object_index(0x1607)]
+    pub channel8: Option<TcOutput>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EL3318PredefinedPdoAssignment {
+    /// TC Inputs only
+    Standard,
+    /// TC Inputs and the cold junction temperature as TC Outputs
+    ProcessDataColdJunction,
+}
+
+impl PredefinedPdoAssignment<EL3318TxPdo, EL3318RxPdo> for EL3318PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL3318TxPdo {
+        EL3318TxPdo {
+            channel1: Some(AiStandard::default()),
+            channel2: Some(AiStandard::default()),
+            channel3: Some(AiStandard::default()),
+            channel4: Some(AiStandard::default()),
+            channel5: Some(AiStandard::default()),
+            channel6: Some(AiStandard::default()),
+            channel7: Some(AiStandard::default()),
+            channel8: Some(AiStandard::default()),
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL3318RxPdo {
+        match self {
+            Self::Standard => EL3318RxPdo {
+                channel1: None,
+                channel2: None,
+                channel3: None,
+                channel4: None,
+                channel5: None,
+                channel6: None,
+                channel7: None,
+                channel8: None,
+            },
+            Self::ProcessDataColdJunction => EL3318RxPdo {
+                channel1: Some(TcOutput::default()),
+                channel2: Some(TcOutput::default()),
+                channel3: Some(TcOutput::default()),
+                channel4: Some(TcOutput::default()),
+                channel5: Some(TcOutput::default()),
+                channel6: Some(TcOutput::default()),
+                channel7: Some(TcOutput::default()),
+                channel8: Some(TcOutput::default()),
+            },
+        }
+    }
+}
+
+pub const EL3318_VENDOR_ID: u32 = 0x2;
+pub const EL3318_PRODUCT_ID: u32 = 0x0cf63052;
+pub const EL3318_REVISION_A: u32 = 0x00110000;
+
+pub const EL3318_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL3318_VENDOR_ID, EL3318_PRODUCT_ID, EL3318_REVISION_A);
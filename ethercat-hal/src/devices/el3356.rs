@@ -0,0 +1,357 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::force_input::{ForceInputDevice, ForceInputInput},
+    pdo::{
+        PredefinedPdoAssignment,
+        basic::F32PdoObject,
+        el3356::{RmbControl, RmbStatus, RmbValueInt},
+    },
+};
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+use units::{f64::Force, force::newton};
+
+/// EL3356 1-channel resistor bridge (load cell) input device
+///
+/// Also covers the EL3356-0010 with the faster conversion.
+#[derive(EthercatDevice)]
+pub struct EL3356 {
+    pub configuration: EL3356Configuration,
+    pub txpdo: EL3356TxPdo,
+    pub rxpdo: EL3356RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL3356 {}
+
+impl std::fmt::Debug for EL3356 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EL3356")
+    }
+}
+
+impl NewEthercatDevice for EL3356 {
+    fn new() -> Self {
+        let configuration = EL3356Configuration::default();
+        let mut rxpdo = configuration.pdo_assignment.rxpdo_assignment();
+        rxpdo.set_sample_mode(configuration.mode);
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo,
+            configuration,
+            is_used: false,
+        }
+    }
+}
+
+impl EL3356 {
+    /// Load in the unit of the nominal load (kg) as reported by the terminal
+    fn load(&self) -> f64 {
+        let scaled = match &self.txpdo {
+            EL3356TxPdo {
+                value_real: Some(value),
+                ..
+            } => f64::from(value.value),
+            EL3356TxPdo {
+                value_int: Some(value),
+                ..
+            } => f64::from(value.value),
+            _ => panic!("Invalid TxPdo assignment"),
+        };
+        scaled / f64::from(self.configuration.scale_factor)
+    }
+
+    /// Hardware tare, the terminal zeroes its value on the rising edge
+    ///
+    /// [`crate::io::force_input::ForceInput::tare`] works without the terminal.
+    pub const fn set_tare(&mut self, tare: bool) {
+        self.rxpdo.control_mut().tare = tare;
+    }
+
+    /// Holds the last value while `freeze` is set
+    pub const fn set_input_freeze(&mut self, freeze: bool) {
+        self.rxpdo.control_mut().input_freeze = freeze;
+    }
+
+    /// Starts a self calibration of the terminal on the rising edge, not a load calibration
+    pub const fn set_start_calibration(&mut self, start: bool) {
+        self.rxpdo.control_mut().start_calibration = start;
+    }
+}
+
+impl ForceInputDevice<EL3356Port> for EL3356 {
+    fn get_input(&self, port: EL3356Port) -> ForceInputInput {
+        let status = match port {
+            EL3356Port::FI1 => self
+                .txpdo
+                .status
+                .as_ref()
+                .expect("RMB Status should be Some(_)"),
+        };
+        let force = self.load() * f64::from(self.configuration.gravity_of_earth);
+        ForceInputInput {
+            force: Force::new::<newton>(force),
+            underrange: status.underrange,
+            overrange: status.overrange,
+            error: status.error,
+            data_invalid: status.data_invalid || status.calibration_in_progress,
+            steady_state: status.steady_state,
+        }
+    }
+}
+
+impl ConfigurableDevice<EL3356Configuration> for EL3356 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL3356Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        self.rxpdo.set_sample_mode(config.mode);
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL3356Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL3356Configuration {
+    pub pdo_assignment: EL3356PredefinedPdoAssignment,
+
+    /// Sample mode selected through the RxPDO
+    pub mode: EL3356Mode,
+
+    // 8000:11
+    // Filter in mode 0, usually a strong filter for standstill
+    pub mode0_filter: EL3356FilterSettings,
+
+    // 8000:13
+    // Averager over 4 values in mode 0
+    pub mode0_averager: bool,
+
+    // 8000:14
+    // Filter in mode 1, usually a weak filter for dynamic loads
+    pub mode1_filter: EL3356FilterSettings,
+
+    // 8000:16
+    // Averager over 4 values in mode 1
+    pub mode1_averager: bool,
+
+    // 8000:23
+    // Nominal characteristic value of the load cell in mV/V
+    pub nominal_characteristic_value: f32,
+
+    // 8000:24
+    // Nominal load of the load cell in kg
+    pub nominal_load: f32,
+
+    // 8000:25
+    // Zero balance of the load cell in mV/V
+    pub zero_balance: f32,
+
+    // 8000:26
+    // Gravity of earth in m/s², converts the load to force
+    pub gravity_of_earth: f32,
+
+    // 8000:27
+    // Scale factor of the value, 1000 reports grams for a nominal load in kg
+    pub scale_factor: f32,
+}
+
+impl Default for EL3356Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL3356PredefinedPdoAssignment::Standard,
+            mode: EL3356Mode::Mode0,
+            mode0_filter: EL3356FilterSettings::FIR50Hz,
+            mode0_averager: true,
+            mode1_filter: EL3356FilterSettings::IIR1,
+            mode1_averager: false,
+            nominal_characteristic_value: 2.0,
+            nominal_load: 5.0,
+            zero_balance: 0.0,
+            gravity_of_earth: 9.806_65,
+            scale_factor: 1000.0,
+        }
+    }
+}
+
+impl Configuration for EL3356Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        device
+            .sdo_write(0x8000, 0x11, u16::from(self.mode0_filter))
+            .await?;
+        device.sdo_write(0x8000, 0x13, self.mode0_averager).await?;
+        device
+            .sdo_write(0x8000, 0x14, u16::from(self.mode1_filter))
+            .await?;
+        device.sdo_write(0x8000, 0x16, self.mode1_averager).await?;
+        device
+            .sdo_write(0x8000, 0x23, self.nominal_characteristic_value)
+            .await?;
+        device.sdo_write(0x8000, 0x24, self.nominal_load).await?;
+        device.sdo_write(0x8000, 0x25, self.zero_balance).await?;
+        device
+            .sdo_write(0x8000, 0x26, self.gravity_of_earth)
+            .await?;
+        device.sdo_write(0x8000, 0x27, self.scale_factor).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL3356Mode {
+    Mode0,
+    Mode1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL3356FilterSettings {
+    FIR50Hz,
+    FIR60Hz,
+    IIR1,
+    IIR2,
+    IIR3,
+    IIR4,
+    IIR5,
+    IIR6,
+    IIR7,
+    IIR8,
+    DynamicIIR,
+    PdoFilterFrequency,
+}
+
+impl From<EL3356FilterSettings> for u16 {
+    fn from(filter_settings: EL3356FilterSettings) -> Self {
+        match filter_settings {
+            EL3356FilterSettings::FIR50Hz => 0,
+            EL3356FilterSettings::FIR60Hz => 1,
+            EL3356FilterSettings::IIR1 => 2,
+            EL3356FilterSettings::IIR2 => 3,
+            EL3356FilterSettings::IIR3 => 4,
+            EL3356FilterSettings::IIR4 => 5,
+            EL3356FilterSettings::IIR5 => 6,
+            EL3356FilterSettings::IIR6 => 7,
+            EL3356FilterSettings::IIR7 => 8,
+            EL3356FilterSettings::IIR8 => 9,
+            EL3356FilterSettings::DynamicIIR => 10,
+            EL3356FilterSettings::PdoFilterFrequency => 11,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL3356Port {
+    FI1,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL3356TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub status: Option<RmbStatus>,
+    #[pdo_object_index(0x1A01)]
+    pub value_int: Option<RmbValueInt>,
+    #[pdo_object_index(0x1A02)]
+    pub value_real: Option<F32PdoObject>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL3356RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub control: Option<RmbControl>,
+}
+
+impl EL3356RxPdo {
+    const fn control_mut(&mut self) -> &mut RmbControl {
+        self.control
+            .as_mut()
+            .expect("RMB Control should be Some(_)")
+    }
+
+    const fn set_sample_mode(&mut self, mode: EL3356Mode) {
+        self.control_mut().sample_mode = matches!(mode, EL3356Mode::Mode1);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EL3356PredefinedPdoAssignment {
+    /// Status and the value as REAL
+    Standard,
+    /// Status and the value as DINT
+    Integer,
+}
+
+impl PredefinedPdoAssignment<EL3356TxPdo, EL3356RxPdo> for EL3356PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL3356TxPdo {
+        match self {
+            Self::Standard => EL3356TxPdo {
+                status: Some(RmbStatus::default()),
+                value_int: None,
+                value_real: Some(F32PdoObject::default()),
+            },
+            Self::Integer => EL3356TxPdo {
+                status: Some(RmbStatus::default()),
+                value_int: Some(RmbValueInt::default()),
+                value_real: None,
+            },
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL3356RxPdo {
+        EL3356RxPdo {
+            control: Some(RmbControl::default()),
+        }
+    }
+}
+
+pub const EL3356_VENDOR_ID: u32 = 0x2;
+pub const EL3356_PRODUCT_ID: u32 = 0x0d1c3052;
+pub const EL3356_REVISION_0000_A: u32 = 0x00120000;
+pub const EL3356_REVISION_0010_A: u32 = 0x0012000a;
+
+pub const EL3356_IDENTITY_0000_A: SubDeviceIdentityTuple =
+    (EL3356_VENDOR_ID, EL3356_PRODUCT_ID, EL3356_REVISION_0000_A);
+
+pub const EL3356_IDENTITY_0010_A: SubDeviceIdentityTuple =
+    (EL3356_VENDOR_ID, EL3356_PRODUCT_ID, EL3356_REVISION_0010_A);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdo::TxPdoObject;
+    use bitvec::prelude::*;
+
+    #[test]
+    fn test_force_from_load() {
+        let mut device = EL3356::new();
+        // 1.5kg in grams
+        device
+            .txpdo
+            .value_real
+            .as_mut()
+            .unwrap()
+            .read(1500.0f32.to_bits().to_le_bytes().view_bits());
+        let input = device.get_input(EL3356Port::FI1);
+        let expected = 1.5 * 9.806_65;
+        assert!((input.force.get::<newton>() - expected).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,272 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::io::analog_input::physical::{AnalogInputRange, AnalogInputValue, AnalogSignalRange};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::analog_output::{AnalogOutputDevice, AnalogOutputOutput},
+    pdo::{PredefinedPdoAssignment, el40xx::AnalogOutput},
+    shared_config::el40xx::EL40XXChannelConfiguration,
+};
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// EL4004/EL4024/EL4104 4-channel analog output terminals
+///
+/// The terminals share the object dictionary and differ in signal range and resolution,
+/// see [`EL40x4Variant`]. The value `0.0` to `1.0` of [`AnalogOutputDevice`] spans the signal range.
+/// The process data has no diagnostics, the terminals report errors in the diag messages only.
+#[derive(EthercatDevice)]
+pub struct EL40x4 {
+    pub variant: EL40x4Variant,
+    pub configuration: EL40x4Configuration,
+    pub txpdo: EL40x4TxPdo,
+    pub rxpdo: EL40x4RxPdo,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL40x4 {}
+
+impl std::fmt::Debug for EL40x4 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.variant)
+    }
+}
+
+impl NewEthercatDevice for EL40x4 {
+    /// An EL4004, use [`EL40x4::from_variant`] for the other terminals
+    fn new() -> Self {
+        Self::from_variant(EL40x4Variant::EL4004)
+    }
+}
+
+impl EL40x4 {
+    pub fn from_variant(variant: EL40x4Variant) -> Self {
+        let configuration = EL40x4Configuration::default();
+        Self {
+            variant,
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            is_used: false,
+        }
+    }
+
+    /// Smallest step of the output
+    pub fn resolution(&self) -> AnalogInputValue {
+        self.range().resolution(self.variant.resolution_bits())
+    }
+
+    fn range(&self) -> AnalogInputRange {
+        self.variant.signal_range().range()
+    }
+
+    const fn channel(
+        &self,
+        port: EL40x4Port,
+    ) -> (&Option<AnalogOutput>, &EL40XXChannelConfiguration) {
+        match port {
+            EL40x4Port::AO1 => (&self.rxpdo.ao_channel1, &self.configuration.channel1),
+            EL40x4Port::AO2 => (&self.rxpdo.ao_channel2, &self.configuration.channel2),
+            EL40x4Port::AO3 => (&self.rxpdo.ao_channel3, &self.configuration.channel3),
+            EL40x4Port::AO4 => (&self.rxpdo.ao_channel4, &self.configuration.channel4),
+        }
+    }
+}
+
+impl AnalogOutputDevice<EL40x4Port> for EL40x4 {
+    fn set_output(&mut self, port: EL40x4Port, value: AnalogOutputOutput) {
+        let (_, channel_config) = self.channel(port);
+        let raw_value = self.range().normalized_to_raw(f64::from(value.0));
+        let raw_value = channel_config.revert_user_scale(raw_value);
+        let channel = match port {
+            EL40x4Port::AO1 => &mut self.rxpdo.ao_channel1,
+            EL40x4Port::AO2 => &mut self.rxpdo.ao_channel2,
+            EL40x4Port::AO3 => &mut self.rxpdo.ao_channel3,
+            EL40x4Port::AO4 => &mut self.rxpdo.ao_channel4,
+        };
+        if let Some(channel) = channel.as_mut() {
+            channel.value = raw_value;
+        }
+    }
+
+    fn get_output(&self, port: EL40x4Port) -> AnalogOutputOutput {
+        let (channel, channel_config) = self.channel(port);
+        let raw_value = channel.as_ref().map_or(0, |channel| channel.value);
+        let raw_value = channel_config.apply_user_scale(raw_value);
+        let normalized = self.range().raw_to_normalized(raw_value);
+        AnalogOutputOutput(normalized as f32)
+    }
+
+    fn analog_output_range(&self) -> Option<AnalogInputRange> {
+        Some(self.range())
+    }
+}
+
+impl ConfigurableDevice<EL40x4Configuration> for EL40x4 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL40x4Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL40x4Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL40x4Variant {
+    /// 0-10V, 12 bit
+    EL4004,
+    /// 4-20mA, 12 bit
+    EL4024,
+    /// 0-10V, 16 bit
+    EL4104,
+}
+
+impl EL40x4Variant {
+    pub const fn signal_range(&self) -> AnalogSignalRange {
+        match self {
+            Self::EL4004 | Self::EL4104 => AnalogSignalRange::Potential0To10V,
+            Self::EL4024 => AnalogSignalRange::Current4To20mA,
+        }
+    }
+
+    /// Resolution including the sign bit
+    pub const fn resolution_bits(&self) -> u32 {
+        match self {
+            Self::EL4004 | Self::EL4024 => 12,
+            Self::EL4104 => 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL40x4Configuration {
+    pub pdo_assignment: EL40x4PredefinedPdoAssignment,
+    // 0x8000
+    pub channel1: EL40XXChannelConfiguration,
+    // 0x8010
+    pub channel2: EL40XXChannelConfiguration,
+    // 0x8020
+    pub channel3: EL40XXChannelConfiguration,
+    // 0x8030
+    pub channel4: EL40XXChannelConfiguration,
+}
+
+impl Default for EL40x4Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL40x4PredefinedPdoAssignment::Standard,
+            channel1: EL40XXChannelConfiguration::default(),
+            channel2: EL40XXChannelConfiguration::default(),
+            channel3: EL40XXChannelConfiguration::default(),
+            channel4: EL40XXChannelConfiguration::default(),
+        }
+    }
+}
+
+impl Configuration for EL40x4Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        self.channel1.write_channel_config(device, 0x8000).await?;
+        self.channel2.write_channel_config(device, 0x8010).await?;
+        self.channel3.write_channel_config(device, 0x8020).await?;
+        self.channel4.write_channel_config(device, 0x8030).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL40x4Port {
+    AO1,
+    AO2,
+    AO3,
+    AO4,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL40x4RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub ao_channel1: Option<AnalogOutput>,
+    #[pdo_object_index(0x1601)]
+    pub ao_channel2: Option<AnalogOutput>,
+    #[pdo_object_index(0x1602)]
+    pub ao_channel3: Option<AnalogOutput>,
+    #[pdo_object_index(0x1603)]
+    pub ao_channel4: Option<AnalogOutput>,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL40x4TxPdo {}
+
+#[derive(Debug, Clone)]
+pub enum EL40x4PredefinedPdoAssignment {
+    Standard,
+}
+
+impl PredefinedPdoAssignment<EL40x4TxPdo, EL40x4RxPdo> for EL40x4PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL40x4TxPdo {
+        EL40x4TxPdo {}
+    }
+
+    fn rxpdo_assignment(&self) -> EL40x4RxPdo {
+        match self {
+            Self::Standard => EL40x4RxPdo {
+                ao_channel1: Some(AnalogOutput::default()),
+                ao_channel2: Some(AnalogOutput::default()),
+                ao_channel3: Some(AnalogOutput::default()),
+                ao_channel4: Some(AnalogOutput::default()),
+            },
+        }
+    }
+}
+
+pub const EL40X4_VENDOR_ID: u32 = 0x2;
+pub const EL4004_PRODUCT_ID: u32 = 0x0fa43052;
+pub const EL4024_PRODUCT_ID: u32 = 0x0fb83052;
+pub const EL4104_PRODUCT_ID: u32 = 0x10083052;
+pub const EL40X4_REVISION_A: u32 = 0x00140000;
+
+pub const EL4004_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL40X4_VENDOR_ID, EL4004_PRODUCT_ID, EL40X4_REVISION_A);
+pub const EL4024_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL40X4_VENDOR_ID, EL4024_PRODUCT_ID, EL40X4_REVISION_A);
+pub const EL4104_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL40X4_VENDOR_ID, EL4104_PRODUCT_ID, EL40X4_REVISION_A);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_scaled_output() {
+        let mut device = EL40x4::from_variant(EL40x4Variant::EL4024);
+        device.configuration.channel3.set_user_scale(2.0, -100);
+
+        device.set_output(EL40x4Port::AO3, AnalogOutputOutput(0.5));
+        // the terminal doubles the value and subtracts 100
+        let value = device.rxpdo.ao_channel3.as_ref().unwrap().value;
+        assert_eq!(value, (16384 + 100) / 2);
+
+        let output = device.get_output(EL40x4Port::AO3);
+        assert!((output.0 - 0.5).abs() < 1e-4);
+    }
+}
@@ -7,9 +7,18 @@ use ethercat_hal_derive::EthercatDevice;
 use pdo::{EL7031RxPdo, EL7031TxPdo};
 
 use crate::{
-    helpers::counter_wrapper_u16_i128::CounterWrapperU16U128,
+    helpers::{
+        counter_wrapper_u16_i128::CounterWrapperU16U128,
+        el70x1_position::{
+            ExecuteEdge, init_position_control, position_input, position_output, write_execute,
+            write_position_output,
+        },
+    },
     io::{
         digital_input::{DigitalInputDevice, DigitalInputInput},
+        stepper_position_el70x1::{
+            StepperPositionEL70x1Device, StepperPositionEL70x1Input, StepperPositionEL70x1Output,
+        },
         stepper_velocity_el70x1::{
             StepperVelocityEL70x1Device, StepperVelocityEL70x1Input, StepperVelocityEL70x1Output,
         },
@@ -27,36 +36,27 @@ pub struct EL7031 {
     is_used: bool,
     pub configuration: EL7031Configuration,
     pub counter_wrapper: CounterWrapperU16U128,
+
+    // positioning interface
+    pub execute_edge: ExecuteEdge,
 }
 
 impl EthercatDeviceProcessing for EL7031 {
     fn input_post_process(&mut self) -> Result<(), anyhow::Error> {
-        let enc_status_compact = match &self.txpdo.enc_status_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_status_compact is None")),
-        };
-
-        // update the counter wrapper
-        self.counter_wrapper.update(
-            enc_status_compact.counter_value,
-            enc_status_compact.counter_underflow,
-            enc_status_compact.counter_overflow,
-        );
+        // the positioning presets map the 32 bit encoder status instead
+        if let Some(enc_status_compact) = &self.txpdo.enc_status_compact {
+            // update the counter wrapper
+            self.counter_wrapper.update(
+                enc_status_compact.counter_value,
+                enc_status_compact.counter_underflow,
+                enc_status_compact.counter_overflow,
+            );
+        }
 
         Ok(())
     }
 
     fn output_pre_process(&mut self) -> Result<(), anyhow::Error> {
-        let enc_status_compact = match &self.txpdo.enc_status_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_status_compact is None")),
-        };
-
-        let enc_control_compact = match &mut self.rxpdo.enc_control_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_control_compact is None")),
-        };
-
         let stm_status = match &self.txpdo.stm_status {
             Some(value) => value,
             None => return Err(anyhow!("stm_status is None")),
@@ -72,24 +72,36 @@ impl EthercatDeviceProcessing for EL7031 {
             stm_control.reset = true;
         }
 
-        // clear counter overflow/underflow flags by setting the counter to the current value
-        if enc_status_compact.counter_overflow || enc_status_compact.counter_underflow {
-            enc_control_compact.set_counter = true;
-            enc_control_compact.set_counter_value = enc_status_compact.counter_value;
-        }
-
-        // set counter
-        match self.counter_wrapper.pop_override() {
-            Some(new_counter) => {
+        if let (Some(enc_status_compact), Some(enc_control_compact)) = (
+            &self.txpdo.enc_status_compact,
+            &mut self.rxpdo.enc_control_compact,
+        ) {
+            // clear counter overflow/underflow flags by setting the counter to the current value
+            if enc_status_compact.counter_overflow || enc_status_compact.counter_underflow {
                 enc_control_compact.set_counter = true;
-                enc_control_compact.set_counter_value = new_counter;
+                enc_control_compact.set_counter_value = enc_status_compact.counter_value;
             }
-            None => {
-                enc_control_compact.set_counter = false;
-                enc_control_compact.set_counter_value = 0;
+
+            // set counter
+            match self.counter_wrapper.pop_override() {
+                Some(new_counter) => {
+                    enc_control_compact.set_counter = true;
+                    enc_control_compact.set_counter_value = new_counter;
+                }
+                None => {
+                    enc_control_compact.set_counter = false;
+                    enc_control_compact.set_counter_value = 0;
+                }
             }
         }
 
+        // rising edge of execute for the positioning interface
+        write_execute(
+            &mut self.execute_edge,
+            self.rxpdo.pos_control_compact.as_mut(),
+            self.rxpdo.pos_control.as_mut(),
+        );
+
         Ok(())
     }
 }
@@ -97,12 +109,19 @@ impl EthercatDeviceProcessing for EL7031 {
 impl NewEthercatDevice for EL7031 {
     fn new() -> Self {
         let configuration: EL7031Configuration = EL7031Configuration::default();
+        let mut rxpdo = configuration.pdo_assignment.rxpdo_assignment();
+        init_position_control(
+            rxpdo.pos_control.as_mut(),
+            &configuration.pos_configuration,
+            &configuration.pos_features,
+        );
         Self {
             txpdo: configuration.pdo_assignment.txpdo_assignment(),
-            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            rxpdo,
             is_used: false,
             configuration,
             counter_wrapper: CounterWrapperU16U128::new(),
+            execute_edge: ExecuteEdge::new(),
         }
     }
 }
@@ -255,6 +274,70 @@ impl DigitalInputDevice<EL7031DigitalInputPort> for EL7031 {
     }
 }
 
+impl StepperPositionEL70x1Device<EL7031StepperPort> for EL7031 {
+    fn set_position_output(
+        &mut self,
+        port: EL7031StepperPort,
+        value: StepperPositionEL70x1Output,
+    ) -> Result<(), anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031StepperPort::STM1 => write_position_output(
+                &value,
+                &mut self.execute_edge,
+                self.rxpdo.stm_control.as_mut(),
+                self.rxpdo.pos_control_compact.as_mut(),
+                self.rxpdo.pos_control.as_mut(),
+            ),
+        }
+    }
+
+    fn get_position_output(
+        &self,
+        port: EL7031StepperPort,
+    ) -> Result<StepperPositionEL70x1Output, anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031StepperPort::STM1 => position_output(
+                &self.execute_edge,
+                self.rxpdo.stm_control.as_ref(),
+                self.rxpdo.pos_control_compact.as_ref(),
+                self.rxpdo.pos_control.as_ref(),
+                &self.configuration.pos_configuration,
+                &self.configuration.pos_features,
+            ),
+        }
+    }
+
+    fn get_position_input(
+        &self,
+        port: EL7031StepperPort,
+    ) -> Result<StepperPositionEL70x1Input, anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031StepperPort::STM1 => position_input(
+                self.txpdo.stm_status.as_ref(),
+                self.txpdo.pos_status_compact.as_ref(),
+                self.txpdo.pos_status.as_ref(),
+                self.txpdo.enc_status.as_ref(),
+            ),
+        }
+    }
+}
+
+impl EL7031 {
+    fn check_positioning(&self) -> Result<(), anyhow::Error> {
+        let operation_mode = self.configuration.stm_features.operation_mode;
+        if !operation_mode.supports_positioning() {
+            return Err(anyhow!(
+                "Operation mode is not position controller, but {:?}",
+                operation_mode
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum EL7031StepperPort {
     STM1,
@@ -1,6 +1,8 @@
 use crate::{
     coe::{ConfigurableDevice, Configuration},
-    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    helpers::{
+        el70x1_position::init_position_control, ethercrab_types::EthercrabSubDevicePreoperational,
+    },
     pdo::PredefinedPdoAssignment,
     shared_config::el70x1::{
         EL70x1InfoData, EL70x1InputFunction, EL70x1OperationMode, EL70x1SpeedRange,
@@ -102,6 +104,11 @@ impl ConfigurableDevice<EL7031_0030Configuration> for EL7031_0030 {
         self.configuration = config.clone();
         self.txpdo = config.pdo_assignment.txpdo_assignment();
         self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        init_position_control(
+            self.rxpdo.pos_control.as_mut(),
+            &config.pos_configuration,
+            &config.pos_features,
+        );
         Ok(())
     }
 
@@ -9,11 +9,19 @@ use units::{electric_potential::volt, f64::ElectricPotential};
 
 use crate::{
     helpers::{
-        counter_wrapper_u16_i128::CounterWrapperU16U128, signing_converter_u16::U16SigningConverter,
+        counter_wrapper_u16_i128::CounterWrapperU16U128,
+        el70x1_position::{
+            ExecuteEdge, init_position_control, position_input, position_output, write_execute,
+            write_position_output,
+        },
+        signing_converter_u16::U16SigningConverter,
     },
     io::{
         analog_input::{AnalogInputDevice, AnalogInputInput, physical::AnalogInputRange},
         digital_input::{DigitalInputDevice, DigitalInputInput},
+        stepper_position_el70x1::{
+            StepperPositionEL70x1Device, StepperPositionEL70x1Input, StepperPositionEL70x1Output,
+        },
         stepper_velocity_el70x1::{
             StepperVelocityEL70x1Device, StepperVelocityEL70x1Input, StepperVelocityEL70x1Output,
         },
@@ -31,36 +39,27 @@ pub struct EL7031_0030 {
     is_used: bool,
     pub configuration: EL7031_0030Configuration,
     pub counter_wrapper: CounterWrapperU16U128,
+
+    // positioning interface
+    pub execute_edge: ExecuteEdge,
 }
 
 impl EthercatDeviceProcessing for EL7031_0030 {
     fn input_post_process(&mut self) -> Result<(), anyhow::Error> {
-        let enc_status_compact = match &self.txpdo.enc_status_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_status_compact is None")),
-        };
-
-        // update the counter wrapper
-        self.counter_wrapper.update(
-            enc_status_compact.counter_value,
-            enc_status_compact.counter_underflow,
-            enc_status_compact.counter_overflow,
-        );
+        // the positioning presets map the 32 bit encoder status instead
+        if let Some(enc_status_compact) = &self.txpdo.enc_status_compact {
+            // update the counter wrapper
+            self.counter_wrapper.update(
+                enc_status_compact.counter_value,
+                enc_status_compact.counter_underflow,
+                enc_status_compact.counter_overflow,
+            );
+        }
 
         Ok(())
     }
 
     fn output_pre_process(&mut self) -> Result<(), anyhow::Error> {
-        let enc_status_compact = match &self.txpdo.enc_status_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_status_compact is None")),
-        };
-
-        let enc_control_compact = match &mut self.rxpdo.enc_control_compact {
-            Some(value) => value,
-            None => return Err(anyhow!("enc_control_compact is None")),
-        };
-
         let stm_status = match &self.txpdo.stm_status {
             Some(value) => value,
             None => return Err(anyhow!("stm_status is None")),
@@ -76,24 +75,36 @@ impl EthercatDeviceProcessing for EL7031_0030 {
             stm_control.reset = true;
         }
 
-        // clear counter overflow/underflow flags by setting the counter to the current value
-        if enc_status_compact.counter_overflow || enc_status_compact.counter_underflow {
-            enc_control_compact.set_counter = true;
-            enc_control_compact.set_counter_value = enc_status_compact.counter_value;
-        }
-
-        // set counter
-        match self.counter_wrapper.pop_override() {
-            Some(new_counter) => {
+        if let (Some(enc_status_compact), Some(enc_control_compact)) = (
+            &self.txpdo.enc_status_compact,
+            &mut self.rxpdo.enc_control_compact,
+        ) {
+            // clear counter overflow/underflow flags by setting the counter to the current value
+            if enc_status_compact.counter_overflow || enc_status_compact.counter_underflow {
                 enc_control_compact.set_counter = true;
-                enc_control_compact.set_counter_value = new_counter;
+                enc_control_compact.set_counter_value = enc_status_compact.counter_value;
             }
-            None => {
-                enc_control_compact.set_counter = false;
-                enc_control_compact.set_counter_value = 0;
+
+            // set counter
+            match self.counter_wrapper.pop_override() {
+                Some(new_counter) => {
+                    enc_control_compact.set_counter = true;
+                    enc_control_compact.set_counter_value = new_counter;
+                }
+                None => {
+                    enc_control_compact.set_counter = false;
+                    enc_control_compact.set_counter_value = 0;
+                }
             }
         }
 
+        // rising edge of execute for the positioning interface
+        write_execute(
+            &mut self.execute_edge,
+            self.rxpdo.pos_control_compact.as_mut(),
+            self.rxpdo.pos_control.as_mut(),
+        );
+
         Ok(())
     }
 }
@@ -101,12 +112,19 @@ impl EthercatDeviceProcessing for EL7031_0030 {
 impl NewEthercatDevice for EL7031_0030 {
     fn new() -> Self {
         let configuration: EL7031_0030Configuration = EL7031_0030Configuration::default();
+        let mut rxpdo = configuration.pdo_assignment.rxpdo_assignment();
+        init_position_control(
+            rxpdo.pos_control.as_mut(),
+            &configuration.pos_configuration,
+            &configuration.pos_features,
+        );
         Self {
             txpdo: configuration.pdo_assignment.txpdo_assignment(),
-            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            rxpdo,
             is_used: false,
             configuration,
             counter_wrapper: CounterWrapperU16U128::new(),
+            execute_edge: ExecuteEdge::new(),
         }
     }
 }
@@ -318,6 +336,7 @@ impl AnalogInputDevice<EL7031_0030AnalogInputPort> for EL7031_0030 {
         AnalogInputInput {
             normalized,
             wiring_error,
+            ..Default::default()
         }
     }
 
@@ -331,6 +350,70 @@ impl AnalogInputDevice<EL7031_0030AnalogInputPort> for EL7031_0030 {
     }
 }
 
+impl StepperPositionEL70x1Device<EL7031_0030StepperPort> for EL7031_0030 {
+    fn set_position_output(
+        &mut self,
+        port: EL7031_0030StepperPort,
+        value: StepperPositionEL70x1Output,
+    ) -> Result<(), anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031_0030StepperPort::STM1 => write_position_output(
+                &value,
+                &mut self.execute_edge,
+                self.rxpdo.stm_control.as_mut(),
+                self.rxpdo.pos_control_compact.as_mut(),
+                self.rxpdo.pos_control.as_mut(),
+            ),
+        }
+    }
+
+    fn get_position_output(
+        &self,
+        port: EL7031_0030StepperPort,
+    ) -> Result<StepperPositionEL70x1Output, anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031_0030StepperPort::STM1 => position_output(
+                &self.execute_edge,
+                self.rxpdo.stm_control.as_ref(),
+                self.rxpdo.pos_control_compact.as_ref(),
+                self.rxpdo.pos_control.as_ref(),
+                &self.configuration.pos_configuration,
+                &self.configuration.pos_features,
+            ),
+        }
+    }
+
+    fn get_position_input(
+        &self,
+        port: EL7031_0030StepperPort,
+    ) -> Result<StepperPositionEL70x1Input, anyhow::Error> {
+        self.check_positioning()?;
+        match port {
+            EL7031_0030StepperPort::STM1 => position_input(
+                self.txpdo.stm_status.as_ref(),
+                self.txpdo.pos_status_compact.as_ref(),
+                self.txpdo.pos_status.as_ref(),
+                self.txpdo.enc_status.as_ref(),
+            ),
+        }
+    }
+}
+
+impl EL7031_0030 {
+    fn check_positioning(&self) -> Result<(), anyhow::Error> {
+        let operation_mode = self.configuration.stm_features.operation_mode;
+        if !operation_mode.supports_positioning() {
+            return Err(anyhow!(
+                "Operation mode is not position controller, but {:?}",
+                operation_mode
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum EL7031_0030StepperPort {
     STM1,
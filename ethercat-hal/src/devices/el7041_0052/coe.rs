@@ -1,6 +1,8 @@
 use crate::{
     coe::{ConfigurableDevice, Configuration},
-    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    helpers::{
+        el70x1_position::init_position_control, ethercrab_types::EthercrabSubDevicePreoperational,
+    },
     pdo::PredefinedPdoAssignment,
     shared_config::el70x1::{
         EncConfiguration, PosConfiguration, PosFeatures, StmControllerConfiguration, StmFeatures,
@@ -87,6 +89,11 @@ impl ConfigurableDevice<EL7041_0052Configuration> for EL7041_0052 {
         self.configuration = config.clone();
         self.txpdo = config.pdo_assignment.txpdo_assignment();
         self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        init_position_control(
+            self.rxpdo.pos_control.as_mut(),
+            &config.pos_configuration,
+            &config.pos_features,
+        );
         Ok(())
     }
 
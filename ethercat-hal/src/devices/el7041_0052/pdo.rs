@@ -240,7 +240,7 @@ impl PredefinedPdoAssignment<EL7041_0052TxPdo, EL7041_0052RxPdo>
                 stm_velocity: None,
                 pos_control_compact: Some(PosControlCompact::default()),
                 pos_control: None,
-                pos_control_2: Some(PosControl2::default()),
+                pos_control_2: None,
             },
             Self::PositionInterface | Self::PositionInterfaceWithInfoData => EL7041_0052RxPdo {
                 enc_control_compact: None,
@@ -0,0 +1,305 @@
+use super::{EthercatDeviceProcessing, NewEthercatDevice, SubDeviceIdentityTuple};
+use crate::pdo::RxPdo;
+use crate::pdo::TxPdo;
+use crate::{
+    coe::{ConfigurableDevice, Configuration},
+    helpers::{
+        cia402::{Cia402Axis, Cia402Mode},
+        ethercrab_types::EthercrabSubDevicePreoperational,
+    },
+    io::servo_drive::{ServoDriveDevice, ServoDriveInput, ServoDriveOutput},
+    pdo::{
+        PredefinedPdoAssignment,
+        el72x1::{
+            DrvControlword, DrvStatusword, DrvTargetPosition, DrvTargetVelocity,
+            DrvVelocityActualValue, FbPosition,
+        },
+    },
+};
+use anyhow::anyhow;
+use ethercat_hal_derive::{EthercatDevice, RxPdo, TxPdo};
+
+/// EL7211/EL7221 1-channel servo motor terminal
+///
+/// Both terminals share the object dictionary, the EL7221 has a higher output current.
+#[derive(EthercatDevice)]
+pub struct EL72x1 {
+    pub configuration: EL72x1Configuration,
+    pub txpdo: EL72x1TxPdo,
+    pub rxpdo: EL72x1RxPdo,
+    pub axis: Cia402Axis,
+    is_used: bool,
+}
+
+impl EthercatDeviceProcessing for EL72x1 {
+    fn output_pre_process(&mut self) -> Result<(), anyhow::Error> {
+        let statusword = match &self.txpdo.statusword {
+            Some(value) => value.statusword,
+            None => return Err(anyhow!("statusword is None")),
+        };
+        let position = match &self.txpdo.fb_position {
+            Some(value) => value.position as i32,
+            None => return Err(anyhow!("fb_position is None")),
+        };
+
+        let controlword = self.axis.update(statusword, position);
+
+        match &mut self.rxpdo.controlword {
+            Some(value) => value.controlword = controlword,
+            None => return Err(anyhow!("controlword is None")),
+        }
+        if let Some(value) = &mut self.rxpdo.target_velocity {
+            value.velocity = self.axis.output.target_velocity;
+        }
+        if let Some(value) = &mut self.rxpdo.target_position {
+            value.position = self.axis.output.target_position as u32;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for EL72x1 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EL72x1")
+    }
+}
+
+impl NewEthercatDevice for EL72x1 {
+    fn new() -> Self {
+        let configuration = EL72x1Configuration::default();
+        Self {
+            txpdo: configuration.pdo_assignment.txpdo_assignment(),
+            rxpdo: configuration.pdo_assignment.rxpdo_assignment(),
+            configuration,
+            axis: Cia402Axis::new(),
+            is_used: false,
+        }
+    }
+}
+
+impl ServoDriveDevice<EL72x1Port> for EL72x1 {
+    fn set_output(
+        &mut self,
+        port: EL72x1Port,
+        value: ServoDriveOutput,
+    ) -> Result<(), anyhow::Error> {
+        match port {
+            EL72x1Port::SRV1 => self.axis.output = value,
+        }
+        Ok(())
+    }
+
+    fn get_output(&self, port: EL72x1Port) -> Result<ServoDriveOutput, anyhow::Error> {
+        match port {
+            EL72x1Port::SRV1 => Ok(self.axis.output.clone()),
+        }
+    }
+
+    fn get_input(&self, port: EL72x1Port) -> Result<ServoDriveInput, anyhow::Error> {
+        match port {
+            EL72x1Port::SRV1 => {
+                let position = match &self.txpdo.fb_position {
+                    Some(value) => value.position as i32,
+                    None => return Err(anyhow!("fb_position is None")),
+                };
+                let velocity = match &self.txpdo.velocity_actual {
+                    Some(value) => value.velocity,
+                    None => return Err(anyhow!("velocity_actual is None")),
+                };
+                let status = self.axis.status();
+                Ok(ServoDriveInput {
+                    state: status.state,
+                    warning: status.warning,
+                    target_reached: status.target_reached,
+                    following_error: status.following_error,
+                    position,
+                    velocity,
+                })
+            }
+        }
+    }
+
+    fn get_mode(&self, _port: EL72x1Port) -> Cia402Mode {
+        self.configuration.pdo_assignment.mode()
+    }
+}
+
+impl ConfigurableDevice<EL72x1Configuration> for EL72x1 {
+    async fn write_config<'maindevice>(
+        &mut self,
+        device: &EthercrabSubDevicePreoperational<'maindevice>,
+        config: &EL72x1Configuration,
+    ) -> Result<(), anyhow::Error> {
+        config.write_config(device).await?;
+        self.configuration = config.clone();
+        self.txpdo = config.pdo_assignment.txpdo_assignment();
+        self.rxpdo = config.pdo_assignment.rxpdo_assignment();
+        Ok(())
+    }
+
+    fn get_config(&self) -> EL72x1Configuration {
+        self.configuration.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL72x1Configuration {
+    /// Also selects the mode of operation
+    pub pdo_assignment: EL72x1PredefinedPdoAssignment,
+
+    // 8010:14
+    // Torque limitation in 0.1% of the rated current
+    pub torque_limitation: u16,
+
+    // 8011:11
+    // Maximum velocity of the motor in rpm
+    pub max_velocity: u32,
+}
+
+impl Default for EL72x1Configuration {
+    fn default() -> Self {
+        Self {
+            pdo_assignment: EL72x1PredefinedPdoAssignment::CyclicSynchronousVelocity,
+            torque_limitation: 1000,
+            max_velocity: 3000,
+        }
+    }
+}
+
+impl Configuration for EL72x1Configuration {
+    async fn write_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+    ) -> Result<(), anyhow::Error> {
+        // 7010:03 Modes of operation
+        device
+            .sdo_write(0x7010, 0x03, u8::from(self.pdo_assignment.mode()))
+            .await?;
+        device
+            .sdo_write(0x8010, 0x14, self.torque_limitation)
+            .await?;
+        device.sdo_write(0x8011, 0x11, self.max_velocity).await?;
+        self.pdo_assignment
+            .txpdo_assignment()
+            .write_config(device)
+            .await?;
+        self.pdo_assignment
+            .rxpdo_assignment()
+            .write_config(device)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EL72x1Port {
+    SRV1,
+}
+
+#[derive(Debug, Clone, TxPdo)]
+pub struct EL72x1TxPdo {
+    #[pdo_object_index(0x1A00)]
+    pub fb_position: Option<FbPosition>,
+    #[pdo_object_index(0x1A01)]
+    pub statusword: Option<DrvStatusword>,
+    #[pdo_object_index(0x1A02)]
+    pub velocity_actual: Option<DrvVelocityActualValue>,
+}
+
+#[derive(Debug, Clone, RxPdo)]
+pub struct EL72x1RxPdo {
+    #[pdo_object_index(0x1600)]
+    pub controlword: Option<DrvControlword>,
+    #[pdo_object_index(0x1601)]
+    pub target_velocity: Option<DrvTargetVelocity>,
+    #[pdo_object_index(0x1606)]
+    pub target_position: Option<DrvTargetPosition>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EL72x1PredefinedPdoAssignment {
+    /// Cyclic synchronous velocity mode
+    CyclicSynchronousVelocity,
+    /// Cyclic synchronous position mode
+    CyclicSynchronousPosition,
+}
+
+impl EL72x1PredefinedPdoAssignment {
+    pub const fn mode(&self) -> Cia402Mode {
+        match self {
+            Self::CyclicSynchronousVelocity => Cia402Mode::CyclicSynchronousVelocity,
+            Self::CyclicSynchronousPosition => Cia402Mode::CyclicSynchronousPosition,
+        }
+    }
+}
+
+impl PredefinedPdoAssignment<EL72x1TxPdo, EL72x1RxPdo> for EL72x1PredefinedPdoAssignment {
+    fn txpdo_assignment(&self) -> EL72x1TxPdo {
+        EL72x1TxPdo {
+            fb_position: Some(FbPosition::default()),
+            statusword: Some(DrvStatusword::default()),
+            velocity_actual: Some(DrvVelocityActualValue::default()),
+        }
+    }
+
+    fn rxpdo_assignment(&self) -> EL72x1RxPdo {
+        match self {
+            Self::CyclicSynchronousVelocity => EL72x1RxPdo {
+                controlword: Some(DrvControlword::default()),
+                target_velocity: Some(DrvTargetVelocity::default()),
+                target_position: None,
+            },
+            Self::CyclicSynchronousPosition => EL72x1RxPdo {
+                controlword: Some(DrvControlword::default()),
+                target_velocity: None,
+                target_position: Some(DrvTargetPosition::default()),
+            },
+        }
+    }
+}
+
+pub const EL72X1_VENDOR_ID: u32 = 0x2;
+pub const EL7211_PRODUCT_ID: u32 = 0x1c2b3052;
+pub const EL7221_PRODUCT_ID: u32 = 0x1c353052;
+pub const EL72X1_REVISION_A: u32 = 0x00110000;
+
+pub const EL7211_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL72X1_VENDOR_ID, EL7211_PRODUCT_ID, EL72X1_REVISION_A);
+
+pub const EL7221_IDENTITY_A: SubDeviceIdentityTuple =
+    (EL72X1_VENDOR_ID, EL7221_PRODUCT_ID, EL72X1_REVISION_A);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::cia402::{CONTROLWORD_SHUTDOWN, Cia402State};
+
+    #[test]
+    fn test_output_pre_process() {
+        let mut device = EL72x1::new();
+        device
+            .set_output(
+                EL72x1Port::SRV1,
+                ServoDriveOutput {
+                    enable: true,
+                    target_velocity: 500,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // switch on disabled
+        device.txpdo.statusword.as_mut().unwrap().statusword = 0x0250;
+        device.output_pre_process().unwrap();
+
+        let rxpdo = &device.rxpdo;
+        assert_eq!(
+            rxpdo.controlword.as_ref().unwrap().controlword,
+            CONTROLWORD_SHUTDOWN
+        );
+        assert_eq!(rxpdo.target_velocity.as_ref().unwrap().velocity, 500);
+        assert_eq!(
+            device.get_input(EL72x1Port::SRV1).unwrap().state,
+            Cia402State::SwitchOnDisabled
+        );
+    }
+}
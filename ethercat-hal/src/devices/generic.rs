@@ -0,0 +1,366 @@
+use super::{
+    EthercatDevice, EthercatDeviceProcessing, EthercatDeviceUsed, Module, NewEthercatDevice,
+    SubDeviceIdentityTuple,
+};
+use crate::{
+    helpers::ethercrab_types::EthercrabSubDevicePreoperational,
+    io::{
+        digital_input::{DigitalInputDevice, DigitalInputInput},
+        digital_output::{DigitalOutputDevice, DigitalOutputOutput},
+    },
+    sii::{SiiPdo, SiiPdos, read_sii_pdos},
+};
+use bitvec::{field::BitField, prelude::*};
+use ethercrab::MainDevice;
+
+/// Fallback for SubDevices without a driver
+///
+/// The process image is sized from the PDOs in the SII of the SubDevice, see [`GenericEthercatDevice::configure_from_sii`].
+/// Machines can access the raw bits, the named PDO entries or use single bits as digital IO with the bit offset as port.
+pub struct GenericEthercatDevice {
+    identity: SubDeviceIdentityTuple,
+    pdos: SiiPdos,
+    input_entries: Vec<GenericPdoEntry>,
+    output_entries: Vec<GenericPdoEntry>,
+    inputs: BitVec<u8, Lsb0>,
+    outputs: BitVec<u8, Lsb0>,
+    is_used: bool,
+}
+
+/// A PDO entry and its position in the process image of the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericPdoEntry {
+    pub pdo_index: u16,
+    pub pdo_name: String,
+    pub index: u16,
+    pub sub_index: u8,
+    pub name: String,
+    pub data_type: u8,
+    pub bit_offset: usize,
+    pub bit_len: usize,
+}
+
+impl GenericPdoEntry {
+    /// Entry names often repeat across channels, the PDO name tells them apart
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.pdo_name, self.name)
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.full_name() == name
+    }
+}
+
+impl GenericEthercatDevice {
+    pub fn from_identity(identity: SubDeviceIdentityTuple) -> Self {
+        Self {
+            identity,
+            ..Self::new()
+        }
+    }
+
+    pub const fn identity(&self) -> SubDeviceIdentityTuple {
+        self.identity
+    }
+
+    pub const fn pdos(&self) -> &SiiPdos {
+        &self.pdos
+    }
+
+    /// Reads the PDOs from the SII and sizes the process image, has to be done in PRE-OP
+    pub async fn configure_from_sii(
+        &mut self,
+        subdevice: &EthercrabSubDevicePreoperational<'_>,
+        maindevice: &MainDevice<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let pdos = read_sii_pdos(subdevice, maindevice).await?;
+        self.set_pdos(pdos);
+        Ok(())
+    }
+
+    /// Lays out the PDOs which are assigned by default like the MainDevice does
+    ///
+    /// PDOs are ordered by sync manager, every sync manager starts at a whole byte.
+    pub fn set_pdos(&mut self, pdos: SiiPdos) {
+        let (input_entries, input_bits) = layout(&pdos.txpdos);
+        let (output_entries, output_bits) = layout(&pdos.rxpdos);
+        self.input_entries = input_entries;
+        self.output_entries = output_entries;
+        self.inputs = bitvec![u8, Lsb0; 0; input_bits];
+        self.outputs = bitvec![u8, Lsb0; 0; output_bits];
+        self.pdos = pdos;
+    }
+
+    /// Input process image of the last cycle
+    pub fn inputs(&self) -> &BitSlice<u8, Lsb0> {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &BitSlice<u8, Lsb0> {
+        &self.outputs
+    }
+
+    /// Output process image for the next cycle
+    pub fn outputs_mut(&mut self) -> &mut BitSlice<u8, Lsb0> {
+        &mut self.outputs
+    }
+
+    pub fn input_entries(&self) -> &[GenericPdoEntry] {
+        &self.input_entries
+    }
+
+    pub fn output_entries(&self) -> &[GenericPdoEntry] {
+        &self.output_entries
+    }
+
+    /// Raw value of an input entry by its name or `<PDO name>.<entry name>`
+    ///
+    /// `None` if there is no such entry or it is longer than 64 bits.
+    pub fn input_value(&self, name: &str) -> Option<u64> {
+        let entry = self
+            .input_entries
+            .iter()
+            .find(|entry| entry.matches(name))?;
+        load(&self.inputs, entry)
+    }
+
+    /// Raw value of an output entry by its name or `<PDO name>.<entry name>`
+    pub fn output_value(&self, name: &str) -> Option<u64> {
+        let entry = self
+            .output_entries
+            .iter()
+            .find(|entry| entry.matches(name))?;
+        load(&self.outputs, entry)
+    }
+
+    /// Sets an output entry by its name or `<PDO name>.<entry name>`, excess bits of `value` are dropped
+    pub fn set_output_value(&mut self, name: &str, value: u64) -> Result<(), anyhow::Error> {
+        let entry = self
+            .output_entries
+            .iter()
+            .find(|entry| entry.matches(name))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "[{}::GenericEthercatDevice::set_output_value] No output entry {}",
+                    module_path!(),
+                    name
+                )
+            })?;
+        let bits = self
+            .outputs
+            .get_mut(entry.bit_offset..entry.bit_offset + entry.bit_len)
+            .filter(|bits| bits.len() <= 64)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "[{}::GenericEthercatDevice::set_output_value] Output entry {} is outside of the process image or longer than 64 bits",
+                    module_path!(),
+                    name
+                )
+            })?;
+        bits.store_le(value);
+        Ok(())
+    }
+}
+
+fn load(image: &BitSlice<u8, Lsb0>, entry: &GenericPdoEntry) -> Option<u64> {
+    image
+        .get(entry.bit_offset..entry.bit_offset + entry.bit_len)
+        .filter(|bits| !bits.is_empty() && bits.len() <= 64)
+        .map(|bits| bits.load_le::<u64>())
+}
+
+/// Named entries of the assigned PDOs and the size of the process image in bits
+fn layout(pdos: &[SiiPdo]) -> (Vec<GenericPdoEntry>, usize) {
+    let mut sync_managers: Vec<u8> = pdos.iter().filter_map(|pdo| pdo.sync_manager).collect();
+    sync_managers.sort_unstable();
+    sync_managers.dedup();
+
+    let mut entries = vec![];
+    let mut offset = 0;
+    for sync_manager in sync_managers {
+        for pdo in pdos
+            .iter()
+            .filter(|pdo| pdo.sync_manager == Some(sync_manager))
+        {
+            for entry in &pdo.entries {
+                if !entry.is_padding() {
+                    entries.push(GenericPdoEntry {
+                        pdo_index: pdo.index,
+                        pdo_name: pdo.name.clone(),
+                        index: entry.index,
+                        sub_index: entry.sub_index,
+                        name: entry.name.clone(),
+                        data_type: entry.data_type,
+                        bit_offset: offset,
+                        bit_len: usize::from(entry.bit_len),
+                    });
+                }
+                offset += usize::from(entry.bit_len);
+            }
+        }
+        offset = offset.div_ceil(8) * 8;
+    }
+    (entries, offset)
+}
+
+impl std::fmt::Debug for GenericEthercatDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GenericEthercatDevice(vendor_id: 0x{:x}, product_id: 0x{:x}, revision: 0x{:x})",
+            self.identity.0, self.identity.1, self.identity.2
+        )
+    }
+}
+
+impl NewEthercatDevice for GenericEthercatDevice {
+    fn new() -> Self {
+        Self {
+            identity: (0, 0, 0),
+            pdos: SiiPdos::default(),
+            input_entries: vec![],
+            output_entries: vec![],
+            inputs: BitVec::new(),
+            outputs: BitVec::new(),
+            is_used: false,
+        }
+    }
+}
+
+impl EthercatDeviceProcessing for GenericEthercatDevice {}
+
+impl EthercatDevice for GenericEthercatDevice {
+    /// Follows the actual size of the process image if it differs from the SII,
+    /// e.g. because the PDO assignment was changed through CoE
+    fn input(&mut self, input: &BitSlice<u8, Lsb0>) -> Result<(), anyhow::Error> {
+        if self.inputs.len() != input.len() {
+            self.inputs.resize(input.len(), false);
+        }
+        self.inputs.copy_from_bitslice(input);
+        Ok(())
+    }
+
+    fn input_len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    fn output(&self, output: &mut BitSlice<u8, Lsb0>) -> Result<(), anyhow::Error> {
+        let len = output.len().min(self.outputs.len());
+        output[..len].copy_from_bitslice(&self.outputs[..len]);
+        Ok(())
+    }
+
+    fn output_len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn is_module(&self) -> bool {
+        false
+    }
+
+    fn get_module(&self) -> Option<Module> {
+        None
+    }
+
+    fn set_module(&mut self, _module: Module) {}
+}
+
+impl EthercatDeviceUsed for GenericEthercatDevice {
+    fn is_used(&self) -> bool {
+        self.is_used
+    }
+
+    fn set_used(&mut self, used: bool) {
+        self.is_used = used;
+    }
+}
+
+/// Port is the bit offset in the input process image
+impl DigitalInputDevice<usize> for GenericEthercatDevice {
+    fn get_input(&self, port: usize) -> Result<DigitalInputInput, anyhow::Error> {
+        let value = self.inputs.get(port).map(|bit| *bit).ok_or_else(|| {
+            anyhow::anyhow!(
+                "[{}::GenericEthercatDevice::get_input] Bit {} is outside of the {} input bits",
+                module_path!(),
+                port,
+                self.inputs.len()
+            )
+        })?;
+        Ok(DigitalInputInput { value })
+    }
+}
+
+/// Port is the bit offset in the output process image, bits outside of it are ignored
+impl DigitalOutputDevice<usize> for GenericEthercatDevice {
+    fn set_output(&mut self, port: usize, value: DigitalOutputOutput) {
+        if let Some(mut bit) = self.outputs.get_mut(port) {
+            *bit = value.into();
+        }
+    }
+
+    fn get_output(&self, port: usize) -> DigitalOutputOutput {
+        self.outputs.get(port).is_some_and(|bit| *bit).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sii::tests::categories;
+
+    fn device() -> GenericEthercatDevice {
+        let mut device = GenericEthercatDevice::from_identity((0x2, 0x1234, 0x1));
+        device.set_pdos(SiiPdos::from_categories(&categories()).unwrap());
+        device
+    }
+
+    #[test]
+    fn test_layout() {
+        let device = device();
+        // 1 + 7 padding + 16, the unassigned PDO is not part of the image
+        assert_eq!(device.input_len(), 24);
+        assert_eq!(device.output_len(), 8);
+
+        let entries = device.input_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].full_name(), "Channel.Input");
+        assert_eq!(entries[1].bit_offset, 8);
+        assert_eq!(entries[1].bit_len, 16);
+    }
+
+    #[test]
+    fn test_process_image() {
+        let mut device = device();
+        let input = [0b1u8, 0x34, 0x12];
+        device.input(input.view_bits()).unwrap();
+        assert_eq!(device.input_value("Input"), Some(1));
+        assert_eq!(device.input_value("Channel.Value"), Some(0x1234));
+        assert_eq!(device.input_value("Missing"), None);
+        assert!(device.get_input(0).unwrap().value);
+        assert!(device.get_input(24).is_err());
+
+        device.set_output_value("Output", 1).unwrap();
+        assert!(device.set_output_value("Missing", 1).is_err());
+        assert!(device.get_output(0).0);
+        device.set_output(0, false.into());
+        device.set_output(100, true.into());
+        assert_eq!(device.output_value("Output"), Some(0));
+
+        // the image follows a larger PDI
+        let input = [0xFFu8; 4];
+        device.input(input.view_bits()).unwrap();
+        assert_eq!(device.input_len(), 32);
+
+        let mut output = [0xFFu8; 2];
+        device.output(output.view_bits_mut()).unwrap();
+        assert_eq!(output, [0x00, 0xFF]);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cia402;
 pub mod ek1100;
 pub mod el1002;
 pub mod el1008;
@@ -7,19 +8,27 @@ pub mod el2008;
 pub mod el2024;
 pub mod el2521;
 pub mod el2522;
+pub mod el2535;
 pub mod el2634;
 pub mod el2809;
 pub mod el3001;
 pub mod el3021;
 pub mod el3024;
 pub mod el3062_0030;
+pub mod el30x4;
 pub mod el3204;
+pub mod el3314;
+pub mod el3318;
+pub mod el3356;
 pub mod el4002;
+pub mod el40x4;
 pub mod el5152;
 pub mod el6021;
 pub mod el7031;
 pub mod el7031_0030;
 pub mod el7041_0052;
+pub mod el72x1;
+pub mod generic;
 pub mod wago_750_354;
 pub mod wago_modules;
 
@@ -31,6 +40,12 @@ use crate::{
 use anyhow::anyhow;
 use bitvec::{order::Lsb0, slice::BitSlice};
 use ek1100::{EK1100, EK1100_IDENTITY_A};
+use el30x4::{
+    EL30x4, EL30x4Variant, EL3054_IDENTITY_A, EL3064_IDENTITY_A, EL3104_IDENTITY_A,
+    EL3164_IDENTITY_A,
+};
+use el40x4::{EL40x4, EL40x4Variant, EL4004_IDENTITY_A, EL4024_IDENTITY_A, EL4104_IDENTITY_A};
+use el72x1::{EL7211_IDENTITY_A, EL7221_IDENTITY_A};
 use el1002::{EL1002, EL1002_IDENTITY_A};
 use el1008::EL1008_IDENTITY_A;
 use el2002::{EL2002, EL2002_IDENTITY_A, EL2002_IDENTITY_B};
@@ -38,12 +53,16 @@ use el2004::{EL2004, EL2004_IDENTITY_A};
 use el2008::{EL2008, EL2008_IDENTITY_A, EL2008_IDENTITY_B};
 use el2521::{EL2521_IDENTITY_0000_A, EL2521_IDENTITY_0000_B, EL2521_IDENTITY_0024_A};
 use el2522::{EL2522, EL2522_IDENTITY_A};
+use el2535::{EL2535, EL2535_IDENTITY_A};
 use el3001::EL3001_IDENTITY_A;
 use el3021::EL3021_IDENTITY_A;
 use el3024::EL3024_IDENTITY_A;
 use el3062_0030::EL3062_0030_IDENTITY_A;
 use el3204::EL3204_IDENTITY_A;
 use el3204::EL3204_IDENTITY_B;
+use el3314::{EL3314_IDENTITY_A, EL3314_IDENTITY_B};
+use el3318::EL3318_IDENTITY_A;
+use el3356::{EL3356_IDENTITY_0000_A, EL3356_IDENTITY_0010_A};
 use el4002::EL4002_IDENTITY_A;
 use el5152::{EL5152, EL5152_IDENTITY_A};
 use el6021::{EL6021_IDENTITY_A, EL6021_IDENTITY_B, EL6021_IDENTITY_C, EL6021_IDENTITY_D};
@@ -210,6 +229,8 @@ pub async fn downcast_device<T: EthercatDevice>(
 }
 
 /// Construct a device from a subdevice name
+///
+/// SubDevices without a driver get a [`generic::GenericEthercatDevice`].
 pub fn device_from_subdevice_identity_tuple(
     subdevice_identity_tuple: SubDeviceIdentityTuple,
 ) -> Result<Arc<RwLock<dyn EthercatDevice>>, anyhow::Error> {
@@ -223,29 +244,62 @@ pub fn device_from_subdevice_identity_tuple(
         EL2004_IDENTITY_A => Ok(Arc::new(RwLock::new(EL2004::new()))),
         EL2008_IDENTITY_A | EL2008_IDENTITY_B => Ok(Arc::new(RwLock::new(EL2008::new()))),
         EL2522_IDENTITY_A => Ok(Arc::new(RwLock::new(EL2522::new()))),
+        EL2535_IDENTITY_A => Ok(Arc::new(RwLock::new(EL2535::new()))),
         EL3001_IDENTITY_A => Ok(Arc::new(RwLock::new(el3001::EL3001::new()))),
         EL3021_IDENTITY_A => Ok(Arc::new(RwLock::new(el3021::EL3021::new()))),
         EL3024_IDENTITY_A => Ok(Arc::new(RwLock::new(el3024::EL3024::new()))),
         EL3062_0030_IDENTITY_A => Ok(Arc::new(RwLock::new(el3062_0030::EL3062_0030::new()))),
+        EL3054_IDENTITY_A => Ok(Arc::new(RwLock::new(EL30x4::from_variant(
+            EL30x4Variant::EL3054,
+        )))),
+        EL3064_IDENTITY_A => Ok(Arc::new(RwLock::new(EL30x4::from_variant(
+            EL30x4Variant::EL3064,
+        )))),
+        EL3104_IDENTITY_A => Ok(Arc::new(RwLock::new(EL30x4::from_variant(
+            EL30x4Variant::EL3104,
+        )))),
+        EL3164_IDENTITY_A => Ok(Arc::new(RwLock::new(EL30x4::from_variant(
+            EL30x4Variant::EL3164,
+        )))),
         EL4002_IDENTITY_A => Ok(Arc::new(RwLock::new(EL4002::new()))),
+        EL4004_IDENTITY_A => Ok(Arc::new(RwLock::new(EL40x4::from_variant(
+            EL40x4Variant::EL4004,
+        )))),
+        EL4024_IDENTITY_A => Ok(Arc::new(RwLock::new(EL40x4::from_variant(
+            EL40x4Variant::EL4024,
+        )))),
+        EL4104_IDENTITY_A => Ok(Arc::new(RwLock::new(EL40x4::from_variant(
+            EL40x4Variant::EL4104,
+        )))),
         EL5152_IDENTITY_A => Ok(Arc::new(RwLock::new(EL5152::new()))),
         EL6021_IDENTITY_A | EL6021_IDENTITY_B | EL6021_IDENTITY_C | EL6021_IDENTITY_D => {
             Ok(Arc::new(RwLock::new(el6021::EL6021::new())))
         }
         EL3204_IDENTITY_A | EL3204_IDENTITY_B => Ok(Arc::new(RwLock::new(el3204::EL3204::new()))),
+        EL3314_IDENTITY_A | EL3314_IDENTITY_B => Ok(Arc::new(RwLock::new(el3314::EL3314::new()))),
+        EL3318_IDENTITY_A => Ok(Arc::new(RwLock::new(el3318::EL3318::new()))),
+        EL3356_IDENTITY_0000_A | EL3356_IDENTITY_0010_A => {
+            Ok(Arc::new(RwLock::new(el3356::EL3356::new())))
+        }
         EL7031_IDENTITY_A | EL7031_IDENTITY_B => Ok(Arc::new(RwLock::new(el7031::EL7031::new()))),
         EL7031_0030_IDENTITY_A => Ok(Arc::new(RwLock::new(el7031_0030::EL7031_0030::new()))),
         EL7041_0052_IDENTITY_A => Ok(Arc::new(RwLock::new(el7041_0052::EL7041_0052::new()))),
+        EL7211_IDENTITY_A | EL7221_IDENTITY_A => Ok(Arc::new(RwLock::new(el72x1::EL72x1::new()))),
         EL2521_IDENTITY_0000_A | EL2521_IDENTITY_0000_B | EL2521_IDENTITY_0024_A => {
             Ok(Arc::new(RwLock::new(EL2521::new())))
         }
-        _ => Err(anyhow::anyhow!(
-            "[{}::device_from_subdevice] No Driver: vendor_id: 0x{:x}, product_id: 0x{:x}, revision: 0x{:x}",
-            module_path!(),
-            subdevice_identity_tuple.0,
-            subdevice_identity_tuple.1,
-            subdevice_identity_tuple.2,
-        )),
+        _ => {
+            tracing::warn!(
+                "[{}::device_from_subdevice] No Driver, using GenericEthercatDevice: vendor_id: 0x{:x}, product_id: 0x{:x}, revision: 0x{:x}",
+                module_path!(),
+                subdevice_identity_tuple.0,
+                subdevice_identity_tuple.1,
+                subdevice_identity_tuple.2,
+            );
+            Ok(Arc::new(RwLock::new(
+                generic::GenericEthercatDevice::from_identity(subdevice_identity_tuple),
+            )))
+        }
     }
 }
 
@@ -59,6 +59,7 @@ impl AnalogInputDevice<Wago750_455Port> for Wago750_455 {
         AnalogInputInput {
             normalized,
             wiring_error,
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,355 @@
+//! CiA 402 (DS402) drive profile
+//!
+//! Servo drives are enabled by walking through the state machine of the profile with the controlword
+//! while the drive reports its state in the statusword.
+
+use crate::io::servo_drive::ServoDriveOutput;
+
+/// Controlword commands
+pub const CONTROLWORD_DISABLE_VOLTAGE: u16 = 0x0000;
+pub const CONTROLWORD_QUICK_STOP: u16 = 0x0002;
+pub const CONTROLWORD_SHUTDOWN: u16 = 0x0006;
+pub const CONTROLWORD_SWITCH_ON: u16 = 0x0007;
+pub const CONTROLWORD_ENABLE_OPERATION: u16 = 0x000F;
+/// Bit 7, the drive resets the fault on the rising edge
+pub const CONTROLWORD_FAULT_RESET: u16 = 0x0080;
+
+const STATUSWORD_WARNING: u16 = 1 << 7;
+const STATUSWORD_TARGET_REACHED: u16 = 1 << 10;
+const STATUSWORD_INTERNAL_LIMIT_ACTIVE: u16 = 1 << 11;
+const STATUSWORD_FOLLOWING_ERROR: u16 = 1 << 13;
+
+/// States of the drive decoded from the statusword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cia402State {
+    #[default]
+    NotReadyToSwitchOn,
+    SwitchOnDisabled,
+    ReadyToSwitchOn,
+    SwitchedOn,
+    OperationEnabled,
+    QuickStopActive,
+    FaultReactionActive,
+    Fault,
+}
+
+impl Cia402State {
+    pub const fn from_statusword(statusword: u16) -> Self {
+        if statusword & 0x004F == 0x0000 {
+            Self::NotReadyToSwitchOn
+        } else if statusword & 0x004F == 0x0040 {
+            Self::SwitchOnDisabled
+        } else if statusword & 0x006F == 0x0021 {
+            Self::ReadyToSwitchOn
+        } else if statusword & 0x006F == 0x0023 {
+            Self::SwitchedOn
+        } else if statusword & 0x006F == 0x0027 {
+            Self::OperationEnabled
+        } else if statusword & 0x006F == 0x0007 {
+            Self::QuickStopActive
+        } else if statusword & 0x004F == 0x000F {
+            Self::FaultReactionActive
+        } else if statusword & 0x004F == 0x0008 {
+            Self::Fault
+        } else {
+            // undefined combinations are treated like a drive which isn't ready yet
+            Self::NotReadyToSwitchOn
+        }
+    }
+
+    pub const fn is_fault(&self) -> bool {
+        matches!(self, Self::Fault | Self::FaultReactionActive)
+    }
+}
+
+/// Status bits besides the state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cia402Status {
+    pub state: Cia402State,
+    pub warning: bool,
+    pub target_reached: bool,
+    pub internal_limit_active: bool,
+    /// Position modes only
+    pub following_error: bool,
+}
+
+impl Cia402Status {
+    pub const fn from_statusword(statusword: u16) -> Self {
+        Self {
+            state: Cia402State::from_statusword(statusword),
+            warning: statusword & STATUSWORD_WARNING != 0,
+            target_reached: statusword & STATUSWORD_TARGET_REACHED != 0,
+            internal_limit_active: statusword & STATUSWORD_INTERNAL_LIMIT_ACTIVE != 0,
+            following_error: statusword & STATUSWORD_FOLLOWING_ERROR != 0,
+        }
+    }
+}
+
+/// Modes of operation (0x6060)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cia402Mode {
+    /// Cyclic synchronous position mode, the target position is followed every cycle
+    CyclicSynchronousPosition,
+    /// Cyclic synchronous velocity mode, the target velocity is followed every cycle
+    CyclicSynchronousVelocity,
+}
+
+impl From<Cia402Mode> for u8 {
+    fn from(mode: Cia402Mode) -> Self {
+        match mode {
+            Cia402Mode::CyclicSynchronousPosition => 8,
+            Cia402Mode::CyclicSynchronousVelocity => 9,
+        }
+    }
+}
+
+/// Controlword which moves the drive one transition towards the requested state
+///
+/// `last_controlword` is needed to create the rising edge for the fault reset.
+pub const fn next_controlword(
+    state: Cia402State,
+    enable: bool,
+    quick_stop: bool,
+    fault_reset: bool,
+    last_controlword: u16,
+) -> u16 {
+    match state {
+        Cia402State::Fault => {
+            if fault_reset && last_controlword & CONTROLWORD_FAULT_RESET == 0 {
+                CONTROLWORD_FAULT_RESET
+            } else {
+                CONTROLWORD_DISABLE_VOLTAGE
+            }
+        }
+        Cia402State::NotReadyToSwitchOn | Cia402State::FaultReactionActive => {
+            CONTROLWORD_DISABLE_VOLTAGE
+        }
+        // the quick stop is kept until it is released, then the drive goes to switch on disabled
+        Cia402State::QuickStopActive if quick_stop => CONTROLWORD_QUICK_STOP,
+        Cia402State::QuickStopActive => CONTROLWORD_DISABLE_VOLTAGE,
+        _ if quick_stop => CONTROLWORD_QUICK_STOP,
+        Cia402State::SwitchOnDisabled => CONTROLWORD_SHUTDOWN,
+        Cia402State::ReadyToSwitchOn if enable => CONTROLWORD_SWITCH_ON,
+        Cia402State::ReadyToSwitchOn => CONTROLWORD_SHUTDOWN,
+        Cia402State::SwitchedOn if enable => CONTROLWORD_ENABLE_OPERATION,
+        Cia402State::SwitchedOn => CONTROLWORD_SHUTDOWN,
+        Cia402State::OperationEnabled if enable => CONTROLWORD_ENABLE_OPERATION,
+        // disable operation
+        Cia402State::OperationEnabled => CONTROLWORD_SWITCH_ON,
+    }
+}
+
+/// State machine of one axis, drivers call [`Cia402Axis::update`] once per cycle
+#[derive(Debug, Clone)]
+pub struct Cia402Axis {
+    /// Requested by the io layer
+    pub output: ServoDriveOutput,
+    status: Cia402Status,
+    controlword: u16,
+}
+
+impl Cia402Axis {
+    pub const fn new() -> Self {
+        Self {
+            output: ServoDriveOutput::new(),
+            status: Cia402Status {
+                state: Cia402State::NotReadyToSwitchOn,
+                warning: false,
+                target_reached: false,
+                internal_limit_active: false,
+                following_error: false,
+            },
+            controlword: CONTROLWORD_DISABLE_VOLTAGE,
+        }
+    }
+
+    /// Decodes the statusword of the last cycle and returns the controlword for the next one
+    ///
+    /// A fault reset request is cleared once the drive left the fault states.
+    /// In position mode the target follows `actual_position` while the drive isn't enabled,
+    /// so it doesn't jump to an old target when it gets enabled.
+    pub const fn update(&mut self, statusword: u16, actual_position: i32) -> u16 {
+        self.status = Cia402Status::from_statusword(statusword);
+        if !self.status.state.is_fault() {
+            self.output.fault_reset = false;
+        }
+        if !matches!(self.status.state, Cia402State::OperationEnabled) {
+            self.output.target_position = actual_position;
+        }
+        self.controlword = next_controlword(
+            self.status.state,
+            self.output.enable,
+            self.output.quick_stop,
+            self.output.fault_reset,
+            self.controlword,
+        );
+        self.controlword
+    }
+
+    pub const fn status(&self) -> Cia402Status {
+        self.status
+    }
+
+    pub const fn controlword(&self) -> u16 {
+        self.controlword
+    }
+}
+
+impl Default for Cia402Axis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_from_statusword() {
+        assert_eq!(
+            Cia402State::from_statusword(0x0000),
+            Cia402State::NotReadyToSwitchOn
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x0250),
+            Cia402State::SwitchOnDisabled
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x0231),
+            Cia402State::ReadyToSwitchOn
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x0233),
+            Cia402State::SwitchedOn
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x1637),
+            Cia402State::OperationEnabled
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x0217),
+            Cia402State::QuickStopActive
+        );
+        assert_eq!(
+            Cia402State::from_statusword(0x021F),
+            Cia402State::FaultReactionActive
+        );
+        assert_eq!(Cia402State::from_statusword(0x0218), Cia402State::Fault);
+
+        let status = Cia402Status::from_statusword(0x2637);
+        assert!(status.target_reached);
+        assert!(status.following_error);
+        assert!(!status.warning);
+    }
+
+    /// Simulates a drive which follows the controlword in one cycle
+    fn drive(state: Cia402State, controlword: u16) -> u16 {
+        let next = match (state, controlword) {
+            (Cia402State::SwitchOnDisabled, CONTROLWORD_SHUTDOWN) => Cia402State::ReadyToSwitchOn,
+            (Cia402State::ReadyToSwitchOn, CONTROLWORD_SWITCH_ON) => Cia402State::SwitchedOn,
+            (Cia402State::SwitchedOn, CONTROLWORD_ENABLE_OPERATION) => {
+                Cia402State::OperationEnabled
+            }
+            (Cia402State::SwitchedOn, CONTROLWORD_SHUTDOWN) => Cia402State::ReadyToSwitchOn,
+            (Cia402State::OperationEnabled, CONTROLWORD_SWITCH_ON) => Cia402State::SwitchedOn,
+            (Cia402State::OperationEnabled, CONTROLWORD_QUICK_STOP) => Cia402State::QuickStopActive,
+            (Cia402State::QuickStopActive, CONTROLWORD_DISABLE_VOLTAGE) => {
+                Cia402State::SwitchOnDisabled
+            }
+            (Cia402State::Fault, CONTROLWORD_FAULT_RESET) => Cia402State::SwitchOnDisabled,
+            (state, _) => state,
+        };
+        match next {
+            Cia402State::NotReadyToSwitchOn => 0x0000,
+            Cia402State::SwitchOnDisabled => 0x0250,
+            Cia402State::ReadyToSwitchOn => 0x0231,
+            Cia402State::SwitchedOn => 0x0233,
+            Cia402State::OperationEnabled => 0x0237,
+            Cia402State::QuickStopActive => 0x0217,
+            Cia402State::FaultReactionActive => 0x021F,
+            Cia402State::Fault => 0x0218,
+        }
+    }
+
+    fn run(axis: &mut Cia402Axis, mut statusword: u16, cycles: usize) -> u16 {
+        for _ in 0..cycles {
+            let controlword = axis.update(statusword, 0);
+            statusword = drive(Cia402State::from_statusword(statusword), controlword);
+        }
+        statusword
+    }
+
+    #[test]
+    fn test_enable_and_disable() {
+        let mut axis = Cia402Axis::new();
+        axis.output.enable = true;
+        let statusword = run(&mut axis, 0x0250, 4);
+        assert_eq!(
+            Cia402State::from_statusword(statusword),
+            Cia402State::OperationEnabled
+        );
+
+        axis.output.enable = false;
+        let statusword = run(&mut axis, statusword, 2);
+        assert_eq!(
+            Cia402State::from_statusword(statusword),
+            Cia402State::ReadyToSwitchOn
+        );
+    }
+
+    #[test]
+    fn test_quick_stop() {
+        let mut axis = Cia402Axis::new();
+        axis.output.enable = true;
+        let statusword = run(&mut axis, 0x0250, 4);
+
+        axis.output.quick_stop = true;
+        let statusword = run(&mut axis, statusword, 3);
+        assert_eq!(
+            Cia402State::from_statusword(statusword),
+            Cia402State::QuickStopActive
+        );
+
+        // released, enables again
+        axis.output.quick_stop = false;
+        let statusword = run(&mut axis, statusword, 5);
+        assert_eq!(
+            Cia402State::from_statusword(statusword),
+            Cia402State::OperationEnabled
+        );
+    }
+
+    #[test]
+    fn test_fault_reset() {
+        let mut axis = Cia402Axis::new();
+        axis.output.enable = true;
+        // stays in fault without a reset
+        let statusword = run(&mut axis, 0x0218, 3);
+        assert_eq!(Cia402State::from_statusword(statusword), Cia402State::Fault);
+
+        axis.output.fault_reset = true;
+        assert_eq!(axis.update(statusword, 0), CONTROLWORD_FAULT_RESET);
+        // the rising edge needs a low cycle first
+        assert_eq!(axis.update(statusword, 0), CONTROLWORD_DISABLE_VOLTAGE);
+
+        let statusword = run(&mut axis, statusword, 5);
+        assert_eq!(
+            Cia402State::from_statusword(statusword),
+            Cia402State::OperationEnabled
+        );
+        assert!(!axis.output.fault_reset);
+    }
+
+    #[test]
+    fn test_target_position_follows_while_disabled() {
+        let mut axis = Cia402Axis::new();
+        axis.output.target_position = 100;
+        axis.update(0x0250, 42);
+        assert_eq!(axis.output.target_position, 42);
+
+        axis.output.target_position = 100;
+        axis.update(0x0237, 42);
+        assert_eq!(axis.output.target_position, 100);
+    }
+}
@@ -0,0 +1,82 @@
+//! Pulse width modulation mode of the EL252x terminals, shared by the device drivers
+//!
+//! In this mode `frequency_value` of [`PtoControl`] is the duty cycle and `frequency_select`
+//! switches between base frequency 1 and 2 of the CoE configuration.
+
+use crate::{io::pwm_output::PwmOutputOutput, pdo::el252x::PtoControl};
+use anyhow::anyhow;
+use units::{f64::Frequency, frequency::hertz};
+
+/// `frequency_value` for a duty cycle of 100%
+pub const DUTY_CYCLE_MAX: i32 = 0x7FFF;
+
+/// Writes duty cycle and frequency to [`PtoControl`]
+///
+/// Fails if the frequency is neither `base_frequency_1` nor `base_frequency_2` in Hz.
+pub fn write_pwm_output(
+    control: &mut PtoControl,
+    value: &PwmOutputOutput,
+    base_frequency_1: u32,
+    base_frequency_2: u32,
+) -> Result<(), anyhow::Error> {
+    let frequency = value.frequency.get::<hertz>().round();
+    control.frequency_select = if frequency == f64::from(base_frequency_1) {
+        false
+    } else if frequency == f64::from(base_frequency_2) {
+        true
+    } else {
+        return Err(anyhow!(
+            "PWM frequency {} Hz is neither base frequency 1 ({} Hz) nor base frequency 2 ({} Hz)",
+            frequency,
+            base_frequency_1,
+            base_frequency_2
+        ));
+    };
+    control.disble_ramp = true;
+    control.frequency_value =
+        (value.duty_cycle.clamp(0.0, 1.0) * f64::from(DUTY_CYCLE_MAX)).round() as i32;
+    Ok(())
+}
+
+/// Reads the output back from [`PtoControl`], see [`write_pwm_output`]
+pub fn pwm_output(
+    control: &PtoControl,
+    base_frequency_1: u32,
+    base_frequency_2: u32,
+) -> PwmOutputOutput {
+    let frequency = match control.frequency_select {
+        false => base_frequency_1,
+        true => base_frequency_2,
+    };
+    PwmOutputOutput {
+        duty_cycle: f64::from(control.frequency_value) / f64::from(DUTY_CYCLE_MAX),
+        frequency: Frequency::new::<hertz>(f64::from(frequency)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_pwm_output() {
+        let mut control = PtoControl::default();
+        let output = PwmOutputOutput {
+            duty_cycle: 0.5,
+            frequency: Frequency::new::<hertz>(100_000.0),
+        };
+        write_pwm_output(&mut control, &output, 50_000, 100_000).unwrap();
+        assert!(control.frequency_select);
+        assert_eq!(control.frequency_value, 0x4000);
+
+        let read_back = pwm_output(&control, 50_000, 100_000);
+        assert!((read_back.duty_cycle - 0.5).abs() < 1e-4);
+        assert_eq!(read_back.frequency.get::<hertz>(), 100_000.0);
+
+        let output = PwmOutputOutput {
+            duty_cycle: 0.5,
+            frequency: Frequency::new::<hertz>(1_000.0),
+        };
+        assert!(write_pwm_output(&mut control, &output, 50_000, 100_000).is_err());
+    }
+}
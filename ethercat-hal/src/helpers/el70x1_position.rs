@@ -0,0 +1,303 @@
+//! Positioning interface of the EL70x1 terminals, shared by the device drivers
+//!
+//! The terminal starts a travel command on the rising edge of `execute` and stops it with the
+//! deceleration ramp on the falling edge, so `execute` stays set while the motor travels.
+
+use crate::{
+    io::stepper_position_el70x1::{StepperPositionEL70x1Input, StepperPositionEL70x1Output},
+    pdo::el70x1::{
+        EncStatus, PosControl, PosControlCompact, PosStatus, PosStatusCompact, StmControl,
+        StmStatus,
+    },
+    shared_config::el70x1::{PosConfiguration, PosFeatures, StartType},
+};
+use anyhow::anyhow;
+
+/// Generates the edges of the `execute` bit
+///
+/// A start while a travel command is executing drops `execute` for one cycle,
+/// so the terminal sees a new rising edge.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteEdge {
+    execute: bool,
+    restart: bool,
+}
+
+impl ExecuteEdge {
+    pub const fn new() -> Self {
+        Self {
+            execute: false,
+            restart: false,
+        }
+    }
+
+    /// Requests a new travel command
+    pub const fn start(&mut self) {
+        if self.execute {
+            self.restart = true;
+        }
+        self.execute = true;
+    }
+
+    /// Stops the travel command with the deceleration ramp
+    pub const fn stop(&mut self) {
+        self.execute = false;
+        self.restart = false;
+    }
+
+    pub const fn is_executing(&self) -> bool {
+        self.execute
+    }
+
+    /// `execute` bit for the next cycle
+    pub const fn next(&mut self) -> bool {
+        if self.restart {
+            self.restart = false;
+            false
+        } else {
+            self.execute
+        }
+    }
+}
+
+/// Builds the input from the status PDOs of the positioning presets
+///
+/// The actual position comes from [`PosStatus`] and falls back to the encoder counter of [`EncStatus`]
+/// with the compact preset.
+pub fn position_input(
+    stm_status: Option<&StmStatus>,
+    pos_status_compact: Option<&PosStatusCompact>,
+    pos_status: Option<&PosStatus>,
+    enc_status: Option<&EncStatus>,
+) -> Result<StepperPositionEL70x1Input, anyhow::Error> {
+    let stm_status = stm_status.ok_or_else(|| anyhow!("stm_status is None"))?;
+    let (busy, in_target, warning, error, calibrated, ready_to_execute) = match (
+        pos_status,
+        pos_status_compact,
+    ) {
+        (Some(status), _) => (
+            status.busy,
+            status.in_target,
+            status.warning,
+            status.error,
+            status.calibrated,
+            status.ready_to_execute,
+        ),
+        (None, Some(status)) => (
+            status.busy,
+            status.in_target,
+            status.warning,
+            status.error,
+            status.calibrated,
+            status.ready_to_execute,
+        ),
+        (None, None) => {
+            return Err(anyhow!(
+                "pos_status and pos_status_compact are None, the PDO assignment has no positioning interface"
+            ));
+        }
+    };
+    let actual_position = match (pos_status, enc_status) {
+        (Some(status), _) => status.actual_position,
+        (None, Some(status)) => status.counter_value,
+        (None, None) => return Err(anyhow!("pos_status and enc_status are None")),
+    };
+
+    Ok(StepperPositionEL70x1Input {
+        actual_position: actual_position as i32,
+        actual_velocity: pos_status.map(|status| status.actual_velocity),
+        busy,
+        in_target,
+        warning: warning || stm_status.warning,
+        error: error || stm_status.error,
+        calibrated,
+        ready_to_execute,
+        ready: stm_status.ready,
+    })
+}
+
+/// Writes the output to the control PDOs of the positioning presets
+///
+/// `execute` is written by [`ExecuteEdge::next`] in the output pre processing.
+/// Velocity, start type and ramps are ignored with the compact preset, the CoE values apply.
+pub fn write_position_output(
+    value: &StepperPositionEL70x1Output,
+    edge: &mut ExecuteEdge,
+    stm_control: Option<&mut StmControl>,
+    pos_control_compact: Option<&mut PosControlCompact>,
+    pos_control: Option<&mut PosControl>,
+) -> Result<(), anyhow::Error> {
+    let stm_control = stm_control.ok_or_else(|| anyhow!("stm_control is None"))?;
+    stm_control.enable = value.enable;
+    stm_control.reset = value.reset;
+
+    match (pos_control, pos_control_compact) {
+        (Some(control), _) => {
+            control.emergency_stop = value.emergency_stop;
+            control.target_position = value.target_position as u32;
+            control.target_velocity = value.target_velocity;
+            control.start_type = u16::from(value.start_type);
+            control.acceleration = value.acceleration;
+            control.deceleration = value.deceleration;
+        }
+        (None, Some(control)) => {
+            control.emergency_stop = value.emergency_stop;
+            control.target_position = value.target_position as u32;
+        }
+        (None, None) => {
+            return Err(anyhow!(
+                "pos_control and pos_control_compact are None, the PDO assignment has no positioning interface"
+            ));
+        }
+    }
+
+    if value.start {
+        edge.start();
+    } else if !value.execute {
+        edge.stop();
+    }
+    Ok(())
+}
+
+/// Reads the output back from the control PDOs, see [`write_position_output`]
+pub fn position_output(
+    edge: &ExecuteEdge,
+    stm_control: Option<&StmControl>,
+    pos_control_compact: Option<&PosControlCompact>,
+    pos_control: Option<&PosControl>,
+    pos_configuration: &PosConfiguration,
+    pos_features: &PosFeatures,
+) -> Result<StepperPositionEL70x1Output, anyhow::Error> {
+    let stm_control = stm_control.ok_or_else(|| anyhow!("stm_control is None"))?;
+    let mut output = StepperPositionEL70x1Output {
+        enable: stm_control.enable,
+        reset: stm_control.reset,
+        emergency_stop: false,
+        execute: edge.is_executing(),
+        start: false,
+        target_position: 0,
+        target_velocity: pos_configuration.velocity_max,
+        start_type: pos_features.start_type,
+        acceleration: pos_configuration.acceleration_pos,
+        deceleration: pos_configuration.deceleration_pos,
+    };
+    match (pos_control, pos_control_compact) {
+        (Some(control), _) => {
+            output.emergency_stop = control.emergency_stop;
+            output.target_position = control.target_position as i32;
+            output.target_velocity = control.target_velocity;
+            output.start_type = StartType::try_from(control.start_type)?;
+            output.acceleration = control.acceleration;
+            output.deceleration = control.deceleration;
+        }
+        (None, Some(control)) => {
+            output.emergency_stop = control.emergency_stop;
+            output.target_position = control.target_position as i32;
+        }
+        (None, None) => {
+            return Err(anyhow!(
+                "pos_control and pos_control_compact are None, the PDO assignment has no positioning interface"
+            ));
+        }
+    }
+    Ok(output)
+}
+
+/// Starts [`PosControl`] with the velocity, ramps and start type of the CoE configuration
+///
+/// Called when the PDOs are created, otherwise the first travel command would have no velocity.
+pub const fn init_position_control(
+    pos_control: Option<&mut PosControl>,
+    pos_configuration: &PosConfiguration,
+    pos_features: &PosFeatures,
+) {
+    if let Some(control) = pos_control {
+        control.target_velocity = pos_configuration.velocity_max;
+        control.start_type = pos_features.start_type as u16;
+        control.acceleration = pos_configuration.acceleration_pos;
+        control.deceleration = pos_configuration.deceleration_pos;
+    }
+}
+
+/// Writes the `execute` bit of the next cycle, does nothing without a positioning preset
+pub const fn write_execute(
+    edge: &mut ExecuteEdge,
+    pos_control_compact: Option<&mut PosControlCompact>,
+    pos_control: Option<&mut PosControl>,
+) {
+    match (pos_control, pos_control_compact) {
+        (Some(control), _) => control.execute = edge.next(),
+        (None, Some(control)) => control.execute = edge.next(),
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_edge() {
+        let mut edge = ExecuteEdge::new();
+        assert!(!edge.next());
+
+        edge.start();
+        assert!(edge.next());
+        assert!(edge.next());
+
+        // new target while executing
+        edge.start();
+        assert!(!edge.next());
+        assert!(edge.next());
+
+        edge.stop();
+        assert!(!edge.next());
+    }
+
+    #[test]
+    fn test_write_position_output() {
+        let mut edge = ExecuteEdge::new();
+        let mut stm_control = StmControl::default();
+        let mut pos_control = PosControl::default();
+        let output = StepperPositionEL70x1Output {
+            enable: true,
+            reset: false,
+            emergency_stop: false,
+            execute: true,
+            start: true,
+            target_position: -10,
+            target_velocity: 500,
+            start_type: StartType::Relative,
+            acceleration: 100,
+            deceleration: 200,
+        };
+        write_position_output(
+            &output,
+            &mut edge,
+            Some(&mut stm_control),
+            None,
+            Some(&mut pos_control),
+        )
+        .unwrap();
+        write_execute(&mut edge, None, Some(&mut pos_control));
+
+        assert!(stm_control.enable);
+        assert!(pos_control.execute);
+        assert_eq!(pos_control.target_position, (-10i32) as u32);
+        assert_eq!(pos_control.start_type, 2);
+
+        let read_back = position_output(
+            &edge,
+            Some(&stm_control),
+            None,
+            Some(&pos_control),
+            &PosConfiguration::default(),
+            &PosFeatures::default(),
+        )
+        .unwrap();
+        assert_eq!(read_back.target_position, -10);
+        assert_eq!(read_back.start_type, StartType::Relative);
+        assert!(read_back.execute);
+        assert!(!read_back.start);
+    }
+}
@@ -1,4 +1,8 @@
+pub mod cia402;
 pub mod counter_wrapper_u16_i128;
+pub mod el252x_pwm;
+pub mod el70x1_position;
 pub mod el70xx_velocity_converter;
 pub mod ethercrab_types;
 pub mod signing_converter_u16;
+pub mod user_scale;
@@ -0,0 +1,52 @@
+//! User scaling of the Beckhoff analog terminals (80n0:01, 80n0:11, 80n0:12)
+//!
+//! The terminal calculates `value * gain + offset`, the gain is a fixed point number with the factor 2^-16.
+
+/// Gain of `1.0`
+pub const USER_SCALE_GAIN_ONE: i32 = 0x0001_0000;
+
+/// The terminals limit the gain to +/- 0x7FFFFF
+const USER_SCALE_GAIN_LIMIT: i32 = 0x007F_FFFF;
+
+/// Converts a gain factor to the fixed point value of 80n0:12
+pub fn gain_to_fixed_point(gain: f64) -> i32 {
+    (gain * f64::from(USER_SCALE_GAIN_ONE)).round().clamp(
+        f64::from(-USER_SCALE_GAIN_LIMIT),
+        f64::from(USER_SCALE_GAIN_LIMIT),
+    ) as i32
+}
+
+/// Converts the fixed point value of 80n0:12 to a gain factor
+pub fn gain_from_fixed_point(gain: i32) -> f64 {
+    f64::from(gain) / f64::from(USER_SCALE_GAIN_ONE)
+}
+
+/// Scales `value` like the terminal does
+pub fn apply_user_scale(value: f64, gain: i32, offset: i16) -> f64 {
+    value.mul_add(gain_from_fixed_point(gain), f64::from(offset))
+}
+
+/// Reverts the scaling of [`apply_user_scale`], a gain of `0` reverts to `0`
+pub fn revert_user_scale(value: f64, gain: i32, offset: i16) -> f64 {
+    match gain {
+        0 => 0.0,
+        gain => (value - f64::from(offset)) / gain_from_fixed_point(gain),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_scale() {
+        assert_eq!(gain_to_fixed_point(1.0), USER_SCALE_GAIN_ONE);
+        assert_eq!(gain_to_fixed_point(0.5), 0x8000);
+        assert_eq!(gain_to_fixed_point(1000.0), USER_SCALE_GAIN_LIMIT);
+
+        let gain = gain_to_fixed_point(2.0);
+        let scaled = apply_user_scale(1000.0, gain, -100);
+        assert_eq!(scaled, 1900.0);
+        assert_eq!(revert_user_scale(scaled, gain, -100), 1000.0);
+    }
+}
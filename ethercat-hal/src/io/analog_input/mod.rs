@@ -63,13 +63,29 @@ impl AnalogInput {
         let input = (self.get_input)();
         input.wiring_error
     }
+
+    /// The signal is below the measuring range, e.g. a broken 4-20mA loop
+    pub fn get_underrange(&self) -> bool {
+        let input = (self.get_input)();
+        input.underrange
+    }
+
+    /// The signal is above the measuring range
+    pub fn get_overrange(&self) -> bool {
+        let input = (self.get_input)();
+        input.overrange
+    }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AnalogInputInput {
     /// from -1.0 to 1.0
     /// Can be converted to voltage or mA knowning the type and range of the device
     pub normalized: f32,
     pub wiring_error: bool,
+    /// Below the measuring range, `false` if the device doesn't report it
+    pub underrange: bool,
+    /// Above the measuring range, `false` if the device doesn't report it
+    pub overrange: bool,
 }
 
 impl AnalogInputInput {
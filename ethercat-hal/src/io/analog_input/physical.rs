@@ -1,3 +1,5 @@
+use units::electric_current::milliampere;
+use units::electric_potential::volt;
 use units::f64::{ElectricCurrent, ElectricPotential};
 
 #[derive(Debug, Clone)]
@@ -56,6 +58,51 @@ impl AnalogInputRange {
         }
     }
 
+    /// Physical value of one step of an ADC/DAC with `resolution_bits` (including the sign bit)
+    ///
+    /// The terminals present every resolution as 16 bit, lower resolutions leave the low bits unused.
+    pub fn resolution(&self, resolution_bits: u32) -> AnalogInputValue {
+        let raw_step = f64::from(1u32 << (16 - resolution_bits.clamp(1, 16)));
+        let raw_span = f64::from(i32::from(self.get_max_raw()) - i32::from(self.get_min_raw()));
+        match self {
+            Self::Potential { min, max, .. } => {
+                AnalogInputValue::Potential((*max - *min).abs() * (raw_step / raw_span))
+            }
+            Self::Current { min, max, .. } => {
+                AnalogInputValue::Current((*max - *min).abs() * (raw_step / raw_span))
+            }
+        }
+    }
+
+    /// Convert a normalized value (0 to 1.0) to a raw value, clamped to the raw range
+    pub fn normalized_to_raw(&self, normalized: f64) -> i16 {
+        let min_raw = f64::from(self.get_min_raw());
+        let max_raw = f64::from(self.get_max_raw());
+        (max_raw - min_raw)
+            .mul_add(normalized, min_raw)
+            .round()
+            .clamp(min_raw, max_raw) as i16
+    }
+
+    /// Convert a physical value to a normalized value (0 to 1.0)
+    ///
+    /// Fails if the value is a current for a potential range or the other way around.
+    pub fn physical_to_normalized(&self, value: &AnalogInputValue) -> Result<f64, anyhow::Error> {
+        match (self, value) {
+            (Self::Potential { min, max, .. }, AnalogInputValue::Potential(value)) => {
+                Ok(((*value - *min) / (*max - *min)).value)
+            }
+            (Self::Current { min, max, .. }, AnalogInputValue::Current(value)) => {
+                Ok(((*value - *min) / (*max - *min)).value)
+            }
+            _ => Err(anyhow::anyhow!(
+                "{:?} doesn't match the range {:?}",
+                value,
+                self
+            )),
+        }
+    }
+
     /// Convert a normalized value (0 to 1.0) to a physical value
     pub fn normalized_to_physical(&self, normalized: f32) -> AnalogInputValue {
         match self {
@@ -71,6 +118,52 @@ impl AnalogInputRange {
     }
 }
 
+/// Standard signal ranges of analog terminals, fixed by the terminal type
+///
+/// Shared by inputs and outputs, e.g. the EL30xx and EL40xx terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalogSignalRange {
+    /// 0 to 10V, `0` to `0x7FFF`
+    Potential0To10V,
+    /// -10 to 10V, `0x8000` to `0x7FFF`
+    PotentialPlusMinus10V,
+    /// 0 to 20mA, `0` to `0x7FFF`
+    Current0To20mA,
+    /// 4 to 20mA, `0` to `0x7FFF`
+    Current4To20mA,
+}
+
+impl AnalogSignalRange {
+    pub fn range(&self) -> AnalogInputRange {
+        match self {
+            Self::Potential0To10V => AnalogInputRange::Potential {
+                min: ElectricPotential::new::<volt>(0.0),
+                max: ElectricPotential::new::<volt>(10.0),
+                min_raw: 0,
+                max_raw: i16::MAX,
+            },
+            Self::PotentialPlusMinus10V => AnalogInputRange::Potential {
+                min: ElectricPotential::new::<volt>(-10.0),
+                max: ElectricPotential::new::<volt>(10.0),
+                min_raw: i16::MIN,
+                max_raw: i16::MAX,
+            },
+            Self::Current0To20mA => AnalogInputRange::Current {
+                min: ElectricCurrent::new::<milliampere>(0.0),
+                max: ElectricCurrent::new::<milliampere>(20.0),
+                min_raw: 0,
+                max_raw: i16::MAX,
+            },
+            Self::Current4To20mA => AnalogInputRange::Current {
+                min: ElectricCurrent::new::<milliampere>(4.0),
+                max: ElectricCurrent::new::<milliampere>(20.0),
+                min_raw: 0,
+                max_raw: i16::MAX,
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::f64;
@@ -118,6 +211,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_resolution_and_physical_to_normalized() {
+        let range = AnalogInputRange::Potential {
+            min: ElectricPotential::new::<volt>(0.0),
+            max: ElectricPotential::new::<volt>(10.0),
+            min_raw: 0,
+            max_raw: i16::MAX,
+        };
+        // 12 bit including the sign, 0x7FF0 / 16 steps
+        match range.resolution(12) {
+            AnalogInputValue::Potential(v) => {
+                assert_relative_eq!(v.get::<volt>(), 10.0 * 16.0 / 32767.0, epsilon = 1e-9);
+            }
+            _ => panic!("Expected a potential value"),
+        }
+
+        let normalized = range
+            .physical_to_normalized(&AnalogInputValue::Potential(
+                ElectricPotential::new::<volt>(2.5),
+            ))
+            .unwrap();
+        assert_relative_eq!(normalized, 0.25, epsilon = 1e-9);
+        assert_eq!(range.normalized_to_raw(normalized), 8192);
+        assert_eq!(range.normalized_to_raw(2.0), i16::MAX);
+
+        let current = AnalogInputValue::Current(ElectricCurrent::new::<milliampere>(4.0));
+        assert!(range.physical_to_normalized(&current).is_err());
+    }
+
     #[test]
     fn test_analog_input_getter_current() {
         let analog_input_getter = AnalogInputRange::Current {
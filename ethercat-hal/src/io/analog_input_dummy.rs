@@ -29,6 +29,7 @@ impl AnalogInputDummy {
         let state = Arc::new(Mutex::new(AnalogInputInput {
             normalized: 0.0,
             wiring_error: false,
+            ..Default::default()
         }));
         Self { state, range }
     }
@@ -72,6 +73,7 @@ mod tests {
         let input = AnalogInputInput {
             normalized: 0.5,
             wiring_error: false,
+            ..Default::default()
         };
         dummy.set_input(input.clone());
 
@@ -1,3 +1,5 @@
+use super::analog_input::physical::{AnalogInputRange, AnalogInputValue};
+use anyhow::anyhow;
 use smol::future::block_on;
 use smol::lock::RwLock;
 use std::fmt;
@@ -13,6 +15,9 @@ pub struct AnalogOutput {
 
     /// Read the state of the analog output
     pub get_output: Box<dyn Fn() -> AnalogOutputOutput + Send + Sync>,
+
+    /// Physical range, `None` for devices that don't describe it
+    pub range: Option<AnalogInputRange>,
 }
 
 impl fmt::Debug for AnalogOutput {
@@ -27,6 +32,8 @@ impl AnalogOutput {
     where
         PORT: Clone + Send + Sync + 'static,
     {
+        let range = block_on(device.read()).analog_output_range();
+
         // build sync write closure
         let port1 = port.clone();
         let device1 = device.clone();
@@ -46,6 +53,7 @@ impl AnalogOutput {
         Self {
             set_output,
             get_output,
+            range,
         }
     }
 
@@ -59,6 +67,25 @@ impl AnalogOutput {
         let output = (self.get_output)();
         output.into()
     }
+
+    /// Set the output to a voltage or current within the range of the device
+    pub fn set_physical(&self, value: AnalogInputValue) -> Result<(), anyhow::Error> {
+        let range = self.range.as_ref().ok_or_else(|| {
+            anyhow!(
+                "[{}::AnalogOutput::set_physical] The device has no physical range",
+                module_path!()
+            )
+        })?;
+        let normalized = range.physical_to_normalized(&value)?;
+        self.set(normalized as f32);
+        Ok(())
+    }
+
+    /// The output as voltage or current, `None` for devices without a physical range
+    pub fn get_physical(&self) -> Option<AnalogInputValue> {
+        let range = self.range.as_ref()?;
+        Some(range.normalized_to_physical(self.get()))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -79,4 +106,9 @@ impl From<AnalogOutputOutput> for f32 {
 pub trait AnalogOutputDevice<PORTS>: Send + Sync {
     fn set_output(&mut self, port: PORTS, value: AnalogOutputOutput);
     fn get_output(&self, port: PORTS) -> AnalogOutputOutput;
+
+    /// Physical range of the outputs, the value `0.0` to `1.0` spans it
+    fn analog_output_range(&self) -> Option<AnalogInputRange> {
+        None
+    }
 }
@@ -0,0 +1,257 @@
+use smol::lock::RwLock;
+use std::{fmt, sync::Arc};
+use units::{f64::Force, force::newton};
+
+/// Force Input (FI) device
+///
+/// Reads the force of a load cell. The value of the device can be corrected with a
+/// tare and a two-point calibration, see [`ForceCalibration`].
+pub struct ForceInput {
+    /// Read the state of the force input
+    get_input: Box<dyn Fn() -> ForceInputInput + Send + Sync>,
+
+    calibration: ForceCalibration,
+
+    /// Uncalibrated force without load, captured by [`ForceInput::calibrate_zero`]
+    zero_point: Option<Force>,
+}
+
+impl fmt::Debug for ForceInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ForceInput")
+    }
+}
+
+impl ForceInput {
+    pub fn new<PORT>(device: Arc<RwLock<dyn ForceInputDevice<PORT>>>, port: PORT) -> Self
+    where
+        PORT: Clone + Send + Sync + 'static,
+    {
+        let get_input = Box::new(move || {
+            let device = device.read_blocking();
+            device.get_input(port.clone())
+        });
+        Self {
+            get_input,
+            calibration: ForceCalibration::default(),
+            zero_point: None,
+        }
+    }
+
+    /// Force as reported by the device, without the calibration
+    pub fn get_uncalibrated_force(&self) -> Result<Force, ForceInputError> {
+        let input = (self.get_input)();
+        if input.error {
+            Err(ForceInputError::Error)
+        } else if input.overrange {
+            Err(ForceInputError::OverRange)
+        } else if input.underrange {
+            Err(ForceInputError::UnderRange)
+        } else if input.data_invalid {
+            Err(ForceInputError::DataInvalid)
+        } else {
+            Ok(input.force)
+        }
+    }
+
+    /// Calibrated force
+    pub fn get_force(&self) -> Result<Force, ForceInputError> {
+        self.get_uncalibrated_force()
+            .map(|force| self.calibration.apply(force))
+    }
+
+    /// If the load didn't change for a while, calibrating is only reliable in steady state
+    pub fn is_steady(&self) -> bool {
+        (self.get_input)().steady_state
+    }
+
+    /// Zeroes the calibrated force with the current load, keeps the gain
+    pub fn tare(&mut self) -> Result<(), ForceInputError> {
+        self.calibration.offset = self.get_uncalibrated_force()?;
+        Ok(())
+    }
+
+    /// First step of the two-point calibration, call without load
+    pub fn calibrate_zero(&mut self) -> Result<(), ForceInputError> {
+        self.zero_point = Some(self.get_uncalibrated_force()?);
+        Ok(())
+    }
+
+    /// Second step of the two-point calibration, call with a known reference load
+    pub fn calibrate_reference(&mut self, reference: Force) -> Result<(), anyhow::Error> {
+        let zero_point = self.zero_point.ok_or_else(|| {
+            anyhow::anyhow!(
+                "[{}::ForceInput::calibrate_reference] calibrate_zero has to be called first",
+                module_path!()
+            )
+        })?;
+        let measured = self
+            .get_uncalibrated_force()
+            .map_err(|err| anyhow::anyhow!("{:?}", err))?;
+        self.calibration = ForceCalibration::from_two_points(zero_point, measured, reference)?;
+        self.zero_point = None;
+        Ok(())
+    }
+
+    pub const fn calibration(&self) -> ForceCalibration {
+        self.calibration
+    }
+
+    /// Restores a stored calibration
+    pub const fn set_calibration(&mut self, calibration: ForceCalibration) {
+        self.calibration = calibration;
+    }
+}
+
+/// Linear correction of the force of a device
+///
+/// `calibrated = (uncalibrated - offset) * gain`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceCalibration {
+    /// Uncalibrated force without load
+    pub offset: Force,
+    pub gain: f64,
+}
+
+impl Default for ForceCalibration {
+    fn default() -> Self {
+        Self {
+            offset: Force::new::<newton>(0.0),
+            gain: 1.0,
+        }
+    }
+}
+
+impl ForceCalibration {
+    pub fn apply(&self, uncalibrated: Force) -> Force {
+        (uncalibrated - self.offset) * self.gain
+    }
+
+    /// `zero` and `measured` are the uncalibrated forces without load and with the `reference` load
+    pub fn from_two_points(
+        zero: Force,
+        measured: Force,
+        reference: Force,
+    ) -> Result<Self, anyhow::Error> {
+        let span = (measured - zero).get::<newton>();
+        if span.abs() < f64::EPSILON {
+            return Err(anyhow::anyhow!(
+                "[{}::ForceCalibration::from_two_points] The reference load didn't change the force",
+                module_path!()
+            ));
+        }
+        Ok(Self {
+            offset: zero,
+            gain: reference.get::<newton>() / span,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForceInputError {
+    /// The signal is over the measuring range
+    OverRange,
+
+    /// The signal is under the measuring range
+    UnderRange,
+
+    /// Wire break or ADC error
+    Error,
+
+    /// The device has no valid value yet
+    DataInvalid,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForceInputInput {
+    /// Force as scaled by the device configuration
+    pub force: Force,
+    pub underrange: bool,
+    pub overrange: bool,
+    pub error: bool,
+    pub data_invalid: bool,
+    pub steady_state: bool,
+}
+
+pub trait ForceInputDevice<PORTS>: Send + Sync {
+    fn get_input(&self, port: PORTS) -> ForceInputInput;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct LoadCell {
+        input: ForceInputInput,
+    }
+
+    impl ForceInputDevice<()> for LoadCell {
+        fn get_input(&self, _port: ()) -> ForceInputInput {
+            self.input.clone()
+        }
+    }
+
+    fn load_cell(newtons: f64) -> ForceInputInput {
+        ForceInputInput {
+            force: Force::new::<newton>(newtons),
+            underrange: false,
+            overrange: false,
+            error: false,
+            data_invalid: false,
+            steady_state: true,
+        }
+    }
+
+    #[test]
+    fn test_two_point_calibration() {
+        let device = Arc::new(RwLock::new(LoadCell {
+            input: load_cell(2.0),
+        }));
+        let mut input = ForceInput::new(device.clone(), ());
+        assert!(
+            input
+                .calibrate_reference(Force::new::<newton>(10.0))
+                .is_err()
+        );
+
+        input.calibrate_zero().unwrap();
+        device.write_blocking().input = load_cell(7.0);
+        input
+            .calibrate_reference(Force::new::<newton>(10.0))
+            .unwrap();
+        assert!((input.get_force().unwrap().get::<newton>() - 10.0).abs() < 1e-9);
+
+        device.write_blocking().input = load_cell(4.5);
+        assert!((input.get_force().unwrap().get::<newton>() - 5.0).abs() < 1e-9);
+
+        // tare keeps the gain
+        input.tare().unwrap();
+        device.write_blocking().input = load_cell(5.5);
+        assert!((input.get_force().unwrap().get::<newton>() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calibration_without_span() {
+        let force = Force::new::<newton>(1.0);
+        assert!(ForceCalibration::from_two_points(force, force, force).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let device = Arc::new(RwLock::new(LoadCell {
+            input: ForceInputInput {
+                overrange: true,
+                ..load_cell(0.0)
+            },
+        }));
+        let mut input = ForceInput::new(device.clone(), ());
+        assert_eq!(input.get_force(), Err(ForceInputError::OverRange));
+        assert!(input.tare().is_err());
+
+        device.write_blocking().input = ForceInputInput {
+            error: true,
+            ..load_cell(0.0)
+        };
+        assert_eq!(input.get_force(), Err(ForceInputError::Error));
+    }
+}
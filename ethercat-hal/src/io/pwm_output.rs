@@ -0,0 +1,124 @@
+use std::{fmt, sync::Arc};
+
+use anyhow::{Error, anyhow};
+use smol::lock::RwLock;
+use units::f64::Frequency;
+
+/// Pulse Width Modulation (PWM) Output device
+///
+/// The device switches the output with the duty cycle and frequency itself, independent of the cycle time.
+/// Which frequencies are available depends on the device configuration.
+pub struct PwmOutput {
+    /// Write duty cycle and frequency
+    set_output: Box<dyn Fn(PwmOutputOutput) -> Result<(), Error> + Send + Sync>,
+    /// Read duty cycle and frequency
+    get_output: Box<dyn Fn() -> Result<PwmOutputOutput, Error> + Send + Sync>,
+    /// Read the state of the output
+    get_input: Box<dyn Fn() -> Result<PwmOutputInput, Error> + Send + Sync>,
+}
+
+impl fmt::Debug for PwmOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PwmOutput")
+    }
+}
+
+impl PwmOutput {
+    pub fn new<PORT, DEVICE>(device: Arc<RwLock<DEVICE>>, port: PORT) -> Self
+    where
+        PORT: Clone + Copy + Send + Sync + 'static,
+        DEVICE: PwmOutputDevice<PORT> + Send + Sync + 'static,
+    {
+        // build sync write closure
+        let device1 = device.clone();
+        let set_output = Box::new(move |value: PwmOutputOutput| -> Result<(), Error> {
+            smol::block_on(async {
+                let mut device = device1.write().await;
+                device.set_output(port, value)
+            })
+        });
+
+        // build sync get closures
+        let device2 = device.clone();
+        let get_output = Box::new(move || -> Result<PwmOutputOutput, Error> {
+            smol::block_on(async {
+                let device = device2.read().await;
+                device.get_output(port)
+            })
+        });
+
+        let device3 = device;
+        let get_input = Box::new(move || -> Result<PwmOutputInput, Error> {
+            smol::block_on(async {
+                let device = device3.read().await;
+                device.get_input(port)
+            })
+        });
+
+        Self {
+            set_output,
+            get_output,
+            get_input,
+        }
+    }
+
+    fn update_output(&self, update: impl FnOnce(&mut PwmOutputOutput)) -> Result<(), Error> {
+        let mut output = (self.get_output)()?;
+        update(&mut output);
+        (self.set_output)(output)
+    }
+
+    /// Set the duty cycle from `0.0` (off) to `1.0` (on)
+    pub fn set_duty_cycle(&mut self, duty_cycle: f64) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&duty_cycle) {
+            return Err(anyhow!(
+                "[{}::PwmOutput::set_duty_cycle] Duty cycle {} is not between 0 and 1",
+                module_path!(),
+                duty_cycle
+            ));
+        }
+        self.update_output(|output| output.duty_cycle = duty_cycle)
+    }
+
+    pub fn get_duty_cycle(&self) -> Result<f64, Error> {
+        Ok((self.get_output)()?.duty_cycle)
+    }
+
+    /// Set the PWM frequency, fails for frequencies the device isn't configured for
+    pub fn set_frequency(&mut self, frequency: Frequency) -> Result<(), Error> {
+        self.update_output(|output| output.frequency = frequency)
+    }
+
+    pub fn get_frequency(&self) -> Result<Frequency, Error> {
+        Ok((self.get_output)()?.frequency)
+    }
+
+    pub fn has_error(&self) -> Result<bool, Error> {
+        Ok((self.get_input)()?.error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PwmOutputOutput {
+    /// `0.0` to `1.0`
+    pub duty_cycle: f64,
+
+    pub frequency: Frequency,
+}
+
+#[derive(Debug, Clone)]
+pub struct PwmOutputInput {
+    /// Open load, short circuit or overtemperature depending on the device
+    pub error: bool,
+
+    pub warning: bool,
+}
+
+pub trait PwmOutputDevice<PORT>: Send + Sync
+where
+    PORT: Clone,
+{
+    fn set_output(&mut self, port: PORT, value: PwmOutputOutput) -> Result<(), Error>;
+    fn get_output(&self, port: PORT) -> Result<PwmOutputOutput, Error>;
+    fn get_input(&self, port: PORT) -> Result<PwmOutputInput, Error>;
+}
@@ -0,0 +1,229 @@
+use std::{fmt, sync::Arc};
+
+use crate::helpers::cia402::{Cia402Mode, Cia402State};
+use anyhow::{Error, anyhow};
+use smol::lock::RwLock;
+
+/// Servo Drive (CiA 402) device
+///
+/// Enables the drive through the CiA 402 state machine and commands a velocity or a position
+/// depending on the configured mode of operation. Velocities and positions are in the raw units of the drive.
+pub struct ServoDrive {
+    /// Write the requested state and targets
+    set_output: Box<dyn Fn(ServoDriveOutput) -> Result<(), Error> + Send + Sync>,
+    /// Read the requested state and targets
+    get_output: Box<dyn Fn() -> Result<ServoDriveOutput, Error> + Send + Sync>,
+    /// Read the state of the drive
+    get_input: Box<dyn Fn() -> Result<ServoDriveInput, Error> + Send + Sync>,
+    /// Get the configured mode of operation
+    get_mode: Box<dyn Fn() -> Cia402Mode + Send + Sync>,
+}
+
+impl fmt::Debug for ServoDrive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ServoDrive")
+    }
+}
+
+impl ServoDrive {
+    pub fn new<PORT, DEVICE>(device: Arc<RwLock<DEVICE>>, port: PORT) -> Self
+    where
+        PORT: Clone + Copy + Send + Sync + 'static,
+        DEVICE: ServoDriveDevice<PORT> + Send + Sync + 'static,
+    {
+        // build sync write closure
+        let device1 = device.clone();
+        let set_output = Box::new(move |value: ServoDriveOutput| -> Result<(), Error> {
+            smol::block_on(async {
+                let mut device = device1.write().await;
+                device.set_output(port, value)
+            })
+        });
+
+        // build sync get closures
+        let device2 = device.clone();
+        let get_output = Box::new(move || -> Result<ServoDriveOutput, Error> {
+            smol::block_on(async {
+                let device = device2.read().await;
+                device.get_output(port)
+            })
+        });
+
+        let device3 = device.clone();
+        let get_input = Box::new(move || -> Result<ServoDriveInput, Error> {
+            smol::block_on(async {
+                let device = device3.read().await;
+                device.get_input(port)
+            })
+        });
+
+        let device4 = device;
+        let get_mode = Box::new(move || -> Cia402Mode {
+            smol::block_on(async {
+                let device = device4.read().await;
+                device.get_mode(port)
+            })
+        });
+
+        Self {
+            set_output,
+            get_output,
+            get_input,
+            get_mode,
+        }
+    }
+
+    fn update_output(&self, update: impl FnOnce(&mut ServoDriveOutput)) -> Result<(), Error> {
+        let mut output = (self.get_output)()?;
+        update(&mut output);
+        (self.set_output)(output)
+    }
+
+    /// Mode of operation from the device configuration
+    pub fn get_mode(&self) -> Cia402Mode {
+        (self.get_mode)()
+    }
+
+    /// Requests operation enabled, the drive is enabled over the next cycles
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+        self.update_output(|output| output.enable = enabled)
+    }
+
+    /// If enabling was requested, see [`ServoDrive::is_operation_enabled`] for the actual state
+    pub fn is_enabled(&self) -> Result<bool, Error> {
+        Ok((self.get_output)()?.enable)
+    }
+
+    /// Stops the drive with the quick stop ramp and keeps it stopped until released
+    pub fn set_quick_stop(&mut self, quick_stop: bool) -> Result<(), Error> {
+        self.update_output(|output| output.quick_stop = quick_stop)
+    }
+
+    /// Resets a fault of the drive, the request is cleared once the drive left the fault state
+    pub fn reset_fault(&mut self) -> Result<(), Error> {
+        self.update_output(|output| output.fault_reset = true)
+    }
+
+    /// Target velocity in cyclic synchronous velocity mode
+    pub fn set_velocity(&mut self, velocity: i32) -> Result<(), Error> {
+        let mode = self.get_mode();
+        if mode != Cia402Mode::CyclicSynchronousVelocity {
+            return Err(anyhow!(
+                "[{}::ServoDrive::set_velocity] Mode of operation is not velocity, but {:?}",
+                module_path!(),
+                mode
+            ));
+        }
+        self.update_output(|output| output.target_velocity = velocity)
+    }
+
+    /// Target position in cyclic synchronous position mode
+    ///
+    /// The target has to move in small steps every cycle, the drive doesn't generate a profile.
+    pub fn set_target_position(&mut self, position: i32) -> Result<(), Error> {
+        let mode = self.get_mode();
+        if mode != Cia402Mode::CyclicSynchronousPosition {
+            return Err(anyhow!(
+                "[{}::ServoDrive::set_target_position] Mode of operation is not position, but {:?}",
+                module_path!(),
+                mode
+            ));
+        }
+        self.update_output(|output| output.target_position = position)
+    }
+
+    pub fn get_target_velocity(&self) -> Result<i32, Error> {
+        Ok((self.get_output)()?.target_velocity)
+    }
+
+    pub fn get_target_position(&self) -> Result<i32, Error> {
+        Ok((self.get_output)()?.target_position)
+    }
+
+    pub fn get_velocity(&self) -> Result<i32, Error> {
+        Ok((self.get_input)()?.velocity)
+    }
+
+    pub fn get_position(&self) -> Result<i32, Error> {
+        Ok((self.get_input)()?.position)
+    }
+
+    pub fn get_state(&self) -> Result<Cia402State, Error> {
+        Ok((self.get_input)()?.state)
+    }
+
+    pub fn is_operation_enabled(&self) -> Result<bool, Error> {
+        Ok(self.get_state()? == Cia402State::OperationEnabled)
+    }
+
+    pub fn has_fault(&self) -> Result<bool, Error> {
+        Ok(self.get_state()?.is_fault())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServoDriveInput {
+    /// State decoded from the statusword
+    pub state: Cia402State,
+
+    /// Warning bit of the statusword
+    pub warning: bool,
+
+    /// Target reached bit of the statusword
+    pub target_reached: bool,
+
+    /// Following error bit of the statusword, position mode only
+    pub following_error: bool,
+
+    /// Actual position
+    pub position: i32,
+
+    /// Actual velocity
+    pub velocity: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServoDriveOutput {
+    /// Requests operation enabled
+    pub enable: bool,
+
+    /// Requests a quick stop
+    pub quick_stop: bool,
+
+    /// Requests a fault reset
+    pub fault_reset: bool,
+
+    /// Cyclic synchronous velocity mode only
+    pub target_velocity: i32,
+
+    /// Cyclic synchronous position mode only
+    pub target_position: i32,
+}
+
+impl ServoDriveOutput {
+    pub const fn new() -> Self {
+        Self {
+            enable: false,
+            quick_stop: false,
+            fault_reset: false,
+            target_velocity: 0,
+            target_position: 0,
+        }
+    }
+}
+
+impl Default for ServoDriveOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait ServoDriveDevice<PORT>: Send + Sync
+where
+    PORT: Clone,
+{
+    fn set_output(&mut self, port: PORT, value: ServoDriveOutput) -> Result<(), Error>;
+    fn get_output(&self, port: PORT) -> Result<ServoDriveOutput, Error>;
+    fn get_input(&self, port: PORT) -> Result<ServoDriveInput, Error>;
+    fn get_mode(&self, port: PORT) -> Cia402Mode;
+}
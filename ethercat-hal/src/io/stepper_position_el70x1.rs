@@ -0,0 +1,228 @@
+use std::{fmt, sync::Arc};
+
+use crate::shared_config::el70x1::StartType;
+use anyhow::Error;
+use smol::lock::RwLock;
+
+/// Stepper with the positioning interface of the EL70x1 terminals
+///
+/// The terminal generates the travel profile from the target position, velocity and ramps,
+/// the io layer only starts and stops travel commands. Requires one of the `PositionInterface` PDO presets.
+pub struct StepperPositionEL70x1 {
+    /// Write the travel command
+    set_output: Box<dyn Fn(StepperPositionEL70x1Output) -> Result<(), Error> + Send + Sync>,
+    /// Read the travel command
+    get_output: Box<dyn Fn() -> Result<StepperPositionEL70x1Output, Error> + Send + Sync>,
+    /// Read the state of the positioning interface
+    get_input: Box<dyn Fn() -> Result<StepperPositionEL70x1Input, Error> + Send + Sync>,
+}
+
+impl fmt::Debug for StepperPositionEL70x1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "StepperPosition")
+    }
+}
+
+impl StepperPositionEL70x1 {
+    pub fn new<PORT, DEVICE>(device: Arc<RwLock<DEVICE>>, port: PORT) -> Self
+    where
+        PORT: Clone + Copy + Send + Sync + 'static,
+        DEVICE: StepperPositionEL70x1Device<PORT> + Send + Sync + 'static,
+    {
+        // build sync write closure
+        let device1 = device.clone();
+        let set_output = Box::new(
+            move |value: StepperPositionEL70x1Output| -> Result<(), Error> {
+                smol::block_on(async {
+                    let mut device = device1.write().await;
+                    device.set_position_output(port, value)
+                })
+            },
+        );
+
+        // build sync get closures
+        let device2 = device.clone();
+        let get_output = Box::new(move || -> Result<StepperPositionEL70x1Output, Error> {
+            smol::block_on(async {
+                let device = device2.read().await;
+                device.get_position_output(port)
+            })
+        });
+
+        let device3 = device;
+        let get_input = Box::new(move || -> Result<StepperPositionEL70x1Input, Error> {
+            smol::block_on(async {
+                let device = device3.read().await;
+                device.get_position_input(port)
+            })
+        });
+
+        Self {
+            set_output,
+            get_output,
+            get_input,
+        }
+    }
+
+    fn update_output(
+        &self,
+        update: impl FnOnce(&mut StepperPositionEL70x1Output),
+    ) -> Result<(), Error> {
+        let mut output = (self.get_output)()?;
+        update(&mut output);
+        (self.set_output)(output)
+    }
+
+    /// Enable or disable the stepper
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+        self.update_output(|output| output.enable = enabled)
+    }
+
+    pub fn is_enabled(&self) -> Result<bool, Error> {
+        Ok((self.get_output)()?.enable)
+    }
+
+    /// Starts a travel command to `target_position` in steps
+    ///
+    /// A running travel command is replaced, the terminal needs one cycle without `execute` for that.
+    pub fn start(&mut self, target_position: i32, start_type: StartType) -> Result<(), Error> {
+        self.update_output(|output| {
+            output.target_position = target_position;
+            output.start_type = start_type;
+            output.execute = true;
+            output.start = true;
+        })
+    }
+
+    /// Travels to an absolute position in steps
+    pub fn move_to(&mut self, position: i32) -> Result<(), Error> {
+        self.start(position, StartType::Absolute)
+    }
+
+    /// Travels by `distance` steps from the current position
+    pub fn move_by(&mut self, distance: i32) -> Result<(), Error> {
+        self.start(distance, StartType::Relative)
+    }
+
+    /// Stops the travel command with the deceleration ramp
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.update_output(|output| output.execute = false)
+    }
+
+    /// Stops with the emergency deceleration (8020:07) and blocks travel commands while set
+    pub fn set_emergency_stop(&mut self, emergency_stop: bool) -> Result<(), Error> {
+        self.update_output(|output| output.emergency_stop = emergency_stop)
+    }
+
+    /// Velocity of the next travel commands, same unit as [`crate::pdo::el70x1::StmVelocity`]
+    pub fn set_velocity(&mut self, velocity: i16) -> Result<(), Error> {
+        self.update_output(|output| output.target_velocity = velocity)
+    }
+
+    /// Ramps of the next travel commands in ms from standstill to the maximum velocity
+    pub fn set_ramps(&mut self, acceleration: u16, deceleration: u16) -> Result<(), Error> {
+        self.update_output(|output| {
+            output.acceleration = acceleration;
+            output.deceleration = deceleration;
+        })
+    }
+
+    pub fn get_target_position(&self) -> Result<i32, Error> {
+        Ok((self.get_output)()?.target_position)
+    }
+
+    pub fn get_position(&self) -> Result<i32, Error> {
+        Ok((self.get_input)()?.actual_position)
+    }
+
+    /// A travel command is active
+    pub fn is_busy(&self) -> Result<bool, Error> {
+        Ok((self.get_input)()?.busy)
+    }
+
+    /// The motor arrived in the target window (8020:0B)
+    pub fn is_in_target(&self) -> Result<bool, Error> {
+        Ok((self.get_input)()?.in_target)
+    }
+
+    pub fn has_error(&self) -> Result<bool, Error> {
+        Ok((self.get_input)()?.error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepperPositionEL70x1Input {
+    /// `actual_position` from [`crate::pdo::el70x1::PosStatus`] or `counter_value` from [`crate::pdo::el70x1::EncStatus`]
+    pub actual_position: i32,
+
+    /// `actual_velocity` from [`crate::pdo::el70x1::PosStatus`], not in the compact preset
+    pub actual_velocity: Option<i16>,
+
+    /// `busy` from [`crate::pdo::el70x1::PosStatus`]
+    pub busy: bool,
+
+    /// `in_target` from [`crate::pdo::el70x1::PosStatus`]
+    pub in_target: bool,
+
+    /// `warning` from [`crate::pdo::el70x1::PosStatus`] or [`crate::pdo::el70x1::StmStatus`]
+    pub warning: bool,
+
+    /// `error` from [`crate::pdo::el70x1::PosStatus`] or [`crate::pdo::el70x1::StmStatus`]
+    pub error: bool,
+
+    /// `calibrated` from [`crate::pdo::el70x1::PosStatus`]
+    pub calibrated: bool,
+
+    /// `ready_to_execute` from [`crate::pdo::el70x1::PosStatus`]
+    pub ready_to_execute: bool,
+
+    /// `ready` from [`crate::pdo::el70x1::StmStatus`]
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepperPositionEL70x1Output {
+    /// `enable` from [`crate::pdo::el70x1::StmControl`]
+    pub enable: bool,
+
+    /// `reset` from [`crate::pdo::el70x1::StmControl`]
+    pub reset: bool,
+
+    /// `emergency_stop` from [`crate::pdo::el70x1::PosControl`]
+    pub emergency_stop: bool,
+
+    /// `execute` from [`crate::pdo::el70x1::PosControl`], held while traveling
+    pub execute: bool,
+
+    /// Starts a new travel command, not stored by the device
+    pub start: bool,
+
+    /// `target_position` from [`crate::pdo::el70x1::PosControl`]
+    pub target_position: i32,
+
+    /// `target_velocity` from [`crate::pdo::el70x1::PosControl`]
+    pub target_velocity: i16,
+
+    /// `start_type` from [`crate::pdo::el70x1::PosControl`]
+    pub start_type: StartType,
+
+    /// `acceleration` from [`crate::pdo::el70x1::PosControl`]
+    pub acceleration: u16,
+
+    /// `deceleration` from [`crate::pdo::el70x1::PosControl`]
+    pub deceleration: u16,
+}
+
+/// Named apart from [`crate::io::stepper_velocity_el70x1::StepperVelocityEL70x1Device`] since the drivers implement both
+pub trait StepperPositionEL70x1Device<PORT>: Send + Sync
+where
+    PORT: Clone,
+{
+    fn set_position_output(
+        &mut self,
+        port: PORT,
+        value: StepperPositionEL70x1Output,
+    ) -> Result<(), Error>;
+    fn get_position_output(&self, port: PORT) -> Result<StepperPositionEL70x1Output, Error>;
+    fn get_position_input(&self, port: PORT) -> Result<StepperPositionEL70x1Input, Error>;
+}
@@ -38,7 +38,9 @@ impl TemperatureInput {
     /// Get the current temperature in degrees Celsius
     pub fn get_temperature(&self) -> Result<f64, TemperatureInputError> {
         let input = (self.get_input)();
-        if input.overvoltage {
+        if input.open_circuit {
+            Err(TemperatureInputError::OpenCircuit)
+        } else if input.overvoltage {
             Err(TemperatureInputError::OverVoltage)
         } else if input.undervoltage {
             Err(TemperatureInputError::UnderVoltage)
@@ -49,11 +51,14 @@ impl TemperatureInput {
 }
 
 pub enum TemperatureInputError {
-    /// Over-voltage error
+    /// Over-voltage error, the value is above the measuring range
     OverVoltage,
 
-    /// Under-voltage error
+    /// Under-voltage error, the value is below the measuring range
     UnderVoltage,
+
+    /// The sensor is not connected or its wire is broken
+    OpenCircuit,
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +78,9 @@ pub struct TemperatureInputInput {
     /// Over-voltage error
     pub overvoltage: bool,
 
+    /// Wire break, if the device can tell it apart from an over-voltage
+    pub open_circuit: bool,
+
     /// Configured limit 1
     pub limit1: Limit,
 
@@ -1,7 +1,9 @@
 pub mod coe;
+pub mod dc;
 pub mod debugging;
 pub mod devices;
 pub mod helpers;
 pub mod io;
 pub mod pdo;
 pub mod shared_config;
+pub mod sii;
@@ -0,0 +1,92 @@
+use super::{RxPdoObject, TxPdoObject};
+use bitvec::prelude::*;
+use ethercat_hal_derive::PdoObject;
+
+/// PDO Object for generic CiA 402 drives
+///
+/// Mapped to 0x1A00 by [`crate::devices::cia402::Cia402Drive`] since the default mappings differ between vendors.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 88)]
+pub struct Cia402Inputs {
+    /// # 6041:00
+    pub statusword: u16,
+
+    /// # 6064:00
+    pub position_actual_value: i32,
+
+    /// # 606C:00
+    pub velocity_actual_value: i32,
+
+    /// # 6061:00
+    pub modes_of_operation_display: i8,
+}
+
+impl TxPdoObject for Cia402Inputs {
+    fn read(&mut self, bits: &BitSlice<u8, Lsb0>) {
+        self.statusword = bits[0..16].load_le();
+        self.position_actual_value = bits[16..48].load_le();
+        self.velocity_actual_value = bits[48..80].load_le();
+        self.modes_of_operation_display = bits[80..88].load_le();
+    }
+}
+
+/// PDO Object for generic CiA 402 drives
+///
+/// Mapped to 0x1600 by [`crate::devices::cia402::Cia402Drive`].
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 88)]
+pub struct Cia402Outputs {
+    /// # 6040:00
+    pub controlword: u16,
+
+    /// # 607A:00
+    pub target_position: i32,
+
+    /// # 60FF:00
+    pub target_velocity: i32,
+
+    /// # 6060:00
+    pub modes_of_operation: i8,
+}
+
+impl RxPdoObject for Cia402Outputs {
+    fn write(&self, bits: &mut BitSlice<u8, Lsb0>) {
+        bits[0..16].store_le(self.controlword);
+        bits[16..48].store_le(self.target_position);
+        bits[48..80].store_le(self.target_velocity);
+        bits[80..88].store_le(self.modes_of_operation);
+    }
+}
+
+/// Entries of [`Cia402Inputs`] as written to the mapping object, `index << 16 | sub_index << 8 | bits`
+pub const CIA402_INPUTS_MAPPING: [u32; 4] = [0x6041_0010, 0x6064_0020, 0x606C_0020, 0x6061_0008];
+
+/// Entries of [`Cia402Outputs`] as written to the mapping object
+pub const CIA402_OUTPUTS_MAPPING: [u32; 4] = [0x6040_0010, 0x607A_0020, 0x60FF_0020, 0x6060_0008];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cia402_roundtrip() {
+        let outputs = Cia402Outputs {
+            controlword: 0x000F,
+            target_position: -5,
+            target_velocity: 1000,
+            modes_of_operation: 9,
+        };
+        let mut buffer = [0u8; 11];
+        outputs.write(buffer.view_bits_mut());
+        assert_eq!(buffer[0..2], [0x0F, 0x00]);
+        assert_eq!(buffer[10], 9);
+
+        // same layout, read back as inputs
+        let mut inputs = Cia402Inputs::default();
+        inputs.read(buffer.view_bits());
+        assert_eq!(inputs.statusword, 0x000F);
+        assert_eq!(inputs.position_actual_value, -5);
+        assert_eq!(inputs.velocity_actual_value, 1000);
+        assert_eq!(inputs.modes_of_operation_display, 9);
+    }
+}
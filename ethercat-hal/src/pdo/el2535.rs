@@ -0,0 +1,93 @@
+use super::{RxPdoObject, TxPdoObject};
+use bitvec::prelude::*;
+use ethercat_hal_derive::PdoObject;
+
+/// PDO Object for EL2535 (current-controlled PWM) devices
+///
+/// The "PWM Status" holds the state of one output stage.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct PwmStatus {
+    /// The output stage can be enabled, the supply voltage is present
+    pub ready_to_enable: bool,
+
+    /// The output stage is enabled and regulates the current
+    pub ready: bool,
+
+    /// Overtemperature warning or the current can't be reached
+    pub warning: bool,
+
+    /// Open load, short circuit or overtemperature, needs a reset
+    pub error: bool,
+
+    /// If the PDO objects data has changed since the last read
+    pub txpdo_toggle: bool,
+}
+
+impl TxPdoObject for PwmStatus {
+    fn read(&mut self, bits: &BitSlice<u8, Lsb0>) {
+        self.ready_to_enable = bits[0];
+        self.ready = bits[1];
+        self.warning = bits[2];
+        self.error = bits[3];
+        self.txpdo_toggle = bits[15];
+    }
+}
+
+/// PDO Object for EL2535 (current-controlled PWM) devices
+///
+/// Enables one output stage and sets its current.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct PwmControl {
+    /// The output stage regulates the current while set
+    pub enable: bool,
+
+    /// Rising edge acknowledges an error
+    pub reset: bool,
+
+    /// Current from `0` to `0x7FFF` (maximum current), negative values reverse the current
+    pub output: i16,
+}
+
+impl RxPdoObject for PwmControl {
+    fn write(&self, buffer: &mut BitSlice<u8, Lsb0>) {
+        buffer.set(0, self.enable);
+        buffer.set(1, self.reset);
+        buffer[16..32].store_le(self.output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pwm_status() {
+        let buffer = [0b0000_1011u8, 0b1000_0000u8];
+        let mut status = PwmStatus::default();
+        status.read(buffer.view_bits::<Lsb0>());
+        assert_eq!(
+            status,
+            PwmStatus {
+                ready_to_enable: true,
+                ready: true,
+                warning: false,
+                error: true,
+                txpdo_toggle: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pwm_control() {
+        let mut buffer = [0u8; 4];
+        let control = PwmControl {
+            enable: true,
+            reset: false,
+            output: 0x1234,
+        };
+        control.write(buffer.view_bits_mut::<Lsb0>());
+        assert_eq!(buffer, [0b0000_0001u8, 0u8, 0x34u8, 0x12u8]);
+    }
+}
@@ -0,0 +1,34 @@
+use super::RxPdoObject;
+use bitvec::prelude::*;
+use ethercat_hal_derive::PdoObject;
+
+/// PDO Object for EL331x (thermocouple) devices
+///
+/// The "TC Outputs" hold the cold junction temperature if the compensation is done via process data.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct TcOutput {
+    /// Cold junction temperature in the configured presentation
+    pub cold_junction_compensation: i16,
+}
+
+impl RxPdoObject for TcOutput {
+    fn write(&self, bits: &mut BitSlice<u8, Lsb0>) {
+        bits[0..16].store_le(self.cold_junction_compensation as u16);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tc_output_write() {
+        let output = TcOutput {
+            cold_junction_compensation: -215,
+        };
+        let mut buffer = [0u8; 2];
+        output.write(buffer.view_bits_mut::<Lsb0>());
+        assert_eq!(i16::from_le_bytes(buffer), -215);
+    }
+}
@@ -0,0 +1,123 @@
+use super::{RxPdoObject, TxPdoObject};
+use bitvec::prelude::*;
+use ethercat_hal_derive::PdoObject;
+
+/// PDO Object for EL3356 (load cell) devices
+///
+/// The "RMB Status" holds the state of the bridge measurement.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct RmbStatus {
+    /// The bridge signal is under the measuring range
+    pub underrange: bool,
+
+    /// The bridge signal is over the measuring range
+    pub overrange: bool,
+
+    /// The value is not valid, e.g. while the terminal is starting up
+    pub data_invalid: bool,
+
+    /// Wire break or ADC error
+    pub error: bool,
+
+    /// The terminal is running a self calibration
+    pub calibration_in_progress: bool,
+
+    /// The load didn't change more than the configured tolerance for the configured time
+    pub steady_state: bool,
+
+    /// Distributed clocks synchronisation error
+    pub sync_error: bool,
+
+    /// If the PDO objects data has changed since the last read
+    pub txpdo_toggle: bool,
+}
+
+impl TxPdoObject for RmbStatus {
+    fn read(&mut self, bits: &BitSlice<u8, Lsb0>) {
+        self.underrange = bits[0];
+        self.overrange = bits[1];
+        self.data_invalid = bits[3];
+        self.error = bits[6];
+        self.calibration_in_progress = bits[7];
+        self.steady_state = bits[8];
+        self.sync_error = bits[13];
+        self.txpdo_toggle = bits[15];
+    }
+}
+
+/// PDO Object for EL3356 (load cell) devices
+///
+/// The load as integer, scaled by the configured scale factor.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct RmbValueInt {
+    pub value: i32,
+}
+
+impl TxPdoObject for RmbValueInt {
+    fn read(&mut self, bits: &BitSlice<u8, Lsb0>) {
+        self.value = bits[0..32].load_le::<i32>();
+    }
+}
+
+/// PDO Object for EL3356 (load cell) devices
+///
+/// Controls the sampling and the hardware calibration of the terminal.
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct RmbControl {
+    /// Rising edge starts a self calibration
+    pub start_calibration: bool,
+
+    /// Disables the cyclic self calibration
+    pub disable_calibration: bool,
+
+    /// Holds the last value, e.g. while the load is changed
+    pub input_freeze: bool,
+
+    /// `false` samples with mode 0, `true` with mode 1
+    pub sample_mode: bool,
+
+    /// Rising edge zeroes the value with the current load
+    pub tare: bool,
+}
+
+impl RxPdoObject for RmbControl {
+    fn write(&self, bits: &mut BitSlice<u8, Lsb0>) {
+        bits.set(0, self.start_calibration);
+        bits.set(1, self.disable_calibration);
+        bits.set(2, self.input_freeze);
+        bits.set(3, self.sample_mode);
+        bits.set(4, self.tare);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rmb_status_read() {
+        let mut status = RmbStatus::default();
+        status.read([0b0100_0010u8, 0b1000_0001].view_bits());
+        assert!(!status.underrange);
+        assert!(status.overrange);
+        assert!(status.error);
+        assert!(status.steady_state);
+        assert!(!status.sync_error);
+        assert!(status.txpdo_toggle);
+    }
+
+    #[test]
+    fn test_rmb_control_write() {
+        let control = RmbControl {
+            sample_mode: true,
+            tare: true,
+            ..Default::default()
+        };
+        let mut buffer = [0u8; 2];
+        control.write(buffer.view_bits_mut());
+        assert_eq!(buffer, [0b0001_1000, 0]);
+    }
+}
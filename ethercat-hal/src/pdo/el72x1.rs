@@ -0,0 +1,105 @@
+use super::{RxPdoObject, TxPdoObject};
+use bitvec::prelude::*;
+use ethercat_hal_derive::PdoObject;
+
+/// # `FbPosition`
+/// 32 bits / 4 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct FbPosition {
+    /// # 6000:11
+    /// Position of the feedback (motor encoder).
+    pub position: u32,
+}
+
+impl TxPdoObject for FbPosition {
+    fn read(&mut self, buffer: &BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        self.position = buffer[0..32].load_le();
+    }
+}
+
+/// # `DrvStatusword`
+/// 16 bits / 2 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct DrvStatusword {
+    /// # 6010:01
+    /// CiA 402 statusword, see [`crate::helpers::cia402::Cia402Status`].
+    pub statusword: u16,
+}
+
+impl TxPdoObject for DrvStatusword {
+    fn read(&mut self, buffer: &BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        self.statusword = buffer[0..16].load_le();
+    }
+}
+
+/// # `DrvVelocityActualValue`
+/// 32 bits / 4 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct DrvVelocityActualValue {
+    /// # 6010:07
+    /// Actual velocity.
+    pub velocity: i32,
+}
+
+impl TxPdoObject for DrvVelocityActualValue {
+    fn read(&mut self, buffer: &BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        self.velocity = buffer[0..32].load_le();
+    }
+}
+
+/// # `DrvControlword`
+/// 16 bits / 2 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 16)]
+pub struct DrvControlword {
+    /// # 7010:01
+    /// CiA 402 controlword, see [`crate::helpers::cia402::next_controlword`].
+    pub controlword: u16,
+}
+
+impl RxPdoObject for DrvControlword {
+    fn write(&self, buffer: &mut BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        buffer[0..16].store_le(self.controlword);
+    }
+}
+
+/// # `DrvTargetVelocity`
+/// 32 bits / 4 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct DrvTargetVelocity {
+    /// # 7010:06
+    /// Target velocity in cyclic synchronous velocity mode.
+    pub velocity: i32,
+}
+
+impl RxPdoObject for DrvTargetVelocity {
+    fn write(&self, buffer: &mut BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        buffer[0..32].store_le(self.velocity);
+    }
+}
+
+/// # `DrvTargetPosition`
+/// 32 bits / 4 bytes
+#[derive(Debug, Clone, Default, PdoObject, PartialEq, Eq)]
+#[pdo_object(bits = 32)]
+pub struct DrvTargetPosition {
+    /// # 7010:05
+    /// Target position in cyclic synchronous position mode.
+    pub position: u32,
+}
+
+impl RxPdoObject for DrvTargetPosition {
+    fn write(&self, buffer: &mut BitSlice<u8, Lsb0>) {
+        // Offset 0.0
+        buffer[0..32].store_le(self.position);
+    }
+}
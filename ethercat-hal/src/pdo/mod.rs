@@ -1,10 +1,15 @@
 pub mod analog_input;
 pub mod basic;
+pub mod cia402;
 pub mod el252x;
+pub mod el2535;
 pub mod el32xx;
+pub mod el331x;
+pub mod el3356;
 pub mod el40xx;
 pub mod el5152;
 pub mod el70x1;
+pub mod el72x1;
 use crate::coe::Configuration;
 use bitvec::prelude::*;
 
@@ -1,4 +1,7 @@
-use crate::helpers::ethercrab_types::EthercrabSubDevicePreoperational;
+use crate::helpers::{
+    ethercrab_types::EthercrabSubDevicePreoperational,
+    user_scale::{gain_to_fixed_point, revert_user_scale},
+};
 
 impl EL30XXChannelConfiguration {
     pub async fn write_channel_config<'a>(
@@ -54,6 +57,30 @@ impl EL30XXChannelConfiguration {
     }
 }
 
+impl EL30XXChannelConfiguration {
+    /// Enables the user scale, the terminal reports `value * gain + offset`
+    pub fn set_user_scale(&mut self, gain: f64, offset: i16) {
+        self.enable_user_scale = true;
+        self.user_scale_gain = gain_to_fixed_point(gain);
+        self.user_scale_offset = offset;
+    }
+
+    /// Reverts the user scale of a reported value, so it matches the
+    /// [`crate::io::analog_input::physical::AnalogInputRange`] again
+    pub fn revert_user_scale(&self, value: i16) -> i16 {
+        if !self.enable_user_scale {
+            return value;
+        }
+        revert_user_scale(
+            f64::from(value),
+            self.user_scale_gain,
+            self.user_scale_offset,
+        )
+        .round()
+        .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EL30XXChannelConfiguration {
     // 80n0:01 User Scaling is Active
@@ -0,0 +1,365 @@
+use crate::{
+    helpers::{
+        ethercrab_types::EthercrabSubDevicePreoperational,
+        signing_converter_u16::U16SigningConverter,
+    },
+    io::temperature_input::TemperatureInputInput,
+    pdo::analog_input::AiStandard,
+};
+
+impl EL331XChannelConfiguration {
+    pub async fn write_channel_config<'a>(
+        &self,
+        device: &EthercrabSubDevicePreoperational<'a>,
+        base_index: u16,
+    ) -> Result<(), anyhow::Error> {
+        device
+            .sdo_write(base_index, 0x01, self.enable_user_scale)
+            .await?;
+        device
+            .sdo_write(base_index, 0x02, u8::from(self.presentation))
+            .await?;
+        device
+            .sdo_write(base_index, 0x06, self.enable_filter)
+            .await?;
+        device
+            .sdo_write(base_index, 0x07, self.enable_limit_1)
+            .await?;
+        device
+            .sdo_write(base_index, 0x08, self.enable_limit_2)
+            .await?;
+        device
+            .sdo_write(base_index, 0x0A, self.enable_user_calibration)
+            .await?;
+        device
+            .sdo_write(base_index, 0x0B, self.enable_vendor_calibration)
+            .await?;
+        device
+            .sdo_write(base_index, 0x0C, u8::from(self.cold_junction_compensation))
+            .await?;
+        device
+            .sdo_write(base_index, 0x11, self.user_scale_offset)
+            .await?;
+        device
+            .sdo_write(base_index, 0x12, self.user_scale_gain)
+            .await?;
+        device.sdo_write(base_index, 0x13, self.limit_1).await?;
+        device.sdo_write(base_index, 0x14, self.limit_2).await?;
+        device
+            .sdo_write(base_index, 0x15, u16::from(self.filter_settings))
+            .await?;
+        device
+            .sdo_write(base_index, 0x17, self.user_calibration_offset)
+            .await?;
+        device
+            .sdo_write(base_index, 0x18, self.user_calibration_gain)
+            .await?;
+        device
+            .sdo_write(base_index, 0x19, u16::from(self.element_type))
+            .await?;
+        Ok(())
+    }
+
+    /// Decodes the status and the temperature of a channel according to its presentation
+    ///
+    /// A broken wire drives the value over the measuring range, so over-range together with the error bit is reported as open circuit.
+    pub fn temperature_input(&self, channel: &AiStandard) -> TemperatureInputInput {
+        let raw_value = U16SigningConverter::load_raw(channel.value);
+        let temperature = match self.presentation {
+            EL331XPresentation::Signed => f32::from(raw_value.as_signed()) / 10.0,
+            EL331XPresentation::SignedMagnitude => {
+                f32::from(raw_value.as_signed_magnitude()) / 10.0
+            }
+            EL331XPresentation::HighResolution => f32::from(raw_value.as_signed()) / 100.0,
+        };
+        TemperatureInputInput {
+            temperature,
+            undervoltage: channel.undervoltage,
+            overvoltage: channel.overvoltage,
+            open_circuit: channel.overvoltage && channel.error,
+            limit1: channel.limit1,
+            limit2: channel.limit2,
+            error: channel.error,
+            txpdo_state: channel.txpdo_state,
+            txpdo_toggle: channel.txpdo_toggle,
+        }
+    }
+
+    /// Encodes a cold junction temperature in degrees Celsius for the process data
+    pub fn cold_junction_raw(&self, temperature: f32) -> i16 {
+        let resolution = match self.presentation {
+            EL331XPresentation::Signed | EL331XPresentation::SignedMagnitude => 10.0,
+            EL331XPresentation::HighResolution => 100.0,
+        };
+        // `as` saturates at the i16 range
+        (temperature * resolution).round() as i16
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EL331XChannelConfiguration {
+    // 80n0:01 User Scaling is Active
+    pub enable_user_scale: bool,
+
+    // 80n0:02
+    // 0: Signed presentation, 0.1°C
+    // 1: Absolute value with MSB as sign, 0.1°C
+    // 2: High resolution, 0.01°C
+    pub presentation: EL331XPresentation,
+
+    // 80n0:06
+    // Enable filter, which makes PLC-cycle-synchronous
+    // data exchange unnecessary
+    pub enable_filter: bool,
+
+    // 80n0:07
+    // limit 1 enabled
+    pub enable_limit_1: bool,
+
+    // 80n0:08
+    // limit2 enabled
+    pub enable_limit_2: bool,
+
+    // 80n0:0A
+    // enabling of user_calibration
+    pub enable_user_calibration: bool,
+
+    // 80n0:0B
+    // enabling of vendor_calibration
+    pub enable_vendor_calibration: bool,
+
+    // 80n0:0C
+    // Source of the cold junction temperature
+    pub cold_junction_compensation: EL331XColdJunctionCompensation,
+
+    // 80n0:11
+    // User Scaling Offset
+    pub user_scale_offset: i16,
+
+    // 80n0:12
+    // Gain of the user scaling
+    // The gain has a fixed-point-representation with the factor
+    // 2^-16 The value 1 corresponds to 65536
+    pub user_scale_gain: i32,
+
+    // 80n0:13
+    // First limit value for setting the status bits
+    pub limit_1: i16,
+
+    // 80n0:14
+    // Second limit value for setting the status bits
+    pub limit_2: i16,
+
+    // 80n0:15
+    // Digital filter if it is active via Enable filter (80n0:06).
+    // Only the setting of channel 1 is used, it applies to all channels.
+    pub filter_settings: EL331XFilterSettings,
+
+    // 80n0:17
+    // User calibration offset
+    pub user_calibration_offset: i16,
+
+    // 80n0:18
+    // User calibration gain
+    pub user_calibration_gain: i16,
+
+    // 80n0:19
+    // Thermocouple element type or voltage measurement
+    pub element_type: EL331XElementType,
+}
+
+impl Default for EL331XChannelConfiguration {
+    fn default() -> Self {
+        Self {
+            enable_user_scale: false,
+            presentation: EL331XPresentation::Signed,
+            enable_filter: true,
+            enable_limit_1: false,
+            enable_limit_2: false,
+            enable_user_calibration: false,
+            enable_vendor_calibration: true,
+            cold_junction_compensation: EL331XColdJunctionCompensation::Internal,
+            user_scale_offset: 0,
+            user_scale_gain: 65536,
+            limit_1: 0,
+            limit_2: 0,
+            filter_settings: EL331XFilterSettings::FIR50Hz,
+            user_calibration_offset: 0,
+            user_calibration_gain: 16384,
+            element_type: EL331XElementType::K,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL331XPresentation {
+    Signed,
+    SignedMagnitude,
+    HighResolution,
+}
+
+impl From<EL331XPresentation> for u8 {
+    fn from(presentation: EL331XPresentation) -> Self {
+        match presentation {
+            EL331XPresentation::Signed => 0,
+            EL331XPresentation::SignedMagnitude => 1,
+            EL331XPresentation::HighResolution => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL331XColdJunctionCompensation {
+    /// Temperature of the terminal contacts
+    Internal,
+    /// No compensation, e.g. for the mV ranges
+    Disabled,
+    /// Written by the controller via the "TC Outputs" RxPDO
+    ProcessData,
+}
+
+impl From<EL331XColdJunctionCompensation> for u8 {
+    fn from(compensation: EL331XColdJunctionCompensation) -> Self {
+        match compensation {
+            EL331XColdJunctionCompensation::Internal => 0,
+            EL331XColdJunctionCompensation::Disabled => 1,
+            EL331XColdJunctionCompensation::ProcessData => 2,
+        }
+    }
+}
+
+/// Notch frequency of the filter, lower frequencies mean a longer conversion time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL331XFilterSettings {
+    FIR5Hz,
+    FIR10Hz,
+    FIR50Hz,
+    FIR60Hz,
+    FIR100Hz,
+    FIR500Hz,
+    FIR1kHz,
+    FIR2kHz,
+    FIR3750Hz,
+    FIR7500Hz,
+    FIR15kHz,
+    FIR30kHz,
+}
+
+impl From<EL331XFilterSettings> for u16 {
+    fn from(filter_settings: EL331XFilterSettings) -> Self {
+        match filter_settings {
+            EL331XFilterSettings::FIR50Hz => 0,
+            EL331XFilterSettings::FIR60Hz => 1,
+            EL331XFilterSettings::FIR100Hz => 2,
+            EL331XFilterSettings::FIR500Hz => 3,
+            EL331XFilterSettings::FIR1kHz => 4,
+            EL331XFilterSettings::FIR2kHz => 5,
+            EL331XFilterSettings::FIR3750Hz => 6,
+            EL331XFilterSettings::FIR7500Hz => 7,
+            EL331XFilterSettings::FIR15kHz => 8,
+            EL331XFilterSettings::FIR30kHz => 9,
+            EL331XFilterSettings::FIR5Hz => 10,
+            EL331XFilterSettings::FIR10Hz => 11,
+        }
+    }
+}
+
+/// Thermocouple types and voltage ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EL331XElementType {
+    /// -200°C to 1370°C
+    K,
+    /// -100°C to 1200°C
+    J,
+    /// 0°C to 900°C
+    L,
+    /// -100°C to 1000°C
+    E,
+    /// -200°C to 400°C
+    T,
+    /// -100°C to 1300°C
+    N,
+    /// 0°C to 600°C
+    U,
+    /// 600°C to 1800°C
+    B,
+    /// 0°C to 1767°C
+    R,
+    /// 0°C to 1760°C
+    S,
+    /// 0°C to 2320°C
+    C,
+    /// ±30mV, 1µV per digit
+    Millivolt30,
+    /// ±60mV, 2µV per digit
+    Millivolt60,
+    /// ±75mV, 4µV per digit
+    Millivolt75,
+}
+
+impl From<EL331XElementType> for u16 {
+    fn from(element_type: EL331XElementType) -> Self {
+        match element_type {
+            EL331XElementType::K => 0,
+            EL331XElementType::J => 1,
+            EL331XElementType::L => 2,
+            EL331XElementType::E => 3,
+            EL331XElementType::T => 4,
+            EL331XElementType::N => 5,
+            EL331XElementType::U => 6,
+            EL331XElementType::B => 7,
+            EL331XElementType::R => 8,
+            EL331XElementType::S => 9,
+            EL331XElementType::C => 10,
+            EL331XElementType::Millivolt30 => 100,
+            EL331XElementType::Millivolt60 => 101,
+            EL331XElementType::Millivolt75 => 102,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temperature_input_presentation() {
+        let channel = AiStandard {
+            value: (-1234i16) as u16,
+            ..Default::default()
+        };
+        let mut config = EL331XChannelConfiguration::default();
+        assert_eq!(config.temperature_input(&channel).temperature, -123.4);
+
+        config.presentation = EL331XPresentation::HighResolution;
+        assert_eq!(config.temperature_input(&channel).temperature, -12.34);
+        assert_eq!(config.cold_junction_raw(21.5), 2150);
+
+        config.presentation = EL331XPresentation::SignedMagnitude;
+        let channel = AiStandard {
+            value: 0x8000 | 215,
+            ..Default::default()
+        };
+        assert_eq!(config.temperature_input(&channel).temperature, -21.5);
+        assert_eq!(config.cold_junction_raw(-21.5), -215);
+    }
+
+    #[test]
+    fn test_temperature_input_open_circuit() {
+        let config = EL331XChannelConfiguration::default();
+        let overrange = AiStandard {
+            overvoltage: true,
+            value: 0x7FFF,
+            ..Default::default()
+        };
+        let input = config.temperature_input(&overrange);
+        assert!(input.overvoltage);
+        assert!(!input.open_circuit);
+
+        let broken = AiStandard {
+            error: true,
+            ..overrange
+        };
+        assert!(config.temperature_input(&broken).open_circuit);
+    }
+}
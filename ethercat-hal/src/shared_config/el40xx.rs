@@ -1,4 +1,7 @@
-use crate::helpers::ethercrab_types::EthercrabSubDevicePreoperational;
+use crate::helpers::{
+    ethercrab_types::EthercrabSubDevicePreoperational,
+    user_scale::{apply_user_scale, gain_to_fixed_point, revert_user_scale},
+};
 
 #[derive(Debug, Clone)]
 pub struct EL40XXChannelConfiguration {
@@ -108,6 +111,33 @@ impl Default for EL40XXChannelConfiguration {
 }
 
 impl EL40XXChannelConfiguration {
+    /// Enables the user scale, the terminal outputs `value * gain + offset`
+    pub fn set_user_scale(&mut self, gain: f64, offset: i16) {
+        self.enable_user_scale = true;
+        self.gain = gain_to_fixed_point(gain);
+        self.offset = offset;
+    }
+
+    /// Value the terminal outputs for `value` after its user scale
+    pub fn apply_user_scale(&self, value: i16) -> i16 {
+        if !self.enable_user_scale {
+            return value;
+        }
+        apply_user_scale(f64::from(value), self.gain, self.offset)
+            .round()
+            .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+    }
+
+    /// Value to write so the terminal outputs `value` after its user scale
+    pub fn revert_user_scale(&self, value: i16) -> i16 {
+        if !self.enable_user_scale {
+            return value;
+        }
+        revert_user_scale(f64::from(value), self.gain, self.offset)
+            .round()
+            .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+    }
+
     pub async fn write_channel_config<'a>(
         &self,
         device: &EthercrabSubDevicePreoperational<'a>,
@@ -727,6 +727,13 @@ impl From<EL70x1OperationMode> for u8 {
     }
 }
 
+impl EL70x1OperationMode {
+    /// The positioning interface needs the position controller, automatic selects it from the PDO assignment
+    pub const fn supports_positioning(&self) -> bool {
+        matches!(self, Self::Automatic | Self::PositionController)
+    }
+}
+
 /// Speed range for EL7031
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum EL70x1SpeedRange {
@@ -1,3 +1,4 @@
 pub mod el30xx;
+pub mod el331x;
 pub mod el40xx;
 pub mod el70x1;
@@ -0,0 +1,267 @@
+//! PDO descriptions from the SubDevice Information Interface (SII) in the EEPROM
+//!
+//! Every SubDevice describes its default process data in the SII, also devices without CoE.
+//! See ETG.2010 for the layout of the categories.
+
+use ethercrab::MainDevice;
+
+use crate::helpers::ethercrab_types::EthercrabSubDevicePreoperational;
+
+/// Word address of the first category
+const CATEGORY_START: u16 = 0x40;
+const CATEGORY_STRINGS: u16 = 10;
+const CATEGORY_TXPDO: u16 = 50;
+const CATEGORY_RXPDO: u16 = 51;
+const CATEGORY_END: u16 = 0xFFFF;
+
+/// Upper bound of categories to walk through, protects against corrupt EEPROMs
+const MAX_CATEGORIES: usize = 64;
+
+const PDO_HEADER_LEN: usize = 8;
+const PDO_ENTRY_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiiPdo {
+    pub index: u16,
+    pub name: String,
+    /// Sync manager the PDO is assigned to by default, `None` if it isn't assigned
+    pub sync_manager: Option<u8>,
+    pub entries: Vec<SiiPdoEntry>,
+}
+
+impl SiiPdo {
+    pub fn bit_len(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| usize::from(entry.bit_len))
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiiPdoEntry {
+    /// `0` for padding
+    pub index: u16,
+    pub sub_index: u8,
+    pub name: String,
+    /// Data type as in ETG.1000.6 table 64, e.g. `0x01` BOOL or `0x07` UDINT
+    pub data_type: u8,
+    pub bit_len: u8,
+}
+
+impl SiiPdoEntry {
+    pub const fn is_padding(&self) -> bool {
+        self.index == 0
+    }
+}
+
+/// TxPDOs (inputs) and RxPDOs (outputs) of a SubDevice
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SiiPdos {
+    pub txpdos: Vec<SiiPdo>,
+    pub rxpdos: Vec<SiiPdo>,
+}
+
+impl SiiPdos {
+    /// Decodes the PDO categories, `categories` are pairs of category type and data
+    pub fn from_categories(categories: &[(u16, Vec<u8>)]) -> Result<Self, anyhow::Error> {
+        let strings = categories
+            .iter()
+            .find(|(category, _)| *category == CATEGORY_STRINGS)
+            .map(|(_, data)| parse_strings(data))
+            .unwrap_or_default();
+
+        let mut pdos = Self::default();
+        for (category, data) in categories {
+            match *category {
+                CATEGORY_TXPDO => pdos.txpdos.extend(parse_pdos(data, &strings)?),
+                CATEGORY_RXPDO => pdos.rxpdos.extend(parse_pdos(data, &strings)?),
+                _ => (),
+            }
+        }
+        Ok(pdos)
+    }
+}
+
+/// Reads the PDOs from the SII of a SubDevice
+///
+/// Only the strings and PDO categories are read, the EEPROM is slow.
+pub async fn read_sii_pdos(
+    subdevice: &EthercrabSubDevicePreoperational<'_>,
+    maindevice: &MainDevice<'_>,
+) -> Result<SiiPdos, anyhow::Error> {
+    let mut categories = vec![];
+    let mut word = CATEGORY_START;
+    for _ in 0..MAX_CATEGORIES {
+        let mut header = [0u8; 4];
+        subdevice
+            .eeprom_read_raw(maindevice, word, &mut header)
+            .await?;
+        let category = u16::from_le_bytes([header[0], header[1]]);
+        let len_words = u16::from_le_bytes([header[2], header[3]]);
+        if category == CATEGORY_END {
+            break;
+        }
+        if matches!(category, CATEGORY_STRINGS | CATEGORY_TXPDO | CATEGORY_RXPDO) {
+            let mut data = vec![0u8; usize::from(len_words) * 2];
+            subdevice
+                .eeprom_read_raw(maindevice, word + 2, &mut data)
+                .await?;
+            categories.push((category, data));
+        }
+        word = word
+            .checked_add(2 + len_words)
+            .ok_or_else(|| anyhow::anyhow!("SII category at word {:#06x} is too long", word))?;
+    }
+    SiiPdos::from_categories(&categories)
+}
+
+/// Strings are referenced by a 1-based index, `0` is no string
+fn parse_strings(data: &[u8]) -> Vec<String> {
+    let Some((&count, mut rest)) = data.split_first() else {
+        return vec![];
+    };
+    let mut strings = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let Some((&len, tail)) = rest.split_first() else {
+            break;
+        };
+        let len = usize::from(len).min(tail.len());
+        strings.push(String::from_utf8_lossy(&tail[..len]).into_owned());
+        rest = &tail[len..];
+    }
+    strings
+}
+
+fn string(strings: &[String], index: u8) -> String {
+    usize::from(index)
+        .checked_sub(1)
+        .and_then(|index| strings.get(index))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn parse_pdos(data: &[u8], strings: &[String]) -> Result<Vec<SiiPdo>, anyhow::Error> {
+    let mut pdos = vec![];
+    let mut rest = data;
+    while rest.len() >= PDO_HEADER_LEN {
+        let (header, tail) = rest.split_at(PDO_HEADER_LEN);
+        let index = u16::from_le_bytes([header[0], header[1]]);
+        // the category is padded to whole words
+        if index == 0 {
+            break;
+        }
+        let entry_count = usize::from(header[2]);
+        let sync_manager = header[3];
+        let entries_len = entry_count * PDO_ENTRY_LEN;
+        if tail.len() < entries_len {
+            anyhow::bail!(
+                "PDO {:#06x} has {} entries but only {} bytes are left",
+                index,
+                entry_count,
+                tail.len()
+            );
+        }
+        let (entries, tail) = tail.split_at(entries_len);
+        pdos.push(SiiPdo {
+            index,
+            name: string(strings, header[5]),
+            // 0xFF and everything beyond the sync managers means not assigned
+            sync_manager: (sync_manager < 16).then_some(sync_manager),
+            entries: entries
+                .chunks_exact(PDO_ENTRY_LEN)
+                .map(|entry| SiiPdoEntry {
+                    index: u16::from_le_bytes([entry[0], entry[1]]),
+                    sub_index: entry[2],
+                    name: string(strings, entry[3]),
+                    data_type: entry[4],
+                    bit_len: entry[5],
+                })
+                .collect(),
+        });
+        rest = tail;
+    }
+    Ok(pdos)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Strings and PDO categories of a device with one digital output PDO and two input PDOs
+    pub fn categories() -> Vec<(u16, Vec<u8>)> {
+        let strings = [
+            &[4u8][..],
+            &[7],
+            b"Channel",
+            &[5],
+            b"Input",
+            &[6],
+            b"Output",
+            &[5],
+            b"Value",
+        ]
+        .concat();
+        let txpdos = [
+            // 0x1A00 Channel, 2 entries, SM3
+            &[0x00u8, 0x1A, 2, 3, 0, 1, 0, 0][..],
+            &[0x00, 0x60, 1, 2, 0x01, 1, 0, 0],
+            // padding
+            &[0x00, 0x00, 0, 0, 0x00, 7, 0, 0],
+            // 0x1A01 Channel, 1 entry, SM3
+            &[0x01, 0x1A, 1, 3, 0, 1, 0, 0],
+            &[0x10, 0x60, 0x11, 4, 0x03, 16, 0, 0],
+            // 0x1A02 not assigned
+            &[0x02, 0x1A, 1, 0xFF, 0, 1, 0, 0],
+            &[0x20, 0x60, 0x11, 4, 0x03, 16, 0, 0],
+        ]
+        .concat();
+        let rxpdos = [
+            &[0x00u8, 0x16, 1, 2, 0, 1, 0, 0][..],
+            &[0x00, 0x70, 1, 3, 0x01, 1, 0, 0],
+            // word padding
+            &[0, 0],
+        ]
+        .concat();
+        vec![
+            (CATEGORY_STRINGS, strings),
+            (30, vec![0; 32]),
+            (CATEGORY_TXPDO, txpdos),
+            (CATEGORY_RXPDO, rxpdos),
+        ]
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        let strings = parse_strings(&[2, 1, b'a', 2, b'b', b'c']);
+        assert_eq!(strings, vec!["a", "bc"]);
+        assert_eq!(string(&strings, 0), "");
+        assert_eq!(string(&strings, 2), "bc");
+        assert_eq!(string(&strings, 3), "");
+        // truncated
+        assert_eq!(parse_strings(&[2, 5, b'a']), vec!["a"]);
+    }
+
+    #[test]
+    fn test_from_categories() {
+        let pdos = SiiPdos::from_categories(&categories()).unwrap();
+        assert_eq!(pdos.txpdos.len(), 3);
+        assert_eq!(pdos.rxpdos.len(), 1);
+
+        let pdo = &pdos.txpdos[0];
+        assert_eq!(pdo.index, 0x1A00);
+        assert_eq!(pdo.name, "Channel");
+        assert_eq!(pdo.sync_manager, Some(3));
+        assert_eq!(pdo.bit_len(), 8);
+        assert_eq!(pdo.entries[0].name, "Input");
+        assert!(pdo.entries[1].is_padding());
+        assert_eq!(pdos.txpdos[2].sync_manager, None);
+        assert_eq!(pdos.rxpdos[0].entries[0].name, "Output");
+    }
+
+    #[test]
+    fn test_truncated_pdo() {
+        let categories = vec![(CATEGORY_TXPDO, vec![0x00, 0x1A, 2, 3, 0, 0, 0, 0])];
+        assert!(SiiPdos::from_categories(&categories).is_err());
+    }
+}
@@ -18,13 +18,17 @@ impl MachineAct for AnalogInputTestMachine {
             }
             crate::MachineMessage::ConnectToMachine(_machine_connection) => {}
             MachineMessage::DisconnectMachine(_machine_connection) => {}
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::Value::Null,
-                        live_values: serde_json::Value::Null,
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::Value::Null,
+                    live_values: serde_json::Value::Null,
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
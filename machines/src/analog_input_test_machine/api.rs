@@ -1,3 +1,5 @@
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 use std::sync::Arc;
 
 use control_core::socketio::{
@@ -47,11 +49,16 @@ pub enum AnalogInputTestMachineEvents {
     State(Event<MeasurementEvent>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct Mutation {
     measurement_rate_hz: i32,
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+}
+
 impl MachineApi for AnalogInputTestMachine {
     fn api_get_sender(&self) -> smol::channel::Sender<crate::MachineMessage> {
         self.api_sender.clone()
@@ -64,6 +71,10 @@ impl MachineApi for AnalogInputTestMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &serde_json::Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<control_core::socketio::namespace::Namespace> {
         self.namespace.namespace.clone()
     }
@@ -32,6 +32,11 @@ impl MachineAct for AquaPathV1 {
         }
     }
 
+    fn safe_stop(&mut self) {
+        self.switch_mode(AquaPathV1Mode::Standby);
+        self.turn_off_all();
+    }
+
     fn act_machine_message(&mut self, msg: MachineMessage) {
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
@@ -40,9 +45,9 @@ impl MachineAct for AquaPathV1 {
             }
             MachineMessage::UnsubscribeNamespace => self.namespace.namespace = None,
             MachineMessage::HttpApiJsonRequest(value) => {
-                use crate::MachineApi;
+                use crate::Machine;
 
-                let _res = self.api_mutate(value);
+                let _res = self.api_mutate_persistent(value);
             }
             MachineMessage::ConnectToMachine(_machine_connection) =>
                 /*Doesnt connect to any Machine so do nothing*/
@@ -50,15 +55,19 @@ impl MachineAct for AquaPathV1 {
             MachineMessage::DisconnectMachine(_machine_connection) =>
                 /*Doesnt connect to any Machine so do nothing*/
                 {}
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
@@ -1,4 +1,6 @@
 use super::{AquaPathV1, AquaPathV1Mode};
+use crate::persistence::PersistentSettings;
+use crate::schema::MachineApiSchema;
 use crate::{MachineApi, MachineMessage};
 use control_core::socketio::{
     event::{Event, GenericEvent},
@@ -6,13 +8,14 @@ use control_core::socketio::{
         CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_first_and_last_event,
     },
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use smol::channel::Sender;
 use std::sync::Arc;
 use tracing::instrument;
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     pub front_flow: f64,
     pub back_flow: f64,
@@ -34,7 +37,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// mode state
@@ -51,50 +54,50 @@ impl StateEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct TempStates {
     pub front: TempState,
     pub back: TempState,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct TempState {
     pub temperature: f64,
     pub target_temperature: f64,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ModeState {
     pub mode: AquaPathV1Mode,
 }
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct FlowStates {
     pub front: FlowState,
     pub back: FlowState,
 }
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct FlowState {
     pub flow: f64,
     pub should_flow: bool,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct FanState {
     pub revolutions: f64,
     pub max_revolutions: f64,
 }
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct FanStates {
     pub front: FanState,
     pub back: FanState,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ToleranceState {
     pub heating: f64,
     pub cooling: f64,
 }
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ToleranceStates {
     pub front: ToleranceState,
     pub back: ToleranceState,
@@ -105,7 +108,7 @@ pub enum AquaPathV1Events {
     State(Event<StateEvent>),
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 enum Mutation {
     //Mode
     SetAquaPathMode(AquaPathV1Mode),
@@ -125,6 +128,13 @@ enum Mutation {
     SetBackCoolingTolerance(f64),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 #[derive(Debug, Clone)]
 pub struct AquaPathV1Namespace {
     pub namespace: Option<Namespace>,
@@ -160,6 +170,22 @@ impl CacheableEvents<AquaPathV1Events> for AquaPathV1Events {
     }
 }
 
+/// Target temperatures, maximum fan revolutions and tolerances restored after a restart.
+/// The mode and the flow switches are not restored, the aquapath always starts in standby.
+const PERSISTENT_SETTINGS: PersistentSettings = PersistentSettings {
+    version: 1,
+    mutations: &[
+        "SetFrontTemperature",
+        "SetBackTemperature",
+        "SetFrontRevolutions",
+        "SetBackRevolutions",
+        "SetFrontHeatingTolerance",
+        "SetBackHeatingTolerance",
+        "SetFrontCoolingTolerance",
+        "SetBackCoolingTolerance",
+    ],
+};
+
 impl MachineApi for AquaPathV1 {
     fn api_get_sender(&self) -> Sender<MachineMessage> {
         self.api_sender.clone()
@@ -205,7 +231,53 @@ impl MachineApi for AquaPathV1 {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
+    fn api_undo(&self, value: &Value) -> Option<Value> {
+        let state = self.get_state();
+        let temperatures = state.temperature_states;
+        let flows = state.flow_states;
+        let fans = state.fan_states;
+        let tolerances = state.tolerance_states;
+        let undo = match Mutation::deserialize(value).ok()? {
+            Mutation::SetAquaPathMode(_) => Mutation::SetAquaPathMode(state.mode_state.mode),
+            Mutation::SetFrontTemperature(_) => {
+                Mutation::SetFrontTemperature(temperatures.front.target_temperature)
+            }
+            Mutation::SetBackTemperature(_) => {
+                Mutation::SetBackTemperature(temperatures.back.target_temperature)
+            }
+            Mutation::SetFrontFlow(_) => Mutation::SetFrontFlow(flows.front.should_flow),
+            Mutation::SetBackFlow(_) => Mutation::SetBackFlow(flows.back.should_flow),
+            Mutation::SetFrontRevolutions(_) => {
+                Mutation::SetFrontRevolutions(fans.front.max_revolutions)
+            }
+            Mutation::SetBackRevolutions(_) => {
+                Mutation::SetBackRevolutions(fans.back.max_revolutions)
+            }
+            Mutation::SetFrontHeatingTolerance(_) => {
+                Mutation::SetFrontHeatingTolerance(tolerances.front.heating)
+            }
+            Mutation::SetBackHeatingTolerance(_) => {
+                Mutation::SetBackHeatingTolerance(tolerances.back.heating)
+            }
+            Mutation::SetFrontCoolingTolerance(_) => {
+                Mutation::SetFrontCoolingTolerance(tolerances.front.cooling)
+            }
+            Mutation::SetBackCoolingTolerance(_) => {
+                Mutation::SetBackCoolingTolerance(tolerances.back.cooling)
+            }
+        };
+        serde_json::to_value(undo).ok()
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
+
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        Some(&PERSISTENT_SETTINGS)
+    }
 }
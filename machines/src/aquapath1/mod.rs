@@ -1,5 +1,6 @@
 use api::{ToleranceState, ToleranceStates};
 use control_core::socketio::namespace::NamespaceCacheingLogic;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 use units::angular_velocity::revolution_per_minute;
@@ -27,7 +28,7 @@ pub mod api;
 pub mod controller;
 pub mod new;
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub enum AquaPathV1Mode {
     Standby,
     Auto,
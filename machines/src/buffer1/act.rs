@@ -20,6 +20,10 @@ impl MachineAct for BufferV1 {
         }
     }
 
+    fn safe_stop(&mut self) {
+        self.switch_mode(super::BufferV1Mode::Standby);
+    }
+
     fn act_machine_message(&mut self, msg: MachineMessage) {
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
@@ -32,21 +36,24 @@ impl MachineAct for BufferV1 {
 
                 let _res = self.api_mutate(value);
             }
-            MachineMessage::ConnectToMachine(_machine_connection) =>
-                /*Doesnt connect to any Machine so do nothing*/
-                {}
-            MachineMessage::DisconnectMachine(_machine_connection) =>
-                /*Doesnt connect to any Machine so do nothing*/
-                {}
+            msg @ (MachineMessage::ConnectToMachine(_) | MachineMessage::DisconnectMachine(_)) => {
+                if self.connections.act_machine_message(msg) {
+                    self.emit_state();
+                }
+            }
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
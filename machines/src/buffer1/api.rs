@@ -1,16 +1,21 @@
 use super::{BufferV1, BufferV1Mode};
-use crate::{MachineApi, MachineMessage, machine_identification::MachineIdentificationUnique};
+use crate::schema::MachineApiSchema;
+use crate::{
+    MachineApi, MachineCrossConnectionState, MachineMessage,
+    machine_identification::MachineIdentificationUnique,
+};
 use control_core::socketio::{
     event::{Event, GenericEvent},
     namespace::{CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_one_event},
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use smol::channel::Sender;
 use std::sync::Arc;
 use tracing::instrument;
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {}
 
 impl LiveValuesEvent {
@@ -19,10 +24,12 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct StateEvent {
     /// mode state
-    pub mode_state: ModeState, // connected machine state
+    pub mode_state: ModeState,
+    /// connected machine state
+    pub connected_machine_state: MachineCrossConnectionState,
 }
 
 impl StateEvent {
@@ -36,7 +43,7 @@ pub enum BufferV1Events {
     State(Event<StateEvent>),
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ModeState {
     pub mode: BufferV1Mode,
 }
@@ -55,7 +62,7 @@ pub struct ConnectedMachineState {
     pub is_available: bool,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 enum Mutation {
     // Mode
     SetBufferMode(BufferV1Mode),
@@ -67,6 +74,13 @@ enum Mutation {
     DisconnectMachine(MachineIdentificationUnique),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 #[derive(Debug)]
 pub struct Buffer1Namespace {
     pub namespace: Option<Namespace>,
@@ -112,12 +126,26 @@ impl MachineApi for BufferV1 {
         let mutation: Mutation = serde_json::from_value(request_body)?;
         match mutation {
             Mutation::SetBufferMode(mode) => self.set_mode_state(mode),
-            Mutation::SetConnectedMachine(_machine_identification_unique) => {}
-            Mutation::DisconnectMachine(_machine_identification_unique) => {}
+            Mutation::SetConnectedMachine(machine_identification_unique) => {
+                self.connections
+                    .request_connect(machine_identification_unique)?;
+            }
+            Mutation::DisconnectMachine(machine_identification_unique) => {
+                if self
+                    .connections
+                    .request_disconnect(machine_identification_unique)?
+                {
+                    self.emit_state();
+                }
+            }
         }
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
@@ -4,12 +4,14 @@ pub mod buffer_tower_controller;
 pub mod new;
 
 use super::machine_identification::{MachineIdentification, MachineIdentificationUnique};
+use crate::connections::MachineConnections;
 use crate::{AsyncThreadMessage, Machine, MachineMessage};
 #[cfg(not(feature = "mock-machine"))]
 use crate::{MACHINE_BUFFER_V1, VENDOR_QITECH};
 use api::{Buffer1Namespace, BufferV1Events, LiveValuesEvent, ModeState, StateEvent};
 use buffer_tower_controller::BufferTowerController;
 use control_core::socketio::namespace::NamespaceCacheingLogic;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use smol::channel::{Receiver, Sender};
 use std::time::Instant;
@@ -25,6 +27,7 @@ pub struct BufferV1 {
     pub machine_identification_unique: MachineIdentificationUnique,
     mode: BufferV1Mode,
     main_sender: Option<Sender<AsyncThreadMessage>>,
+    connections: MachineConnections,
 }
 
 impl Machine for BufferV1 {
@@ -63,7 +66,7 @@ impl BufferV1 {
             mode_state: ModeState {
                 mode: self.mode.clone(),
             },
-            // connected_machine_state: self.connected_winder.to_state(),
+            connected_machine_state: self.connections.get_cross_connection_state(),
         }
     }
 
@@ -131,7 +134,7 @@ impl BufferV1 {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub enum BufferV1Mode {
     Standby,
     FillingBuffer,
@@ -17,6 +17,7 @@ use ethercat_hal::{
     shared_config::el70x1::{EL70x1OperationMode, StmMotorConfiguration},
 };
 
+use crate::connections::MachineConnections;
 use crate::{
     MachineNewHardware, MachineNewHardwareEthercat, MachineNewParams, MachineNewTrait,
     buffer1::BufferV1Mode, get_ethercat_device, validate_same_machine_identification_unique,
@@ -138,6 +139,10 @@ impl MachineNewTrait for BufferV1 {
                 mode: BufferV1Mode::Standby,
                 buffer_tower_controller,
                 machine_identification_unique: machine_identification_unique.clone(),
+                connections: MachineConnections::new(1).with_main_sender(
+                    machine_identification_unique.clone(),
+                    params.main_thread_channel.clone(),
+                ),
             };
             buffer.emit_state();
             Ok(buffer)
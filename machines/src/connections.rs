@@ -0,0 +1,478 @@
+use crate::machine_identification::{MachineIdentification, MachineIdentificationUnique};
+use crate::{
+    AsyncThreadMessage, CrossConnection, MachineConnection, MachineCrossConnectionState,
+    MachineMessage, MachineValues,
+};
+use anyhow::Result;
+use control_core::socketio::event::GenericEvent;
+use control_core::socketio::namespace::{CacheFn, CacheableEvents, cache_first_and_last_event};
+use control_core_derive::BuildEvent;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use smol::channel::{Receiver, Sender, TryRecvError};
+use std::time::Instant;
+
+/// Values received from a connected machine
+#[derive(Debug, Clone)]
+pub struct PeerValues {
+    pub values: MachineValues,
+    /// When the values arrived
+    pub received: Instant,
+}
+
+impl PeerValues {
+    /// Deserialize the state of the peer into its `StateEvent` type
+    pub fn state<S: DeserializeOwned>(&self) -> Result<S> {
+        Ok(serde_json::from_value(self.values.state.clone())?)
+    }
+
+    /// Deserialize the live values of the peer into its `LiveValuesEvent` type
+    pub fn live_values<L: DeserializeOwned>(&self) -> Result<L> {
+        Ok(serde_json::from_value(self.values.live_values.clone())?)
+    }
+}
+
+#[derive(Debug)]
+struct Peer {
+    connection: MachineConnection,
+    /// Answer to the last [`MachineMessage::RequestValues`] we sent
+    pending: Option<Receiver<MachineValues>>,
+    values: Option<PeerValues>,
+    /// Set when `values` were updated and not yet taken
+    fresh: bool,
+}
+
+/// The owner of a [`MachineConnections`] and its channel to the main thread,
+/// which looks up the machines to connect
+#[derive(Debug)]
+struct MainThread {
+    owner: MachineIdentificationUnique,
+    sender: Sender<AsyncThreadMessage>,
+}
+
+/// Registry of the machines a machine is connected to
+///
+/// Connections are one way: the owner can request values from its peers
+/// without the peers knowing about the owner.
+/// Everything is non-blocking so it can be used from `act`.
+///
+/// Connecting goes through the main thread: [`Self::request_connect`] asks it, it answers with
+/// [`MachineMessage::ConnectToMachine`] which the owner passes to [`Self::act_machine_message`],
+/// like [`MachineMessage::DisconnectMachine`].
+#[derive(Debug)]
+pub struct MachineConnections {
+    peers: Vec<Peer>,
+    max_connections: usize,
+    main_thread: Option<MainThread>,
+}
+
+impl Default for MachineConnections {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_CONNECTIONS)
+    }
+}
+
+impl MachineConnections {
+    pub const DEFAULT_MAX_CONNECTIONS: usize = 2;
+
+    pub const fn new(max_connections: usize) -> Self {
+        Self {
+            peers: vec![],
+            max_connections,
+            main_thread: None,
+        }
+    }
+
+    /// Allows `owner` to request connections through the main thread
+    pub fn with_main_sender(
+        mut self,
+        owner: MachineIdentificationUnique,
+        main_sender: Option<Sender<AsyncThreadMessage>>,
+    ) -> Self {
+        self.main_thread = main_sender.map(|sender| MainThread { owner, sender });
+        self
+    }
+
+    pub const fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    pub fn get_main_sender(&self) -> Option<Sender<AsyncThreadMessage>> {
+        Some(self.main_thread.as_ref()?.sender.clone())
+    }
+
+    pub const fn get_max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Adds a connection
+    ///
+    /// Reconnecting to an already connected machine replaces its sender.
+    /// Fails if the limit of connections is reached.
+    pub fn connect(&mut self, connection: MachineConnection) -> Result<()> {
+        if let Some(peer) = self.get_peer_mut(&connection.ident) {
+            peer.connection = connection;
+            peer.pending = None;
+            return Ok(());
+        }
+
+        if self.peers.len() >= self.max_connections {
+            return Err(anyhow::anyhow!(
+                "Refusing connection to {:?}, limit of {} connections reached",
+                connection.ident,
+                self.max_connections
+            ));
+        }
+
+        self.peers.push(Peer {
+            connection,
+            pending: None,
+            values: None,
+            fresh: false,
+        });
+        Ok(())
+    }
+
+    /// Removes a connection, returns if the machine was connected
+    pub fn disconnect(&mut self, ident: &MachineIdentificationUnique) -> bool {
+        let len = self.peers.len();
+        self.peers.retain(|peer| &peer.connection.ident != ident);
+        len != self.peers.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.peers.clear();
+    }
+
+    /// Asks the main thread to connect the owner to `dest`
+    pub fn request_connect(&self, dest: MachineIdentificationUnique) -> Result<()> {
+        self.send_main(|src| {
+            AsyncThreadMessage::ConnectOneWayRequest(CrossConnection { src, dest })
+        })
+    }
+
+    /// Disconnects the owner from `dest` and tells the main thread about it,
+    /// returns if `dest` was connected
+    ///
+    /// The main thread also tells `dest` to drop a connection back to the owner.
+    /// Disconnecting doesn't wait for the main thread, so it works for peers which are gone.
+    pub fn request_disconnect(&mut self, dest: MachineIdentificationUnique) -> Result<bool> {
+        self.send_main(|src| {
+            AsyncThreadMessage::DisconnectMachines(CrossConnection {
+                src,
+                dest: dest.clone(),
+            })
+        })?;
+        Ok(self.disconnect(&dest))
+    }
+
+    /// Applies [`MachineMessage::ConnectToMachine`] and [`MachineMessage::DisconnectMachine`]
+    ///
+    /// Returns if the connections changed, other messages are ignored.
+    pub fn act_machine_message(&mut self, msg: MachineMessage) -> bool {
+        match msg {
+            MachineMessage::ConnectToMachine(connection) => match self.connect(connection) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::debug!("{}", e);
+                    false
+                }
+            },
+            MachineMessage::DisconnectMachine(connection) => self.disconnect(&connection.ident),
+            _ => false,
+        }
+    }
+
+    fn send_main(
+        &self,
+        message: impl FnOnce(MachineIdentificationUnique) -> AsyncThreadMessage,
+    ) -> Result<()> {
+        let main_thread = self
+            .main_thread
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Machine cannot connect to others!"))?;
+        main_thread
+            .sender
+            .try_send(message(main_thread.owner.clone()))?;
+        Ok(())
+    }
+
+    pub fn is_connected(&self, ident: &MachineIdentificationUnique) -> bool {
+        self.get_peer(ident).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MachineIdentificationUnique> {
+        self.peers.iter().map(|peer| &peer.connection.ident)
+    }
+
+    /// First connected machine of the given type
+    pub fn find(
+        &self,
+        machine_identification: &MachineIdentification,
+    ) -> Option<&MachineIdentificationUnique> {
+        self.iter()
+            .find(|ident| &ident.machine_identification == machine_identification)
+    }
+
+    /// Asks a connected machine for its values
+    ///
+    /// Does nothing if the previous request was not answered yet.
+    /// The answer is collected by [`Self::poll`].
+    pub fn request_values(&mut self, ident: &MachineIdentificationUnique) -> Result<()> {
+        let peer = self
+            .get_peer_mut(ident)
+            .ok_or_else(|| anyhow::anyhow!("Machine {:?} is not connected", ident))?;
+
+        if peer.pending.is_some() {
+            return Ok(());
+        }
+
+        let (sender, receiver) = smol::channel::bounded(1);
+        peer.connection
+            .connection
+            .try_send(MachineMessage::RequestValues(sender))?;
+        peer.pending = Some(receiver);
+        Ok(())
+    }
+
+    /// Collects answers to [`Self::request_values`]
+    pub fn poll(&mut self, now: Instant) {
+        for peer in self.peers.iter_mut() {
+            let receiver = match &peer.pending {
+                Some(receiver) => receiver,
+                None => continue,
+            };
+
+            match receiver.try_recv() {
+                Ok(values) => {
+                    peer.values = Some(PeerValues {
+                        values,
+                        received: now,
+                    });
+                    peer.fresh = true;
+                    peer.pending = None;
+                }
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Closed) => peer.pending = None,
+            }
+        }
+    }
+
+    /// Latest values received from a connected machine
+    pub fn get_values(&self, ident: &MachineIdentificationUnique) -> Option<&PeerValues> {
+        self.get_peer(ident)?.values.as_ref()
+    }
+
+    /// Values received since the last call, `None` if nothing new arrived
+    pub fn take_values(&mut self, ident: &MachineIdentificationUnique) -> Option<&PeerValues> {
+        let peer = self.get_peer_mut(ident)?;
+        if !std::mem::replace(&mut peer.fresh, false) {
+            return None;
+        }
+        peer.values.as_ref()
+    }
+
+    /// First connection in the shape of the legacy single connection state
+    pub fn get_cross_connection_state(&self) -> MachineCrossConnectionState {
+        let ident = self.iter().next().cloned();
+        MachineCrossConnectionState {
+            is_available: ident.is_some(),
+            machine_identification_unique: ident,
+        }
+    }
+
+    pub fn get_connected_machines_event(&self) -> ConnectedMachinesEvent {
+        ConnectedMachinesEvent {
+            connected_machines: self.iter().cloned().collect(),
+            max_connected_machines: self.max_connections,
+        }
+    }
+
+    fn get_peer(&self, ident: &MachineIdentificationUnique) -> Option<&Peer> {
+        self.peers
+            .iter()
+            .find(|peer| &peer.connection.ident == ident)
+    }
+
+    fn get_peer_mut(&mut self, ident: &MachineIdentificationUnique) -> Option<&mut Peer> {
+        self.peers
+            .iter_mut()
+            .find(|peer| &peer.connection.ident == ident)
+    }
+}
+
+/// Reports the connections of a machine to its namespace
+#[derive(Serialize, Debug, Clone, BuildEvent)]
+pub struct ConnectedMachinesEvent {
+    pub connected_machines: Vec<MachineIdentificationUnique>,
+    pub max_connected_machines: usize,
+}
+
+impl CacheableEvents<Self> for ConnectedMachinesEvent {
+    fn event_value(&self) -> GenericEvent {
+        use control_core::socketio::event::BuildEvent;
+        self.build().into()
+    }
+
+    fn event_cache_fn(&self) -> CacheFn {
+        cache_first_and_last_event()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MACHINE_LASER_V1, MACHINE_WINDER_V1, VENDOR_QITECH};
+
+    fn ident(machine: u16, serial: u16) -> MachineIdentificationUnique {
+        MachineIdentificationUnique {
+            machine_identification: MachineIdentification {
+                vendor: VENDOR_QITECH,
+                machine,
+            },
+            serial,
+        }
+    }
+
+    fn connection(
+        ident: MachineIdentificationUnique,
+    ) -> (MachineConnection, Receiver<MachineMessage>) {
+        let (sender, receiver) = smol::channel::unbounded();
+        (
+            MachineConnection {
+                ident,
+                connection: sender,
+            },
+            receiver,
+        )
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let mut connections = MachineConnections::new(1);
+        let (laser, _laser_rx) = connection(ident(MACHINE_LASER_V1, 1));
+        let (winder, _winder_rx) = connection(ident(MACHINE_WINDER_V1, 1));
+
+        assert!(connections.connect(laser).is_ok());
+        assert!(connections.connect(winder).is_err());
+
+        // reconnecting does not count against the limit
+        let (laser, _laser_rx) = connection(ident(MACHINE_LASER_V1, 1));
+        assert!(connections.connect(laser).is_ok());
+        assert_eq!(connections.len(), 1);
+
+        assert!(connections.disconnect(&ident(MACHINE_LASER_V1, 1)));
+        assert!(!connections.disconnect(&ident(MACHINE_LASER_V1, 1)));
+        assert!(connections.is_empty());
+    }
+
+    #[test]
+    fn test_connect_through_main_thread() {
+        let winder_ident = ident(MACHINE_WINDER_V1, 1);
+        let laser_ident = ident(MACHINE_LASER_V1, 1);
+        let (main_sender, main_rx) = smol::channel::unbounded();
+        let mut connections =
+            MachineConnections::default().with_main_sender(winder_ident.clone(), Some(main_sender));
+
+        connections.request_connect(laser_ident.clone()).unwrap();
+        match main_rx.try_recv().unwrap() {
+            AsyncThreadMessage::ConnectOneWayRequest(cross_connection) => {
+                assert_eq!(cross_connection.src, winder_ident);
+                assert_eq!(cross_connection.dest, laser_ident);
+            }
+            _ => panic!("expected a connect request"),
+        }
+
+        // answer like the main thread would
+        let (laser, _laser_rx) = connection(laser_ident.clone());
+        assert!(connections.act_machine_message(MachineMessage::ConnectToMachine(laser)));
+        assert!(connections.is_connected(&laser_ident));
+
+        assert!(connections.request_disconnect(laser_ident.clone()).unwrap());
+        assert!(!connections.is_connected(&laser_ident));
+        match main_rx.try_recv().unwrap() {
+            AsyncThreadMessage::DisconnectMachines(cross_connection) => {
+                assert_eq!(cross_connection.src, winder_ident);
+                assert_eq!(cross_connection.dest, laser_ident);
+            }
+            _ => panic!("expected a disconnect request"),
+        }
+
+        // the main thread echoes the disconnect, which changes nothing
+        let (laser, _laser_rx) = connection(laser_ident);
+        assert!(!connections.act_machine_message(MachineMessage::DisconnectMachine(laser)));
+    }
+
+    #[test]
+    fn test_request_connect_without_main_thread() {
+        let connections = MachineConnections::default();
+        assert!(
+            connections
+                .request_connect(ident(MACHINE_LASER_V1, 1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_find_by_machine_identification() {
+        let mut connections = MachineConnections::default();
+        let (winder, _winder_rx) = connection(ident(MACHINE_WINDER_V1, 7));
+        let (laser, _laser_rx) = connection(ident(MACHINE_LASER_V1, 3));
+        connections.connect(winder).unwrap();
+        connections.connect(laser).unwrap();
+
+        let laser_identification = ident(MACHINE_LASER_V1, 0).machine_identification;
+        assert_eq!(
+            connections.find(&laser_identification),
+            Some(&ident(MACHINE_LASER_V1, 3))
+        );
+    }
+
+    #[test]
+    fn test_request_values() {
+        #[derive(serde::Deserialize)]
+        struct LiveValues {
+            diameter: f64,
+        }
+
+        let mut connections = MachineConnections::default();
+        let laser_ident = ident(MACHINE_LASER_V1, 1);
+        let (laser, laser_rx) = connection(laser_ident.clone());
+        connections.connect(laser).unwrap();
+
+        connections.request_values(&laser_ident).unwrap();
+        // only one request in flight
+        connections.request_values(&laser_ident).unwrap();
+        assert_eq!(laser_rx.len(), 1);
+
+        let now = Instant::now();
+        connections.poll(now);
+        assert!(connections.take_values(&laser_ident).is_none());
+
+        // answer like the peer would in its `act`
+        match laser_rx.try_recv().unwrap() {
+            MachineMessage::RequestValues(sender) => sender
+                .try_send(MachineValues {
+                    state: serde_json::json!({}),
+                    live_values: serde_json::json!({ "diameter": 1.75 }),
+                })
+                .unwrap(),
+            _ => panic!("expected RequestValues"),
+        }
+
+        connections.poll(now);
+        let values = connections.take_values(&laser_ident).unwrap();
+        assert_eq!(values.live_values::<LiveValues>().unwrap().diameter, 1.75);
+        assert!(connections.take_values(&laser_ident).is_none());
+        assert!(connections.get_values(&laser_ident).is_some());
+    }
+}
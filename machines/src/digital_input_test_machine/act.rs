@@ -31,14 +31,18 @@ impl MachineAct for DigitalInputTestMachine {
             MachineMessage::DisconnectMachine(_machine_connection) => {
                 // Does not connect to any Machine; do nothing
             }
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::Value::Null,
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::Value::Null,
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
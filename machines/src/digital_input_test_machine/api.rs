@@ -1,3 +1,5 @@
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 use std::sync::Arc;
 
 use control_core::socketio::{
@@ -10,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{MachineApi, digital_input_test_machine::DigitalInputTestMachine};
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct StateEvent {
     pub led_on: [bool; 4],
 }
@@ -24,11 +26,17 @@ pub enum DigitalInputTestMachineEvents {
     State(Event<StateEvent>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(tag = "action", content = "value")]
 pub enum Mutation {
     SetLed { index: usize, on: bool },
 }
+
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>().with_state::<StateEvent>()
+}
+
 #[derive(Debug, Clone)]
 pub struct DigitalInputTestMachineNamespace {
     pub namespace: Option<Namespace>,
@@ -71,6 +79,11 @@ impl MachineApi for DigitalInputTestMachine {
         Ok(())
     }
 
+    fn api_validate(&self, _value: &serde_json::Value) -> Result<(), anyhow::Error> {
+        // accepts every mutation like `api_mutate`
+        Ok(())
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
@@ -1,8 +1,12 @@
 use super::{ExtruderV2Mode, mitsubishi_cs80::MotorStatus};
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 
 #[cfg(not(feature = "mock-machine"))]
 use super::ExtruderV2;
 
+#[cfg(not(feature = "mock-machine"))]
+use crate::persistence::PersistentSettings;
 #[cfg(not(feature = "mock-machine"))]
 use crate::{MachineMessage, extruder1::HeatingType};
 
@@ -27,7 +31,7 @@ use units::electric_current::ampere;
 use units::electric_potential::volt;
 use units::frequency::hertz;
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
 pub struct MotorStatusValues {
     pub screw_rpm: f64, // rpm of motor
     pub frequency: f64, // frequency of motor
@@ -51,7 +55,7 @@ impl From<MotorStatus> for MotorStatusValues {
     }
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     /// screw rpm
     pub motor_status: MotorStatusValues,
@@ -85,7 +89,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent)]
+#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// rotation state
@@ -108,33 +112,33 @@ pub struct StateEvent {
     pub pid_settings: PidSettingsStates,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct RotationState {
     pub forward: bool,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct ModeState {
     pub mode: ExtruderV2Mode,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct RegulationState {
     pub uses_rpm: bool,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct PressureState {
     pub target_bar: f64,
     pub wiring_error: bool,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct ScrewState {
     pub target_rpm: f64,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct HeatingStates {
     pub nozzle: HeatingState,
     pub front: HeatingState,
@@ -142,19 +146,19 @@ pub struct HeatingStates {
     pub middle: HeatingState,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct HeatingState {
     pub target_temperature: f64,
     pub wiring_error: bool,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct ExtruderSettingsState {
     pub pressure_limit: f64,
     pub pressure_limit_enabled: bool,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct InverterStatusState {
     /// RUN (Inverter running)
     pub running: bool,
@@ -176,14 +180,14 @@ pub struct InverterStatusState {
     pub fault_occurence: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct PidSettings {
     pub ki: f64,
     pub kp: f64,
     pub kd: f64,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct TemperaturePidStates {
     pub front: TemperaturePid,
     pub middle: TemperaturePid,
@@ -191,7 +195,7 @@ pub struct TemperaturePidStates {
     pub nozzle: TemperaturePid,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct TemperaturePid {
     pub ki: f64,
     pub kp: f64,
@@ -199,7 +203,20 @@ pub struct TemperaturePid {
     pub zone: String,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq)]
+impl TemperaturePidStates {
+    /// Settings of the zone named like [`TemperaturePid::zone`]
+    pub fn get_zone(&self, zone: &str) -> Option<&TemperaturePid> {
+        match zone {
+            "front" => Some(&self.front),
+            "middle" => Some(&self.middle),
+            "back" => Some(&self.back),
+            "nozzle" => Some(&self.nozzle),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct PidSettingsStates {
     pub temperature: TemperaturePidStates,
     pub pressure: PidSettings,
@@ -210,7 +227,7 @@ pub enum ExtruderV2Events {
     State(Event<StateEvent>),
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub enum Mutation {
     /// INVERTER
     /// Frequency Control
@@ -239,6 +256,13 @@ pub enum Mutation {
     ResetInverter(bool),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 #[derive(Debug)]
 pub struct ExtruderV2Namespace {
     pub namespace: Option<Namespace>,
@@ -274,6 +298,27 @@ impl CacheableEvents<Self> for ExtruderV2Events {
     }
 }
 
+/// Inverter, heating, pressure and PID settings restored after a restart.
+/// `SetExtruderMode` and `ResetInverter` are not restored, the extruder always starts in standby.
+#[cfg(not(feature = "mock-machine"))]
+const PERSISTENT_SETTINGS: PersistentSettings = PersistentSettings {
+    version: 1,
+    mutations: &[
+        "SetInverterRotationDirection",
+        "SetInverterTargetPressure",
+        "SetInverterTargetRpm",
+        "SetInverterRegulation",
+        "SetFrontHeatingTargetTemperature",
+        "SetBackHeatingTargetTemperature",
+        "SetMiddleHeatingTemperature",
+        "SetNozzleHeatingTemperature",
+        "SetExtruderPressureLimit",
+        "SetExtruderPressureLimitIsEnabled",
+        "SetPressurePidSettings",
+        "SetTemperaturePidSettings",
+    ],
+};
+
 #[cfg(not(feature = "mock-machine"))]
 impl MachineApi for ExtruderV2 {
     fn api_get_sender(&self) -> Sender<MachineMessage> {
@@ -321,7 +366,67 @@ impl MachineApi for ExtruderV2 {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
+    fn api_undo(&self, value: &Value) -> Option<Value> {
+        let state = self.get_state();
+        let heating = &state.heating_states;
+        let undo = match Mutation::deserialize(value).ok()? {
+            Mutation::SetInverterRotationDirection(_) => {
+                Mutation::SetInverterRotationDirection(state.rotation_state.forward)
+            }
+            Mutation::SetInverterTargetPressure(_) => {
+                Mutation::SetInverterTargetPressure(state.pressure_state.target_bar)
+            }
+            Mutation::SetInverterTargetRpm(_) => {
+                Mutation::SetInverterTargetRpm(state.screw_state.target_rpm)
+            }
+            Mutation::SetInverterRegulation(_) => {
+                Mutation::SetInverterRegulation(state.regulation_state.uses_rpm)
+            }
+            Mutation::SetExtruderMode(_) => Mutation::SetExtruderMode(state.mode_state.mode),
+            Mutation::SetFrontHeatingTargetTemperature(_) => {
+                Mutation::SetFrontHeatingTargetTemperature(heating.front.target_temperature)
+            }
+            Mutation::SetBackHeatingTargetTemperature(_) => {
+                Mutation::SetBackHeatingTargetTemperature(heating.back.target_temperature)
+            }
+            Mutation::SetMiddleHeatingTemperature(_) => {
+                Mutation::SetMiddleHeatingTemperature(heating.middle.target_temperature)
+            }
+            Mutation::SetNozzleHeatingTemperature(_) => {
+                Mutation::SetNozzleHeatingTemperature(heating.nozzle.target_temperature)
+            }
+            Mutation::SetExtruderPressureLimit(_) => {
+                Mutation::SetExtruderPressureLimit(state.extruder_settings_state.pressure_limit)
+            }
+            Mutation::SetExtruderPressureLimitIsEnabled(_) => {
+                Mutation::SetExtruderPressureLimitIsEnabled(
+                    state.extruder_settings_state.pressure_limit_enabled,
+                )
+            }
+            Mutation::SetPressurePidSettings(_) => {
+                Mutation::SetPressurePidSettings(state.pid_settings.pressure)
+            }
+            Mutation::SetTemperaturePidSettings(settings) => Mutation::SetTemperaturePidSettings(
+                state
+                    .pid_settings
+                    .temperature
+                    .get_zone(&settings.zone)?
+                    .clone(),
+            ),
+            Mutation::ResetInverter(_) => return None,
+        };
+        serde_json::to_value(undo).ok()
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
+
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        Some(&PERSISTENT_SETTINGS)
+    }
 }
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 #[cfg(not(feature = "mock-machine"))]
 use std::time::Instant;
 
@@ -38,7 +39,7 @@ pub mod new;
 pub mod screw_speed_controller;
 pub mod temperature_controller;
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub enum ExtruderV2Mode {
     Standby,
     Heat,
@@ -52,6 +52,12 @@ impl MachineAct for ExtruderV3 {
         }
     }
 
+    fn safe_stop(&mut self) {
+        // stops the screw motor only once, the inverter queues every request
+        self.switch_mode(super::ExtruderV3Mode::Standby);
+        self.turn_heating_off();
+    }
+
     fn act_machine_message(&mut self, msg: MachineMessage) {
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
@@ -61,23 +67,27 @@ impl MachineAct for ExtruderV3 {
             }
             MachineMessage::UnsubscribeNamespace => self.namespace.namespace = None,
             MachineMessage::HttpApiJsonRequest(value) => {
-                use crate::MachineApi;
+                use crate::Machine;
 
-                let _res = self.api_mutate(value);
+                let _res = self.api_mutate_persistent(value);
             }
             MachineMessage::ConnectToMachine(_machine_connection) => {}
             MachineMessage::DisconnectMachine(_machine_connection) =>
                 /*Doesnt connect to any Machine so do nothing*/
                 {}
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.build_state_event())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.build_state_event())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
@@ -1,3 +1,5 @@
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 use std::sync::Arc;
 
 use crate::extruder1::{
@@ -8,6 +10,8 @@ use crate::extruder1::{
     mitsubishi_cs80::MotorStatus,
 };
 #[cfg(not(feature = "mock-machine"))]
+use crate::persistence::PersistentSettings;
+#[cfg(not(feature = "mock-machine"))]
 use crate::{MachineApi, MachineMessage};
 use control_core::socketio::{
     event::{Event, GenericEvent},
@@ -31,7 +35,7 @@ use units::{
 use super::ExtruderV3;
 use super::ExtruderV3Mode;
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
 pub struct MotorStatusValues {
     pub screw_rpm: f64, // rpm of motor
     pub frequency: f64, // frequency of motor
@@ -55,7 +59,7 @@ impl From<MotorStatus> for MotorStatusValues {
     }
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     /// screw rpm
     pub motor_status: MotorStatusValues,
@@ -89,7 +93,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent)]
+#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// rotation state
@@ -112,7 +116,7 @@ pub struct StateEvent {
     pub pid_settings: PidSettingsStates,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct ModeState {
     pub mode: ExtruderV3Mode,
 }
@@ -122,7 +126,7 @@ pub enum ExtruderV3Events {
     State(Event<StateEvent>),
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub enum Mutation {
     /// INVERTER
     /// Frequency Control
@@ -151,6 +155,13 @@ pub enum Mutation {
     ResetInverter(bool),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 #[derive(Debug)]
 pub struct ExtruderV3Namespace {
     pub namespace: Option<Namespace>,
@@ -186,6 +197,27 @@ impl CacheableEvents<Self> for ExtruderV3Events {
     }
 }
 
+/// Inverter, heating, pressure and PID settings restored after a restart.
+/// `SetExtruderMode` and `ResetInverter` are not restored, the extruder always starts in standby.
+#[cfg(not(feature = "mock-machine"))]
+const PERSISTENT_SETTINGS: PersistentSettings = PersistentSettings {
+    version: 1,
+    mutations: &[
+        "SetInverterRotationDirection",
+        "SetInverterTargetPressure",
+        "SetInverterTargetRpm",
+        "SetInverterRegulation",
+        "SetFrontHeatingTargetTemperature",
+        "SetBackHeatingTargetTemperature",
+        "SetMiddleHeatingTemperature",
+        "SetNozzleHeatingTemperature",
+        "SetExtruderPressureLimit",
+        "SetExtruderPressureLimitIsEnabled",
+        "SetPressurePidSettings",
+        "SetTemperaturePidSettings",
+    ],
+};
+
 #[cfg(not(feature = "mock-machine"))]
 impl MachineApi for ExtruderV3 {
     fn api_get_sender(&self) -> Sender<MachineMessage> {
@@ -234,7 +266,67 @@ impl MachineApi for ExtruderV3 {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
+    fn api_undo(&self, value: &Value) -> Option<Value> {
+        let state = self.get_state();
+        let heating = &state.heating_states;
+        let undo = match Mutation::deserialize(value).ok()? {
+            Mutation::SetInverterRotationDirection(_) => {
+                Mutation::SetInverterRotationDirection(state.rotation_state.forward)
+            }
+            Mutation::SetInverterTargetPressure(_) => {
+                Mutation::SetInverterTargetPressure(state.pressure_state.target_bar)
+            }
+            Mutation::SetInverterTargetRpm(_) => {
+                Mutation::SetInverterTargetRpm(state.screw_state.target_rpm)
+            }
+            Mutation::SetInverterRegulation(_) => {
+                Mutation::SetInverterRegulation(state.regulation_state.uses_rpm)
+            }
+            Mutation::SetExtruderMode(_) => Mutation::SetExtruderMode(state.mode_state.mode),
+            Mutation::SetFrontHeatingTargetTemperature(_) => {
+                Mutation::SetFrontHeatingTargetTemperature(heating.front.target_temperature)
+            }
+            Mutation::SetBackHeatingTargetTemperature(_) => {
+                Mutation::SetBackHeatingTargetTemperature(heating.back.target_temperature)
+            }
+            Mutation::SetMiddleHeatingTemperature(_) => {
+                Mutation::SetMiddleHeatingTemperature(heating.middle.target_temperature)
+            }
+            Mutation::SetNozzleHeatingTemperature(_) => {
+                Mutation::SetNozzleHeatingTemperature(heating.nozzle.target_temperature)
+            }
+            Mutation::SetExtruderPressureLimit(_) => {
+                Mutation::SetExtruderPressureLimit(state.extruder_settings_state.pressure_limit)
+            }
+            Mutation::SetExtruderPressureLimitIsEnabled(_) => {
+                Mutation::SetExtruderPressureLimitIsEnabled(
+                    state.extruder_settings_state.pressure_limit_enabled,
+                )
+            }
+            Mutation::SetPressurePidSettings(_) => {
+                Mutation::SetPressurePidSettings(state.pid_settings.pressure)
+            }
+            Mutation::SetTemperaturePidSettings(settings) => Mutation::SetTemperaturePidSettings(
+                state
+                    .pid_settings
+                    .temperature
+                    .get_zone(&settings.zone)?
+                    .clone(),
+            ),
+            Mutation::ResetInverter(_) => return None,
+        };
+        serde_json::to_value(undo).ok()
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
+
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        Some(&PERSISTENT_SETTINGS)
+    }
 }
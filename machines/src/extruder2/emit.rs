@@ -31,13 +31,19 @@ use super::{ExtruderV3, ExtruderV3Mode};
 #[cfg(not(feature = "mock-machine"))]
 impl ExtruderV3 {
     pub fn build_state_event(&mut self) -> StateEvent {
+        let state = self.get_state();
+        self.emitted_default_state = true;
+        state
+    }
+
+    pub fn get_state(&self) -> StateEvent {
         use crate::{
             extruder1::api::{TemperaturePid, TemperaturePidStates},
             extruder2::api::ModeState,
         };
 
         StateEvent {
-            is_default_state: !std::mem::replace(&mut self.emitted_default_state, true),
+            is_default_state: !self.emitted_default_state,
             rotation_state: RotationState {
                 forward: self.screw_speed_controller.get_rotation_direction(),
             },
@@ -45,6 +45,10 @@ impl MachineApi for ExtruderV2 {
         Ok(())
     }
 
+    fn api_validate(&self, value: &serde_json::Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(
         &mut self,
     ) -> std::option::Option<control_core::socketio::namespace::Namespace> {
@@ -1,4 +1,5 @@
 use super::IP20TestMachine;
+use crate::schema::MachineApiSchema;
 use crate::{MachineApi, MachineMessage};
 use control_core::socketio::{
     event::{Event, GenericEvent},
@@ -6,11 +7,12 @@ use control_core::socketio::{
         CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_first_and_last_event,
     },
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct StateEvent {
     pub outputs: [bool; 8],
 }
@@ -21,7 +23,7 @@ impl StateEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct LiveValuesEvent {
     pub inputs: [bool; 8],
 }
@@ -37,13 +39,20 @@ pub enum IP20TestMachineEvents {
     LiveValues(Event<LiveValuesEvent>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(tag = "action", content = "value")]
 pub enum Mutation {
     SetOutput { index: usize, on: bool },
     SetAllOutputs { on: bool },
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 #[derive(Debug, Clone)]
 pub struct IP20TestMachineNamespace {
     pub namespace: Option<Namespace>,
@@ -87,6 +96,10 @@ impl MachineApi for IP20TestMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
@@ -57,8 +57,8 @@ impl MachineAct for LaserMachine {
                 None => todo!(),
             },
             MachineMessage::HttpApiJsonRequest(value) => {
-                use crate::MachineApi;
-                let _res = self.api_mutate(value);
+                use crate::Machine;
+                let _res = self.api_mutate_persistent(value);
             }
             MachineMessage::ConnectToMachine(_machine_connection) =>
                 /*Doesnt connect to any Machine so do nothing*/
@@ -66,15 +66,22 @@ impl MachineAct for LaserMachine {
             MachineMessage::DisconnectMachine(_machine_connection) =>
                 /*Doesnt connect to any Machine so do nothing*/
                 {}
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                // Connected machines poll these values, so a requester that went away
+                // in the meantime must not take down the loop
+                let res = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                });
+                if let Err(e) = res {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
 
                 ()
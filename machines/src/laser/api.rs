@@ -1,4 +1,7 @@
+use crate::persistence::PersistentSettings;
+use crate::schema::MachineApiSchema;
 use crate::{MachineApi, MachineMessage};
+use schemars::JsonSchema;
 
 use super::LaserMachine;
 use control_core::socketio::{
@@ -13,7 +16,7 @@ use serde_json::Value;
 use std::sync::Arc;
 use tracing::instrument;
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     /// diameter measurement in mm
     pub diameter: f64,
@@ -28,7 +31,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone, BuildEvent)]
+#[derive(Serialize, Debug, Clone, BuildEvent, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// laser state
@@ -41,7 +44,7 @@ impl StateEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct LaserState {
     /// higher tolerance in mm
     pub higher_tolerance: f64,
@@ -81,7 +84,7 @@ impl CacheableEvents<Self> for LaserEvents {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 /// All values in the Mutation enum should be positive.
 /// This ensures that the parameters for setting tolerances and target diameter
 /// are valid and meaningful within the context of the LaserMachine's operation.
@@ -91,6 +94,13 @@ enum Mutation {
     SetHigherTolerance(f64),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 impl NamespaceCacheingLogic<LaserEvents> for LaserMachineNamespace {
     #[instrument(skip_all)]
     fn emit(&mut self, events: LaserEvents) {
@@ -104,6 +114,16 @@ impl NamespaceCacheingLogic<LaserEvents> for LaserMachineNamespace {
     }
 }
 
+/// Target diameter and tolerances restored after a restart, the laser has no other mutations
+const PERSISTENT_SETTINGS: PersistentSettings = PersistentSettings {
+    version: 1,
+    mutations: &[
+        "SetTargetDiameter",
+        "SetLowerTolerance",
+        "SetHigherTolerance",
+    ],
+};
+
 impl MachineApi for LaserMachine {
     fn api_mutate(&mut self, request_body: Value) -> Result<(), anyhow::Error> {
         let mutation: Mutation = serde_json::from_value(request_body)?;
@@ -121,10 +141,28 @@ impl MachineApi for LaserMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
+    fn api_undo(&self, value: &Value) -> Option<Value> {
+        let state = self.get_state().laser_state;
+        let undo = match Mutation::deserialize(value).ok()? {
+            Mutation::SetTargetDiameter(_) => Mutation::SetTargetDiameter(state.target_diameter),
+            Mutation::SetLowerTolerance(_) => Mutation::SetLowerTolerance(state.lower_tolerance),
+            Mutation::SetHigherTolerance(_) => Mutation::SetHigherTolerance(state.higher_tolerance),
+        };
+        serde_json::to_value(undo).ok()
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
 
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        Some(&PERSISTENT_SETTINGS)
+    }
+
     fn api_get_sender(&self) -> smol::channel::Sender<MachineMessage> {
         self.api_sender.clone()
     }
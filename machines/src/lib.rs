@@ -1,4 +1,5 @@
 use anyhow::{Error, Result};
+use connections::MachineConnections;
 use control_core::socketio::event::GenericEvent;
 use control_core::socketio::namespace::{CacheableEvents, Namespace, NamespaceCacheingLogic};
 use ethercat_hal::devices::{
@@ -10,7 +11,9 @@ use machine_identification::{
     DeviceHardwareIdentification, DeviceHardwareIdentificationEthercat, DeviceIdentification,
     DeviceIdentificationIdentified, MachineIdentificationUnique,
 };
-use serde::Serialize;
+use persistence::{MACHINE_SETTINGS_STORE, PersistentSettings, mutation_key};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use smol::channel::{Receiver, Sender};
 use socketioxide::extract::SocketRef;
 use std::any::Any;
@@ -21,6 +24,7 @@ pub mod analog_input_test_machine;
 pub mod aquapath1;
 #[cfg(not(feature = "mock-machine"))]
 pub mod buffer1;
+pub mod connections;
 pub mod digital_input_test_machine;
 pub mod extruder1;
 pub mod extruder2;
@@ -28,7 +32,9 @@ pub mod ip20_test_machine;
 pub mod laser;
 pub mod machine_identification;
 pub mod mock;
+pub mod persistence;
 pub mod registry;
+pub mod schema;
 pub mod serial;
 pub mod test_machine;
 pub mod wago_ai_test_machine;
@@ -53,7 +59,7 @@ pub const DIGITAL_INPUT_TEST_MACHINE: u16 = 0x0040;
 use serde_json::Value;
 use smol::lock::RwLock;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct MachineCrossConnectionState {
     machine_identification_unique: Option<MachineIdentificationUnique>,
     is_available: bool,
@@ -279,6 +285,12 @@ pub struct MachineConnection {
 pub trait MachineAct {
     fn act_machine_message(&mut self, msg: MachineMessage);
     fn act(&mut self, now: Instant);
+
+    /// Drives the outputs into a safe state, e.g. heaters, pumps and motors off
+    ///
+    /// Called after a fatal EtherCAT fault, right after every [`MachineAct::act`]
+    /// until the process exits. Must be idempotent.
+    fn safe_stop(&mut self) {}
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -299,17 +311,170 @@ pub enum MachineMessage {
     ConnectToMachine(MachineConnection),
     DisconnectMachine(MachineConnection),
     RequestValues(Sender<MachineValues>),
+    HttpApiJsonBatch(MutationBatch),
+}
+
+/// Mutations applied back to back in a single `act` cycle
+///
+/// Nothing else runs on the machine between the mutations of a batch.
+/// The batch is applied either completely or not at all, see [`Machine::api_mutate_batch`].
+/// The rejected mutations are sent back through `result` once the batch was handled.
+#[derive(Debug)]
+pub struct MutationBatch {
+    pub mutations: Vec<Value>,
+    pub result: Sender<Vec<RejectedMutation>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RejectedMutation {
+    /// Position of the mutation in the batch
+    pub index: usize,
+    pub mutation: Value,
+    pub error: String,
+}
+
+/// [`MachineApi::api_validate`] for machines whose mutations need no checks beyond their type
+pub fn validate_mutation<'de, M: Deserialize<'de>>(value: &'de Value) -> Result<(), anyhow::Error> {
+    M::deserialize(value)?;
+    Ok(())
 }
 
 pub trait MachineApi {
     fn api_get_sender(&self) -> Sender<MachineMessage>;
     fn api_mutate(&mut self, value: Value) -> Result<(), anyhow::Error>;
+    /// Checks a mutation without applying it, see [`Machine::api_mutate_batch`]
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error>;
     fn api_event_namespace(&mut self) -> Option<Namespace>;
+
+    /// Mutation which sets what `value` changes back to its current value
+    ///
+    /// Used to roll back a [`MutationBatch`], `None` if `value` can't be undone.
+    fn api_undo(&self, _value: &Value) -> Option<Value> {
+        None
+    }
+
+    /// Mutations which are stored and replayed when the machine is rebuilt
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        None
+    }
 }
 
 pub trait Machine: MachineAct + MachineApi + Any + Debug + Send + Sync {
     fn get_machine_identification_unique(&self) -> MachineIdentificationUnique;
     fn get_main_sender(&self) -> Option<Sender<AsyncThreadMessage>>;
+
+    /// Like [`MachineApi::api_mutate`] but records accepted settings in the [`MACHINE_SETTINGS_STORE`]
+    fn api_mutate_persistent(&mut self, value: Value) -> Result<(), anyhow::Error> {
+        let persistent = self.api_persistent_settings().and_then(|settings| {
+            let key = mutation_key(&value)?;
+            settings
+                .is_persistent(&key)
+                .then(|| (settings.version, key, value.clone()))
+        });
+
+        self.api_mutate(value)?;
+
+        if let Some((version, key, value)) = persistent {
+            MACHINE_SETTINGS_STORE.record(
+                &self.get_machine_identification_unique(),
+                version,
+                key,
+                value,
+            );
+        }
+        Ok(())
+    }
+
+    /// Applies a [`MutationBatch`] and reports the rejected mutations
+    ///
+    /// Every mutation is validated with [`MachineApi::api_validate`] first. Every mutation
+    /// except the last one also needs an [`MachineApi::api_undo`]. If one of them is invalid
+    /// nothing is applied and every mutation of the batch is reported as rejected.
+    ///
+    /// If a mutation fails while applying, the mutations applied before it are rolled back
+    /// in reverse order and every mutation of the batch is reported as rejected.
+    fn api_mutate_batch(&mut self, batch: MutationBatch) {
+        let last = batch.mutations.len().saturating_sub(1);
+        let invalid: Vec<(usize, String)> = batch
+            .mutations
+            .iter()
+            .enumerate()
+            .filter_map(|(index, mutation)| match self.api_validate(mutation) {
+                Err(e) => Some((index, e.to_string())),
+                Ok(()) if index < last && self.api_undo(mutation).is_none() => Some((
+                    index,
+                    "Can't be rolled back, only allowed as the last mutation of a batch"
+                        .to_string(),
+                )),
+                Ok(()) => None,
+            })
+            .collect();
+
+        let rejected = if invalid.is_empty() {
+            let mut undos = vec![];
+            let mut failed = None;
+            for (index, mutation) in batch.mutations.iter().enumerate() {
+                let undo = self.api_undo(mutation);
+                if let Err(e) = self.api_mutate_persistent(mutation.clone()) {
+                    failed = Some((index, e.to_string()));
+                    break;
+                }
+                undos.extend(undo);
+            }
+
+            match failed {
+                Some((failed_index, error)) => {
+                    for undo in undos.into_iter().rev() {
+                        if let Err(e) = self.api_mutate_persistent(undo) {
+                            tracing::error!("Failed to roll back mutation batch: {}", e);
+                        }
+                    }
+                    reject_batch(batch.mutations, &[(failed_index, error)], |index| {
+                        if index < failed_index {
+                            format!("Rolled back, mutation {failed_index} failed")
+                        } else {
+                            format!("Not applied, mutation {failed_index} failed")
+                        }
+                    })
+                }
+                None => vec![],
+            }
+        } else {
+            let invalid_indices: Vec<usize> = invalid.iter().map(|(index, _)| *index).collect();
+            reject_batch(batch.mutations, &invalid, |_| {
+                format!("Not applied, mutations {invalid_indices:?} are invalid")
+            })
+        };
+
+        if let Err(e) = batch.result.try_send(rejected) {
+            tracing::warn!("Failed to report mutation batch result: {}", e);
+        }
+        batch.result.close();
+    }
+}
+
+/// Rejects every mutation of a batch, with `errors` for the ones that caused it
+/// and `other` for the rest
+fn reject_batch(
+    mutations: Vec<Value>,
+    errors: &[(usize, String)],
+    other: impl Fn(usize) -> String,
+) -> Vec<RejectedMutation> {
+    mutations
+        .into_iter()
+        .enumerate()
+        .map(|(index, mutation)| {
+            let error = errors
+                .iter()
+                .find(|(error_index, _)| *error_index == index)
+                .map_or_else(|| other(index), |(_, error)| error.clone());
+            RejectedMutation {
+                index,
+                mutation,
+                error,
+            }
+        })
+        .collect()
 }
 
 pub trait AnyGetters: Any {
@@ -426,8 +591,9 @@ pub struct MachineChannel {
     api_receiver: Receiver<MachineMessage>,
     api_sender: Sender<MachineMessage>,
     machine_identification_unique: MachineIdentificationUnique,
-    main_sender: Option<Sender<AsyncThreadMessage>>,
     namespace: Option<Namespace>,
+    /// Machines this machine is connected to
+    pub connections: MachineConnections,
 }
 
 impl MachineChannel {
@@ -438,10 +604,29 @@ impl MachineChannel {
             api_sender: sender,
             api_receiver: receiver,
             machine_identification_unique,
-            main_sender: None,
             namespace: None,
+            connections: MachineConnections::default(),
         }
     }
+
+    /// Allows the machine to request connections through the main thread
+    pub fn with_main_sender(mut self, main_sender: Sender<AsyncThreadMessage>) -> Self {
+        self.connections = self.connections.with_main_sender(
+            self.machine_identification_unique.clone(),
+            Some(main_sender),
+        );
+        self
+    }
+
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.connections = self.connections.with_max_connections(max_connections);
+        self
+    }
+
+    fn emit_connections(&mut self) {
+        let event = self.connections.get_connected_machines_event();
+        self.emit(event);
+    }
 }
 
 impl<E> NamespaceCacheingLogic<E> for MachineChannel
@@ -467,8 +652,16 @@ pub trait MachineWithChannel: Send + Debug + Sync {
 
     fn on_namespace(&mut self) {}
 
+    /// Called after a machine was connected or disconnected
+    fn on_connections_changed(&mut self) {}
+
+    /// See [`MachineAct::safe_stop`]
+    fn on_safe_stop(&mut self) {}
+
     fn update(&mut self, now: std::time::Instant) -> Result<()>;
     fn mutate(&mut self, value: Value) -> Result<()>;
+    /// See [`MachineApi::api_validate`]
+    fn validate(&self, value: &Value) -> Result<()>;
 
     fn get_state(&self) -> Self::State;
     fn get_live_values(&self) -> Option<Self::LiveValues> {
@@ -494,6 +687,10 @@ where
         res
     }
 
+    fn api_validate(&self, value: &Value) -> Result<()> {
+        self.validate(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.get_machine_channel().namespace.clone()
     }
@@ -501,13 +698,15 @@ where
 
 impl<C> MachineAct for C
 where
-    C: MachineWithChannel,
+    C: MachineWithChannel + 'static,
 {
     fn act(&mut self, now: Instant) {
         while let Ok(msg) = self.get_machine_channel_mut().api_receiver.try_recv() {
             self.act_machine_message(msg);
         }
 
+        self.get_machine_channel_mut().connections.poll(now);
+
         if let Err(e) = self.update(now) {
             tracing::error!("Machine errored while updating: {}, ", e);
         }
@@ -519,6 +718,7 @@ where
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
                 channel.namespace = Some(namespace);
+                channel.emit_connections();
                 self.on_namespace();
             }
             MachineMessage::UnsubscribeNamespace => {
@@ -527,25 +727,30 @@ where
             MachineMessage::HttpApiJsonRequest(value) => {
                 let _ = self.api_mutate(value);
             }
-            MachineMessage::ConnectToMachine(_machine_connection) => {
-                todo!();
-            }
-            MachineMessage::DisconnectMachine(_machine_connection) => {
-                todo!();
+            msg @ (MachineMessage::ConnectToMachine(_) | MachineMessage::DisconnectMachine(_)) => {
+                if channel.connections.act_machine_message(msg) {
+                    channel.emit_connections();
+                    self.on_connections_changed();
+                }
             }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
+            MachineMessage::HttpApiJsonBatch(batch) => self.api_mutate_batch(batch),
         }
     }
+
+    fn safe_stop(&mut self) {
+        self.on_safe_stop();
+    }
 }
 
 impl<C> Machine for C
@@ -559,6 +764,145 @@ where
     }
 
     fn get_main_sender(&self) -> Option<Sender<AsyncThreadMessage>> {
-        self.get_machine_channel().main_sender.clone()
+        self.get_machine_channel().connections.get_main_sender()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine_identification::MachineIdentification;
+    use serde_json::json;
+
+    #[derive(Deserialize, Serialize)]
+    enum Mutation {
+        SetValue(f64),
+        Reset,
+    }
+
+    /// Machine which rejects negative values only while applying them
+    #[derive(Debug)]
+    struct BatchMachine {
+        value: f64,
+        api_sender: Sender<MachineMessage>,
+    }
+
+    impl BatchMachine {
+        fn new() -> Self {
+            Self {
+                value: 1.0,
+                api_sender: smol::channel::unbounded().0,
+            }
+        }
+
+        fn mutate_batch(&mut self, mutations: Vec<Value>) -> Vec<RejectedMutation> {
+            let (result, receiver) = smol::channel::bounded(1);
+            self.api_mutate_batch(MutationBatch { mutations, result });
+            receiver.try_recv().expect("batch result was sent")
+        }
+    }
+
+    impl MachineAct for BatchMachine {
+        fn act_machine_message(&mut self, _msg: MachineMessage) {}
+        fn act(&mut self, _now: Instant) {}
+    }
+
+    impl MachineApi for BatchMachine {
+        fn api_get_sender(&self) -> Sender<MachineMessage> {
+            self.api_sender.clone()
+        }
+
+        fn api_mutate(&mut self, value: Value) -> Result<(), anyhow::Error> {
+            match serde_json::from_value(value)? {
+                Mutation::SetValue(value) if value < 0.0 => anyhow::bail!("Value is negative"),
+                Mutation::SetValue(value) => self.value = value,
+                Mutation::Reset => self.value = 0.0,
+            }
+            Ok(())
+        }
+
+        fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+            validate_mutation::<Mutation>(value)
+        }
+
+        fn api_undo(&self, value: &Value) -> Option<Value> {
+            match Mutation::deserialize(value).ok()? {
+                Mutation::SetValue(_) => serde_json::to_value(Mutation::SetValue(self.value)).ok(),
+                Mutation::Reset => None,
+            }
+        }
+
+        fn api_event_namespace(&mut self) -> Option<Namespace> {
+            None
+        }
+    }
+
+    impl Machine for BatchMachine {
+        fn get_machine_identification_unique(&self) -> MachineIdentificationUnique {
+            MachineIdentificationUnique {
+                machine_identification: MachineIdentification {
+                    vendor: VENDOR_QITECH,
+                    machine: TEST_MACHINE,
+                },
+                serial: 1,
+            }
+        }
+
+        fn get_main_sender(&self) -> Option<Sender<AsyncThreadMessage>> {
+            None
+        }
+    }
+
+    fn errors(rejected: &[RejectedMutation]) -> Vec<&str> {
+        rejected
+            .iter()
+            .map(|rejected| rejected.error.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_is_applied() {
+        let mut machine = BatchMachine::new();
+        let rejected =
+            machine.mutate_batch(vec![json!({ "SetValue": 2.0 }), json!({ "SetValue": 3.0 })]);
+        assert!(rejected.is_empty());
+        assert_eq!(machine.value, 3.0);
+    }
+
+    #[test]
+    fn test_batch_is_rolled_back_when_applying_fails() {
+        let mut machine = BatchMachine::new();
+        let rejected = machine.mutate_batch(vec![
+            json!({ "SetValue": 2.0 }),
+            json!({ "SetValue": -1.0 }),
+            json!({ "SetValue": 3.0 }),
+        ]);
+        assert_eq!(machine.value, 1.0);
+        assert_eq!(
+            errors(&rejected),
+            [
+                "Rolled back, mutation 1 failed",
+                "Value is negative",
+                "Not applied, mutation 1 failed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mutation_without_undo_must_be_last() {
+        let mut machine = BatchMachine::new();
+        let rejected = machine.mutate_batch(vec![json!("Reset"), json!({ "SetValue": 2.0 })]);
+        assert_eq!(machine.value, 1.0);
+        assert_eq!(
+            errors(&rejected),
+            [
+                "Can't be rolled back, only allowed as the last mutation of a batch",
+                "Not applied, mutations [0] are invalid",
+            ]
+        );
+
+        let rejected = machine.mutate_batch(vec![json!({ "SetValue": 2.0 }), json!("Reset")]);
+        assert!(rejected.is_empty());
+        assert_eq!(machine.value, 0.0);
     }
 }
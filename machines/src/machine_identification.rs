@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use std::fmt::Display;
 
 use ethercat_hal::devices::wago_750_354::WAGO_750_354_IDENTITY_A;
@@ -6,7 +7,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 /// Identifies a spacifi machine
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub struct MachineIdentificationUnique {
     pub machine_identification: MachineIdentification,
     pub serial: u16,
@@ -30,7 +31,7 @@ impl Display for MachineIdentificationUnique {
 }
 
 /// Identifies a machine
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub struct MachineIdentification {
     pub vendor: u16,
     pub machine: u16,
@@ -43,28 +44,20 @@ impl MachineIdentification {
     }
 
     pub fn vendor_str(&self) -> String {
-        match self.vendor {
-            x if x == VENDOR_QITECH => "QiTech".to_string(),
-            _ => "N/A".to_string(),
-        }
+        MACHINE_REGISTRY
+            .get_vendor_name(self.vendor)
+            .unwrap_or("N/A")
+            .to_string()
     }
 
-    pub fn slug(&self) -> String {
-        match self.machine {
-            x if x == MACHINE_WINDER_V1 => "winder_v1".to_string(),
-            x if x == MACHINE_EXTRUDER_V1 => "extruder_v1".to_string(),
-            x if x == MACHINE_LASER_V1 => "laser_v1".to_string(),
-            x if x == MACHINE_MOCK => "mock".to_string(),
-            x if x == MACHINE_AQUAPATH_V1 => "aquapath_v1".to_string(),
-            x if x == MACHINE_BUFFER_V1 => "buffer_v1".to_string(),
-            x if x == MACHINE_EXTRUDER_V2 => "extruder_v2".to_string(),
-            x if x == MACHINE_WAGO_POWER_V1 => "wago_power_v1".to_string(),
-            x if x == TEST_MACHINE => "test_machine".to_string(),
-            x if x == IP20_TEST_MACHINE => "ip20_test_machine".to_string(),
-            x if x == ANALOG_INPUT_TEST_MACHINE => "analog_input_test_machine".to_string(),
-            x if x == WAGO_AI_TEST_MACHINE => "wago_ai_test_machine".to_string(),
-            _ => unreachable!("Unknown machine id"),
-        }
+    /// Identifier of the machine type used in REST paths, see [`crate::registry::MachineDescriptor`]
+    pub fn slug(&self) -> Result<String, Error> {
+        Ok(MACHINE_REGISTRY.get_descriptor(self)?.slug.to_string())
+    }
+
+    /// Human readable name of the machine type, see [`crate::registry::MachineDescriptor`]
+    pub fn name(&self) -> Result<String, Error> {
+        Ok(MACHINE_REGISTRY.get_descriptor(self)?.name.to_string())
     }
 }
 
@@ -174,19 +167,7 @@ use ethercat_hal::helpers::ethercrab_types::{
 use ethercrab::MainDevice;
 use ethercrab::SubDeviceIdentity;
 
-use crate::ANALOG_INPUT_TEST_MACHINE;
-use crate::IP20_TEST_MACHINE;
-use crate::MACHINE_AQUAPATH_V1;
-use crate::MACHINE_BUFFER_V1;
-use crate::MACHINE_EXTRUDER_V1;
-use crate::MACHINE_EXTRUDER_V2;
-use crate::MACHINE_LASER_V1;
-use crate::MACHINE_MOCK;
-use crate::MACHINE_WAGO_POWER_V1;
-use crate::MACHINE_WINDER_V1;
-use crate::TEST_MACHINE;
-use crate::VENDOR_QITECH;
-use crate::WAGO_AI_TEST_MACHINE;
+use crate::registry::MACHINE_REGISTRY;
 
 #[derive(Debug)]
 pub struct MachineIdentificationAddresses {
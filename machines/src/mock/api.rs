@@ -1,4 +1,6 @@
+use crate::schema::MachineApiSchema;
 use crate::{MachineApi, MachineMessage};
+use schemars::JsonSchema;
 
 use super::MockMachine;
 use control_core::socketio::{
@@ -14,13 +16,13 @@ use smol::channel::Sender;
 use std::sync::Arc;
 use tracing::instrument;
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub enum Mode {
     Standby,
     Running,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     pub amplitude_sum: f64,
     pub amplitude1: f64,
@@ -34,7 +36,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent)]
+#[derive(Serialize, Debug, Clone, PartialEq, BuildEvent, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// sine wave frequencies in millihertz
@@ -45,7 +47,7 @@ pub struct StateEvent {
     pub mode_state: ModeState,
 }
 
-#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct ModeState {
     /// current mode
     pub mode: Mode,
@@ -79,7 +81,7 @@ impl CacheableEvents<Self> for MockEvents {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 /// Mutation for controlling the mock machine
 enum Mutation {
     /// Set the frequency of the sine wave in millihertz
@@ -89,6 +91,13 @@ enum Mutation {
     SetMode(Mode),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
 impl NamespaceCacheingLogic<MockEvents> for MockMachineNamespace {
     #[instrument(skip_all)]
     fn emit(&mut self, events: MockEvents) {
@@ -126,6 +135,10 @@ impl MachineApi for MockMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
@@ -0,0 +1,466 @@
+use crate::Machine;
+use crate::machine_identification::MachineIdentificationUnique;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+
+/// Environment variable to override where machine settings are stored
+pub const MACHINE_SETTINGS_DIR_ENV: &str = "QITECH_MACHINE_SETTINGS_DIR";
+
+/// Describes which mutations of a machine are settings worth keeping across restarts
+///
+/// Only mutations that configure the machine belong here. Actions (homing, resets)
+/// and mode changes which start motors or heaters must never be replayed on startup.
+#[derive(Debug)]
+pub struct PersistentSettings {
+    /// Bump whenever the `Mutation` schema of the listed variants changes.
+    /// Stored settings with a different version are skipped.
+    pub version: u32,
+    /// `Mutation` variant names which are recorded
+    pub mutations: &'static [&'static str],
+}
+
+impl PersistentSettings {
+    pub fn is_persistent(&self, key: &str) -> bool {
+        self.mutations.contains(&key)
+    }
+}
+
+/// A recorded mutation, `value` is the raw JSON the machine accepted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistedMutation {
+    pub key: String,
+    pub value: Value,
+}
+
+/// Settings of one machine as stored on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MachineSettings {
+    pub machine_identification_unique: MachineIdentificationUnique,
+    pub version: u32,
+    /// Last accepted value per mutation, ordered by when it was last set
+    pub mutations: Vec<PersistedMutation>,
+}
+
+impl MachineSettings {
+    pub const fn new(
+        machine_identification_unique: MachineIdentificationUnique,
+        version: u32,
+    ) -> Self {
+        Self {
+            machine_identification_unique,
+            version,
+            mutations: vec![],
+        }
+    }
+
+    /// Stores the latest value of a mutation and moves it to the end,
+    /// so replaying applies the settings in the order they were last set
+    pub fn set(&mut self, key: String, value: Value) {
+        self.mutations.retain(|mutation| mutation.key != key);
+        self.mutations.push(PersistedMutation { key, value });
+    }
+}
+
+/// Name of the `Mutation` variant of a JSON request
+///
+/// Supports externally tagged enums (`"Variant"` or `{"Variant": value}`)
+/// and adjacently tagged enums (`{"action": "Variant", "value": value}`).
+pub fn mutation_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(variant) => Some(variant.clone()),
+        Value::Object(map) => match map.get("action") {
+            Some(Value::String(action)) => Some(action.clone()),
+            _ if map.len() == 1 => map.keys().next().cloned(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A change to the settings of one machine, applied by the writer thread
+#[derive(Debug)]
+enum SettingsUpdate {
+    Record {
+        ident: MachineIdentificationUnique,
+        version: u32,
+        key: String,
+        value: Value,
+    },
+    Forget {
+        ident: MachineIdentificationUnique,
+        keys: Vec<String>,
+    },
+}
+
+/// On-disk store of machine settings, one JSON file per [`MachineIdentificationUnique`]
+///
+/// Recording only sends the mutation to a background thread, which loads, merges and
+/// writes the settings, so it is cheap enough for the realtime thread.
+#[derive(Debug)]
+pub struct MachineSettingsStore {
+    dir: PathBuf,
+    settings: Arc<Mutex<HashMap<MachineIdentificationUnique, MachineSettings>>>,
+    updates: Sender<SettingsUpdate>,
+}
+
+impl MachineSettingsStore {
+    pub fn new(dir: PathBuf) -> Self {
+        let settings = Arc::new(Mutex::new(HashMap::new()));
+        let (updates, receiver) = channel::<SettingsUpdate>();
+        {
+            let dir = dir.clone();
+            let settings = settings.clone();
+            std::thread::Builder::new()
+                .name("machine-settings".to_string())
+                .spawn(move || apply_updates(&dir, &settings, receiver))
+                .expect("Failed to spawn machine settings thread");
+        }
+        Self {
+            dir,
+            settings,
+            updates,
+        }
+    }
+
+    /// `$QITECH_MACHINE_SETTINGS_DIR`, `$HOME/.qitech/machine-settings` or `./machine-settings`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var(MACHINE_SETTINGS_DIR_ENV) {
+            return PathBuf::from(dir);
+        }
+        match std::env::var("HOME") {
+            Ok(home) => Path::new(&home).join(".qitech").join("machine-settings"),
+            Err(_) => PathBuf::from("machine-settings"),
+        }
+    }
+
+    pub fn get_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn file_path(&self, ident: &MachineIdentificationUnique) -> PathBuf {
+        settings_file_path(&self.dir, ident)
+    }
+
+    /// Reads the settings of a machine from disk
+    ///
+    /// Returns `Ok(None)` if nothing was stored yet.
+    pub fn read(&self, ident: &MachineIdentificationUnique) -> Result<Option<MachineSettings>> {
+        read_settings(&self.dir, ident)
+    }
+
+    /// Loads the settings of a machine, skipping them if they don't match `version`
+    ///
+    /// The loaded settings become the base for further [`Self::record`] calls,
+    /// so skipped settings are overwritten by the next accepted mutation.
+    pub fn load(&self, ident: &MachineIdentificationUnique, version: u32) -> MachineSettings {
+        let settings = load_settings(&self.dir, ident, version);
+        self.settings
+            .lock()
+            .expect("settings lock poisoned")
+            .insert(ident.clone(), settings.clone());
+        settings
+    }
+
+    /// Settings currently known for a machine
+    ///
+    /// Mutations recorded in the last moments may not be included yet.
+    pub fn get(&self, ident: &MachineIdentificationUnique) -> Option<MachineSettings> {
+        self.settings
+            .lock()
+            .expect("settings lock poisoned")
+            .get(ident)
+            .cloned()
+    }
+
+    /// Records an accepted mutation, it is merged and written to disk by the writer thread
+    pub fn record(
+        &self,
+        ident: &MachineIdentificationUnique,
+        version: u32,
+        key: String,
+        value: Value,
+    ) {
+        self.send(SettingsUpdate::Record {
+            ident: ident.clone(),
+            version,
+            key,
+            value,
+        });
+    }
+
+    /// Removes mutations that could not be replayed
+    fn forget(&self, ident: &MachineIdentificationUnique, keys: Vec<String>) {
+        self.send(SettingsUpdate::Forget {
+            ident: ident.clone(),
+            keys,
+        });
+    }
+
+    /// Applies the stored settings to a freshly constructed machine
+    ///
+    /// Mutations which are no longer persistent or get rejected by the machine are
+    /// skipped with a warning and dropped from the store.
+    pub fn replay(&self, machine: &mut dyn Machine) {
+        let Some(persistent_settings) = machine.api_persistent_settings() else {
+            return;
+        };
+        let ident = machine.get_machine_identification_unique();
+        let settings = self.load(&ident, persistent_settings.version);
+
+        let mut rejected = vec![];
+        for mutation in settings.mutations {
+            if !persistent_settings.is_persistent(&mutation.key) {
+                tracing::warn!("Skipping stored setting {} of {}", mutation.key, ident);
+                rejected.push(mutation.key);
+                continue;
+            }
+            if let Err(e) = machine.api_mutate(mutation.value) {
+                tracing::warn!(
+                    "Skipping stored setting {} of {}: {}",
+                    mutation.key,
+                    ident,
+                    e
+                );
+                rejected.push(mutation.key);
+            }
+        }
+
+        if !rejected.is_empty() {
+            self.forget(&ident, rejected);
+        }
+    }
+
+    fn send(&self, update: SettingsUpdate) {
+        if let Err(e) = self.updates.send(update) {
+            tracing::error!("Failed to schedule storing machine settings: {}", e);
+        }
+    }
+}
+
+/// Runs on the writer thread until the store is dropped
+fn apply_updates(
+    dir: &Path,
+    settings: &Mutex<HashMap<MachineIdentificationUnique, MachineSettings>>,
+    receiver: Receiver<SettingsUpdate>,
+) {
+    for update in receiver {
+        let snapshot = match update {
+            SettingsUpdate::Record {
+                ident,
+                version,
+                key,
+                value,
+            } => {
+                let is_loaded = settings
+                    .lock()
+                    .expect("settings lock poisoned")
+                    .contains_key(&ident);
+                // don't overwrite what is on disk with a single mutation
+                let loaded = (!is_loaded).then(|| load_settings(dir, &ident, version));
+
+                let mut settings = settings.lock().expect("settings lock poisoned");
+                let machine_settings = settings.entry(ident.clone()).or_insert_with(|| {
+                    loaded.unwrap_or_else(|| MachineSettings::new(ident.clone(), version))
+                });
+                if machine_settings.version != version {
+                    *machine_settings = MachineSettings::new(ident.clone(), version);
+                }
+                machine_settings.set(key, value);
+                machine_settings.clone()
+            }
+            SettingsUpdate::Forget { ident, keys } => {
+                let mut settings = settings.lock().expect("settings lock poisoned");
+                let Some(machine_settings) = settings.get_mut(&ident) else {
+                    continue;
+                };
+                machine_settings
+                    .mutations
+                    .retain(|mutation| !keys.contains(&mutation.key));
+                machine_settings.clone()
+            }
+        };
+
+        if let Err(e) = write_settings(dir, &snapshot) {
+            tracing::error!(
+                "Failed to store settings of {}: {}",
+                snapshot.machine_identification_unique,
+                e
+            );
+        }
+    }
+}
+
+fn read_settings(
+    dir: &Path,
+    ident: &MachineIdentificationUnique,
+) -> Result<Option<MachineSettings>> {
+    let path = settings_file_path(dir, ident);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow::anyhow!("Failed to read {}: {}", path.display(), e)),
+    };
+    let settings: MachineSettings = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(Some(settings))
+}
+
+/// Stored settings matching `version`, or empty settings
+fn load_settings(dir: &Path, ident: &MachineIdentificationUnique, version: u32) -> MachineSettings {
+    match read_settings(dir, ident) {
+        Ok(Some(settings)) if settings.version == version => settings,
+        Ok(Some(settings)) => {
+            tracing::warn!(
+                "Skipping stored settings of {} with version {}, expected version {}",
+                ident,
+                settings.version,
+                version
+            );
+            MachineSettings::new(ident.clone(), version)
+        }
+        Ok(None) => MachineSettings::new(ident.clone(), version),
+        Err(e) => {
+            tracing::warn!("Skipping stored settings of {}: {}", ident, e);
+            MachineSettings::new(ident.clone(), version)
+        }
+    }
+}
+
+fn settings_file_path(dir: &Path, ident: &MachineIdentificationUnique) -> PathBuf {
+    dir.join(format!(
+        "{}-{}-{}.json",
+        ident.machine_identification.vendor, ident.machine_identification.machine, ident.serial
+    ))
+}
+
+/// Writes to a temporary file first so a crash never leaves a half written file
+fn write_settings(dir: &Path, settings: &MachineSettings) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = settings_file_path(dir, &settings.machine_identification_unique);
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(settings)?)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+lazy_static! {
+    pub static ref MACHINE_SETTINGS_STORE: MachineSettingsStore =
+        MachineSettingsStore::new(MachineSettingsStore::default_dir());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine_identification::MachineIdentification;
+    use crate::{MACHINE_WINDER_V1, VENDOR_QITECH};
+    use serde_json::json;
+
+    fn ident(serial: u16) -> MachineIdentificationUnique {
+        MachineIdentificationUnique {
+            machine_identification: MachineIdentification {
+                vendor: VENDOR_QITECH,
+                machine: MACHINE_WINDER_V1,
+            },
+            serial,
+        }
+    }
+
+    fn temp_store(name: &str) -> MachineSettingsStore {
+        let dir =
+            std::env::temp_dir().join(format!("machine-settings-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        MachineSettingsStore::new(dir)
+    }
+
+    #[test]
+    fn test_mutation_key() {
+        assert_eq!(
+            mutation_key(&json!({ "SetTraverseLimitOuter": 90.0 })),
+            Some("SetTraverseLimitOuter".to_string())
+        );
+        assert_eq!(
+            mutation_key(&json!("GotoTraverseHome")),
+            Some("GotoTraverseHome".to_string())
+        );
+        assert_eq!(
+            mutation_key(&json!({ "action": "SetLed", "value": { "index": 0, "on": true } })),
+            Some("SetLed".to_string())
+        );
+        assert_eq!(mutation_key(&json!({ "a": 1, "b": 2 })), None);
+        assert_eq!(mutation_key(&json!(1.0)), None);
+    }
+
+    #[test]
+    fn test_set_keeps_last_value_in_order() {
+        let mut settings = MachineSettings::new(ident(1), 1);
+        settings.set("A".to_string(), json!({ "A": 1 }));
+        settings.set("B".to_string(), json!({ "B": 2 }));
+        settings.set("A".to_string(), json!({ "A": 3 }));
+
+        assert_eq!(
+            settings.mutations,
+            vec![
+                PersistedMutation {
+                    key: "B".to_string(),
+                    value: json!({ "B": 2 })
+                },
+                PersistedMutation {
+                    key: "A".to_string(),
+                    value: json!({ "A": 3 })
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_skips_other_version() {
+        let store = temp_store("version");
+        let mut settings = MachineSettings::new(ident(2), 1);
+        settings.set("A".to_string(), json!({ "A": 1 }));
+        write_settings(store.get_dir(), &settings).unwrap();
+
+        assert_eq!(store.read(&ident(2)).unwrap(), Some(settings.clone()));
+        assert_eq!(store.load(&ident(2), 1), settings);
+        assert!(store.load(&ident(2), 2).mutations.is_empty());
+        // nothing stored yet
+        assert!(store.load(&ident(3), 1).mutations.is_empty());
+
+        // broken files are skipped instead of failing
+        std::fs::write(store.file_path(&ident(4)), "{ not json").unwrap();
+        assert!(store.read(&ident(4)).is_err());
+        assert!(store.load(&ident(4), 1).mutations.is_empty());
+
+        let _ = std::fs::remove_dir_all(store.get_dir());
+    }
+
+    #[test]
+    fn test_record_merges_with_stored_settings() {
+        let store = temp_store("record");
+        let mut settings = MachineSettings::new(ident(5), 1);
+        settings.set("A".to_string(), json!({ "A": 1 }));
+        write_settings(store.get_dir(), &settings).unwrap();
+
+        store.record(&ident(5), 1, "B".to_string(), json!({ "B": 2 }));
+
+        // written by the background thread
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let stored = loop {
+            let stored = store.read(&ident(5)).unwrap().unwrap();
+            if stored.mutations.len() == 2 || std::time::Instant::now() > deadline {
+                break stored;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        settings.set("B".to_string(), json!({ "B": 2 }));
+        assert_eq!(stored, settings);
+        assert_eq!(store.get(&ident(5)), Some(settings));
+
+        let _ = std::fs::remove_dir_all(store.get_dir());
+    }
+}
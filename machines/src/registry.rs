@@ -10,6 +10,7 @@ use crate::{
 
 use crate::{
     Machine, MachineNewParams, MachineNewTrait, machine_identification::MachineIdentification,
+    persistence::MACHINE_SETTINGS_STORE,
 };
 
 #[cfg(not(feature = "mock-machine"))]
@@ -20,18 +21,38 @@ use crate::{
     winder2::Winder2,
 };
 
+use crate::VENDOR_QITECH;
+use crate::schema::MachineApiSchema;
 use crate::test_machine::TestMachine;
+use crate::wago_power::WagoPower;
 
 use lazy_static::lazy_static;
 
 use anyhow::Error;
-use std::{any::TypeId, collections::HashMap};
+use std::collections::HashMap;
 
 pub type MachineNewClosure =
     Box<dyn Fn(&MachineNewParams) -> Result<Box<dyn Machine>, Error> + Send + Sync>;
 
+/// Everything the server needs to know about a machine type
+///
+/// The REST routes and the names shown to the user are derived from this.
+#[derive(Debug, Clone)]
+pub struct MachineDescriptor {
+    pub machine_identification: MachineIdentification,
+    /// Used in REST paths, e.g. `/api/v2/machine/winder_v1/<serial>`
+    pub slug: &'static str,
+    /// Human readable name
+    pub name: &'static str,
+    /// JSON Schemas of the payloads, see [`MachineApiSchema`]
+    pub api_schema: fn() -> MachineApiSchema,
+}
+
 pub struct MachineRegistry {
-    type_map: HashMap<TypeId, (MachineIdentification, MachineNewClosure)>,
+    vendors: HashMap<u16, &'static str>,
+    descriptors: Vec<MachineDescriptor>,
+    /// Machines which are not constructed by the registry, like the modbus machines, have no entry
+    constructors: HashMap<MachineIdentification, MachineNewClosure>,
 }
 
 impl Default for MachineRegistry {
@@ -43,22 +64,61 @@ impl Default for MachineRegistry {
 impl MachineRegistry {
     pub fn new() -> Self {
         Self {
-            type_map: HashMap::new(),
+            vendors: HashMap::new(),
+            descriptors: vec![],
+            constructors: HashMap::new(),
         }
     }
 
-    pub fn register<T: MachineNewTrait + 'static>(
-        &mut self,
-        machine_identficiation: MachineIdentification,
-    ) {
-        self.type_map.insert(
-            TypeId::of::<T>(),
-            (
-                machine_identficiation.clone(),
-                // create a machine construction closure
-                Box::new(|machine_new_params| Ok(Box::new(T::new(machine_new_params)?))),
-            ),
+    pub fn register_vendor(&mut self, vendor: u16, name: &'static str) {
+        self.vendors.insert(vendor, name);
+    }
+
+    pub fn register<T: MachineNewTrait + 'static>(&mut self, descriptor: MachineDescriptor) {
+        self.constructors.insert(
+            descriptor.machine_identification.clone(),
+            // create a machine construction closure
+            Box::new(|machine_new_params| Ok(Box::new(T::new(machine_new_params)?))),
         );
+        self.describe(descriptor);
+    }
+
+    /// Registers a machine which is constructed elsewhere
+    pub fn describe(&mut self, descriptor: MachineDescriptor) {
+        self.descriptors
+            .retain(|d| d.machine_identification != descriptor.machine_identification);
+        self.descriptors.push(descriptor);
+    }
+
+    /// All registered machine types in registration order
+    pub fn descriptors(&self) -> &[MachineDescriptor] {
+        &self.descriptors
+    }
+
+    pub fn get_descriptor(
+        &self,
+        machine_identification: &MachineIdentification,
+    ) -> Result<&MachineDescriptor, Error> {
+        self.descriptors
+            .iter()
+            .find(|d| &d.machine_identification == machine_identification)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "[{}::MachineRegistry::get_descriptor] Unknown machine {:?}",
+                    module_path!(),
+                    machine_identification
+                )
+            })
+    }
+
+    pub fn get_vendor_name(&self, vendor: u16) -> Result<&'static str, Error> {
+        self.vendors.get(&vendor).copied().ok_or_else(|| {
+            anyhow::anyhow!(
+                "[{}::MachineRegistry::get_vendor_name] Unknown vendor {}",
+                module_path!(),
+                vendor
+            )
+        })
     }
 
     pub fn new_machine(
@@ -76,64 +136,225 @@ impl MachineRegistry {
                 ))?;
 
         // find machine new function by comparing MachineIdentification
-        let (_, machine_new_closure) = self
-            .type_map
-            .values()
-            .find(|(mi, _)| {
-                mi == &device_identification
+        let machine_new_closure = self
+            .constructors
+            .get(
+                &device_identification
                     .device_machine_identification
                     .machine_identification_unique
-                    .machine_identification
-            })
+                    .machine_identification,
+            )
             .ok_or(anyhow::anyhow!(
                 "[{}::MachineConstructor::new_machine] Machine not found",
                 module_path!()
             ))?;
 
         // call machine new function by reference
-        (machine_new_closure)(machine_new_params)
+        let mut machine = (machine_new_closure)(machine_new_params)?;
+
+        // restore the settings from the last run
+        MACHINE_SETTINGS_STORE.replay(machine.as_mut());
+
+        Ok(machine)
     }
 }
 
 lazy_static! {
     pub static ref MACHINE_REGISTRY: MachineRegistry = {
         let mut mc = MachineRegistry::new();
-        mc.register::<Winder2>(Winder2::MACHINE_IDENTIFICATION);
+
+        mc.register_vendor(VENDOR_QITECH, "QiTech");
+
+        mc.register::<Winder2>(MachineDescriptor {
+            machine_identification: Winder2::MACHINE_IDENTIFICATION,
+            slug: "winder_v1",
+            name: "Winder V1",
+            api_schema: crate::winder2::api::api_schema,
+        });
 
         #[cfg(feature = "mock-machine")]
-        mc.register::<ExtruderV2Mock1>(ExtruderV2Mock1::MACHINE_IDENTIFICATION);
+        mc.register::<ExtruderV2Mock1>(MachineDescriptor {
+            machine_identification: ExtruderV2Mock1::MACHINE_IDENTIFICATION,
+            slug: "extruder_v1",
+            name: "Extruder V1",
+            api_schema: crate::extruder1::api::api_schema,
+        });
 
         #[cfg(feature = "mock-machine")]
-        mc.register::<ExtruderV2Mock2>(ExtruderV2Mock2::MACHINE_IDENTIFICATION);
+        mc.register::<ExtruderV2Mock2>(MachineDescriptor {
+            machine_identification: ExtruderV2Mock2::MACHINE_IDENTIFICATION,
+            slug: "extruder_v2",
+            name: "Extruder V2",
+            api_schema: crate::extruder2::api::api_schema,
+        });
 
         #[cfg(not(feature = "mock-machine"))]
-        mc.register::<ExtruderV2>(ExtruderV2::MACHINE_IDENTIFICATION);
+        mc.register::<ExtruderV2>(MachineDescriptor {
+            machine_identification: ExtruderV2::MACHINE_IDENTIFICATION,
+            slug: "extruder_v1",
+            name: "Extruder V1",
+            api_schema: crate::extruder1::api::api_schema,
+        });
 
         #[cfg(not(feature = "mock-machine"))]
-        mc.register::<ExtruderV3>(ExtruderV3::MACHINE_IDENTIFICATION);
+        mc.register::<ExtruderV3>(MachineDescriptor {
+            machine_identification: ExtruderV3::MACHINE_IDENTIFICATION,
+            slug: "extruder_v2",
+            name: "Extruder V2",
+            api_schema: crate::extruder2::api::api_schema,
+        });
 
         #[cfg(feature = "mock-machine")]
-        mc.register::<MockMachine>(MockMachine::MACHINE_IDENTIFICATION);
+        mc.register::<MockMachine>(MachineDescriptor {
+            machine_identification: MockMachine::MACHINE_IDENTIFICATION,
+            slug: "mock",
+            name: "Mock",
+            api_schema: crate::mock::api::api_schema,
+        });
 
         #[cfg(not(feature = "mock-machine"))]
-        mc.register::<LaserMachine>(LaserMachine::MACHINE_IDENTIFICATION);
+        mc.register::<LaserMachine>(MachineDescriptor {
+            machine_identification: LaserMachine::MACHINE_IDENTIFICATION,
+            slug: "laser_v1",
+            name: "Laser V1",
+            api_schema: crate::laser::api::api_schema,
+        });
 
         #[cfg(not(feature = "mock-machine"))]
-        mc.register::<BufferV1>(BufferV1::MACHINE_IDENTIFICATION);
+        mc.register::<BufferV1>(MachineDescriptor {
+            machine_identification: BufferV1::MACHINE_IDENTIFICATION,
+            slug: "buffer_v1",
+            name: "Buffer V1",
+            api_schema: crate::buffer1::api::api_schema,
+        });
 
         #[cfg(not(feature = "mock-machine"))]
-        mc.register::<AquaPathV1>(AquaPathV1::MACHINE_IDENTIFICATION);
+        mc.register::<AquaPathV1>(MachineDescriptor {
+            machine_identification: AquaPathV1::MACHINE_IDENTIFICATION,
+            slug: "aquapath_v1",
+            name: "AquaPath V1",
+            api_schema: crate::aquapath1::api::api_schema,
+        });
 
-        mc.register::<TestMachine>(TestMachine::MACHINE_IDENTIFICATION);
+        // constructed by the modbus tcp discovery
+        mc.describe(MachineDescriptor {
+            machine_identification: WagoPower::MACHINE_IDENTIFICATION,
+            slug: "wago_power_v1",
+            name: "Wago Power V1",
+            api_schema: crate::wago_power::api_schema,
+        });
 
-        mc.register::<IP20TestMachine>(IP20TestMachine::MACHINE_IDENTIFICATION);
+        mc.register::<TestMachine>(MachineDescriptor {
+            machine_identification: TestMachine::MACHINE_IDENTIFICATION,
+            slug: "test_machine",
+            name: "Test Machine",
+            api_schema: crate::test_machine::api::api_schema,
+        });
 
-        mc.register::<AnalogInputTestMachine>(AnalogInputTestMachine::MACHINE_IDENTIFICATION);
+        mc.register::<IP20TestMachine>(MachineDescriptor {
+            machine_identification: IP20TestMachine::MACHINE_IDENTIFICATION,
+            slug: "ip20_test_machine",
+            name: "IP20 Test Machine",
+            api_schema: crate::ip20_test_machine::api::api_schema,
+        });
 
-        mc.register::<WagoAiTestMachine>(WagoAiTestMachine::MACHINE_IDENTIFICATION);
+        mc.register::<AnalogInputTestMachine>(MachineDescriptor {
+            machine_identification: AnalogInputTestMachine::MACHINE_IDENTIFICATION,
+            slug: "analog_input_test_machine",
+            name: "Analog Input Test Machine",
+            api_schema: crate::analog_input_test_machine::api::api_schema,
+        });
 
-        mc.register::<DigitalInputTestMachine>(DigitalInputTestMachine::MACHINE_IDENTIFICATION);
+        mc.register::<WagoAiTestMachine>(MachineDescriptor {
+            machine_identification: WagoAiTestMachine::MACHINE_IDENTIFICATION,
+            slug: "wago_ai_test_machine",
+            name: "Wago AI Test Machine",
+            api_schema: crate::wago_ai_test_machine::api::api_schema,
+        });
+
+        mc.register::<DigitalInputTestMachine>(MachineDescriptor {
+            machine_identification: DigitalInputTestMachine::MACHINE_IDENTIFICATION,
+            slug: "digital_input_test_machine",
+            name: "Digital Input Test Machine",
+            api_schema: crate::digital_input_test_machine::api::api_schema,
+        });
 
         mc
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "mock-machine")]
+    use crate::MACHINE_MOCK;
+    use crate::{
+        ANALOG_INPUT_TEST_MACHINE, DIGITAL_INPUT_TEST_MACHINE, IP20_TEST_MACHINE,
+        MACHINE_EXTRUDER_V1, MACHINE_EXTRUDER_V2, MACHINE_WAGO_POWER_V1, MACHINE_WINDER_V1,
+        TEST_MACHINE, WAGO_AI_TEST_MACHINE,
+    };
+    #[cfg(not(feature = "mock-machine"))]
+    use crate::{MACHINE_AQUAPATH_V1, MACHINE_BUFFER_V1, MACHINE_LASER_V1};
+
+    #[test]
+    fn test_descriptors_are_unique() {
+        let descriptors = MACHINE_REGISTRY.descriptors();
+        for (i, a) in descriptors.iter().enumerate() {
+            for b in &descriptors[i + 1..] {
+                assert_ne!(a.machine_identification, b.machine_identification);
+                assert_ne!(a.slug, b.slug);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_machine_is_an_error() {
+        let unknown = MachineIdentification {
+            vendor: VENDOR_QITECH,
+            machine: 0xFFFF,
+        };
+        assert!(MACHINE_REGISTRY.get_descriptor(&unknown).is_err());
+        assert!(unknown.slug().is_err());
+        assert!(MACHINE_REGISTRY.get_vendor_name(0xFFFF).is_err());
+    }
+
+    #[test]
+    fn test_every_machine_has_a_slug() {
+        for machine in [
+            MACHINE_WINDER_V1,
+            MACHINE_EXTRUDER_V1,
+            MACHINE_EXTRUDER_V2,
+            MACHINE_WAGO_POWER_V1,
+            TEST_MACHINE,
+            IP20_TEST_MACHINE,
+            ANALOG_INPUT_TEST_MACHINE,
+            WAGO_AI_TEST_MACHINE,
+            DIGITAL_INPUT_TEST_MACHINE,
+        ] {
+            let id = MachineIdentification {
+                vendor: VENDOR_QITECH,
+                machine,
+            };
+            assert!(id.slug().is_ok(), "{id:?} has no slug");
+        }
+
+        #[cfg(not(feature = "mock-machine"))]
+        for machine in [MACHINE_LASER_V1, MACHINE_BUFFER_V1, MACHINE_AQUAPATH_V1] {
+            let id = MachineIdentification {
+                vendor: VENDOR_QITECH,
+                machine,
+            };
+            assert!(id.slug().is_ok(), "{id:?} has no slug");
+        }
+
+        #[cfg(feature = "mock-machine")]
+        assert!(
+            MachineIdentification {
+                vendor: VENDOR_QITECH,
+                machine: MACHINE_MOCK,
+            }
+            .slug()
+            .is_ok()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::Serialize;
+
+/// JSON Schemas (draft 2020-12) of the payloads a machine type accepts and reports
+///
+/// - `mutation`: one item of `POST /api/v2/machine/<slug>/<serial>`
+/// - `state` and `live_values`: the values of `GET /api/v2/machine/<slug>/<serial>`
+///   and of the `StateEvent` and `LiveValuesEvent` socket.io events
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineApiSchema {
+    pub mutation: Schema,
+    /// `None` if the machine reports no state
+    pub state: Option<Schema>,
+    /// `None` if the machine reports no live values
+    pub live_values: Option<Schema>,
+}
+
+impl MachineApiSchema {
+    pub fn new<M: JsonSchema>() -> Self {
+        Self {
+            mutation: schema_for!(M),
+            state: None,
+            live_values: None,
+        }
+    }
+
+    pub fn with_state<S: JsonSchema>(mut self) -> Self {
+        self.state = Some(schema_for!(S));
+        self
+    }
+
+    pub fn with_live_values<L: JsonSchema>(mut self) -> Self {
+        self.live_values = Some(schema_for!(L));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::MACHINE_REGISTRY;
+
+    #[test]
+    fn test_every_machine_has_a_mutation_schema() {
+        for descriptor in MACHINE_REGISTRY.descriptors() {
+            let schema = (descriptor.api_schema)();
+            let mutation = serde_json::to_value(&schema.mutation).unwrap();
+            assert!(
+                mutation.get("oneOf").is_some() || mutation.get("properties").is_some(),
+                "{} has an empty mutation schema: {}",
+                descriptor.slug,
+                mutation
+            );
+        }
+    }
+
+    #[test]
+    fn test_winder_schema_follows_serde() {
+        let schema = crate::winder2::api::api_schema();
+        let mutation = serde_json::to_string(&schema.mutation).unwrap();
+        assert!(mutation.contains("SetTraverseLimitOuter"));
+        assert!(mutation.contains("GotoTraverseHome"));
+
+        let state = serde_json::to_value(schema.state.unwrap()).unwrap();
+        assert!(state["properties"]["traverse_state"].is_object());
+        assert!(schema.live_values.is_some());
+    }
+}
@@ -14,6 +14,13 @@ impl MachineAct for TestMachine {
         }
     }
 
+    fn safe_stop(&mut self) {
+        self.set_all_leds(false);
+        for dout in &self.douts {
+            dout.set(false);
+        }
+    }
+
     fn act_machine_message(&mut self, msg: MachineMessage) {
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
@@ -31,14 +38,18 @@ impl MachineAct for TestMachine {
             MachineMessage::DisconnectMachine(_machine_connection) => {
                 // Does not connect to any Machine; do nothing
             }
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
+            }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.get_state())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::Value::Null,
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.get_state())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::Value::Null,
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
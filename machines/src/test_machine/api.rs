@@ -1,4 +1,5 @@
 use super::TestMachine;
+use crate::schema::MachineApiSchema;
 use crate::{MachineApi, MachineMessage};
 use control_core::socketio::{
     event::{Event, GenericEvent},
@@ -6,11 +7,12 @@ use control_core::socketio::{
         CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_first_and_last_event,
     },
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::sync::Arc;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct StateEvent {
     pub led_on: [bool; 4],
 }
@@ -25,13 +27,18 @@ pub enum TestMachineEvents {
     State(Event<StateEvent>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(tag = "action", content = "value")]
 pub enum Mutation {
     SetLed { index: usize, on: bool },
     SetAllLeds { on: bool },
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>().with_state::<StateEvent>()
+}
+
 #[derive(Debug, Clone)]
 pub struct TestMachineNamespace {
     pub namespace: Option<Namespace>,
@@ -78,6 +85,10 @@ impl MachineApi for TestMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
@@ -1,3 +1,5 @@
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 use std::sync::Arc;
 
 use control_core::socketio::{
@@ -48,11 +50,16 @@ pub enum WagoAiTestMachineEvents {
     State(Event<AnalogInputsEvent>),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct Mutation {
     measurement_rate_hz: i32,
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+}
+
 impl MachineApi for WagoAiTestMachine {
     fn api_get_sender(&self) -> smol::channel::Sender<crate::MachineMessage> {
         self.api_sender.clone()
@@ -65,6 +72,10 @@ impl MachineApi for WagoAiTestMachine {
         Ok(())
     }
 
+    fn api_validate(&self, value: &serde_json::Value) -> Result<(), anyhow::Error> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn api_event_namespace(&mut self) -> Option<control_core::socketio::namespace::Namespace> {
         self.namespace.namespace.clone()
     }
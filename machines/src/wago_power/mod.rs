@@ -1,3 +1,4 @@
+use crate::schema::MachineApiSchema;
 use crate::{
     MACHINE_WAGO_POWER_V1, MachineChannel, MachineWithChannel, VENDOR_QITECH,
     machine_identification::MachineIdentification,
@@ -11,6 +12,7 @@ use control_core::socketio::{
     },
 };
 use control_core_derive::BuildEvent;
+use schemars::JsonSchema;
 use serde::*;
 use std::time::{Duration, Instant};
 
@@ -33,7 +35,7 @@ const MODBUS_DC_OFF: u16 = 0;
 const MODBUS_DC_ON: u16 = 1;
 const MODBUS_HICCUP_POWER: u16 = 1 << 8;
 
-#[derive(Serialize, Debug, Clone, BuildEvent)]
+#[derive(Serialize, Debug, Clone, BuildEvent, JsonSchema)]
 pub struct LiveValues {
     voltage: f64,
     current: f64,
@@ -49,7 +51,7 @@ impl CacheableEvents<Self> for LiveValues {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub enum Mode {
     Off,
     On24V,
@@ -64,7 +66,7 @@ impl Mode {
     }
 }
 
-#[derive(Serialize, Debug, Clone, BuildEvent)]
+#[derive(Serialize, Debug, Clone, BuildEvent, JsonSchema)]
 pub struct State {
     mode: Mode,
     is_default_state: bool,
@@ -80,11 +82,18 @@ impl CacheableEvents<Self> for State {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub enum Mutation {
     SetMode(Mode),
 }
 
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<State>()
+        .with_live_values::<LiveValues>()
+}
+
 #[derive(Debug)]
 pub struct WagoPower {
     mode: Mode,
@@ -216,6 +225,10 @@ impl MachineWithChannel for WagoPower {
         Ok(())
     }
 
+    fn validate(&self, value: &serde_json::Value) -> Result<()> {
+        crate::validate_mutation::<Mutation>(value)
+    }
+
     fn on_namespace(&mut self) {
         self.emit_state();
     }
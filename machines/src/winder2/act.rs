@@ -16,6 +16,9 @@ impl MachineAct for Winder2 {
         // sync the spool speed
         self.sync_spool_speed(now);
 
+        // poll the connected laser for the diameter regulation
+        self.sync_laser_diameter(now);
+
         // sync the puller speed
         self.sync_puller_speed(now);
 
@@ -36,6 +39,16 @@ impl MachineAct for Winder2 {
         }
     }
 
+    fn safe_stop(&mut self) {
+        // steppers are disabled in standby
+        if self.mode != super::Winder2Mode::Standby {
+            self.set_mode(&super::Winder2Mode::Standby);
+        }
+        if self.laser.get() {
+            self.set_laser(false);
+        }
+    }
+
     fn act_machine_message(&mut self, msg: MachineMessage) {
         match msg {
             MachineMessage::SubscribeNamespace(namespace) => {
@@ -44,32 +57,27 @@ impl MachineAct for Winder2 {
             }
             MachineMessage::UnsubscribeNamespace => self.namespace.namespace = None,
             MachineMessage::HttpApiJsonRequest(value) => {
-                use crate::MachineApi;
-                let _res = self.api_mutate(value);
+                use crate::Machine;
+                let _res = self.api_mutate_persistent(value);
             }
-            MachineMessage::ConnectToMachine(machine_connection) => {
-                if self.connected_machines.len() >= self.max_connected_machines {
-                    tracing::debug!(
-                        "Refusing to add Machine Connection {:?}, since self.connected_machines would be over the limit of {:?}",
-                        machine_connection,
-                        self.max_connected_machines
-                    );
-                    return;
+            msg @ (MachineMessage::ConnectToMachine(_) | MachineMessage::DisconnectMachine(_)) => {
+                if self.connections.act_machine_message(msg) {
+                    self.emit_state();
                 }
-                self.connected_machines.push(machine_connection);
             }
-            MachineMessage::DisconnectMachine(_machine_connection) => {
-                self.connected_machines.clear();
+            MachineMessage::HttpApiJsonBatch(batch) => {
+                use crate::Machine;
+                self.api_mutate_batch(batch);
             }
             MachineMessage::RequestValues(sender) => {
-                sender
-                    .send_blocking(MachineValues {
-                        state: serde_json::to_value(self.build_state_event())
-                            .expect("Failed to serialize state"),
-                        live_values: serde_json::to_value(self.get_live_values())
-                            .expect("Failed to serialize live values"),
-                    })
-                    .expect("Failed to send values");
+                if let Err(e) = sender.send_blocking(MachineValues {
+                    state: serde_json::to_value(self.build_state_event())
+                        .expect("Failed to serialize state"),
+                    live_values: serde_json::to_value(self.get_live_values())
+                        .expect("Failed to serialize live values"),
+                }) {
+                    tracing::warn!("Failed to send values: {:?}", e);
+                }
                 sender.close();
             }
         }
@@ -1,7 +1,9 @@
 #[cfg(feature = "mock-machine")]
 mod winder2_imports {
     pub use super::super::Winder2Mode;
-    pub use super::super::puller_speed_controller::{GearRatio, PullerRegulationMode};
+    pub use super::super::puller_speed_controller::{
+        GearRatio, PullerDiameterRegulationSettings, PullerRegulationMode, validate_target_diameter,
+    };
     pub use control_core::socketio::{
         event::{Event, GenericEvent},
         namespace::{
@@ -23,7 +25,9 @@ mod winder2_imports {
 
 #[cfg(not(feature = "mock-machine"))]
 mod winder2_imports {
-    pub use super::super::puller_speed_controller::{GearRatio, PullerRegulationMode};
+    pub use super::super::puller_speed_controller::{
+        GearRatio, PullerDiameterRegulationSettings, PullerRegulationMode, validate_target_diameter,
+    };
     pub use super::super::{Winder2, Winder2Mode};
     pub use control_core::socketio::{
         event::{Event, GenericEvent},
@@ -44,15 +48,19 @@ mod winder2_imports {
     pub use tracing::instrument;
 }
 
+use crate::schema::MachineApiSchema;
+use schemars::JsonSchema;
 #[cfg(not(feature = "mock-machine"))]
 use smol::channel::Sender;
 pub use winder2_imports::*;
 
+#[cfg(not(feature = "mock-machine"))]
+use crate::persistence::PersistentSettings;
 #[cfg(not(feature = "mock-machine"))]
 use crate::{MachineApi, MachineMessage};
 use crate::{MachineCrossConnectionState, machine_identification::MachineIdentificationUnique};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, JsonSchema)]
 pub enum Mode {
     #[default]
     Standby,
@@ -83,7 +91,7 @@ impl From<Mode> for Winder2Mode {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub enum Mutation {
     // Traverse
     /// Position in mm from home point
@@ -107,6 +115,8 @@ pub enum Mutation {
     SetPullerTargetDiameter(f64),
     SetPullerForward(bool),
     SetPullerGearRatio(GearRatio),
+    /// Tuning of the diameter regulation mode
+    SetPullerDiameterRegulation(PullerDiameterRegulationSettings),
 
     // Spool Speed Controller
     SetSpoolRegulationMode(super::spool_speed_controller::SpoolSpeedControllerType),
@@ -139,7 +149,14 @@ pub enum Mutation {
     DisconnectMachine(MachineIdentificationUnique),
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+/// JSON Schemas of the mutations, state and live values
+pub fn api_schema() -> MachineApiSchema {
+    MachineApiSchema::new::<Mutation>()
+        .with_state::<StateEvent>()
+        .with_live_values::<LiveValuesEvent>()
+}
+
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct LiveValuesEvent {
     /// traverse position in mm
     pub traverse_position: Option<f64>,
@@ -151,6 +168,8 @@ pub struct LiveValuesEvent {
     pub tension_arm_angle: f64,
     // spool progress in meters (pulled distance of filament)
     pub spool_progress: f64,
+    /// diameter in mm from the connected laser used by the diameter regulation
+    pub puller_measured_diameter: Option<f64>,
 }
 
 impl LiveValuesEvent {
@@ -159,7 +178,7 @@ impl LiveValuesEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone, BuildEvent)]
+#[derive(Serialize, Debug, Clone, BuildEvent, JsonSchema)]
 pub struct StateEvent {
     pub is_default_state: bool,
     /// traverse state
@@ -178,7 +197,7 @@ pub struct StateEvent {
     pub connected_machine_state: MachineCrossConnectionState,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct TraverseState {
     /// min position in mm
     pub limit_inner: f64,
@@ -212,7 +231,7 @@ pub struct TraverseState {
     pub can_go_home: bool,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct PullerState {
     /// regulation type
     pub regulation: PullerRegulationMode,
@@ -224,9 +243,11 @@ pub struct PullerState {
     pub forward: bool,
     /// gear ratio for winding speed
     pub gear_ratio: GearRatio,
+    /// diameter regulation tuning
+    pub diameter_regulation: PullerDiameterRegulationSettings,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, JsonSchema)]
 pub enum SpoolAutomaticActionMode {
     #[default]
     NoAction,
@@ -234,13 +255,13 @@ pub enum SpoolAutomaticActionMode {
     Hold,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct SpoolAutomaticActionState {
     pub spool_required_meters: f64,
     pub spool_automatic_action_mode: SpoolAutomaticActionMode,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct ModeState {
     /// mode
     pub mode: Mode,
@@ -248,13 +269,13 @@ pub struct ModeState {
     pub can_wind: bool,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct TensionArmState {
     /// is zeroed
     pub zeroed: bool,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Serialize, Debug, Clone, Default, JsonSchema)]
 pub struct SpoolSpeedControllerState {
     /// regulation mode
     pub regulation_mode: super::spool_speed_controller::SpoolSpeedControllerType,
@@ -315,6 +336,37 @@ impl CacheableEvents<Self> for Winder2Events {
     }
 }
 
+/// Traverse, puller and spool settings restored after a restart, including the regulation modes
+/// and the automatic action. The machine mode, the laserpointer, traverse moves, resets and
+/// connections are not restored, the winder always starts in standby.
+#[cfg(not(feature = "mock-machine"))]
+const PERSISTENT_SETTINGS: PersistentSettings = PersistentSettings {
+    version: 1,
+    mutations: &[
+        "SetTraverseLimitOuter",
+        "SetTraverseLimitInner",
+        "SetTraverseStepSize",
+        "SetTraversePadding",
+        "SetPullerRegulationMode",
+        "SetPullerTargetSpeed",
+        "SetPullerTargetDiameter",
+        "SetPullerForward",
+        "SetPullerGearRatio",
+        "SetPullerDiameterRegulation",
+        "SetSpoolRegulationMode",
+        "SetSpoolMinMaxMinSpeed",
+        "SetSpoolMinMaxMaxSpeed",
+        "SetSpoolForward",
+        "SetSpoolAdaptiveTensionTarget",
+        "SetSpoolAdaptiveRadiusLearningRate",
+        "SetSpoolAdaptiveMaxSpeedMultiplier",
+        "SetSpoolAdaptiveAccelerationFactor",
+        "SetSpoolAdaptiveDeaccelerationUrgencyMultiplier",
+        "SetSpoolAutomaticRequiredMeters",
+        "SetSpoolAutomaticAction",
+    ],
+};
+
 #[cfg(not(feature = "mock-machine"))]
 impl MachineApi for Winder2 {
     fn api_get_sender(&self) -> Sender<MachineMessage> {
@@ -322,8 +374,6 @@ impl MachineApi for Winder2 {
     }
 
     fn api_mutate(&mut self, request_body: Value) -> Result<(), anyhow::Error> {
-        use crate::Machine;
-
         let mutation: Mutation = serde_json::from_value(request_body)?;
         match mutation {
             Mutation::EnableTraverseLaserpointer(enable) => self.set_laser(enable),
@@ -337,9 +387,12 @@ impl MachineApi for Winder2 {
             Mutation::GotoTraverseHome => self.traverse_goto_home(),
             Mutation::SetPullerRegulationMode(regulation) => self.puller_set_regulation(regulation),
             Mutation::SetPullerTargetSpeed(value) => self.puller_set_target_speed(value),
-            Mutation::SetPullerTargetDiameter(_) => todo!(),
+            Mutation::SetPullerTargetDiameter(value) => self.puller_set_target_diameter(value)?,
             Mutation::SetPullerForward(value) => self.puller_set_forward(value),
             Mutation::SetPullerGearRatio(gear_ratio) => self.puller_set_gear_ratio(gear_ratio),
+            Mutation::SetPullerDiameterRegulation(settings) => {
+                self.puller_set_diameter_regulation(settings)?
+            }
             Mutation::SetSpoolRegulationMode(mode) => self.spool_set_regulation_mode(mode),
             Mutation::SetSpoolMinMaxMinSpeed(speed) => self.spool_set_minmax_min_speed(speed),
             Mutation::SetSpoolMinMaxMaxSpeed(speed) => self.spool_set_minmax_max_speed(speed),
@@ -366,37 +419,111 @@ impl MachineApi for Winder2 {
             Mutation::ResetSpoolProgress => self.stop_or_pull_spool_reset(Instant::now()),
             Mutation::ZeroTensionArmAngle => self.tension_arm_zero(),
             Mutation::SetConnectedMachine(machine_identification_unique) => {
-                let main_sender = match &self.main_sender {
-                    Some(sender) => sender,
-                    None => {
-                        return Err(anyhow::anyhow!(
-                            "Machine cannot connect to others! {:?}",
-                            self.get_machine_identification_unique()
-                        ));
-                    }
-                };
-                main_sender.try_send(crate::AsyncThreadMessage::ConnectOneWayRequest(
-                    crate::CrossConnection {
-                        src: self.get_machine_identification_unique(),
-                        dest: machine_identification_unique,
-                    },
-                ))?;
-                self.emit_state();
+                self.connections
+                    .request_connect(machine_identification_unique)?;
             }
-            Mutation::DisconnectMachine(_machine_identification_unique) => {
-                self.connected_machines.clear();
-                /*let main_sender = match &self.main_sender {
-                    Some(sender) => sender,
-                    None => return Err(anyhow::anyhow!("[DisconnectMachine] Machine cannot connect to others! {:?}", self.machine_identification_unique)),
-                };*/
-                //main_sender.try_send(crate::AsyncThreadMessage::ConnectOneWayRequest(crate::CrossConnection { src: self.get_machine_identification_unique(), dest: machine_identification_unique }))?;
-                self.emit_state();
+            Mutation::DisconnectMachine(machine_identification_unique) => {
+                if self
+                    .connections
+                    .request_disconnect(machine_identification_unique)?
+                {
+                    self.emit_state();
+                }
             }
         }
         Ok(())
     }
 
+    fn api_validate(&self, request_body: &Value) -> Result<(), anyhow::Error> {
+        match Mutation::deserialize(request_body)? {
+            Mutation::SetPullerDiameterRegulation(settings) => settings.validate(),
+            Mutation::SetPullerTargetDiameter(value) => validate_target_diameter(value),
+            _ => Ok(()),
+        }
+    }
+
+    fn api_undo(&self, value: &Value) -> Option<Value> {
+        let state = self.get_state();
+        let traverse = state.traverse_state;
+        let puller = state.puller_state;
+        let spool = state.spool_speed_controller_state;
+        let automatic = state.spool_automatic_action_state;
+        let undo = match Mutation::deserialize(value).ok()? {
+            Mutation::SetTraverseLimitOuter(_) => {
+                Mutation::SetTraverseLimitOuter(traverse.limit_outer)
+            }
+            Mutation::SetTraverseLimitInner(_) => {
+                Mutation::SetTraverseLimitInner(traverse.limit_inner)
+            }
+            Mutation::SetTraverseStepSize(_) => Mutation::SetTraverseStepSize(traverse.step_size),
+            Mutation::SetTraversePadding(_) => Mutation::SetTraversePadding(traverse.padding),
+            Mutation::EnableTraverseLaserpointer(_) => {
+                Mutation::EnableTraverseLaserpointer(traverse.laserpointer)
+            }
+            Mutation::SetPullerRegulationMode(_) => {
+                Mutation::SetPullerRegulationMode(puller.regulation)
+            }
+            Mutation::SetPullerTargetSpeed(_) => {
+                Mutation::SetPullerTargetSpeed(puller.target_speed)
+            }
+            Mutation::SetPullerTargetDiameter(_) => {
+                Mutation::SetPullerTargetDiameter(puller.target_diameter)
+            }
+            Mutation::SetPullerForward(_) => Mutation::SetPullerForward(puller.forward),
+            Mutation::SetPullerGearRatio(_) => Mutation::SetPullerGearRatio(puller.gear_ratio),
+            Mutation::SetPullerDiameterRegulation(_) => {
+                Mutation::SetPullerDiameterRegulation(puller.diameter_regulation)
+            }
+            Mutation::SetSpoolRegulationMode(_) => {
+                Mutation::SetSpoolRegulationMode(spool.regulation_mode)
+            }
+            Mutation::SetSpoolMinMaxMinSpeed(_) => {
+                Mutation::SetSpoolMinMaxMinSpeed(spool.minmax_min_speed)
+            }
+            Mutation::SetSpoolMinMaxMaxSpeed(_) => {
+                Mutation::SetSpoolMinMaxMaxSpeed(spool.minmax_max_speed)
+            }
+            Mutation::SetSpoolForward(_) => Mutation::SetSpoolForward(spool.forward),
+            Mutation::SetSpoolAdaptiveTensionTarget(_) => {
+                Mutation::SetSpoolAdaptiveTensionTarget(spool.adaptive_tension_target)
+            }
+            Mutation::SetSpoolAdaptiveRadiusLearningRate(_) => {
+                Mutation::SetSpoolAdaptiveRadiusLearningRate(spool.adaptive_radius_learning_rate)
+            }
+            Mutation::SetSpoolAdaptiveMaxSpeedMultiplier(_) => {
+                Mutation::SetSpoolAdaptiveMaxSpeedMultiplier(spool.adaptive_max_speed_multiplier)
+            }
+            Mutation::SetSpoolAdaptiveAccelerationFactor(_) => {
+                Mutation::SetSpoolAdaptiveAccelerationFactor(spool.adaptive_acceleration_factor)
+            }
+            Mutation::SetSpoolAdaptiveDeaccelerationUrgencyMultiplier(_) => {
+                Mutation::SetSpoolAdaptiveDeaccelerationUrgencyMultiplier(
+                    spool.adaptive_deacceleration_urgency_multiplier,
+                )
+            }
+            Mutation::SetSpoolAutomaticRequiredMeters(_) => {
+                Mutation::SetSpoolAutomaticRequiredMeters(automatic.spool_required_meters)
+            }
+            Mutation::SetSpoolAutomaticAction(_) => {
+                Mutation::SetSpoolAutomaticAction(automatic.spool_automatic_action_mode)
+            }
+            Mutation::SetMode(_) => Mutation::SetMode(state.mode_state.mode),
+            Mutation::GotoTraverseLimitOuter
+            | Mutation::GotoTraverseLimitInner
+            | Mutation::GotoTraverseHome
+            | Mutation::ResetSpoolProgress
+            | Mutation::ZeroTensionArmAngle
+            | Mutation::SetConnectedMachine(_)
+            | Mutation::DisconnectMachine(_) => return None,
+        };
+        serde_json::to_value(undo).ok()
+    }
+
     fn api_event_namespace(&mut self) -> Option<Namespace> {
         self.namespace.namespace.clone()
     }
+
+    fn api_persistent_settings(&self) -> Option<&'static PersistentSettings> {
+        Some(&PERSISTENT_SETTINGS)
+    }
 }
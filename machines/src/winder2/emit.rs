@@ -1,6 +1,8 @@
 #[cfg(not(feature = "mock-machine"))]
 mod winder2_imports {
-    pub use super::super::puller_speed_controller::PullerRegulationMode;
+    pub use super::super::puller_speed_controller::{
+        PullerDiameterRegulationSettings, PullerRegulationMode, validate_target_diameter,
+    };
     pub use super::super::{TraverseMode, Winder2, Winder2Mode, api, spool_speed_controller};
     pub use crate::buffer1::BufferV1;
     pub use api::{
@@ -201,6 +203,10 @@ impl Winder2 {
             spool_rpm,
             tension_arm_angle: angle_deg,
             spool_progress: self.spool_automatic_action.progress.get::<meter>(),
+            puller_measured_diameter: self
+                .puller_speed_controller
+                .get_measured_diameter(Instant::now())
+                .map(|x| x.get::<millimeter>()),
         }
     }
 
@@ -210,20 +216,16 @@ impl Winder2 {
     }
 
     pub fn build_state_event(&mut self) -> StateEvent {
-        use crate::MachineCrossConnectionState;
+        let state = self.get_state();
+        self.emitted_default_state = true;
+        state
+    }
 
-        let connected_machine = self.connected_machines.get(0);
-        let ident = match connected_machine {
-            Some(machine) => Some(machine.ident.clone()),
-            None => None,
-        };
-        let cross_conn = MachineCrossConnectionState {
-            machine_identification_unique: ident,
-            is_available: connected_machine.is_some(),
-        };
+    pub fn get_state(&self) -> StateEvent {
+        let cross_conn = self.connections.get_cross_connection_state();
 
         StateEvent {
-            is_default_state: !std::mem::replace(&mut self.emitted_default_state, true),
+            is_default_state: !self.emitted_default_state,
             traverse_state: TraverseState {
                 limit_inner: self
                     .traverse_controller
@@ -265,6 +267,7 @@ impl Winder2 {
                     .get::<millimeter>(),
                 forward: self.puller_speed_controller.forward,
                 gear_ratio: self.puller_speed_controller.gear_ratio,
+                diameter_regulation: self.puller_speed_controller.diameter_regulation.clone(),
             },
             mode_state: ModeState {
                 mode: self.mode.clone().into(),
@@ -414,12 +417,16 @@ impl Winder2 {
     }
 
     /// Set target diameter in mm
-    pub fn puller_set_target_diameter(&mut self, target_diameter: f64) {
-        // Convert m/min to velocity
+    pub fn puller_set_target_diameter(
+        &mut self,
+        target_diameter: f64,
+    ) -> Result<(), anyhow::Error> {
+        validate_target_diameter(target_diameter)?;
         let target_diameter = Length::new::<millimeter>(target_diameter);
         self.puller_speed_controller
             .set_target_diameter(target_diameter);
         self.emit_state();
+        Ok(())
     }
 
     /// Set forward direction
@@ -434,6 +441,17 @@ impl Winder2 {
         self.emit_state();
     }
 
+    /// Set diameter regulation tuning
+    pub fn puller_set_diameter_regulation(
+        &mut self,
+        settings: PullerDiameterRegulationSettings,
+    ) -> Result<(), anyhow::Error> {
+        self.puller_speed_controller
+            .set_diameter_regulation(settings)?;
+        self.emit_state();
+        Ok(())
+    }
+
     // Spool Speed Controller API methods
     pub fn spool_set_regulation_mode(
         &mut self,
@@ -1,5 +1,9 @@
 use super::Winder2;
-use crate::{MachineApi, winder2::api::Mutation};
+use crate::{
+    MachineApi,
+    winder2::{api::Mutation, puller_speed_controller::validate_target_diameter},
+};
+use serde::Deserialize;
 use serde_json::Value;
 use std::time::Instant;
 
@@ -18,9 +22,12 @@ impl MachineApi for Winder2 {
             Mutation::GotoTraverseHome => self.traverse_goto_home(),
             Mutation::SetPullerRegulationMode(regulation) => self.puller_set_regulation(regulation),
             Mutation::SetPullerTargetSpeed(value) => self.puller_set_target_speed(value),
-            Mutation::SetPullerTargetDiameter(_) => todo!(),
+            Mutation::SetPullerTargetDiameter(value) => self.puller_set_target_diameter(value)?,
             Mutation::SetPullerForward(value) => self.puller_set_forward(value),
             Mutation::SetPullerGearRatio(gear_ratio) => self.puller_set_gear_ratio(gear_ratio),
+            Mutation::SetPullerDiameterRegulation(settings) => {
+                self.puller_set_diameter_regulation(settings)?
+            }
             Mutation::SetSpoolRegulationMode(mode) => self.spool_set_regulation_mode(mode),
             Mutation::SetSpoolMinMaxMinSpeed(speed) => self.spool_set_minmax_min_speed(speed),
             Mutation::SetSpoolMinMaxMaxSpeed(speed) => self.spool_set_minmax_max_speed(speed),
@@ -56,6 +63,14 @@ impl MachineApi for Winder2 {
         Ok(())
     }
 
+    fn api_validate(&self, request_body: &Value) -> Result<(), anyhow::Error> {
+        match Mutation::deserialize(request_body)? {
+            Mutation::SetPullerDiameterRegulation(settings) => settings.validate(),
+            Mutation::SetPullerTargetDiameter(value) => validate_target_diameter(value),
+            _ => Ok(()),
+        }
+    }
+
     fn api_event_namespace(
         &mut self,
     ) -> std::option::Option<control_core::socketio::namespace::Namespace> {
@@ -5,7 +5,9 @@ use crate::machine_identification::{MachineIdentification, MachineIdentification
 use crate::winder2::Winder2Mode;
 use crate::winder2::api::LiveValuesEvent;
 use crate::winder2::api::{ModeState, SpoolAutomaticActionMode, StateEvent, Winder2Events};
-use crate::winder2::puller_speed_controller::{GearRatio, PullerRegulationMode};
+use crate::winder2::puller_speed_controller::{
+    GearRatio, PullerDiameterRegulationSettings, PullerRegulationMode, validate_target_diameter,
+};
 use crate::winder2::spool_speed_controller::SpoolSpeedControllerType;
 use crate::{MACHINE_WINDER_V1, VENDOR_QITECH};
 use control_core::socketio::event::BuildEvent;
@@ -80,6 +82,7 @@ impl Winder2 {
             spool_rpm: 0.0,
             tension_arm_angle: 0.0,
             spool_progress: 0.0,
+            puller_measured_diameter: None,
         }
     }
 
@@ -157,9 +160,14 @@ impl Winder2 {
     }
 
     /// Set target diameter in mm
-    pub fn puller_set_target_diameter(&mut self, target_diameter: f64) {
+    pub fn puller_set_target_diameter(
+        &mut self,
+        target_diameter: f64,
+    ) -> Result<(), anyhow::Error> {
+        validate_target_diameter(target_diameter)?;
         self.puller_state.target_diameter = target_diameter;
         self.emit_state();
+        Ok(())
     }
 
     /// Set forward direction
@@ -174,6 +182,17 @@ impl Winder2 {
         self.emit_state();
     }
 
+    /// Set diameter regulation tuning
+    pub fn puller_set_diameter_regulation(
+        &mut self,
+        settings: PullerDiameterRegulationSettings,
+    ) -> Result<(), anyhow::Error> {
+        settings.validate()?;
+        self.puller_state.diameter_regulation = settings;
+        self.emit_state();
+        Ok(())
+    }
+
     // Spool Speed Controller API methods
     pub fn spool_set_regulation_mode(&mut self, regulation_mode: SpoolSpeedControllerType) {
         self.spool_speed_controller_state.regulation_mode = regulation_mode;
@@ -26,7 +26,7 @@ mod winder2_imports {
 mod winder2_imports {
     pub use super::api::SpoolAutomaticActionMode;
     pub use super::api::Winder2Namespace;
-    pub use super::puller_speed_controller::PullerSpeedController;
+    pub use super::puller_speed_controller::{PullerRegulationMode, PullerSpeedController};
     pub use super::spool_speed_controller::SpoolSpeedController;
     pub use super::tension_arm::TensionArm;
     pub use super::traverse_controller::TraverseController;
@@ -37,9 +37,15 @@ mod winder2_imports {
     };
     pub use smol::channel::{Receiver, Sender};
     pub use smol::lock::RwLock;
-    pub use std::{fmt::Debug, sync::Weak, time::Instant};
+    pub use std::{
+        fmt::Debug,
+        sync::Weak,
+        time::{Duration, Instant},
+    };
 
     pub use crate::buffer1::BufferV1;
+    pub use crate::connections::MachineConnections;
+    pub use crate::laser::LaserMachine;
     pub use crate::{AsyncThreadMessage, Machine};
     pub use units::ConstZero;
     pub use units::f64::Length;
@@ -50,7 +56,7 @@ pub use winder2_imports::*;
 
 #[cfg(not(feature = "mock-machine"))]
 use crate::{
-    MACHINE_WINDER_V1, MachineConnection, MachineMessage, VENDOR_QITECH,
+    MACHINE_WINDER_V1, MachineMessage, VENDOR_QITECH,
     machine_identification::{MachineIdentification, MachineIdentificationUnique},
 };
 
@@ -91,8 +97,7 @@ pub struct Winder2 {
     api_sender: Sender<MachineMessage>,
     main_sender: Option<Sender<AsyncThreadMessage>>,
 
-    connected_machines: Vec<MachineConnection>,
-    max_connected_machines: usize,
+    connections: MachineConnections,
     // drivers
     pub traverse: StepperVelocityEL70x1,
     pub puller: StepperVelocityEL70x1,
@@ -125,6 +130,9 @@ pub struct Winder2 {
     // control circuit puller
     pub puller_speed_controller: PullerSpeedController,
 
+    // diameter feedback from a connected laser
+    last_laser_values_request: Instant,
+
     /// Will be initialized as false and set to true by emit_state
     /// This way we can signal to the client that the first state emission is a default state
     emitted_default_state: bool,
@@ -137,6 +145,9 @@ impl Winder2 {
         machine: MACHINE_WINDER_V1,
     };
 
+    /// How often the connected laser is asked for its diameter
+    const LASER_VALUES_INTERVAL: Duration = Duration::from_millis(50);
+
     /// Validates that traverse limits maintain proper constraints:
     /// - Inner limit must be smaller than outer limit
     /// - At least 0.9mm difference between inner and outer limits
@@ -334,6 +345,42 @@ impl Winder2 {
             .angular_velocity_to_steps(angular_velocity);
         let _ = self.puller.set_speed(steps_per_second);
     }
+
+    /// Poll the connected laser for the diameter used by the puller diameter regulation
+    /// called by `act`
+    pub fn sync_laser_diameter(&mut self, now: Instant) {
+        self.connections.poll(now);
+
+        let laser = match self.connections.find(&LaserMachine::MACHINE_IDENTIFICATION) {
+            Some(laser) => laser.clone(),
+            None => return,
+        };
+
+        // Collect the answer to the last request
+        if let Some(values) = self.connections.take_values(&laser) {
+            match values.live_values::<crate::laser::api::LiveValuesEvent>() {
+                Ok(live_values) => self
+                    .puller_speed_controller
+                    .set_measured_diameter(Length::new::<millimeter>(live_values.diameter), now),
+                Err(e) => tracing::warn!("Invalid laser live values: {:?}", e),
+            }
+        }
+
+        if !matches!(
+            self.puller_speed_controller.regulation_mode,
+            PullerRegulationMode::Diameter
+        ) {
+            return;
+        }
+
+        if now.duration_since(self.last_laser_values_request) < Self::LASER_VALUES_INTERVAL {
+            return;
+        }
+
+        if self.connections.request_values(&laser).is_ok() {
+            self.last_laser_values_request = now;
+        }
+    }
 }
 
 #[cfg(feature = "mock-machine")]
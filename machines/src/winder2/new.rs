@@ -3,6 +3,7 @@ mod winder2_imports {
     pub use super::super::api::Winder2Namespace;
     pub use super::super::tension_arm::TensionArm;
     pub use super::super::{Winder2, Winder2Mode};
+    pub use crate::connections::MachineConnections;
     pub use crate::winder2::puller_speed_controller::PullerSpeedController;
     pub use crate::winder2::spool_speed_controller::SpoolSpeedController;
     pub use crate::winder2::traverse_controller::TraverseController;
@@ -199,7 +200,6 @@ impl MachineNewTrait for Winder2 {
             let (sender, receiver) = smol::channel::unbounded();
             let mut new = Self {
                 main_sender: params.main_thread_channel.clone(),
-                max_connected_machines: 2,
                 api_receiver: receiver,
                 api_sender: sender,
                 traverse: StepperVelocityEL70x1::new(el7031.clone(), EL7031StepperPort::STM1),
@@ -237,6 +237,7 @@ impl MachineNewTrait for Winder2 {
                     Length::new::<millimeter>(92.0), // Default outer limit
                     64,                              // Microsteps
                 ),
+                last_laser_values_request: Instant::now(),
                 emitted_default_state: false,
                 spool_automatic_action: super::SpoolAutomaticAction {
                     progress: Length::ZERO,
@@ -244,8 +245,9 @@ impl MachineNewTrait for Winder2 {
                     target_length: Length::new::<meter>(250.0),
                     mode: super::api::SpoolAutomaticActionMode::NoAction,
                 },
+                connections: MachineConnections::new(2)
+                    .with_main_sender(machine_id.clone(), params.main_thread_channel.clone()),
                 machine_identification_unique: machine_id,
-                connected_machines: vec![],
             };
 
             // initalize events
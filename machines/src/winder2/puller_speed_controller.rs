@@ -1,7 +1,11 @@
+use schemars::JsonSchema;
 use std::time::Instant;
 
 use control_core::{
-    controllers::second_degree_motion::linear_jerk_speed_controller::LinearJerkSpeedController,
+    controllers::{
+        pid::PidController,
+        second_degree_motion::linear_jerk_speed_controller::LinearJerkSpeedController,
+    },
     converters::linear_step_converter::LinearStepConverter,
 };
 use serde::{Deserialize, Serialize};
@@ -9,9 +13,10 @@ use units::ConstZero;
 use units::acceleration::meter_per_minute_per_second;
 use units::f64::*;
 use units::jerk::meter_per_minute_per_second_squared;
+use units::length::millimeter;
 use units::velocity::meter_per_minute;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
 pub enum GearRatio {
     OneToOne,
     OneToFive,
@@ -35,6 +40,95 @@ impl Default for GearRatio {
     }
 }
 
+/// Tuning of the closed-loop diameter regulation
+///
+/// The regulation adds a PID correction on top of [`PullerSpeedController::target_speed`].
+/// A diameter above the target speeds the puller up, a diameter below slows it down.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct PullerDiameterRegulationSettings {
+    /// proportional gain in m/min per mm diameter error
+    pub kp: f64,
+    /// integral gain in m/min per mm*s diameter error
+    pub ki: f64,
+    /// derivative gain in m/min per mm/s diameter error
+    pub kd: f64,
+    /// lowest speed in m/min the regulation may command
+    pub min_speed: f64,
+    /// highest speed in m/min the regulation may command
+    pub max_speed: f64,
+    /// seconds without a valid measurement until the fallback is used
+    pub measurement_timeout: f64,
+    /// what to do when the laser stops reporting
+    pub fallback: PullerDiameterFallback,
+}
+
+impl Default for PullerDiameterRegulationSettings {
+    fn default() -> Self {
+        Self {
+            kp: 5.0,
+            ki: 1.0,
+            kd: 0.0,
+            min_speed: 0.5,
+            max_speed: 50.0,
+            measurement_timeout: 1.0,
+            fallback: PullerDiameterFallback::default(),
+        }
+    }
+}
+
+impl PullerDiameterRegulationSettings {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        for (name, gain) in [("kp", self.kp), ("ki", self.ki), ("kd", self.kd)] {
+            if !gain.is_finite() || gain < 0.0 {
+                return Err(anyhow::anyhow!(
+                    "{} {} must be finite and not negative",
+                    name,
+                    gain
+                ));
+            }
+        }
+        if !self.min_speed.is_finite()
+            || !self.max_speed.is_finite()
+            || self.min_speed < 0.0
+            || self.min_speed > self.max_speed
+        {
+            return Err(anyhow::anyhow!(
+                "min speed {} must be positive and not above max speed {}, both finite",
+                self.min_speed,
+                self.max_speed
+            ));
+        }
+        if !self.measurement_timeout.is_finite() || self.measurement_timeout <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "measurement timeout {} must be finite and positive",
+                self.measurement_timeout
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks a target diameter in mm before it is used by [`PullerSpeedController`]
+pub fn validate_target_diameter(target_diameter: f64) -> Result<(), anyhow::Error> {
+    if !target_diameter.is_finite() || target_diameter <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "target diameter {} must be finite and positive",
+            target_diameter
+        ));
+    }
+    Ok(())
+}
+
+/// Behaviour of the diameter regulation without a valid laser measurement
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default, JsonSchema)]
+pub enum PullerDiameterFallback {
+    /// Keep pulling with the last regulated speed
+    #[default]
+    HoldLastSpeed,
+    /// Return to the configured target speed
+    TargetSpeed,
+}
+
 #[derive(Debug)]
 pub struct PullerSpeedController {
     enabled: bool,
@@ -50,6 +144,14 @@ pub struct PullerSpeedController {
     /// Converter for linear to angular transformations
     pub converter: LinearStepConverter,
     pub last_speed: Velocity,
+    /// Tuning for [`PullerRegulationMode::Diameter`]
+    pub diameter_regulation: PullerDiameterRegulationSettings,
+    /// Correction of the target speed based on the diameter error
+    diameter_pid: PidController,
+    /// Last valid diameter measurement and when it was received
+    measured_diameter: Option<(Length, Instant)>,
+    /// Last speed commanded by the diameter regulation (before gear ratio and direction)
+    regulated_speed: Option<Velocity>,
 }
 
 impl PullerSpeedController {
@@ -61,6 +163,7 @@ impl PullerSpeedController {
         let acceleration = Acceleration::new::<meter_per_minute_per_second>(5.0);
         let jerk = Jerk::new::<meter_per_minute_per_second_squared>(10.0);
         let speed = Velocity::new::<meter_per_minute>(50.0);
+        let diameter_regulation = PullerDiameterRegulationSettings::default();
 
         Self {
             enabled: false,
@@ -76,6 +179,14 @@ impl PullerSpeedController {
             ),
             converter,
             last_speed: Velocity::ZERO,
+            diameter_pid: PidController::new(
+                diameter_regulation.kp,
+                diameter_regulation.ki,
+                diameter_regulation.kd,
+            ),
+            diameter_regulation,
+            measured_diameter: None,
+            regulated_speed: None,
         }
     }
 
@@ -91,10 +202,43 @@ impl PullerSpeedController {
         self.target_diameter = target;
     }
 
-    pub const fn set_regulation_mode(&mut self, regulation: PullerRegulationMode) {
+    pub fn set_regulation_mode(&mut self, regulation: PullerRegulationMode) {
+        // start every diameter regulation from the target speed
+        self.diameter_pid.reset();
+        self.regulated_speed = None;
         self.regulation_mode = regulation;
     }
 
+    pub fn set_diameter_regulation(
+        &mut self,
+        settings: PullerDiameterRegulationSettings,
+    ) -> Result<(), anyhow::Error> {
+        settings.validate()?;
+        self.diameter_pid
+            .configure(settings.ki, settings.kp, settings.kd);
+        self.diameter_regulation = settings;
+        Ok(())
+    }
+
+    /// Feed a diameter measurement into the diameter regulation
+    ///
+    /// Non-positive diameters mean the laser has no filament in view and are ignored.
+    pub fn set_measured_diameter(&mut self, diameter: Length, t: Instant) {
+        if diameter > Length::ZERO {
+            self.measured_diameter = Some((diameter, t));
+        }
+    }
+
+    /// Returns the measured diameter if it is not older than the measurement timeout
+    pub fn get_measured_diameter(&self, t: Instant) -> Option<Length> {
+        self.measured_diameter
+            .filter(|(_, received)| {
+                t.saturating_duration_since(*received).as_secs_f64()
+                    <= self.diameter_regulation.measurement_timeout
+            })
+            .map(|(diameter, _)| diameter)
+    }
+
     pub const fn set_forward(&mut self, forward: bool) {
         self.forward = forward;
     }
@@ -111,7 +255,7 @@ impl PullerSpeedController {
         let base_speed = match self.enabled {
             true => match self.regulation_mode {
                 PullerRegulationMode::Speed => self.target_speed,
-                PullerRegulationMode::Diameter => unimplemented!(),
+                PullerRegulationMode::Diameter => self.diameter_regulated_speed(t),
             },
             false => Velocity::ZERO,
         };
@@ -127,6 +271,35 @@ impl PullerSpeedController {
         speed
     }
 
+    /// Base speed while in [`PullerRegulationMode::Diameter`]
+    fn diameter_regulated_speed(&mut self, t: Instant) -> Velocity {
+        let diameter = match self.get_measured_diameter(t) {
+            Some(diameter) => diameter,
+            None => {
+                // Don't let the integral wind up while we are blind
+                self.diameter_pid.reset();
+                return match self.diameter_regulation.fallback {
+                    PullerDiameterFallback::HoldLastSpeed => {
+                        self.regulated_speed.unwrap_or(self.target_speed)
+                    }
+                    PullerDiameterFallback::TargetSpeed => self.target_speed,
+                };
+            }
+        };
+
+        let error = (diameter - self.target_diameter).get::<millimeter>();
+        let correction = self.diameter_pid.update(error, t);
+
+        let speed = (self.target_speed.get::<meter_per_minute>() + correction).clamp(
+            self.diameter_regulation.min_speed,
+            self.diameter_regulation.max_speed,
+        );
+        let speed = Velocity::new::<meter_per_minute>(speed);
+
+        self.regulated_speed = Some(speed);
+        speed
+    }
+
     pub fn speed_to_angular_velocity(&self, speed: Velocity) -> AngularVelocity {
         // Use the converter to transform from linear velocity to angular velocity
         self.converter.velocity_to_angular_velocity(speed)
@@ -147,9 +320,149 @@ impl PullerSpeedController {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, JsonSchema)]
 pub enum PullerRegulationMode {
     #[default]
     Speed,
     Diameter,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::time::Duration;
+    use units::length::centimeter;
+
+    fn diameter_controller() -> PullerSpeedController {
+        let mut controller = PullerSpeedController::new(
+            Velocity::new::<meter_per_minute>(10.0),
+            Length::new::<millimeter>(1.75),
+            LinearStepConverter::from_diameter(200, Length::new::<centimeter>(8.0)),
+        );
+        controller.set_regulation_mode(PullerRegulationMode::Diameter);
+        controller
+    }
+
+    #[test]
+    fn test_diameter_regulation_direction() {
+        let t0 = Instant::now();
+
+        // too thick filament has to be pulled faster
+        let mut controller = diameter_controller();
+        controller.set_measured_diameter(Length::new::<millimeter>(1.85), t0);
+        let speed = controller.diameter_regulated_speed(t0);
+        assert!(speed.get::<meter_per_minute>() > 10.0);
+
+        // too thin filament has to be pulled slower
+        let mut controller = diameter_controller();
+        controller.set_measured_diameter(Length::new::<millimeter>(1.65), t0);
+        let speed = controller.diameter_regulated_speed(t0);
+        assert!(speed.get::<meter_per_minute>() < 10.0);
+    }
+
+    #[test]
+    fn test_diameter_regulation_speed_limits() {
+        let t0 = Instant::now();
+        let mut controller = diameter_controller();
+        controller
+            .set_diameter_regulation(PullerDiameterRegulationSettings {
+                kp: 1000.0,
+                min_speed: 2.0,
+                max_speed: 20.0,
+                ..Default::default()
+            })
+            .unwrap();
+
+        controller.set_measured_diameter(Length::new::<millimeter>(3.0), t0);
+        let speed = controller.diameter_regulated_speed(t0);
+        assert_relative_eq!(speed.get::<meter_per_minute>(), 20.0, epsilon = 1e-9);
+
+        let t1 = t0 + Duration::from_millis(10);
+        controller.set_measured_diameter(Length::new::<millimeter>(0.5), t1);
+        let speed = controller.diameter_regulated_speed(t1);
+        assert_relative_eq!(speed.get::<meter_per_minute>(), 2.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_diameter_regulation_fallback() {
+        let t0 = Instant::now();
+        let mut controller = diameter_controller();
+
+        // no measurement yet
+        let speed = controller.diameter_regulated_speed(t0);
+        assert_relative_eq!(speed.get::<meter_per_minute>(), 10.0, epsilon = 1e-9);
+
+        controller.set_measured_diameter(Length::new::<millimeter>(1.85), t0);
+        let regulated = controller.diameter_regulated_speed(t0);
+
+        // zero diameter means no filament in view and is ignored
+        controller.set_measured_diameter(Length::ZERO, t0);
+        assert!(controller.get_measured_diameter(t0).is_some());
+
+        // measurement timed out, hold last regulated speed
+        let t1 = t0 + Duration::from_secs(2);
+        assert!(controller.get_measured_diameter(t1).is_none());
+        let speed = controller.diameter_regulated_speed(t1);
+        assert_eq!(speed, regulated);
+
+        // measurement timed out, go back to target speed
+        controller
+            .set_diameter_regulation(PullerDiameterRegulationSettings {
+                fallback: PullerDiameterFallback::TargetSpeed,
+                ..Default::default()
+            })
+            .unwrap();
+        let speed = controller.diameter_regulated_speed(t1);
+        assert_relative_eq!(speed.get::<meter_per_minute>(), 10.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_diameter_regulation_settings_validation() {
+        let settings = PullerDiameterRegulationSettings {
+            min_speed: 30.0,
+            max_speed: 20.0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = PullerDiameterRegulationSettings {
+            measurement_timeout: 0.0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = PullerDiameterRegulationSettings {
+            max_speed: f64::INFINITY,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = PullerDiameterRegulationSettings {
+            kp: -1.0,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = PullerDiameterRegulationSettings {
+            ki: f64::NAN,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        assert!(
+            PullerDiameterRegulationSettings::default()
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_target_diameter_validation() {
+        assert!(validate_target_diameter(1.75).is_ok());
+        assert!(validate_target_diameter(0.0).is_err());
+        assert!(validate_target_diameter(-1.75).is_err());
+        assert!(validate_target_diameter(f64::NAN).is_err());
+        assert!(validate_target_diameter(f64::INFINITY).is_err());
+    }
+}
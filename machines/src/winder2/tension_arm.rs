@@ -129,6 +129,7 @@ mod tests {
             // 5V of 10V in positive range
             normalized: (5.0 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let physical = tension_arm.analog_input.get_physical();
         match physical {
@@ -158,6 +159,7 @@ mod tests {
         analog_input_dummy.set_input(AnalogInputInput {
             normalized: (1.25 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let angle = tension_arm.raw_angle();
         assert_relative_eq!(angle.get::<revolution>(), 0.25, epsilon = f64::EPSILON);
@@ -166,6 +168,7 @@ mod tests {
         analog_input_dummy.set_input(AnalogInputInput {
             normalized: (2.5 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let angle = tension_arm.raw_angle();
         assert_relative_eq!(angle.get::<revolution>(), 0.5, epsilon = f64::EPSILON);
@@ -174,6 +177,7 @@ mod tests {
         analog_input_dummy.set_input(AnalogInputInput {
             normalized: (3.75 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let angle = tension_arm.raw_angle();
         assert_relative_eq!(angle.get::<revolution>(), 0.75, epsilon = f64::EPSILON);
@@ -182,6 +186,7 @@ mod tests {
         analog_input_dummy.set_input(AnalogInputInput {
             normalized: (5.0 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let angle = tension_arm.raw_angle();
         assert_relative_eq!(angle.get::<revolution>(), 0.0, epsilon = f64::EPSILON);
@@ -190,6 +195,7 @@ mod tests {
         analog_input_dummy.set_input(AnalogInputInput {
             normalized: (6.25 / 10.0),
             wiring_error: false,
+            ..Default::default()
         });
         let angle = tension_arm.raw_angle();
         assert_relative_eq!(angle.get::<revolution>(), 0.25, epsilon = f64::EPSILON);
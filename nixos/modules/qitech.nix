@@ -24,6 +24,13 @@ in {
       default = pkgs.qitech-control-server or null;
       description = "The QiTech server package to use";
     };
+
+    disableAuth = mkOption {
+      type = types.bool;
+      default = false;
+      description =
+        "Disable authentication, every client is admin. Only for panels on an isolated network";
+    };
   };
 
   config = mkIf cfg.enable {
@@ -102,7 +109,7 @@ in {
       environment = {
         RUST_BACKTRACE = "full";
         RUST_LOG = "info";
-      };
+      } // optionalAttrs cfg.disableAuth { QITECH_AUTH_DISABLED = "true"; };
     };
 
     # Add real-time privileges
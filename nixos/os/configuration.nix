@@ -222,6 +222,8 @@ in {
     user = "qitech-service";
     group = "qitech-service";
     package = pkgs.qitechPackages.server;
+    # The line runs on its own subnet, see docs/rest-api.md
+    disableAuth = true;
   };
 
   users.users.qitech = {
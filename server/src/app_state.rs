@@ -1,6 +1,15 @@
+use crate::audit::AuditLog;
+use crate::auth::Auth;
 use crate::ethercat::config::{MAX_SUBDEVICES, PDI_LEN};
+use crate::ethercat::dc::EthercatDc;
+use crate::ethercat::diagnosis::DiagnosisLog;
+use crate::ethercat::reconfigure::SubDeviceConfiguration;
+use crate::ethercat::sdo::{SdoRequest, SdoRequests, SdoResult};
+use crate::history::HistoryStore;
+use crate::recipes::RecipeStore;
 use crate::rest::handlers::write_machine_device_identification::MachineDeviceInfoRequest;
 use crate::socketio::main_namespace::MainNamespaceEvents;
+use crate::socketio::main_namespace::ethercat_link_quality_event::EthercatLinkQualityEvent;
 use crate::socketio::main_namespace::machines_event::{MachineObj, MachinesEventBuilder};
 use crate::socketio::namespaces::Namespaces;
 use anyhow::{Result, bail};
@@ -42,8 +51,11 @@ pub enum HotThreadMessage {
     NoMsg,
     AddMachines(Vec<Box<dyn Machine>>),
     AddEtherCatSetup(EthercatSetup),
-    WriteMachineDeviceInfo(MachineDeviceInfoRequest),
+    /// Reports back if the identification was written
+    WriteMachineDeviceInfo(MachineDeviceInfoRequest, Sender<Result<(), String>>),
     DeleteMachine(MachineIdentificationUnique),
+    /// Executes a CoE request and reports back its result
+    Sdo(SdoRequest, Sender<Result<SdoResult, String>>),
 }
 
 use crate::AsyncThreadMessage;
@@ -84,6 +96,15 @@ pub struct SharedState {
     pub rt_machine_creation_channel: Sender<HotThreadMessage>,
     pub main_channel: Sender<AsyncThreadMessage>,
     pub ethercat_meta_data: RwLock<Vec<EtherCatDeviceMetaData>>,
+    pub recipe_store: RecipeStore,
+    pub auth: Auth,
+    pub audit_log: AuditLog,
+    pub history: HistoryStore,
+    /// Published by the loop thread every second
+    pub ethercat_link_quality: RwLock<Option<EthercatLinkQualityEvent>>,
+    /// Diagnosis history of the SubDevices, collected by [`crate::ethercat::diagnosis::collect_diagnosis`]
+    pub ethercat_diagnosis: DiagnosisLog,
+    pub sdo_requests: SdoRequests,
 }
 
 impl fmt::Debug for EthercatSetup {
@@ -102,22 +123,33 @@ pub struct EthercatSetup {
     /// All Ethercat devices
     /// Generic interface for all devices
     /// Needed to interface with the devices on an Ethercat level
-    pub group: SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>,
+    /// Shared with the tasks reading mailboxes next to the loop, e.g. the diagnosis history
+    pub group: Arc<SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>>,
     /// The Ethercat main device
     /// Needed to interface with the devices
-    pub maindevice: MainDevice<'static>,
+    /// Lives as long as the process, re-initializations of the bus reuse it
+    pub maindevice: &'static MainDevice<'static>,
+    /// Distributed clocks, `None` if the bus runs in free run
+    pub dc: Option<EthercatDc>,
+    /// Written again to recovered SubDevices, same order as `devices`
+    /// `None` if it couldn't be read, the SubDevice can only be requested to go back to OP
+    pub configurations: Arc<Vec<Option<SubDeviceConfiguration>>>,
 }
 
 impl EthercatSetup {
     pub fn new(
         devices: Vec<(DeviceIdentification, Arc<RwLock<dyn EthercatDevice>>)>,
-        group: SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>,
-        maindevice: MainDevice<'static>,
+        group: Arc<SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>>,
+        maindevice: &'static MainDevice<'static>,
+        dc: Option<EthercatDc>,
+        configurations: Arc<Vec<Option<SubDeviceConfiguration>>>,
     ) -> Self {
         Self {
             devices,
             group,
             maindevice,
+            dc,
+            configurations,
         }
     }
 }
@@ -202,6 +234,19 @@ impl SharedState {
         });
     }
 
+    pub async fn clear_machine_error(
+        &self,
+        machine_identification_unique: &MachineIdentificationUnique,
+    ) {
+        let mut current_machines = self.current_machines_meta.lock().await;
+
+        for machine in current_machines.iter_mut() {
+            if &machine.machine_identification_unique == machine_identification_unique {
+                machine.error = None;
+            }
+        }
+    }
+
     pub async fn add_machines(&self, machines: Vec<Box<dyn Machine>>) {
         let mut api_machines = self.api_machines.lock().await;
         for machine in machines.iter() {
@@ -246,6 +291,13 @@ impl SharedState {
             api_machines: Mutex::new(HashMap::new()),
             rt_machine_creation_channel: sender,
             main_channel: main_async_channel,
+            recipe_store: RecipeStore::new(RecipeStore::default_dir()),
+            auth: Auth::from_env(),
+            audit_log: AuditLog::new(AuditLog::default_dir()),
+            history: HistoryStore::new(HistoryStore::default_dir()),
+            ethercat_link_quality: RwLock::new(None),
+            ethercat_diagnosis: DiagnosisLog::default(),
+            sdo_requests: SdoRequests::default(),
         }
     }
 }
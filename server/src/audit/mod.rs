@@ -0,0 +1,529 @@
+use anyhow::{Context, Result};
+use machines::machine_identification::MachineIdentificationUnique;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Environment variable to override where the audit log is stored
+pub const AUDIT_DIR_ENV: &str = "QITECH_AUDIT_DIR";
+
+const LOG_FILE: &str = "audit.jsonl";
+const RETENTION_FILE: &str = "retention.json";
+
+/// How often appending also drops entries which are out of retention
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// What was requested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AuditAction {
+    MachineMutation,
+    /// One entry per mutation of the recipe
+    RecipeApply {
+        name: String,
+        version: u32,
+    },
+    WriteMachineDeviceInfo,
+    /// CoE upload, download or object dictionary request to a SubDevice
+    Sdo {
+        subdevice_index: usize,
+    },
+}
+
+impl AuditAction {
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::MachineMutation => "MachineMutation",
+            Self::RecipeApply { .. } => "RecipeApply",
+            Self::WriteMachineDeviceInfo => "WriteMachineDeviceInfo",
+            Self::Sdo { .. } => "Sdo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "error")]
+pub enum AuditResult {
+    Accepted,
+    Rejected(String),
+    /// The request was sent but no result came back in time
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: u64,
+    /// Milliseconds since the unix epoch
+    pub timestamp_ms: u64,
+    /// Name of the authenticated user
+    pub client: String,
+    pub client_address: Option<String>,
+    pub action: AuditAction,
+    pub machine: Option<MachineIdentificationUnique>,
+    pub payload: Value,
+    pub result: AuditResult,
+}
+
+/// An entry which is not recorded yet, [`AuditLog::record`] assigns id and timestamp
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub client: String,
+    pub client_address: Option<String>,
+    pub action: AuditAction,
+    pub machine: Option<MachineIdentificationUnique>,
+    pub payload: Value,
+    pub result: AuditResult,
+}
+
+/// How long entries are kept, `None` keeps them forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRetention {
+    pub max_age_days: Option<u32>,
+    pub max_entries: Option<usize>,
+}
+
+impl Default for AuditRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(365),
+            max_entries: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Milliseconds since the unix epoch, inclusive
+    pub from: Option<u64>,
+    /// Milliseconds since the unix epoch, exclusive
+    pub to: Option<u64>,
+    pub client: Option<String>,
+    /// Machine type as in [`machines::machine_identification::MachineIdentification`]
+    pub vendor: Option<u16>,
+    pub machine: Option<u16>,
+    pub serial: Option<u16>,
+    /// Only the newest entries
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.from.is_none_or(|from| entry.timestamp_ms >= from)
+            && self.to.is_none_or(|to| entry.timestamp_ms < to)
+            && self
+                .client
+                .as_ref()
+                .is_none_or(|client| &entry.client == client)
+            && self.matches_machine(entry.machine.as_ref())
+    }
+
+    fn matches_machine(&self, machine: Option<&MachineIdentificationUnique>) -> bool {
+        if self.vendor.is_none() && self.machine.is_none() && self.serial.is_none() {
+            return true;
+        }
+        machine.is_some_and(|machine| {
+            self.vendor
+                .is_none_or(|vendor| machine.machine_identification.vendor == vendor)
+                && self
+                    .machine
+                    .is_none_or(|id| machine.machine_identification.machine == id)
+                && self.serial.is_none_or(|serial| machine.serial == serial)
+        })
+    }
+}
+
+#[derive(Debug)]
+struct AuditLogState {
+    next_id: u64,
+    retention: AuditRetention,
+    last_prune: Option<Instant>,
+}
+
+/// Append-only log of requests which change machines, one JSON line per [`AuditEntry`]
+#[derive(Debug)]
+pub struct AuditLog {
+    dir: PathBuf,
+    /// Serializes appends and rewrites of the file
+    state: Mutex<AuditLogState>,
+}
+
+impl AuditLog {
+    pub fn new(dir: PathBuf) -> Self {
+        let retention = read_retention(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read audit retention, using the default\n{:?}", e);
+            AuditRetention::default()
+        });
+        let next_id = read_entries(&dir.join(LOG_FILE))
+            .map(|entries| entries.last().map_or(1, |entry| entry.id + 1))
+            .unwrap_or(1);
+
+        Self {
+            dir,
+            state: Mutex::new(AuditLogState {
+                next_id,
+                retention,
+                last_prune: None,
+            }),
+        }
+    }
+
+    /// `$QITECH_AUDIT_DIR`, `$HOME/.qitech/audit` or `./audit`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var(AUDIT_DIR_ENV) {
+            return PathBuf::from(dir);
+        }
+        std::env::var("HOME").map_or_else(
+            |_| PathBuf::from("audit"),
+            |home| Path::new(&home).join(".qitech").join("audit"),
+        )
+    }
+
+    /// Appends the entry and syncs it to disk
+    pub fn record(&self, entry: NewAuditEntry) -> Result<AuditEntry> {
+        let mut state = self.state.lock().expect("audit lock poisoned");
+
+        let entry = AuditEntry {
+            id: state.next_id,
+            timestamp_ms: now_ms(),
+            client: entry.client,
+            client_address: entry.client_address,
+            action: entry.action,
+            machine: entry.machine,
+            payload: entry.payload,
+            result: entry.result,
+        };
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path())?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        state.next_id += 1;
+
+        if state
+            .last_prune
+            .is_none_or(|last_prune| last_prune.elapsed() >= PRUNE_INTERVAL)
+        {
+            self.prune(&mut state)?;
+        }
+        drop(state);
+
+        Ok(entry)
+    }
+
+    /// Logs instead of failing, a failed audit must not fail the request itself
+    pub fn record_or_log(&self, entry: NewAuditEntry) {
+        if let Err(e) = self.record(entry) {
+            tracing::error!(
+                "[{}::record_or_log] Failed to record audit entry\n{:?}",
+                module_path!(),
+                e
+            );
+        }
+    }
+
+    /// Matching entries, oldest first
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        let _state = self.state.lock().expect("audit lock poisoned");
+        let mut entries: Vec<AuditEntry> = read_entries(&self.log_path())?
+            .into_iter()
+            .filter(|entry| query.matches(entry))
+            .collect();
+        if let Some(limit) = query.limit {
+            entries.drain(..entries.len().saturating_sub(limit));
+        }
+        Ok(entries)
+    }
+
+    pub fn retention(&self) -> AuditRetention {
+        self.state.lock().expect("audit lock poisoned").retention
+    }
+
+    /// Stores the retention and drops the entries outside of it right away
+    pub fn set_retention(&self, retention: AuditRetention) -> Result<()> {
+        let mut state = self.state.lock().expect("audit lock poisoned");
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        std::fs::write(
+            self.dir.join(RETENTION_FILE),
+            serde_json::to_string_pretty(&retention)?,
+        )?;
+        state.retention = retention;
+        let res = self.prune(&mut state);
+        drop(state);
+        res
+    }
+
+    fn prune(&self, state: &mut AuditLogState) -> Result<()> {
+        state.last_prune = Some(Instant::now());
+
+        let path = self.log_path();
+        let mut entries = read_entries(&path)?;
+        let len = entries.len();
+        if let Some(max_age_days) = state.retention.max_age_days {
+            let oldest = now_ms().saturating_sub(u64::from(max_age_days) * MS_PER_DAY);
+            entries.retain(|entry| entry.timestamp_ms >= oldest);
+        }
+        if let Some(max_entries) = state.retention.max_entries {
+            entries.drain(..entries.len().saturating_sub(max_entries));
+        }
+        if entries.len() == len {
+            return Ok(());
+        }
+
+        // write next to the log and rename, so a crash leaves either the old or the new file
+        let tmp_path = path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp_path)?;
+        for entry in &entries {
+            serde_json::to_writer(&mut file, entry)?;
+            file.write_all(b"\n")?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        tracing::info!("Dropped {} audit entries", len - entries.len());
+        Ok(())
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE)
+    }
+}
+
+fn read_retention(dir: &Path) -> Result<AuditRetention> {
+    let path = dir.join(RETENTION_FILE);
+    if !path.exists() {
+        return Ok(AuditRetention::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Skips lines which can't be parsed, e.g. cut off by a power loss
+fn read_entries(path: &Path) -> Result<Vec<AuditEntry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    let mut entries = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => tracing::warn!("Skipping invalid audit entry: {}", e),
+        }
+    }
+    Ok(entries)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Entries as CSV with a header, nested values are written as JSON
+pub fn to_csv(entries: &[AuditEntry]) -> String {
+    let mut csv = String::from(
+        "id,timestamp_ms,client,client_address,action,recipe,machine_vendor,machine,machine_serial,payload,result,error\n",
+    );
+    for entry in entries {
+        let recipe = match &entry.action {
+            AuditAction::RecipeApply { name, version } => format!("{name} v{version}"),
+            _ => String::new(),
+        };
+        let (result, error) = match &entry.result {
+            AuditResult::Accepted => ("Accepted", ""),
+            AuditResult::Rejected(error) => ("Rejected", error.as_str()),
+            AuditResult::Unknown(error) => ("Unknown", error.as_str()),
+        };
+        let machine = entry.machine.as_ref();
+        let fields = [
+            entry.id.to_string(),
+            entry.timestamp_ms.to_string(),
+            entry.client.clone(),
+            entry.client_address.clone().unwrap_or_default(),
+            entry.action.name().to_string(),
+            recipe,
+            machine.map_or_else(String::new, |m| m.machine_identification.vendor.to_string()),
+            machine.map_or_else(String::new, |m| {
+                m.machine_identification.machine.to_string()
+            }),
+            machine.map_or_else(String::new, |m| m.serial.to_string()),
+            entry.payload.to_string(),
+            result.to_string(),
+            error.to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machines::machine_identification::MachineIdentification;
+    use machines::{MACHINE_WINDER_V1, VENDOR_QITECH};
+    use serde_json::json;
+
+    const WINDER: MachineIdentificationUnique = MachineIdentificationUnique {
+        machine_identification: MachineIdentification {
+            vendor: VENDOR_QITECH,
+            machine: MACHINE_WINDER_V1,
+        },
+        serial: 7,
+    };
+
+    fn temp_log(name: &str) -> AuditLog {
+        let dir = std::env::temp_dir().join(format!("audit-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        AuditLog::new(dir)
+    }
+
+    fn mutation(client: &str, payload: Value, result: AuditResult) -> NewAuditEntry {
+        NewAuditEntry {
+            client: client.to_string(),
+            client_address: None,
+            action: AuditAction::MachineMutation,
+            machine: Some(WINDER),
+            payload,
+            result,
+        }
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let log = temp_log("query");
+        log.record(mutation(
+            "line",
+            json!({ "SetPullerTargetSpeed": 10.0 }),
+            AuditResult::Accepted,
+        ))
+        .unwrap();
+        log.record(mutation(
+            "service",
+            json!({ "SetTraverseLimitOuter": 200.0 }),
+            AuditResult::Rejected("out of range".to_string()),
+        ))
+        .unwrap();
+        log.record(mutation(
+            "line",
+            json!({ "SetPullerTargetSpeed": 11.0 }),
+            AuditResult::Accepted,
+        ))
+        .unwrap();
+
+        let all = log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(all.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let other_serial = log
+            .query(&AuditQuery {
+                serial: Some(8),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(other_serial.is_empty());
+
+        let line = log
+            .query(&AuditQuery {
+                client: Some("line".to_string()),
+                machine: Some(MACHINE_WINDER_V1),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(line.len(), 1);
+        assert_eq!(line[0].payload, json!({ "SetPullerTargetSpeed": 11.0 }));
+
+        // ids continue after a restart
+        let reopened = AuditLog::new(log.dir.clone());
+        let entry = reopened
+            .record(mutation(
+                "line",
+                json!("ResetSpoolProgress"),
+                AuditResult::Accepted,
+            ))
+            .unwrap();
+        assert_eq!(entry.id, 4);
+
+        let _ = std::fs::remove_dir_all(&log.dir);
+    }
+
+    #[test]
+    fn test_retention() {
+        let log = temp_log("retention");
+        for i in 0..5 {
+            log.record(mutation(
+                "line",
+                json!({ "SetPullerTargetSpeed": i }),
+                AuditResult::Accepted,
+            ))
+            .unwrap();
+        }
+
+        log.set_retention(AuditRetention {
+            max_age_days: Some(30),
+            max_entries: Some(2),
+        })
+        .unwrap();
+        let ids: Vec<u64> = log
+            .query(&AuditQuery::default())
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![4, 5]);
+        assert_eq!(AuditLog::new(log.dir.clone()).retention(), log.retention());
+
+        let _ = std::fs::remove_dir_all(&log.dir);
+    }
+
+    #[test]
+    fn test_csv() {
+        let entry = AuditEntry {
+            id: 1,
+            timestamp_ms: 1000,
+            client: "line".to_string(),
+            client_address: Some("10.10.10.20".to_string()),
+            action: AuditAction::RecipeApply {
+                name: "PLA, black".to_string(),
+                version: 2,
+            },
+            machine: Some(WINDER),
+            payload: json!({ "SetPullerTargetSpeed": 10.0 }),
+            result: AuditResult::Rejected("out of range".to_string()),
+        };
+        let csv = to_csv(&[entry]);
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            format!(
+                "1,1000,line,10.10.10.20,RecipeApply,\"PLA, black v2\",{},{},7,\"{{\"\"SetPullerTargetSpeed\"\":10.0}}\",Rejected,out of range",
+                VENDOR_QITECH, MACHINE_WINDER_V1
+            )
+        );
+    }
+}
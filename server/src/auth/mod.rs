@@ -0,0 +1,385 @@
+use anyhow::{Context, Result};
+use machines::machine_identification::MachineIdentification;
+use machines::persistence::mutation_key;
+use machines::registry::MACHINE_REGISTRY;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable to override where the auth config is read from
+pub const AUTH_CONFIG_ENV: &str = "QITECH_AUTH_CONFIG";
+
+/// Environment variable which disables authentication when set to `1` or `true`
+pub const AUTH_DISABLED_ENV: &str = "QITECH_AUTH_DISABLED";
+
+/// Key of [`AuthConfig::mutation_roles`] which applies to every machine
+pub const ANY_MACHINE: &str = "*";
+
+/// What a user is allowed to do, every role includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read machines, state, recipes and metrics
+    Viewer,
+    /// Operate machines and apply recipes
+    Operator,
+    /// Save recipes and assign devices to machines
+    Technician,
+    Admin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Viewer => "viewer",
+            Self::Operator => "operator",
+            Self::Technician => "technician",
+            Self::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthUser {
+    pub name: String,
+    /// Sent as `Authorization: Bearer <token>`
+    pub token: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub users: Vec<AuthUser>,
+    /// Role of requests without a token, `None` rejects them
+    #[serde(default)]
+    pub anonymous_role: Option<Role>,
+    /// Role needed for a mutation variant by machine slug or [`ANY_MACHINE`]
+    ///
+    /// Mutations which are not listed need [`Role::Operator`].
+    #[serde(default)]
+    pub mutation_roles: HashMap<String, HashMap<String, Role>>,
+}
+
+/// Who sent a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+    /// Address the request came from, if known
+    pub address: Option<String>,
+}
+
+impl Principal {
+    pub fn require(&self, role: Role) -> Result<(), AuthError> {
+        match self.role >= role {
+            true => Ok(()),
+            false => Err(AuthError::Forbidden(format!(
+                "Needs the role {}, {} has {}",
+                role, self.name, self.role
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// Missing or unknown token
+    Unauthorized(String),
+    /// Known user without the needed role
+    Forbidden(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized(e) | Self::Forbidden(e) => f.write_str(e),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Authenticates tokens and decides which role a request needs
+///
+/// Without a config everyone is [`Role::Admin`], which is only used when authentication
+/// is disabled explicitly with [`AUTH_DISABLED_ENV`].
+#[derive(Debug)]
+pub struct Auth {
+    config: Option<AuthConfig>,
+}
+
+impl Auth {
+    pub const fn new(config: Option<AuthConfig>) -> Self {
+        Self { config }
+    }
+
+    /// `$QITECH_AUTH_CONFIG`, `$HOME/.qitech/auth.json` or `./auth.json`
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var(AUTH_CONFIG_ENV) {
+            return PathBuf::from(path);
+        }
+        std::env::var("HOME").map_or_else(
+            |_| PathBuf::from("auth.json"),
+            |home| Path::new(&home).join(".qitech").join("auth.json"),
+        )
+    }
+
+    /// Reads [`AUTH_DISABLED_ENV`] and the config at [`Self::default_path`]
+    pub fn from_env() -> Self {
+        let disabled = std::env::var(AUTH_DISABLED_ENV).ok();
+        Self::load(
+            &Self::default_path(),
+            matches!(disabled.as_deref(), Some("1" | "true")),
+        )
+    }
+
+    /// Reads the config at `path` unless authentication is `disabled`
+    ///
+    /// A config which is missing or can't be read rejects every request
+    /// instead of disabling authentication.
+    pub fn load(path: &Path, disabled: bool) -> Self {
+        if disabled {
+            tracing::warn!(
+                "Authentication is disabled by {}, every client is {}",
+                AUTH_DISABLED_ENV,
+                Role::Admin
+            );
+            return Self::new(None);
+        }
+
+        if !path.exists() {
+            tracing::error!(
+                "No auth config at {}, rejecting all requests. Create it or set {}=true to disable authentication",
+                path.display(),
+                AUTH_DISABLED_ENV
+            );
+            return Self::new(Some(AuthConfig::default()));
+        }
+
+        match Self::read(path) {
+            Ok(config) => {
+                tracing::info!(
+                    "Loaded auth config with {} users from {}",
+                    config.users.len(),
+                    path.display()
+                );
+                Self::new(Some(config))
+            }
+            Err(e) => {
+                tracing::error!(
+                    "[{}::load] Failed to read auth config, rejecting all requests\n{:?}",
+                    module_path!(),
+                    e
+                );
+                Self::new(Some(AuthConfig::default()))
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<AuthConfig> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn authenticate(&self, token: Option<&str>) -> Result<Principal, AuthError> {
+        let Some(config) = &self.config else {
+            return Ok(Principal {
+                name: "anonymous".to_string(),
+                role: Role::Admin,
+                address: None,
+            });
+        };
+
+        let Some(token) = token else {
+            return config
+                .anonymous_role
+                .map(|role| Principal {
+                    name: "anonymous".to_string(),
+                    role,
+                    address: None,
+                })
+                .ok_or_else(|| AuthError::Unauthorized("Missing token".to_string()));
+        };
+
+        config
+            .users
+            .iter()
+            .find(|user| constant_time_eq(user.token.as_bytes(), token.as_bytes()))
+            .map(|user| Principal {
+                name: user.name.clone(),
+                role: user.role,
+                address: None,
+            })
+            .ok_or_else(|| AuthError::Unauthorized("Unknown token".to_string()))
+    }
+
+    /// Role needed to send `mutation` to a machine of type `slug`
+    pub fn mutation_role(&self, slug: Option<&str>, mutation: &Value) -> Role {
+        let (Some(config), Some(variant)) = (&self.config, mutation_key(mutation)) else {
+            return Role::Operator;
+        };
+
+        slug.and_then(|slug| config.mutation_roles.get(slug))
+            .and_then(|roles| roles.get(&variant))
+            .or_else(|| {
+                config
+                    .mutation_roles
+                    .get(ANY_MACHINE)
+                    .and_then(|roles| roles.get(&variant))
+            })
+            .copied()
+            .unwrap_or(Role::Operator)
+    }
+
+    /// Checks every mutation for a machine of type `machine_identification`
+    pub fn authorize_mutations<'a>(
+        &self,
+        principal: &Principal,
+        machine_identification: &MachineIdentification,
+        mutations: impl IntoIterator<Item = &'a Value>,
+    ) -> Result<(), AuthError> {
+        let slug = MACHINE_REGISTRY
+            .get_descriptor(machine_identification)
+            .ok()
+            .map(|descriptor| descriptor.slug);
+        for mutation in mutations {
+            let role = self.mutation_role(slug, mutation);
+            if principal.role < role {
+                return Err(AuthError::Forbidden(format!(
+                    "Mutation {} needs the role {}, {} has {}",
+                    mutation_key(mutation).unwrap_or_default(),
+                    role,
+                    principal.name,
+                    principal.role
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares without returning early, so the time doesn't leak how much of a token matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machines::{MACHINE_WINDER_V1, VENDOR_QITECH};
+    use serde_json::json;
+
+    const WINDER: MachineIdentification = MachineIdentification {
+        vendor: VENDOR_QITECH,
+        machine: MACHINE_WINDER_V1,
+    };
+
+    fn auth() -> Auth {
+        let config: AuthConfig = serde_json::from_value(json!({
+            "users": [
+                { "name": "line", "token": "op-token", "role": "operator" },
+                { "name": "service", "token": "tech-token", "role": "technician" },
+            ],
+            "mutation_roles": {
+                "*": { "SetTemperaturePidSettings": "technician" },
+                "winder_v1": { "SetTraverseLimitOuter": "technician" },
+            },
+        }))
+        .unwrap();
+        Auth::new(Some(config))
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let auth = auth();
+        assert_eq!(
+            auth.authenticate(Some("op-token")).unwrap().role,
+            Role::Operator
+        );
+        assert!(matches!(
+            auth.authenticate(Some("op-tokens")),
+            Err(AuthError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            auth.authenticate(None),
+            Err(AuthError::Unauthorized(_))
+        ));
+
+        let disabled = Auth::new(None);
+        assert_eq!(disabled.authenticate(None).unwrap().role, Role::Admin);
+    }
+
+    #[test]
+    fn test_missing_config_rejects_requests() {
+        let path = std::env::temp_dir().join(format!("missing-auth-{}.json", std::process::id()));
+
+        let auth = Auth::load(&path, false);
+        assert!(matches!(
+            auth.authenticate(None),
+            Err(AuthError::Unauthorized(_))
+        ));
+
+        let disabled = Auth::load(&path, true);
+        assert_eq!(disabled.authenticate(None).unwrap().role, Role::Admin);
+    }
+
+    #[test]
+    fn test_anonymous_role() {
+        let auth = Auth::new(Some(AuthConfig {
+            anonymous_role: Some(Role::Viewer),
+            ..Default::default()
+        }));
+        let principal = auth.authenticate(None).unwrap();
+        assert!(principal.require(Role::Viewer).is_ok());
+        assert!(matches!(
+            principal.require(Role::Operator),
+            Err(AuthError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_mutation_roles() {
+        let auth = auth();
+        let pid = json!({ "SetTemperaturePidSettings": { "kp": 1.0 } });
+        let limit = json!({ "SetTraverseLimitOuter": 90.0 });
+        let speed = json!({ "SetPullerTargetSpeed": 10.0 });
+
+        assert_eq!(
+            auth.mutation_role(Some("extruder_v2"), &pid),
+            Role::Technician
+        );
+        assert_eq!(
+            auth.mutation_role(Some("winder_v1"), &limit),
+            Role::Technician
+        );
+        assert_eq!(
+            auth.mutation_role(Some("buffer_v1"), &limit),
+            Role::Operator
+        );
+        assert_eq!(auth.mutation_role(None, &speed), Role::Operator);
+
+        let operator = auth.authenticate(Some("op-token")).unwrap();
+        let technician = auth.authenticate(Some("tech-token")).unwrap();
+        assert!(
+            auth.authorize_mutations(&operator, &WINDER, [&speed])
+                .is_ok()
+        );
+        assert!(matches!(
+            auth.authorize_mutations(&operator, &WINDER, [&speed, &limit]),
+            Err(AuthError::Forbidden(_))
+        ));
+        assert!(
+            auth.authorize_mutations(&technician, &WINDER, [&speed, &limit])
+                .is_ok()
+        );
+    }
+}
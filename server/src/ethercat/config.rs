@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use ethercrab::PduStorage;
 
 /// Maximum number of SubDevices that can be stored. This must be a power of 2 greater than 1.
@@ -8,3 +10,5 @@ pub const MAX_PDU_DATA: usize = PduStorage::element_size(512);
 pub const MAX_FRAMES: usize = 16;
 /// Maximum total PDI length.
 pub const PDI_LEN: usize = 512;
+/// Cycle time of the RT loop, also the SYNC0 period with distributed clocks.
+pub const CYCLE_TARGET_TIME: Duration = Duration::from_micros(700);
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use ethercat_hal::dc::{
+    DC_SYSTEM_TIME, DcSyncMode, configure_sync0, cycle_start_offset,
+    dc_sync_mode_from_subdevice_identity_tuple, next_cycle_wait, read_system_time_difference,
+    set_sync_manager_mode,
+};
+use ethercat_hal::devices::subdevice_identity_to_tuple;
+use ethercrab::std::ethercat_now;
+use ethercrab::{Command, DcSupport, MainDevice, SubDevice, SubDeviceRef};
+
+use crate::app_state::EthercatSetup;
+use crate::ethercat::config::CYCLE_TARGET_TIME;
+use crate::metrics::histogram::DurationHistogram;
+
+/// Enables DC, `1` or `on`
+pub const DC_ENV: &str = "QITECH_ETHERCAT_DC";
+/// `subdevice` (default) or `host`, see [`DcReference`]
+pub const DC_REFERENCE_ENV: &str = "QITECH_ETHERCAT_DC_REFERENCE";
+/// Time from SYNC0 until the frame is sent, in microseconds
+pub const DC_SHIFT_ENV: &str = "QITECH_ETHERCAT_DC_SHIFT_US";
+
+/// Leaves the loop enough time to send the frame before outputs are latched on the next SYNC0
+const DEFAULT_SYNC0_SHIFT: Duration = Duration::from_micros(100);
+
+/// Time until the first SYNC0 pulse, has to cover the remaining setup of all SubDevices
+pub const SYNC0_START_DELAY: Duration = Duration::from_millis(100);
+
+/// Cycles with drift compensation before the group goes into OP
+pub const DRIFT_COMPENSATION_CYCLES: usize = 1000;
+
+/// One SubDevice is read per interval, so the reads don't add up in a single cycle
+const TIME_DIFFERENCE_READ_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Which clock the DC system time follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DcReference {
+    /// The first SubDevice with DC is the reference, the loop follows its clock
+    #[default]
+    SubDevice,
+    /// The reference SubDevice is adjusted to the clock of the host every cycle,
+    /// the loop keeps its own timing
+    Host,
+}
+
+/// DC configuration from the environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DcConfig {
+    pub enabled: bool,
+    pub reference: DcReference,
+    /// Time from SYNC0 until the loop sends the next frame
+    pub sync0_shift: Duration,
+}
+
+impl Default for DcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reference: DcReference::default(),
+            sync0_shift: DEFAULT_SYNC0_SHIFT,
+        }
+    }
+}
+
+impl DcConfig {
+    /// Reads [`DC_ENV`], [`DC_REFERENCE_ENV`] and [`DC_SHIFT_ENV`], invalid values are logged and ignored
+    pub fn from_env() -> Self {
+        let enabled = std::env::var(DC_ENV).ok();
+        let reference = std::env::var(DC_REFERENCE_ENV).ok();
+        let shift = std::env::var(DC_SHIFT_ENV).ok();
+        Self::parse(enabled.as_deref(), reference.as_deref(), shift.as_deref())
+    }
+
+    fn parse(enabled: Option<&str>, reference: Option<&str>, shift: Option<&str>) -> Self {
+        let mut config = Self {
+            enabled: matches!(enabled, Some("1" | "on" | "true")),
+            ..Self::default()
+        };
+        match reference {
+            None | Some("subdevice") => (),
+            Some("host") => config.reference = DcReference::Host,
+            Some(other) => tracing::warn!("Invalid {}: {}", DC_REFERENCE_ENV, other),
+        }
+        if let Some(shift) = shift {
+            match shift.parse::<u64>().map(Duration::from_micros) {
+                Ok(shift) if shift < CYCLE_TARGET_TIME => config.sync0_shift = shift,
+                _ => tracing::warn!(
+                    "Invalid {}: {}, it has to be less than the cycle time of {:?}",
+                    DC_SHIFT_ENV,
+                    shift,
+                    CYCLE_TARGET_TIME
+                ),
+            }
+        }
+        config
+    }
+}
+
+/// DC state of a running [`EthercatSetup`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthercatDc {
+    pub reference: DcReference,
+    /// Configured address of the reference SubDevice
+    pub reference_address: u16,
+    pub sync0_period: Duration,
+    pub sync0_shift: Duration,
+    /// Indices of the SubDevices which latch their process data on SYNC0
+    pub synchronised: Vec<usize>,
+}
+
+impl EthercatDc {
+    /// Adjusts the reference SubDevice to the clock of the host, only for [`DcReference::Host`]
+    pub async fn write_host_time(&self, maindevice: &MainDevice<'_>) -> Result<(), anyhow::Error> {
+        if self.reference == DcReference::Host {
+            Command::fpwr(self.reference_address, DC_SYSTEM_TIME)
+                .send(maindevice, ethercat_now())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// When the next cycle should start, measured from the start of the cycle which read `dc_system_time`
+    ///
+    /// With [`DcReference::Host`] the loop keeps its own timing.
+    pub fn next_cycle_wait(&self, dc_system_time: u64) -> Option<Duration> {
+        match self.reference {
+            DcReference::SubDevice => Some(next_cycle_wait(
+                dc_system_time,
+                self.sync0_period,
+                self.sync0_shift,
+            )),
+            DcReference::Host => None,
+        }
+    }
+}
+
+/// Starts SYNC0 on all SubDevices whose type wants it, has to be done in PRE-OP
+///
+/// Returns `None` if there is no DC reference or no SubDevice to synchronise, the bus then runs in free run.
+/// SubDevices which fail to be configured stay in free run.
+pub async fn configure_dc<S>(
+    subdevices: &[SubDeviceRef<'_, S>],
+    config: &DcConfig,
+) -> Option<EthercatDc>
+where
+    S: std::ops::Deref<Target = SubDevice> + Sync,
+{
+    // ethercrab aligns all clocks to the first SubDevice with DC and uses it as reference
+    let Some(reference) = subdevices
+        .iter()
+        .find(|subdevice| subdevice.dc_support().any())
+    else {
+        tracing::warn!("DC is enabled but no SubDevice supports it");
+        return None;
+    };
+
+    let mut synchronised = vec![];
+    for (subdevice_index, subdevice) in subdevices.iter().enumerate() {
+        let mode = dc_sync_mode_from_subdevice_identity_tuple(subdevice_identity_to_tuple(
+            &subdevice.identity(),
+        ));
+        // ESCs with `RefOnly` have a clock but no SYNC units
+        if mode != DcSyncMode::Sync0
+            || !matches!(
+                subdevice.dc_support(),
+                DcSupport::Bits32 | DcSupport::Bits64
+            )
+        {
+            continue;
+        }
+        if let Err(e) = set_sync_manager_mode(subdevice, mode).await {
+            tracing::warn!(
+                "SubDevice {} ({}) keeps its sync mode: {:?}",
+                subdevice_index,
+                subdevice.name(),
+                e
+            );
+        }
+        match configure_sync0(subdevice, SYNC0_START_DELAY, CYCLE_TARGET_TIME).await {
+            Ok(()) => synchronised.push(subdevice_index),
+            Err(e) => tracing::error!(
+                "Failed to configure SYNC0 of SubDevice {} ({}), it stays in free run: {:?}",
+                subdevice_index,
+                subdevice.name(),
+                e
+            ),
+        }
+    }
+
+    if synchronised.is_empty() {
+        tracing::info!("DC is enabled but no SubDevice uses SYNC0");
+        return None;
+    }
+    tracing::info!(
+        "SYNC0 every {:?} on SubDevices {:?}, reference {:#06x} ({:?})",
+        CYCLE_TARGET_TIME,
+        synchronised,
+        reference.configured_address(),
+        config.reference
+    );
+    Some(EthercatDc {
+        reference: config.reference,
+        reference_address: reference.configured_address(),
+        sync0_period: CYCLE_TARGET_TIME,
+        sync0_shift: config.sync0_shift,
+        synchronised,
+    })
+}
+
+/// Offset of the frame from the last SYNC0 pulse when it passed the reference
+pub static DC_CYCLE_OFFSET: DurationHistogram = DurationHistogram::new();
+
+/// SubDevice index to its name and last system time difference in nanoseconds
+static TIME_DIFFERENCES: Mutex<BTreeMap<usize, (String, i64)>> = Mutex::new(BTreeMap::new());
+
+/// Last system time difference of every synchronised SubDevice, by SubDevice index
+pub fn time_differences() -> Vec<(usize, String, i64)> {
+    TIME_DIFFERENCES
+        .lock()
+        .expect("dc lock poisoned")
+        .iter()
+        .map(|(index, (name, difference))| (*index, name.clone(), *difference))
+        .collect()
+}
+
+/// Monitors the DC synchronisation from the loop thread
+///
+/// - records the offset of every cycle from SYNC0
+/// - reads the system time difference (`0x092C`) of one synchronised SubDevice every [`TIME_DIFFERENCE_READ_INTERVAL`],
+///   on a task next to the loop thread which publishes it to [`time_differences`]
+#[derive(Debug, Default)]
+pub struct DcMonitor {
+    initialized: bool,
+    next_subdevice: usize,
+    last_read: Option<Instant>,
+    read: Option<smol::Task<()>>,
+}
+
+impl DcMonitor {
+    pub fn record_cycle(&self, dc: &EthercatDc, dc_system_time: u64) {
+        DC_CYCLE_OFFSET.observe(cycle_start_offset(dc_system_time, dc.sync0_period));
+    }
+
+    pub fn poll(&mut self, setup: &EthercatSetup, now: Instant) {
+        if !self.initialized {
+            self.initialized = true;
+            // differences of the previous setup
+            TIME_DIFFERENCES.lock().expect("dc lock poisoned").clear();
+        }
+        let Some(dc) = &setup.dc else {
+            return;
+        };
+        if self.read.as_ref().is_some_and(|read| !read.is_finished()) {
+            return;
+        }
+        if dc.synchronised.is_empty()
+            || self
+                .last_read
+                .is_some_and(|last| now.duration_since(last) < TIME_DIFFERENCE_READ_INTERVAL)
+        {
+            return;
+        }
+        self.last_read = Some(now);
+
+        let subdevice_index = dc.synchronised[self.next_subdevice % dc.synchronised.len()];
+        self.next_subdevice = (self.next_subdevice + 1) % dc.synchronised.len();
+        let maindevice = setup.maindevice;
+        let group = setup.group.clone();
+        self.read = Some(smol::spawn(async move {
+            let Ok(subdevice) = group.subdevice(maindevice, subdevice_index) else {
+                return;
+            };
+            match read_system_time_difference(&subdevice).await {
+                Ok(difference) => {
+                    TIME_DIFFERENCES
+                        .lock()
+                        .expect("dc lock poisoned")
+                        .insert(subdevice_index, (subdevice.name().to_string(), difference));
+                }
+                Err(e) => tracing::debug!(
+                    "Failed to read the system time difference of SubDevice {}: {:?}",
+                    subdevice_index,
+                    e
+                ),
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(DcConfig::parse(None, None, None), DcConfig::default());
+
+        let config = DcConfig::parse(Some("on"), Some("host"), Some("150"));
+        assert!(config.enabled);
+        assert_eq!(config.reference, DcReference::Host);
+        assert_eq!(config.sync0_shift, Duration::from_micros(150));
+
+        // invalid values fall back to the defaults
+        let config = DcConfig::parse(Some("1"), Some("master"), Some("5000"));
+        assert!(config.enabled);
+        assert_eq!(config.reference, DcReference::SubDevice);
+        assert_eq!(config.sync0_shift, DEFAULT_SYNC0_SHIFT);
+    }
+
+    #[test]
+    fn test_next_cycle_wait() {
+        let dc = EthercatDc {
+            reference: DcReference::SubDevice,
+            reference_address: 0x1000,
+            sync0_period: Duration::from_micros(700),
+            sync0_shift: Duration::from_micros(100),
+            synchronised: vec![2],
+        };
+        // the frame passed the reference 150µs after SYNC0, 50µs late
+        assert_eq!(
+            dc.next_cycle_wait(7_000_000 + 150_000),
+            Some(Duration::from_micros(650))
+        );
+        let dc = EthercatDc {
+            reference: DcReference::Host,
+            ..dc
+        };
+        assert_eq!(dc.next_cycle_wait(7_150_000), None);
+    }
+}
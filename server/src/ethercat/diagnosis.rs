@@ -0,0 +1,425 @@
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use control_core::socketio::namespace::NamespaceCacheingLogic;
+use ethercat_hal::debugging::diagnosis_history::{
+    DiagnosisHistoryInfo, read_diagnosis_history_info, read_diagnosis_message,
+    read_newest_message_index,
+};
+use ethercrab::error::{Error, MailboxError};
+use ethercrab::{MainDevice, SubDevice, SubDeviceGroup, SubDeviceRef, subdevice_group::Op};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::SharedState,
+    ethercat::config::{MAX_SUBDEVICES, PDI_LEN},
+    history::recorder::now_ms,
+    socketio::main_namespace::{
+        MainNamespaceEvents, ethercat_diagnosis_event::EthercatDiagnosisEvent,
+    },
+};
+
+/// Pause between two passes checking every SubDevice for new messages
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Entries kept in memory, older ones are dropped
+const MAX_LOG_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosisSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<ethercat_hal::debugging::diagnosis_history::DiagnosisSeverity> for DiagnosisSeverity {
+    fn from(severity: ethercat_hal::debugging::diagnosis_history::DiagnosisSeverity) -> Self {
+        use ethercat_hal::debugging::diagnosis_history::DiagnosisSeverity as Hal;
+        match severity {
+            Hal::Info => Self::Info,
+            Hal::Warning => Self::Warning,
+            Hal::Error => Self::Error,
+        }
+    }
+}
+
+/// One message of the diagnosis history (`0x10F3`) of a SubDevice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosisLogEntry {
+    /// Increasing, assigned when the entry is added to the [`DiagnosisLog`]
+    pub id: u64,
+    pub subdevice_index: usize,
+    pub configured_address: u16,
+    pub subdevice_name: String,
+    pub severity: DiagnosisSeverity,
+    pub diag_code: u32,
+    pub text_id: u16,
+    /// Text of the message with its parameters filled in
+    pub message: String,
+    /// Nanoseconds of the DC system time since 2000-01-01, or of the local clock of the SubDevice
+    pub timestamp_ns: u64,
+    /// `timestamp_ns` is from the local clock of the SubDevice
+    pub local_timestamp: bool,
+    /// Milliseconds since the unix epoch when the entry was read
+    pub received_ms: u64,
+}
+
+impl DiagnosisLogEntry {
+    /// Same message of the same SubDevice, regardless of when it was read
+    fn same_message(&self, other: &Self) -> bool {
+        self.configured_address == other.configured_address
+            && self.subdevice_name == other.subdevice_name
+            && self.timestamp_ns == other.timestamp_ns
+            && self.diag_code == other.diag_code
+            && self.text_id == other.text_id
+            && self.message == other.message
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiagnosisQuery {
+    /// Only entries with at least this severity
+    pub min_severity: Option<DiagnosisSeverity>,
+    pub configured_address: Option<u16>,
+    /// Only entries with a greater id, to poll for new entries
+    pub since_id: Option<u64>,
+    /// Only the newest entries
+    pub limit: Option<usize>,
+}
+
+impl DiagnosisQuery {
+    fn matches(&self, entry: &DiagnosisLogEntry) -> bool {
+        self.min_severity
+            .is_none_or(|severity| entry.severity >= severity)
+            && self
+                .configured_address
+                .is_none_or(|address| entry.configured_address == address)
+            && self.since_id.is_none_or(|id| entry.id > id)
+    }
+}
+
+#[derive(Debug, Default)]
+struct DiagnosisLogState {
+    entries: VecDeque<DiagnosisLogEntry>,
+    next_id: u64,
+}
+
+/// Diagnosis messages of all SubDevices, oldest first
+///
+/// Kept in memory, the SubDevices keep their own history anyways.
+#[derive(Debug, Default)]
+pub struct DiagnosisLog {
+    state: Mutex<DiagnosisLogState>,
+}
+
+impl DiagnosisLog {
+    /// Adds the entries which are not in the log yet and returns them with their id
+    pub fn append(&self, entries: Vec<DiagnosisLogEntry>) -> Vec<DiagnosisLogEntry> {
+        let mut state = self.state.lock().expect("diagnosis lock poisoned");
+        let mut added = vec![];
+        for mut entry in entries {
+            if state
+                .entries
+                .iter()
+                .any(|existing| existing.same_message(&entry))
+            {
+                continue;
+            }
+            entry.id = state.next_id;
+            state.next_id += 1;
+            if state.entries.len() >= MAX_LOG_ENTRIES {
+                state.entries.pop_front();
+            }
+            state.entries.push_back(entry.clone());
+            added.push(entry);
+        }
+        drop(state);
+        added
+    }
+
+    pub fn query(&self, query: &DiagnosisQuery) -> Vec<DiagnosisLogEntry> {
+        let state = self.state.lock().expect("diagnosis lock poisoned");
+        let mut entries: Vec<DiagnosisLogEntry> = state
+            .entries
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect();
+        drop(state);
+        if let Some(limit) = query.limit {
+            entries.drain(..entries.len().saturating_sub(limit));
+        }
+        entries
+    }
+}
+
+/// Identifies a message in the ring buffer of one SubDevice
+type MessageKey = (u64, u32, u16, String);
+
+#[derive(Debug)]
+enum HistoryState {
+    /// Not read yet or the last read failed
+    Unknown,
+    /// The SubDevice has no mailbox or no diagnosis history
+    Unsupported,
+    Supported {
+        info: DiagnosisHistoryInfo,
+        /// Newest message of the last complete walk
+        newest_walked: u8,
+        known: VecDeque<MessageKey>,
+    },
+}
+
+/// Collects the diagnosis history of every SubDevice of `group` until the task is dropped
+///
+/// Runs next to the loop thread, the mailbox reads share the bus with the cyclic tx/rx.
+/// Every [`SCAN_INTERVAL`] each SubDevice is asked for its newest message. If it changed,
+/// the ring buffer is walked back until a message which was already seen.
+/// SubDevices without diagnosis history are skipped after the first try.
+/// New messages are added to [`SharedState::ethercat_diagnosis`] and sent as [`EthercatDiagnosisEvent`].
+pub async fn collect_diagnosis(
+    app_state: Arc<SharedState>,
+    maindevice: &'static MainDevice<'static>,
+    group: Arc<SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>>,
+) {
+    let mut subdevices = (0..group.len())
+        .map(|_| HistoryState::Unknown)
+        .collect::<Vec<_>>();
+    loop {
+        let mut found = vec![];
+        for (subdevice_index, state) in subdevices.iter_mut().enumerate() {
+            let subdevice = match group.subdevice(maindevice, subdevice_index) {
+                Ok(subdevice) => subdevice,
+                Err(e) => {
+                    tracing::debug!("Failed to get SubDevice {}: {:?}", subdevice_index, e);
+                    continue;
+                }
+            };
+            found.extend(collect_subdevice(&subdevice, subdevice_index, state).await);
+        }
+        if !found.is_empty() {
+            publish(&app_state, found).await;
+        }
+        smol::Timer::after(SCAN_INTERVAL).await;
+    }
+}
+
+/// New messages of one SubDevice, oldest first
+async fn collect_subdevice<S: Deref<Target = SubDevice> + Sync>(
+    subdevice: &SubDeviceRef<'_, S>,
+    subdevice_index: usize,
+    state: &mut HistoryState,
+) -> Vec<DiagnosisLogEntry> {
+    let newest_message = match state {
+        HistoryState::Unknown => match read_diagnosis_history_info(subdevice).await {
+            Ok(Some(info)) => {
+                *state = HistoryState::Supported {
+                    info,
+                    newest_walked: 0,
+                    known: VecDeque::new(),
+                };
+                info.newest_message
+            }
+            Ok(None)
+            | Err(Error::Mailbox(MailboxError::Aborted { .. } | MailboxError::NoMailbox)) => {
+                *state = HistoryState::Unsupported;
+                return vec![];
+            }
+            Err(e) => {
+                tracing::debug!(
+                    "Failed to read diagnosis history of SubDevice {}: {:?}",
+                    subdevice_index,
+                    e
+                );
+                return vec![];
+            }
+        },
+        HistoryState::Supported { .. } => match read_newest_message_index(subdevice).await {
+            Ok(newest_message) => newest_message,
+            Err(e) => {
+                tracing::debug!(
+                    "Failed to read newest diagnosis message of SubDevice {}: {:?}",
+                    subdevice_index,
+                    e
+                );
+                return vec![];
+            }
+        },
+        HistoryState::Unsupported => return vec![],
+    };
+
+    let HistoryState::Supported {
+        info,
+        newest_walked,
+        known,
+    } = state
+    else {
+        return vec![];
+    };
+    if newest_message == 0 || newest_message == *newest_walked {
+        return vec![];
+    }
+
+    // from the newest message back to a known one
+    let mut found = vec![];
+    let mut complete = true;
+    let mut sub_index = newest_message;
+    for _ in 0..info.maximum_messages {
+        let message = match read_diagnosis_message(subdevice, sub_index).await {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::debug!(
+                    "Failed to read diagnosis message {} of SubDevice {}: {:?}",
+                    sub_index,
+                    subdevice_index,
+                    e
+                );
+                complete = false;
+                break;
+            }
+        };
+        if message.is_empty() {
+            break;
+        }
+
+        let entry = DiagnosisLogEntry {
+            id: 0,
+            subdevice_index,
+            configured_address: subdevice.configured_address(),
+            subdevice_name: subdevice.name().to_string(),
+            severity: message.severity().into(),
+            diag_code: message.diag_code,
+            text_id: message.text_id,
+            message: message.text(subdevice.identity().vendor_id),
+            timestamp_ns: message.timestamp,
+            local_timestamp: message.has_local_timestamp(),
+            received_ms: now_ms(),
+        };
+        if known.contains(&message_key(&entry)) {
+            break;
+        }
+        found.push(entry);
+        sub_index = info.previous_message(sub_index);
+    }
+    found.reverse();
+
+    // an incomplete walk is repeated in the next pass
+    if complete {
+        *newest_walked = newest_message;
+        known.extend(found.iter().map(message_key));
+        // the ring buffer can't hold more, older ones can't show up again
+        let max_known = usize::from(info.maximum_messages) * 2;
+        known.drain(..known.len().saturating_sub(max_known));
+    }
+    found
+}
+
+fn message_key(entry: &DiagnosisLogEntry) -> MessageKey {
+    (
+        entry.timestamp_ns,
+        entry.diag_code,
+        entry.text_id,
+        entry.message.clone(),
+    )
+}
+
+async fn publish(app_state: &SharedState, entries: Vec<DiagnosisLogEntry>) {
+    let entries = app_state.ethercat_diagnosis.append(entries);
+    if entries.is_empty() {
+        return;
+    }
+    for entry in &entries {
+        tracing::info!(
+            "Diagnosis of SubDevice {} ({}): {:?} {}",
+            entry.subdevice_index,
+            entry.subdevice_name,
+            entry.severity,
+            entry.message
+        );
+    }
+    let main_namespace = &mut app_state
+        .socketio_setup
+        .namespaces
+        .write()
+        .await
+        .main_namespace;
+    main_namespace.emit(MainNamespaceEvents::EthercatDiagnosisEvent(
+        EthercatDiagnosisEvent { entries }.build(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        configured_address: u16,
+        severity: DiagnosisSeverity,
+        timestamp_ns: u64,
+    ) -> DiagnosisLogEntry {
+        DiagnosisLogEntry {
+            id: 0,
+            subdevice_index: 1,
+            configured_address,
+            subdevice_name: "EL3204".to_string(),
+            severity,
+            diag_code: 0xE800,
+            text_id: 0x4101,
+            message: "Terminal-Overtemperature".to_string(),
+            timestamp_ns,
+            local_timestamp: false,
+            received_ms: 1_718_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_append_deduplicates() {
+        let log = DiagnosisLog::default();
+        let added = log.append(vec![
+            entry(0x1001, DiagnosisSeverity::Info, 1),
+            entry(0x1001, DiagnosisSeverity::Warning, 2),
+        ]);
+        assert_eq!(added.iter().map(|e| e.id).collect::<Vec<_>>(), vec![0, 1]);
+
+        // read again after the bus was initialized again
+        let mut again = entry(0x1001, DiagnosisSeverity::Warning, 2);
+        again.received_ms += 60_000;
+        let added = log.append(vec![again, entry(0x1002, DiagnosisSeverity::Error, 2)]);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].id, 2);
+        assert_eq!(added[0].configured_address, 0x1002);
+    }
+
+    #[test]
+    fn test_query() {
+        let log = DiagnosisLog::default();
+        log.append(vec![
+            entry(0x1001, DiagnosisSeverity::Info, 1),
+            entry(0x1001, DiagnosisSeverity::Warning, 2),
+            entry(0x1002, DiagnosisSeverity::Error, 3),
+        ]);
+
+        let warnings = log.query(&DiagnosisQuery {
+            min_severity: Some(DiagnosisSeverity::Warning),
+            ..Default::default()
+        });
+        assert_eq!(warnings.len(), 2);
+
+        let subdevice = log.query(&DiagnosisQuery {
+            configured_address: Some(0x1001),
+            since_id: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(subdevice.len(), 1);
+        assert_eq!(subdevice[0].id, 1);
+
+        let newest = log.query(&DiagnosisQuery {
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(newest[0].id, 2);
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use control_core::socketio::namespace::NamespaceCacheingLogic;
+
+use crate::{
+    app_state::SharedState,
+    socketio::main_namespace::{MainNamespaceEvents, ethercat_fault_event::EthercatFaultEvent},
+};
+
+/// Consecutive cycles with a transient error before the loop faults
+pub const MAX_CONSECUTIVE_TRANSIENT_ERRORS: u32 = 10;
+
+/// How long the safe outputs are written after a fault before the process exits
+///
+/// Long enough for queued serial requests, like stopping an inverter, to go out.
+pub const SAFE_STOP_DURATION: Duration = Duration::from_millis(500);
+
+/// How long to wait for the fault event to reach the clients
+const FAULT_EVENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Errors which are retried in the next cycle instead of faulting the loop
+///
+/// Single frames can get lost or arrive with a wrong working counter,
+/// e.g. through EMI or while a SubDevice is busy.
+pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ethercrab::error::Error>(),
+        Some(
+            ethercrab::error::Error::WorkingCounter { .. }
+                | ethercrab::error::Error::Timeout
+                | ethercrab::error::Error::Pdu(_)
+                | ethercrab::error::Error::ReceiveFrame
+        )
+    )
+}
+
+/// Emits an [`EthercatFaultEvent`] and waits until the socket queue is flushed
+pub async fn send_ethercat_fault(app_state: Arc<SharedState>, event: EthercatFaultEvent) {
+    {
+        let main_namespace = &mut app_state
+            .socketio_setup
+            .namespaces
+            .write()
+            .await
+            .main_namespace;
+        main_namespace.emit(MainNamespaceEvents::EthercatFaultEvent(event.build()));
+    }
+
+    let start = Instant::now();
+    while !app_state.socketio_setup.socket_queue_tx.is_empty()
+        && start.elapsed() < FAULT_EVENT_TIMEOUT
+    {
+        smol::Timer::after(Duration::from_millis(10)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_is_transient_error() {
+        let wkc: Result<(), ethercrab::error::Error> =
+            Err(ethercrab::error::Error::WorkingCounter {
+                expected: 3,
+                received: 2,
+            });
+        let wkc = wkc.context("copy_ethercat_inputs failed").unwrap_err();
+        assert!(is_transient_error(&wkc));
+
+        let timeout = anyhow::Error::from(ethercrab::error::Error::Timeout);
+        assert!(is_transient_error(&timeout));
+
+        let device = anyhow::anyhow!("SubDevice with index 3 failed to copy inputs");
+        assert!(!is_transient_error(&device));
+
+        let send = anyhow::Error::from(ethercrab::error::Error::SendFrame);
+        assert!(!is_transient_error(&send));
+    }
+}
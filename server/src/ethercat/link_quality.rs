@@ -0,0 +1,276 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use control_core::socketio::namespace::NamespaceCacheingLogic;
+use ethercrab::{AlStatusCode, MainDevice, SubDeviceGroup, SubDeviceState, subdevice_group::Op};
+
+use crate::{
+    app_state::{EthercatSetup, SharedState},
+    ethercat::config::{MAX_SUBDEVICES, PDI_LEN},
+    history::recorder::now_ms,
+    socketio::main_namespace::{
+        MainNamespaceEvents,
+        ethercat_link_quality_event::{
+            EthercatLinkQualityEvent, PortErrorCounters, SubDeviceLinkQuality,
+        },
+    },
+};
+
+/// One SubDevice is read per interval, so the reads don't add up in a single cycle
+const SUBDEVICE_READ_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the statistics are published to the REST API and socket.io
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// First ESC error counter register, RX error counters of port 0
+const ESC_ERROR_COUNTERS: u16 = 0x0300;
+
+/// Registers `0x0300` to `0x0313`
+type EscErrorCounters = [u8; 0x14];
+
+type LinkRegisters = (SubDeviceState, AlStatusCode, EscErrorCounters);
+
+/// Collects the link quality of the EtherCAT bus from the loop thread
+///
+/// - validates the working counter of every tx/rx
+/// - counts timed out and lost frames
+/// - reads AL status and ESC error counters of one SubDevice every [`SUBDEVICE_READ_INTERVAL`],
+///   on a task next to the loop thread
+#[derive(Debug, Default)]
+pub struct LinkMonitor {
+    stats: EthercatLinkQualityEvent,
+    initialized: bool,
+    next_subdevice: usize,
+    last_read: Option<Instant>,
+    /// Returns the registers of the SubDevice with the index
+    read: Option<smol::Task<(usize, Result<LinkRegisters, ethercrab::error::Error>)>>,
+    last_publish: Option<Instant>,
+    published_mismatches: u64,
+}
+
+impl LinkMonitor {
+    pub fn check_working_counter(&mut self, setup: &EthercatSetup, working_counter: u16) {
+        if !self.initialized {
+            self.initialize(setup);
+        }
+        self.stats.cycles += 1;
+        self.stats.last_working_counter = working_counter;
+        if working_counter != self.stats.expected_working_counter {
+            self.stats.working_counter_mismatches += 1;
+        }
+    }
+
+    /// Counts a failed cycle as timed out or lost frame
+    pub fn count_error(&mut self, error: &anyhow::Error) {
+        match error.downcast_ref::<ethercrab::error::Error>() {
+            Some(ethercrab::error::Error::Timeout) => self.stats.timed_out_frames += 1,
+            Some(ethercrab::error::Error::Pdu(_) | ethercrab::error::Error::ReceiveFrame) => {
+                self.stats.lost_frames += 1;
+            }
+            _ => (),
+        }
+    }
+
+    /// Reads the next SubDevice and publishes the statistics when they are due
+    pub fn poll(&mut self, setup: &EthercatSetup, app_state: &Arc<SharedState>, now: Instant) {
+        if !self.initialized {
+            return;
+        }
+
+        // finished tasks return right away
+        if let Some(read) = self.read.take_if(|read| read.is_finished()) {
+            let (subdevice_index, result) = smol::block_on(read);
+            self.apply_link_registers(subdevice_index, result);
+        }
+        if self.read.is_none()
+            && self
+                .last_read
+                .is_none_or(|last| now.duration_since(last) >= SUBDEVICE_READ_INTERVAL)
+        {
+            self.last_read = Some(now);
+            self.read_subdevice(setup);
+        }
+
+        if self
+            .last_publish
+            .is_none_or(|last| now.duration_since(last) >= PUBLISH_INTERVAL)
+        {
+            self.last_publish = Some(now);
+            self.publish(app_state);
+        }
+    }
+
+    fn initialize(&mut self, setup: &EthercatSetup) {
+        self.initialized = true;
+        // LRW: reading the inputs increments by 1, writing the outputs by 2
+        self.stats.expected_working_counter = setup
+            .group
+            .iter(setup.maindevice)
+            .map(|subdevice| {
+                u16::from(!subdevice.inputs_raw().is_empty())
+                    + 2 * u16::from(!subdevice.outputs_raw().is_empty())
+            })
+            .sum();
+        self.stats.subdevices = setup
+            .group
+            .iter(setup.maindevice)
+            .enumerate()
+            .map(|(subdevice_index, subdevice)| SubDeviceLinkQuality {
+                subdevice_index,
+                configured_address: subdevice.configured_address(),
+                name: subdevice.name().to_string(),
+                al_state: None,
+                al_status_code: None,
+                ports: vec![PortErrorCounters::default(); 4],
+                processing_unit_errors: 0,
+                pdi_errors: 0,
+                last_read_ms: None,
+            })
+            .collect();
+    }
+
+    fn read_subdevice(&mut self, setup: &EthercatSetup) {
+        if self.stats.subdevices.is_empty() {
+            return;
+        }
+        let subdevice_index = self.next_subdevice % self.stats.subdevices.len();
+        self.next_subdevice = subdevice_index + 1;
+
+        let maindevice = setup.maindevice;
+        let group = setup.group.clone();
+        self.read = Some(smol::spawn(async move {
+            let result = read_link_registers(maindevice, &group, subdevice_index).await;
+            (subdevice_index, result)
+        }));
+    }
+
+    fn apply_link_registers(
+        &mut self,
+        subdevice_index: usize,
+        result: Result<LinkRegisters, ethercrab::error::Error>,
+    ) {
+        match result {
+            Ok((state, code, counters)) => {
+                let stats = &mut self.stats.subdevices[subdevice_index];
+                stats.al_state = Some(state.to_string());
+                stats.al_status_code = Some(code.to_string());
+                apply_error_counters(stats, &counters);
+                stats.last_read_ms = Some(now_ms());
+            }
+            Err(e) => tracing::debug!(
+                "Failed to read link quality of SubDevice {}: {:?}",
+                subdevice_index,
+                e
+            ),
+        }
+    }
+
+    fn publish(&mut self, app_state: &Arc<SharedState>) {
+        let mismatches = self.stats.working_counter_mismatches - self.published_mismatches;
+        if mismatches > 0 {
+            tracing::warn!(
+                "Working counter was {} instead of {} in {} cycles",
+                self.stats.last_working_counter,
+                self.stats.expected_working_counter,
+                mismatches
+            );
+        }
+        self.published_mismatches = self.stats.working_counter_mismatches;
+
+        let stats = self.stats.clone();
+        let app_state = app_state.clone();
+        smol::spawn(async move {
+            *app_state.ethercat_link_quality.write().await = Some(stats.clone());
+            let main_namespace = &mut app_state
+                .socketio_setup
+                .namespaces
+                .write()
+                .await
+                .main_namespace;
+            main_namespace.emit(MainNamespaceEvents::EthercatLinkQualityEvent(stats.build()));
+        })
+        .detach();
+    }
+}
+
+async fn read_link_registers(
+    maindevice: &MainDevice<'_>,
+    group: &SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>,
+    subdevice_index: usize,
+) -> Result<LinkRegisters, ethercrab::error::Error> {
+    let subdevice = group.subdevice(maindevice, subdevice_index)?;
+    let ((state, code), counters) = smol::future::try_zip(
+        subdevice.status(),
+        subdevice.register_read::<EscErrorCounters>(ESC_ERROR_COUNTERS),
+    )
+    .await?;
+    Ok((state, code, counters))
+}
+
+/// Layout of the ESC error counters, see the EtherCAT slave controller datasheet section II
+fn apply_error_counters(stats: &mut SubDeviceLinkQuality, counters: &EscErrorCounters) {
+    stats.ports = (0..4)
+        .map(|port| PortErrorCounters {
+            invalid_frames: counters[2 * port],
+            rx_errors: counters[2 * port + 1],
+            forwarded_rx_errors: counters[0x08 + port],
+            lost_links: counters[0x10 + port],
+        })
+        .collect();
+    stats.processing_unit_errors = counters[0x0C];
+    stats.pdi_errors = counters[0x0D];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_error_counters() {
+        let mut stats = SubDeviceLinkQuality {
+            subdevice_index: 0,
+            configured_address: 0x1000,
+            name: "EL2008".to_string(),
+            al_state: None,
+            al_status_code: None,
+            ports: vec![],
+            processing_unit_errors: 0,
+            pdi_errors: 0,
+            last_read_ms: None,
+        };
+        let mut counters = EscErrorCounters::default();
+        // port 1: invalid frame, rx error, forwarded error, lost link
+        counters[0x02] = 1;
+        counters[0x03] = 2;
+        counters[0x09] = 3;
+        counters[0x11] = 4;
+        counters[0x0C] = 5;
+        counters[0x0D] = 6;
+
+        apply_error_counters(&mut stats, &counters);
+        assert_eq!(stats.ports.len(), 4);
+        assert_eq!(stats.ports[0], PortErrorCounters::default());
+        assert_eq!(
+            stats.ports[1],
+            PortErrorCounters {
+                invalid_frames: 1,
+                rx_errors: 2,
+                forwarded_rx_errors: 3,
+                lost_links: 4,
+            }
+        );
+        assert_eq!(stats.processing_unit_errors, 5);
+        assert_eq!(stats.pdi_errors, 6);
+    }
+
+    #[test]
+    fn test_count_error() {
+        let mut monitor = LinkMonitor::default();
+        monitor
+            .count_error(&anyhow::Error::from(ethercrab::error::Error::Timeout).context("tx/rx"));
+        monitor.count_error(&anyhow::Error::from(ethercrab::error::Error::ReceiveFrame));
+        monitor.count_error(&anyhow::anyhow!("unrelated"));
+        assert_eq!(monitor.stats.timed_out_frames, 1);
+        assert_eq!(monitor.stats.lost_frames, 1);
+    }
+}
@@ -1,4 +1,11 @@
 pub mod config;
+pub mod dc;
+pub mod diagnosis;
 pub mod ethercat_discovery_info;
+pub mod fault;
 pub mod init;
+pub mod link_quality;
+pub mod reconfigure;
+pub mod recovery;
+pub mod sdo;
 pub mod setup;
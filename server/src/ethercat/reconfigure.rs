@@ -0,0 +1,280 @@
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use ethercat_hal::dc::{DcSyncMode, configure_sync0, set_sync_manager_mode};
+use ethercat_hal::devices::{SubDeviceIdentityTuple, subdevice_identity_to_tuple};
+use ethercrab::{Command, MainDevice, RegisterAddress, SubDevice, SubDeviceRef, SubDeviceState};
+
+use crate::ethercat::dc::SYNC0_START_DELAY;
+
+/// RxPDO assignment, written in PRE-OP
+const RX_PDO_ASSIGNMENT: u16 = 0x1C12;
+/// TxPDO assignment, written in PRE-OP
+const TX_PDO_ASSIGNMENT: u16 = 0x1C13;
+/// More PDOs per direction than any of our SubDevices assigns
+const MAX_ASSIGNED_PDOS: usize = 32;
+
+/// EEPROM words of the identity, see ETG.2010
+const EEPROM_VENDOR_ID: u16 = 0x0008;
+const EEPROM_PRODUCT_ID: u16 = 0x000A;
+const EEPROM_REVISION: u16 = 0x000C;
+
+/// Bits 0-1 of the control byte of a sync manager, `0b10` is a mailbox
+const SM_MODE_MASK: u64 = 0b11 << 32;
+const SM_MODE_MAILBOX: u64 = 0b10 << 32;
+
+/// Acknowledges the error which made the SubDevice leave its state
+const AL_CONTROL_ACK: u16 = 0x10;
+
+/// Time a SubDevice gets for each state transition
+const STATE_TRANSITION_TIMEOUT: Duration = Duration::from_secs(5);
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// What the initialization wrote to a SubDevice, read once the group is in SAFE-OP
+///
+/// A SubDevice which lost power forgets all of it. Writing it again brings the SubDevice back
+/// into the running group without initializing the bus, see [`SubDeviceConfiguration::reidentify`]
+/// and [`SubDeviceConfiguration::restore`].
+/// CoE objects other than the PDO assignment are kept by the SubDevices themselves.
+#[derive(Debug, Clone)]
+pub struct SubDeviceConfiguration {
+    configured_address: u16,
+    identity: SubDeviceIdentityTuple,
+    /// Raw registers of every sync manager, 8 bytes from `0x0800`
+    sync_managers: Vec<u64>,
+    /// Raw registers of every FMMU, 16 bytes from `0x0600`
+    fmmus: Vec<[u64; 2]>,
+    /// System time offset and transmission delay, only SubDevices with DC
+    dc_offsets: Option<(u64, u32)>,
+    /// `None` for SubDevices without CoE, they use the PDOs of their SII
+    rx_pdo_assignment: Option<Vec<u16>>,
+    tx_pdo_assignment: Option<Vec<u16>>,
+}
+
+impl SubDeviceConfiguration {
+    pub async fn read<S>(subdevice: &SubDeviceRef<'_, S>) -> Result<Self, anyhow::Error>
+    where
+        S: Deref<Target = SubDevice> + Sync,
+    {
+        let sync_manager_count: u8 = subdevice
+            .register_read(RegisterAddress::SyncManagerChannels)
+            .await?;
+        let mut sync_managers = vec![];
+        for index in 0..sync_manager_count.min(16) {
+            sync_managers.push(
+                subdevice
+                    .register_read(RegisterAddress::sync_manager(index))
+                    .await?,
+            );
+        }
+
+        let fmmu_count: u8 = subdevice.register_read(RegisterAddress::FmmuCount).await?;
+        let mut fmmus = vec![];
+        for index in 0..fmmu_count.min(16) {
+            let address = u16::from(RegisterAddress::fmmu(index));
+            fmmus.push([
+                subdevice.register_read(address).await?,
+                subdevice.register_read(address + 8).await?,
+            ]);
+        }
+
+        let dc_offsets = match subdevice.dc_support().any() {
+            true => Some((
+                subdevice
+                    .register_read(RegisterAddress::DcSystemTimeOffset)
+                    .await?,
+                subdevice
+                    .register_read(RegisterAddress::DcSystemTimeTransmissionDelay)
+                    .await?,
+            )),
+            false => None,
+        };
+
+        Ok(Self {
+            configured_address: subdevice.configured_address(),
+            identity: subdevice_identity_to_tuple(&subdevice.identity()),
+            sync_managers,
+            fmmus,
+            dc_offsets,
+            rx_pdo_assignment: read_pdo_assignment(subdevice, RX_PDO_ASSIGNMENT).await,
+            tx_pdo_assignment: read_pdo_assignment(subdevice, TX_PDO_ASSIGNMENT).await,
+        })
+    }
+
+    /// Gives the SubDevice at `subdevice_index` its address again, `false` if it was replaced
+    ///
+    /// A replaced SubDevice has a different identity, it can only be used after a restart.
+    pub async fn reidentify<S>(
+        &self,
+        maindevice: &MainDevice<'_>,
+        subdevice: &SubDeviceRef<'_, S>,
+        subdevice_index: usize,
+    ) -> Result<bool, anyhow::Error>
+    where
+        S: Deref<Target = SubDevice> + Sync,
+    {
+        self.assign_address(maindevice, subdevice_index).await?;
+
+        let identity = (
+            subdevice.eeprom_read(maindevice, EEPROM_VENDOR_ID).await?,
+            subdevice.eeprom_read(maindevice, EEPROM_PRODUCT_ID).await?,
+            subdevice.eeprom_read(maindevice, EEPROM_REVISION).await?,
+        );
+        if identity != self.identity {
+            tracing::warn!(
+                "SubDevice {} was replaced, found {:?} instead of {:?}",
+                subdevice_index,
+                identity,
+                self.identity
+            );
+        }
+        Ok(identity == self.identity)
+    }
+
+    /// Writes the configuration again and brings the SubDevice back to OP
+    ///
+    /// Only after [`SubDeviceConfiguration::reidentify`] found the same SubDevice.
+    /// The group keeps cycling meanwhile, in OP the SubDevice takes its outputs from the PDI again.
+    /// SubDevices with `sync0_period` get their SYNC0 pulse started again.
+    pub async fn restore<S>(
+        &self,
+        subdevice: &SubDeviceRef<'_, S>,
+        sync0_period: Option<Duration>,
+    ) -> Result<(), anyhow::Error>
+    where
+        S: Deref<Target = SubDevice> + Sync,
+    {
+        request_state(subdevice, SubDeviceState::Init).await?;
+        let (mailboxes, process_data): (Vec<_>, Vec<_>) = self
+            .sync_managers
+            .iter()
+            .enumerate()
+            .partition(|(_, sync_manager)| **sync_manager & SM_MODE_MASK == SM_MODE_MAILBOX);
+        for (index, sync_manager) in mailboxes {
+            subdevice
+                .register_write(RegisterAddress::sync_manager(index as u8), *sync_manager)
+                .await?;
+        }
+
+        request_state(subdevice, SubDeviceState::PreOp).await?;
+        if let Some(assignment) = &self.rx_pdo_assignment {
+            subdevice
+                .sdo_write_array(RX_PDO_ASSIGNMENT, assignment)
+                .await?;
+        }
+        if let Some(assignment) = &self.tx_pdo_assignment {
+            subdevice
+                .sdo_write_array(TX_PDO_ASSIGNMENT, assignment)
+                .await?;
+        }
+        for (index, sync_manager) in process_data {
+            subdevice
+                .register_write(RegisterAddress::sync_manager(index as u8), *sync_manager)
+                .await?;
+        }
+        for (index, [low, high]) in self.fmmus.iter().enumerate() {
+            let address = u16::from(RegisterAddress::fmmu(index as u8));
+            subdevice.register_write(address, *low).await?;
+            subdevice.register_write(address + 8, *high).await?;
+        }
+
+        if let Some((offset, delay)) = self.dc_offsets {
+            subdevice
+                .register_write(RegisterAddress::DcSystemTimeOffset, offset)
+                .await?;
+            subdevice
+                .register_write(RegisterAddress::DcSystemTimeTransmissionDelay, delay)
+                .await?;
+        }
+        if let Some(sync0_period) = sync0_period {
+            set_sync_manager_mode(subdevice, DcSyncMode::Sync0).await?;
+            configure_sync0(subdevice, SYNC0_START_DELAY, sync0_period).await?;
+        }
+
+        request_state(subdevice, SubDeviceState::SafeOp).await?;
+        request_state(subdevice, SubDeviceState::Op).await
+    }
+
+    /// A SubDevice which lost power answers at its position again, but without its address
+    async fn assign_address(
+        &self,
+        maindevice: &MainDevice<'_>,
+        subdevice_index: usize,
+    ) -> Result<(), anyhow::Error> {
+        let answers = Command::fprd(self.configured_address, RegisterAddress::AlStatus.into())
+            .receive::<u16>(maindevice)
+            .await
+            .is_ok();
+        if answers {
+            return Ok(());
+        }
+
+        let position = u16::try_from(subdevice_index)?;
+        let address: u16 =
+            Command::aprd(position, RegisterAddress::ConfiguredStationAddress.into())
+                .receive(maindevice)
+                .await?;
+        // with a SubDevice missing further up the bus this is the next one, which keeps its address
+        if address != 0 {
+            bail!(
+                "SubDevice at position {} has the address {:#06x} instead of {:#06x}",
+                position,
+                address,
+                self.configured_address
+            );
+        }
+        Command::apwr(position, RegisterAddress::ConfiguredStationAddress.into())
+            .send(maindevice, self.configured_address)
+            .await?;
+        Ok(())
+    }
+}
+
+async fn read_pdo_assignment<S>(subdevice: &SubDeviceRef<'_, S>, index: u16) -> Option<Vec<u16>>
+where
+    S: Deref<Target = SubDevice> + Sync,
+{
+    subdevice
+        .sdo_read_array::<u16, MAX_ASSIGNED_PDOS>(index)
+        .await
+        .ok()
+        .map(|assignment| assignment.to_vec())
+}
+
+/// Requests `state`, acknowledging any error, and waits until the SubDevice reached it
+async fn request_state<S>(
+    subdevice: &SubDeviceRef<'_, S>,
+    state: SubDeviceState,
+) -> Result<(), anyhow::Error>
+where
+    S: Deref<Target = SubDevice> + Sync,
+{
+    let control = match state {
+        SubDeviceState::Init => 0x01,
+        SubDeviceState::PreOp => 0x02,
+        SubDeviceState::SafeOp => 0x04,
+        SubDeviceState::Op => 0x08,
+        state => bail!("Can't request {}", state),
+    };
+    subdevice
+        .register_write(RegisterAddress::AlControl, control | AL_CONTROL_ACK)
+        .await?;
+
+    let start = Instant::now();
+    loop {
+        let (current, status_code) = subdevice.status().await?;
+        if current == state {
+            return Ok(());
+        }
+        if start.elapsed() > STATE_TRANSITION_TIMEOUT {
+            bail!(
+                "Stayed in {} ({}) instead of going to {}",
+                current,
+                status_code,
+                state
+            );
+        }
+        smol::Timer::after(STATE_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,529 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use control_core::socketio::namespace::NamespaceCacheingLogic;
+use ethercrab::{
+    Command, MainDevice, RegisterAddress, SubDeviceGroup, SubDeviceState, subdevice_group::Op,
+};
+use machines::Machine;
+use machines::machine_identification::MachineIdentificationUnique;
+
+use crate::{
+    app_state::{EthercatSetup, SharedState},
+    ethercat::{
+        config::{MAX_SUBDEVICES, PDI_LEN},
+        reconfigure::SubDeviceConfiguration,
+    },
+    socketio::main_namespace::{
+        MainNamespaceEvents,
+        ethercat_bus_event::{EthercatBusEvent, FaultedSubDevice},
+    },
+};
+
+/// How often a SubDevice which left OP is requested to go back
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Requests to go back to OP before the configuration of the SubDevice is written again
+const MAX_RECOVERY_ATTEMPTS: u32 = 3;
+
+/// How often the SubDevices on the bus are counted to notice added or lost ones
+const BUS_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay between failed attempts to write the configuration again
+const RECONFIGURE_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the SubDevices are recovered while the tx/rx keeps failing, afterwards the loop faults
+const BUS_RECOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// AL control value requesting OP and acknowledging the error which made the SubDevice leave it
+const AL_CONTROL_OP_ACK: u16 = 0x08 | 0x10;
+
+#[derive(Debug)]
+struct SubDeviceFault {
+    subdevice_index: usize,
+    state: SubDeviceState,
+    attempts: u32,
+    /// `None` if the next attempt is due right away
+    last_attempt: Option<Instant>,
+    /// A different SubDevice answers at its position, it is not recovered
+    replaced: bool,
+}
+
+impl SubDeviceFault {
+    /// Requests to go back to OP come faster than writing the configuration again
+    fn is_due(&self, now: Instant) -> bool {
+        let interval = match self.attempts <= MAX_RECOVERY_ATTEMPTS {
+            true => RECOVERY_INTERVAL,
+            false => RECONFIGURE_RETRY_INTERVAL,
+        };
+        !self.replaced
+            && self
+                .last_attempt
+                .is_none_or(|last_attempt| now.duration_since(last_attempt) >= interval)
+    }
+}
+
+/// Bus I/O of one recovery step, done by [`recover_subdevices`] next to the loop
+#[derive(Debug, Default)]
+struct RecoveryStep {
+    /// Requested to go back to OP
+    request_op: Vec<usize>,
+    /// Get their configuration written again, with their SYNC0 period
+    restore: Vec<(usize, Option<Duration>)>,
+    /// Asks every SubDevice for its state
+    probe: bool,
+    /// Counts the SubDevices on the bus
+    scan: bool,
+}
+
+impl RecoveryStep {
+    fn is_empty(&self) -> bool {
+        self.request_op.is_empty() && self.restore.is_empty() && !self.probe && !self.scan
+    }
+}
+
+#[derive(Debug, Default)]
+struct RecoveryReport {
+    /// Still not configured
+    failed: Vec<usize>,
+    /// A different SubDevice answers at their position
+    replaced: Vec<usize>,
+    /// Not in OP when probed
+    probed: Vec<usize>,
+    subdevice_count: Option<u16>,
+}
+
+/// Watches the SubDevices from the loop thread and recovers the ones which left OP
+///
+/// 1. A SubDevice which left OP, e.g. through a watchdog, is requested to go back.
+///    Meanwhile the machines using it are kept in their safe state, all others keep running.
+/// 2. If it doesn't come back, e.g. because it lost power, its configuration is written again.
+///
+/// The loop thread only compares the states of its tx/rx, all other bus I/O is done by
+/// [`recover_subdevices`] on a separate task while the group keeps cycling.
+/// SubDevices added to the bus or replaced by a different one are only used after a restart.
+#[derive(Debug, Default)]
+pub struct BusMonitor {
+    /// States of the last tx/rx, by SubDevice index
+    states: Vec<SubDeviceState>,
+    faults: Vec<SubDeviceFault>,
+    /// Machines using a faulted SubDevice
+    stopped_machines: Vec<MachineIdentificationUnique>,
+    last_bus_scan: Option<Instant>,
+    /// SubDevices counted by the last bus scan
+    subdevice_count: Option<u16>,
+    recovery: Option<smol::Task<RecoveryReport>>,
+    /// The next recovery step probes all SubDevices
+    probe: bool,
+    /// First cycle of the current series of failed tx/rx
+    bus_failed_since: Option<Instant>,
+}
+
+impl BusMonitor {
+    pub fn set_states(&mut self, states: &[SubDeviceState]) {
+        self.states.clear();
+        self.states.extend_from_slice(states);
+    }
+
+    /// Keeps the machines using a faulted SubDevice in their safe state
+    pub fn stop_machines(&self, machines: &mut [Box<dyn Machine>]) {
+        if self.stopped_machines.is_empty() {
+            return;
+        }
+        for machine in machines.iter_mut() {
+            if self
+                .stopped_machines
+                .contains(&machine.get_machine_identification_unique())
+            {
+                machine.safe_stop();
+            }
+        }
+    }
+
+    /// Compares the states of the last tx/rx with OP and tries to recover
+    pub fn check(&mut self, setup: &EthercatSetup, app_state: &Arc<SharedState>, now: Instant) {
+        self.bus_failed_since = None;
+        let mut changed = false;
+        for (subdevice_index, state) in self.states.iter().enumerate() {
+            let fault = self
+                .faults
+                .iter()
+                .position(|fault| fault.subdevice_index == subdevice_index);
+            match (fault, *state == SubDeviceState::Op) {
+                (None, false) => {
+                    tracing::warn!("SubDevice {} left OP, now in {}", subdevice_index, state);
+                    self.faults.push(SubDeviceFault {
+                        subdevice_index,
+                        state: *state,
+                        attempts: 0,
+                        last_attempt: Some(now),
+                        replaced: false,
+                    });
+                    changed = true;
+                }
+                (Some(fault), true) => {
+                    tracing::info!("SubDevice {} is back in OP", subdevice_index);
+                    self.faults.remove(fault);
+                    changed = true;
+                }
+                (Some(fault), false) => self.faults[fault].state = *state,
+                (None, true) => (),
+            }
+        }
+        if changed {
+            self.update_stopped_machines(setup, app_state);
+        }
+        self.step(setup, app_state, now);
+    }
+
+    /// If the SubDevices are still recovered while the tx/rx keeps failing
+    ///
+    /// Gives up [`BUS_RECOVERY_TIMEOUT`] after the first failed tx/rx, e.g. when a cable was pulled.
+    pub fn can_recover_bus(&self, now: Instant) -> bool {
+        self.bus_failed_since
+            .is_none_or(|since| now.duration_since(since) < BUS_RECOVERY_TIMEOUT)
+    }
+
+    /// Probes the SubDevices while the tx/rx keeps failing and recovers the ones not in OP
+    pub fn recover_bus(
+        &mut self,
+        setup: &EthercatSetup,
+        app_state: &Arc<SharedState>,
+        now: Instant,
+    ) {
+        self.bus_failed_since.get_or_insert(now);
+        self.probe = true;
+        self.step(setup, app_state, now);
+    }
+
+    /// Takes the report of the last recovery step and starts the next one once it finished
+    fn step(&mut self, setup: &EthercatSetup, app_state: &Arc<SharedState>, now: Instant) {
+        // finished tasks return right away
+        if let Some(recovery) = self.recovery.take_if(|recovery| recovery.is_finished()) {
+            let report = smol::block_on(recovery);
+            self.apply_report(setup, app_state, report);
+        }
+        if self.recovery.is_some() {
+            return;
+        }
+
+        let mut step = RecoveryStep {
+            probe: std::mem::take(&mut self.probe),
+            ..Default::default()
+        };
+        for fault in self.faults.iter_mut().filter(|fault| fault.is_due(now)) {
+            match fault.attempts < MAX_RECOVERY_ATTEMPTS {
+                true => step.request_op.push(fault.subdevice_index),
+                false => step.restore.push((
+                    fault.subdevice_index,
+                    setup
+                        .dc
+                        .as_ref()
+                        .filter(|dc| dc.synchronised.contains(&fault.subdevice_index))
+                        .map(|dc| dc.sync0_period),
+                )),
+            }
+            fault.attempts += 1;
+            fault.last_attempt = Some(now);
+        }
+        if self
+            .last_bus_scan
+            .is_none_or(|last_bus_scan| now.duration_since(last_bus_scan) >= BUS_SCAN_INTERVAL)
+        {
+            self.last_bus_scan = Some(now);
+            step.scan = true;
+        }
+        if step.is_empty() {
+            return;
+        }
+
+        let faulted = self
+            .faults
+            .iter()
+            .filter(|fault| {
+                step.restore
+                    .iter()
+                    .any(|(subdevice_index, _)| *subdevice_index == fault.subdevice_index)
+            })
+            .map(|fault| faulted_subdevice(setup, fault.subdevice_index, fault.state))
+            .collect();
+        self.recovery = Some(smol::spawn(recover_subdevices(
+            app_state.clone(),
+            setup.maindevice,
+            setup.group.clone(),
+            setup.configurations.clone(),
+            step,
+            faulted,
+        )));
+    }
+
+    fn apply_report(
+        &mut self,
+        setup: &EthercatSetup,
+        app_state: &Arc<SharedState>,
+        report: RecoveryReport,
+    ) {
+        if !report.failed.is_empty() {
+            tracing::warn!(
+                "Failed to recover SubDevices {:?}, retrying in {:?}",
+                report.failed,
+                RECONFIGURE_RETRY_INTERVAL
+            );
+        }
+
+        let mut changed = false;
+        for subdevice_index in report.probed {
+            if !self
+                .faults
+                .iter()
+                .any(|fault| fault.subdevice_index == subdevice_index)
+            {
+                self.faults.push(SubDeviceFault {
+                    subdevice_index,
+                    state: SubDeviceState::None,
+                    attempts: MAX_RECOVERY_ATTEMPTS,
+                    last_attempt: None,
+                    replaced: false,
+                });
+                changed = true;
+            }
+        }
+        if changed {
+            self.update_stopped_machines(setup, app_state);
+        }
+
+        for fault in self
+            .faults
+            .iter_mut()
+            .filter(|fault| report.replaced.contains(&fault.subdevice_index))
+        {
+            fault.replaced = true;
+        }
+        if !report.replaced.is_empty() {
+            let replaced = affected_machines(setup, &report.replaced);
+            let app_state = app_state.clone();
+            smol::spawn(async move {
+                for id in replaced {
+                    app_state
+                        .report_machine_error(
+                            id,
+                            "Stopped because an EtherCAT SubDevice was replaced, restart the server to use it"
+                                .to_string(),
+                        )
+                        .await;
+                }
+                app_state.send_machines_event().await;
+            })
+            .detach();
+        }
+
+        // lost SubDevices leave OP and are recovered when they come back
+        if let Some(count) = report.subdevice_count {
+            let expected = setup.group.len() as u16;
+            if count > expected && self.subdevice_count != Some(count) {
+                tracing::warn!(
+                    "Found {} SubDevices on the bus instead of {}, added ones are used after a restart",
+                    count,
+                    expected
+                );
+            }
+            self.subdevice_count = Some(count);
+        }
+    }
+
+    fn faulted_subdevices(&self) -> Vec<usize> {
+        self.faults
+            .iter()
+            .map(|fault| fault.subdevice_index)
+            .collect()
+    }
+
+    fn update_stopped_machines(&mut self, setup: &EthercatSetup, app_state: &Arc<SharedState>) {
+        let stopped = affected_machines(setup, &self.faulted_subdevices());
+        let newly_stopped = stopped
+            .iter()
+            .filter(|id| !self.stopped_machines.contains(id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let recovered = self
+            .stopped_machines
+            .iter()
+            .filter(|id| !stopped.contains(id))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.stopped_machines = stopped;
+
+        let faulted = self
+            .faults
+            .iter()
+            .map(|fault| faulted_subdevice(setup, fault.subdevice_index, fault.state))
+            .collect::<Vec<_>>();
+        let event = match faulted.is_empty() {
+            true => EthercatBusEvent::Ok(true),
+            false => EthercatBusEvent::Degraded(faulted),
+        };
+
+        let app_state = app_state.clone();
+        smol::spawn(async move {
+            for id in newly_stopped {
+                app_state
+                    .report_machine_error(
+                        id,
+                        "Stopped because an EtherCAT SubDevice left OP".to_string(),
+                    )
+                    .await;
+            }
+            for id in recovered {
+                app_state.clear_machine_error(&id).await;
+            }
+            app_state.send_machines_event().await;
+            send_ethercat_bus_event(&app_state, event).await;
+        })
+        .detach();
+    }
+}
+
+/// Machines using one of the SubDevices
+pub fn affected_machines(
+    setup: &EthercatSetup,
+    subdevice_indices: &[usize],
+) -> Vec<MachineIdentificationUnique> {
+    let mut machines = vec![];
+    for subdevice_index in subdevice_indices {
+        let Some((device_identification, _)) = setup.devices.get(*subdevice_index) else {
+            continue;
+        };
+        let Some(device_machine_identification) =
+            &device_identification.device_machine_identification
+        else {
+            continue;
+        };
+        let id = &device_machine_identification.machine_identification_unique;
+        if !machines.contains(id) {
+            machines.push(id.clone());
+        }
+    }
+    machines
+}
+
+fn faulted_subdevice(
+    setup: &EthercatSetup,
+    subdevice_index: usize,
+    state: SubDeviceState,
+) -> FaultedSubDevice {
+    let subdevice = setup
+        .group
+        .subdevice(setup.maindevice, subdevice_index)
+        .ok();
+    FaultedSubDevice {
+        subdevice_index,
+        configured_address: subdevice
+            .as_ref()
+            .map_or(0, |subdevice| subdevice.configured_address()),
+        name: subdevice.map_or_else(String::new, |subdevice| subdevice.name().to_string()),
+        state: state.to_string(),
+    }
+}
+
+/// Does the bus I/O of one recovery step
+///
+/// Only talks to the SubDevices of the step, the loop thread keeps cycling the group meanwhile.
+async fn recover_subdevices(
+    app_state: Arc<SharedState>,
+    maindevice: &'static MainDevice<'static>,
+    group: Arc<SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>>,
+    configurations: Arc<Vec<Option<SubDeviceConfiguration>>>,
+    step: RecoveryStep,
+    faulted: Vec<FaultedSubDevice>,
+) -> RecoveryReport {
+    let mut report = RecoveryReport::default();
+
+    for subdevice_index in step.request_op {
+        let res = match group.subdevice(maindevice, subdevice_index) {
+            Ok(subdevice) => subdevice
+                .register_write(RegisterAddress::AlControl, AL_CONTROL_OP_ACK)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            tracing::warn!(
+                "Failed to request OP for SubDevice {}: {:?}",
+                subdevice_index,
+                e
+            );
+        }
+    }
+
+    if !step.restore.is_empty() {
+        send_ethercat_bus_event(&app_state, EthercatBusEvent::Recovering(faulted)).await;
+    }
+    for (subdevice_index, sync0_period) in step.restore {
+        let result = match (
+            group.subdevice(maindevice, subdevice_index),
+            configurations.get(subdevice_index).and_then(Option::as_ref),
+        ) {
+            (Ok(subdevice), Some(configuration)) => {
+                match configuration
+                    .reidentify(maindevice, &subdevice, subdevice_index)
+                    .await
+                {
+                    Ok(true) => configuration.restore(&subdevice, sync0_period).await,
+                    Ok(false) => {
+                        report.replaced.push(subdevice_index);
+                        continue;
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (Err(e), _) => Err(e.into()),
+            (_, None) => Err(anyhow::anyhow!("Its configuration is unknown")),
+        };
+        match result {
+            Ok(()) => tracing::info!("Wrote the configuration of SubDevice {}", subdevice_index),
+            Err(e) => {
+                tracing::error!(
+                    "[{}::recover_subdevices] Failed to recover SubDevice {}\n{:?}",
+                    module_path!(),
+                    subdevice_index,
+                    e
+                );
+                report.failed.push(subdevice_index);
+            }
+        }
+    }
+
+    // finds the culprit when the whole tx/rx keeps failing
+    if step.probe {
+        for (subdevice_index, subdevice) in group.iter(maindevice).enumerate() {
+            if !matches!(subdevice.status().await, Ok((SubDeviceState::Op, _))) {
+                report.probed.push(subdevice_index);
+            }
+        }
+    }
+
+    // every SubDevice increments the working counter of a broadcast read
+    if step.scan {
+        let expected = group.len() as u16;
+        let res = Command::brd(RegisterAddress::Type.into())
+            .with_wkc(expected)
+            .receive::<u8>(maindevice)
+            .await;
+        report.subdevice_count = Some(match res {
+            Err(ethercrab::error::Error::WorkingCounter { received, .. }) => received,
+            _ => expected,
+        });
+    }
+
+    report
+}
+
+pub async fn send_ethercat_bus_event(app_state: &SharedState, event: EthercatBusEvent) {
+    let main_namespace = &mut app_state
+        .socketio_setup
+        .namespaces
+        .write()
+        .await
+        .main_namespace;
+    main_namespace.emit(MainNamespaceEvents::EthercatBusEvent(event.build()));
+}
@@ -0,0 +1,395 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use anyhow::{Result, anyhow};
+use ethercat_hal::debugging::sdo_info::{
+    data_type_name, read_entry_description, read_object_description, read_object_list,
+};
+use ethercrab::{MainDevice, SubDeviceGroup, subdevice_group::Op};
+use serde::{Deserialize, Serialize};
+
+use crate::ethercat::config::{MAX_SUBDEVICES, PDI_LEN};
+
+/// Finished requests which are kept to be fetched
+const MAX_KEPT_REQUESTS: usize = 100;
+
+/// Largest string or byte array which can be uploaded
+const MAX_UPLOAD_LENGTH: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SdoValueType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    String,
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum SdoValue {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum SdoOperation {
+    Upload {
+        index: u16,
+        sub_index: u8,
+        value_type: SdoValueType,
+    },
+    /// Only expedited transfers, see [`SdoValue::is_expedited`]
+    Download {
+        index: u16,
+        sub_index: u8,
+        value: SdoValue,
+    },
+    /// Indices of all objects, through the SDO information service
+    ListObjects,
+    /// Description of an object and all its subindices
+    DescribeObject { index: u16 },
+}
+
+impl SdoValue {
+    /// Fits into an expedited transfer, the only download ethercrab supports
+    pub const fn is_expedited(&self) -> bool {
+        matches!(
+            self,
+            Self::Bool(_)
+                | Self::U8(_)
+                | Self::I8(_)
+                | Self::U16(_)
+                | Self::I16(_)
+                | Self::U32(_)
+                | Self::I32(_)
+                | Self::F32(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SdoEntry {
+    pub sub_index: u8,
+    pub name: String,
+    pub data_type: u16,
+    /// Name of the CoE data type if it is a base type
+    pub data_type_name: Option<String>,
+    pub bit_length: u16,
+    pub readable: bool,
+    pub writable_in_op: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SdoObject {
+    pub index: u16,
+    pub name: String,
+    /// `0x07` variable, `0x08` array, `0x09` record
+    pub object_code: u8,
+    pub max_sub_index: u8,
+    pub entries: Vec<SdoEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SdoResult {
+    Uploaded { value: SdoValue },
+    Downloaded,
+    Objects { indices: Vec<u16> },
+    Object { object: SdoObject },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SdoRequestStatus {
+    /// Queued to the loop thread or executing
+    Pending,
+    Done {
+        result: SdoResult,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// A CoE request to one SubDevice, executed on a task started by the loop thread
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SdoRequest {
+    pub id: u64,
+    pub subdevice_index: usize,
+    #[serde(flatten)]
+    pub operation: SdoOperation,
+    /// Name of the authenticated user
+    pub client: String,
+    /// Milliseconds since the unix epoch
+    pub requested_ms: u64,
+    pub finished_ms: Option<u64>,
+    #[serde(flatten)]
+    pub status: SdoRequestStatus,
+}
+
+#[derive(Debug, Default)]
+struct SdoRequestsState {
+    requests: VecDeque<SdoRequest>,
+    next_id: u64,
+}
+
+/// Pending and recently finished [`SdoRequest`]s
+#[derive(Debug, Default)]
+pub struct SdoRequests {
+    state: Mutex<SdoRequestsState>,
+}
+
+impl SdoRequests {
+    /// Adds a pending request and assigns its id
+    pub fn add(&self, mut request: SdoRequest) -> SdoRequest {
+        let mut state = self.state.lock().expect("sdo lock poisoned");
+        request.id = state.next_id;
+        state.next_id += 1;
+        if state.requests.len() >= MAX_KEPT_REQUESTS {
+            state.requests.pop_front();
+        }
+        state.requests.push_back(request.clone());
+        drop(state);
+        request
+    }
+
+    /// Sets the result of a request, returns the updated request
+    pub fn finish(
+        &self,
+        id: u64,
+        status: SdoRequestStatus,
+        finished_ms: u64,
+    ) -> Option<SdoRequest> {
+        let mut state = self.state.lock().expect("sdo lock poisoned");
+        let request = state.requests.iter_mut().find(|request| request.id == id)?;
+        request.status = status;
+        request.finished_ms = Some(finished_ms);
+        let request = request.clone();
+        drop(state);
+        Some(request)
+    }
+
+    pub fn get(&self, id: u64) -> Option<SdoRequest> {
+        let state = self.state.lock().expect("sdo lock poisoned");
+        state
+            .requests
+            .iter()
+            .find(|request| request.id == id)
+            .cloned()
+    }
+
+    /// Newest first
+    pub fn list(&self) -> Vec<SdoRequest> {
+        let state = self.state.lock().expect("sdo lock poisoned");
+        state.requests.iter().rev().cloned().collect()
+    }
+}
+
+/// Executes the operation next to the loop thread, the group keeps cycling meanwhile
+pub async fn execute_sdo_operation(
+    maindevice: &MainDevice<'_>,
+    group: &SubDeviceGroup<MAX_SUBDEVICES, PDI_LEN, Op>,
+    subdevice_index: usize,
+    operation: &SdoOperation,
+) -> Result<SdoResult> {
+    let subdevice = group.subdevice(maindevice, subdevice_index)?;
+    match *operation {
+        SdoOperation::Upload {
+            index,
+            sub_index,
+            value_type,
+        } => {
+            let value = match value_type {
+                SdoValueType::Bool => SdoValue::Bool(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::U8 => SdoValue::U8(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::I8 => SdoValue::I8(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::U16 => SdoValue::U16(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::I16 => SdoValue::I16(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::U32 => SdoValue::U32(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::I32 => SdoValue::I32(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::U64 => SdoValue::U64(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::I64 => SdoValue::I64(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::F32 => SdoValue::F32(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::F64 => SdoValue::F64(subdevice.sdo_read(index, sub_index).await?),
+                SdoValueType::String | SdoValueType::Bytes => {
+                    let bytes = subdevice
+                        .sdo_read::<heapless::Vec<u8, MAX_UPLOAD_LENGTH>>(index, sub_index)
+                        .await?;
+                    match value_type {
+                        SdoValueType::String => SdoValue::String(
+                            String::from_utf8_lossy(&bytes)
+                                .trim_end_matches('\0')
+                                .to_string(),
+                        ),
+                        _ => SdoValue::Bytes(bytes.to_vec()),
+                    }
+                }
+            };
+            Ok(SdoResult::Uploaded { value })
+        }
+        SdoOperation::Download {
+            index,
+            sub_index,
+            ref value,
+        } => {
+            match *value {
+                SdoValue::Bool(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::U8(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::I8(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::U16(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::I16(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::U32(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::I32(v) => subdevice.sdo_write(index, sub_index, v).await?,
+                SdoValue::F32(v) => subdevice.sdo_write(index, sub_index, v.to_bits()).await?,
+                SdoValue::U64(_)
+                | SdoValue::I64(_)
+                | SdoValue::F64(_)
+                | SdoValue::String(_)
+                | SdoValue::Bytes(_) => {
+                    return Err(anyhow!("Only values of up to 4 bytes can be written"));
+                }
+            }
+            Ok(SdoResult::Downloaded)
+        }
+        SdoOperation::ListObjects => Ok(SdoResult::Objects {
+            indices: read_object_list(maindevice, &subdevice).await?,
+        }),
+        SdoOperation::DescribeObject { index } => {
+            let description = read_object_description(maindevice, &subdevice, index)
+                .await
+                .map_err(|e| anyhow!("Failed to describe object {:#06x}: {}", index, e))?;
+            let mut entries = vec![];
+            for sub_index in 0..=description.max_sub_index {
+                // records can have gaps in their subindices
+                let Ok(entry) =
+                    read_entry_description(maindevice, &subdevice, index, sub_index).await
+                else {
+                    continue;
+                };
+                entries.push(SdoEntry {
+                    sub_index,
+                    readable: entry.readable(),
+                    writable_in_op: entry.writable_in_op(),
+                    data_type_name: data_type_name(entry.data_type).map(str::to_string),
+                    name: entry.name,
+                    data_type: entry.data_type,
+                    bit_length: entry.bit_length,
+                });
+            }
+            Ok(SdoResult::Object {
+                object: SdoObject {
+                    index,
+                    name: description.name,
+                    object_code: description.object_code,
+                    max_sub_index: description.max_sub_index,
+                    entries,
+                },
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(operation: SdoOperation) -> SdoRequest {
+        SdoRequest {
+            id: 0,
+            subdevice_index: 2,
+            operation,
+            client: "admin".to_string(),
+            requested_ms: 1_718_000_000_000,
+            finished_ms: None,
+            status: SdoRequestStatus::Pending,
+        }
+    }
+
+    #[test]
+    fn test_request_json() {
+        let download: SdoRequest = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "subdevice_index": 2,
+            "operation": "download",
+            "index": 0x8010,
+            "sub_index": 1,
+            "value": { "type": "u16", "value": 1500 },
+            "client": "admin",
+            "requested_ms": 1_718_000_000_000u64,
+            "finished_ms": null,
+            "status": "pending",
+        }))
+        .unwrap();
+        assert_eq!(
+            download,
+            request(SdoOperation::Download {
+                index: 0x8010,
+                sub_index: 1,
+                value: SdoValue::U16(1500),
+            })
+        );
+
+        let mut upload = request(SdoOperation::Upload {
+            index: 0x8000,
+            sub_index: 0x15,
+            value_type: SdoValueType::U16,
+        });
+        upload.status = SdoRequestStatus::Done {
+            result: SdoResult::Uploaded {
+                value: SdoValue::U16(2),
+            },
+        };
+        let json = serde_json::to_value(&upload).unwrap();
+        assert_eq!(json["operation"], "upload");
+        assert_eq!(json["status"], "done");
+        assert_eq!(json["result"]["type"], "uploaded");
+        assert_eq!(
+            json["result"]["value"],
+            serde_json::json!({ "type": "u16", "value": 2 })
+        );
+    }
+
+    #[test]
+    fn test_requests() {
+        let requests = SdoRequests::default();
+        let first = requests.add(request(SdoOperation::ListObjects));
+        let second = requests.add(request(SdoOperation::ListObjects));
+        assert_eq!((first.id, second.id), (0, 1));
+
+        let finished = requests
+            .finish(
+                first.id,
+                SdoRequestStatus::Failed {
+                    error: "No EtherCAT setup".to_string(),
+                },
+                1_718_000_000_100,
+            )
+            .unwrap();
+        assert_eq!(finished.finished_ms, Some(1_718_000_000_100));
+        assert_eq!(requests.get(first.id), Some(finished));
+        assert_eq!(requests.list()[0].id, second.id);
+        assert!(requests.finish(42, SdoRequestStatus::Pending, 0).is_none());
+    }
+}
@@ -1,4 +1,6 @@
-use crate::app_state::{EtherCatDeviceMetaData, EthercatSetup};
+use crate::app_state::{EtherCatDeviceMetaData, EthercatSetup, HotThreadMessage};
+use crate::ethercat::dc::{DRIFT_COMPENSATION_CYCLES, DcConfig, configure_dc};
+use crate::ethercat::reconfigure::SubDeviceConfiguration;
 use crate::socketio::main_namespace::MainNamespaceEvents;
 use crate::socketio::main_namespace::ethercat_devices_event::EthercatDevicesEventBuilder;
 use crate::socketio::main_namespace::machines_event::MachineObj;
@@ -10,14 +12,17 @@ use control_core::realtime::set_core_affinity;
 use control_core::socketio::namespace::NamespaceCacheingLogic;
 #[cfg(all(target_os = "linux", not(feature = "development-build")))]
 use control_core::{irq_handling::set_irq_affinity, realtime::set_realtime_priority};
+use ethercat_hal::coe::ConfigurableDevice;
 use ethercat_hal::debugging::diagnosis_history::get_most_recent_diagnosis_message;
-use ethercat_hal::devices::devices_from_subdevices;
+use ethercat_hal::devices::cia402::{Cia402Configuration, Cia402Drive, is_cia402_subdevice};
+use ethercat_hal::devices::generic::GenericEthercatDevice;
 use ethercat_hal::devices::wago_750_354::{
     WAGO_750_354_PRODUCT_ID, WAGO_750_354_VENDOR_ID, Wago750_354,
 };
 use ethercat_hal::devices::wago_modules::ip20_ec_di8_do8::{
     IP20_EC_DI8_DO8_PRODUCT_ID, IP20_EC_DI8_DO8_VENDOR_ID, IP20EcDi8Do8,
 };
+use ethercat_hal::devices::{NewEthercatDevice, devices_from_subdevices};
 
 use crate::utils::{start_dnsmasq, stop_dnsmasq};
 use ethercrab::std::ethercat_now;
@@ -29,6 +34,7 @@ use machines::machine_identification::{
 use machines::registry::{MACHINE_REGISTRY, MachineRegistry};
 use machines::{Machine, MachineNewHardware, MachineNewHardwareEthercat, MachineNewParams};
 use smol::channel::Sender;
+use smol::lock::RwLock;
 use socketioxide::extract::SocketRef;
 use std::{sync::Arc, time::Duration};
 
@@ -158,7 +164,7 @@ pub async fn set_ethercat_devices<const MAX_SUBDEVICES: usize, const MAX_PDI: us
     }
     let _ = shared_state
         .rt_machine_creation_channel
-        .send(crate::app_state::HotThreadMessage::AddMachines(machines))
+        .send(HotThreadMessage::AddMachines(machines))
         .await;
 
     shared_state.add_machines_if_not_exists(machine_objs).await;
@@ -181,11 +187,26 @@ pub async fn setup_loop(
     // Small Timeout to ensure interfaces get released
     smol::Timer::after(Duration::from_millis(1500)).await;
 
+    let maindevice = start_maindevice(interface);
+    let setup = init_ethercat_setup(maindevice, app_state).await?;
+
+    let res = start_dnsmasq();
+    match res {
+        Ok(o) => o,
+        Err(e) => tracing::error!("Failed to start dnsmasq: {:?}", e),
+    };
+
+    Ok(setup)
+}
+
+/// Starts the TX/RX thread on `interface` and creates the [`MainDevice`]
+///
+/// Both live until the process exits, re-initializations of the bus reuse them.
+pub fn start_maindevice(interface: &str) -> &'static MainDevice<'static> {
     // Setup ethercrab tx/rx task
     let pdu_storage = Box::leak(Box::new(PduStorage::<MAX_FRAMES, MAX_PDU_DATA>::new()));
     let (tx, rx, pdu) = pdu_storage.try_split().expect("can only split once");
     let interface = interface.to_string();
-    let mut has_dc = false;
 
     std::thread::Builder::new()
         .name("EthercatTxRxThread".to_owned())
@@ -248,6 +269,15 @@ pub async fn setup_loop(
             dc_static_sync_iterations: 10_000,
         },
     );
+    Box::leak(Box::new(maindevice))
+}
+
+/// Initializes all SubDevices on the bus, builds the machines and puts the group into OP
+pub async fn init_ethercat_setup(
+    maindevice: &'static MainDevice<'static>,
+    app_state: Arc<SharedState>,
+) -> Result<EthercatSetup, anyhow::Error> {
+    let mut has_dc = false;
     {
         let app_state_clone = app_state.clone();
         let main_namespace = &mut app_state_clone
@@ -279,12 +309,53 @@ pub async fn setup_loop(
     };
 
     // create devices
-    let devices =
-        devices_from_subdevices::<MAX_SUBDEVICES, PDI_LEN>(&mut group_preop, &maindevice)?;
-    let subdevices = group_preop.iter(&maindevice).collect::<Vec<_>>();
+    let mut devices =
+        devices_from_subdevices::<MAX_SUBDEVICES, PDI_LEN>(&mut group_preop, maindevice)?;
+    let subdevices = group_preop.iter(maindevice).collect::<Vec<_>>();
+
+    // SubDevices without a driver are either CiA 402 drives or sized from their SII
+    for (device, subdevice) in devices.iter_mut().zip(&subdevices) {
+        let is_generic = device.read().await.as_any().is::<GenericEthercatDevice>();
+        if !is_generic {
+            continue;
+        }
+        if is_cia402_subdevice(subdevice).await {
+            let mut drive = Cia402Drive::new();
+            match drive
+                .write_config(subdevice, &Cia402Configuration::default())
+                .await
+            {
+                Ok(()) => {
+                    *device = Arc::new(RwLock::new(drive));
+                    continue;
+                }
+                // fall back to the PDOs of the SII
+                Err(err) => tracing::warn!(
+                    "[{}::setup_loop] Failed to configure {} as CiA 402 drive: {:?}",
+                    module_path!(),
+                    subdevice.name(),
+                    err
+                ),
+            }
+        }
+        let mut device = device.write().await;
+        let Some(generic) = device.as_any_mut().downcast_mut::<GenericEthercatDevice>() else {
+            continue;
+        };
+        let result = generic.configure_from_sii(subdevice, maindevice).await;
+        drop(device);
+        if let Err(err) = result {
+            tracing::warn!(
+                "[{}::setup_loop] Failed to read the PDOs of {} from the SII: {:?}",
+                module_path!(),
+                subdevice.name(),
+                err
+            );
+        }
+    }
 
     // extract device identifications
-    let device_identifications = read_device_identifications(&subdevices, &maindevice)
+    let device_identifications = read_device_identifications(&subdevices, maindevice)
         .await
         .into_iter()
         .enumerate()
@@ -414,6 +485,13 @@ pub async fn setup_loop(
         }
     }
 
+    // SYNC0 has to be running before the SubDevices go into SAFE-OP
+    let dc_config = DcConfig::from_env();
+    let dc = match dc_config.enabled {
+        true => configure_dc(&subdevices, &dc_config).await,
+        false => None,
+    };
+
     // remove subdevice from devices tuple
     let devices = devices
         .iter()
@@ -433,7 +511,7 @@ pub async fn setup_loop(
     )
     .await?;
 
-    let group_safe = match group_preop.into_safe_op(&maindevice).await {
+    let group_safe = match group_preop.into_safe_op(maindevice).await {
         Ok(group_op) => {
             tracing::info!("Group in Safe-OP state");
             group_op
@@ -445,12 +523,34 @@ pub async fn setup_loop(
         ))?,
     };
 
+    // with the PDI configured and before the watchdogs of OP run
+    let mut configurations = vec![];
+    for (subdevice_index, subdevice) in group_safe.iter(maindevice).enumerate() {
+        match SubDeviceConfiguration::read(&subdevice).await {
+            Ok(configuration) => configurations.push(Some(configuration)),
+            Err(err) => {
+                tracing::warn!(
+                    "[{}::setup_loop] Failed to read the configuration of SubDevice {}, it can't be recovered: {:?}",
+                    module_path!(),
+                    subdevice_index,
+                    err
+                );
+                configurations.push(None);
+            }
+        }
+    }
+
     // TODO Make a more extensive init for the case of DC-Sync
     // Maybe we need multiple groups? like one DC group and one non dc sync group?
     // For now we just check if we use wago coupler or IP20
-    if has_dc {
-        for _ in 1..1000 {
-            let res = group_safe.tx_rx_sync_system_time(&maindevice).await;
+    if has_dc || dc.is_some() {
+        for _ in 1..DRIFT_COMPENSATION_CYCLES {
+            if let Some(dc) = &dc {
+                if let Err(e) = dc.write_host_time(maindevice).await {
+                    tracing::error!("Failed to write the host time: {:?}", e);
+                }
+            }
+            let res = group_safe.tx_rx_sync_system_time(maindevice).await;
             match res {
                 Ok(_) => (),
                 Err(e) => tracing::error!(
@@ -463,7 +563,7 @@ pub async fn setup_loop(
     }
 
     // Put group in operational state
-    let group_op = match group_safe.into_op(&maindevice).await {
+    let group_op = match group_safe.into_op(maindevice).await {
         Ok(group_op) => {
             tracing::info!("Group in OP state");
             group_op
@@ -490,15 +590,11 @@ pub async fn setup_loop(
         main_namespace.emit(MainNamespaceEvents::EthercatDevicesEvent(event));
     }
 
-    let res = start_dnsmasq();
-    match res {
-        Ok(o) => o,
-        Err(e) => tracing::error!("Failed to start dnsmasq: {:?}", e),
-    };
-
     Ok(EthercatSetup {
         devices,
-        group: group_op,
+        group: Arc::new(group_op),
         maindevice,
+        dc,
+        configurations: Arc::new(configurations),
     })
 }
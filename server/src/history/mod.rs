@@ -0,0 +1,774 @@
+use anyhow::{Context, Result, bail};
+use machines::machine_identification::MachineIdentificationUnique;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub mod recorder;
+
+/// Environment variable to override where the history is stored
+pub const HISTORY_DIR_ENV: &str = "QITECH_HISTORY_DIR";
+
+const CONFIG_FILE: &str = "config.json";
+const RAW_DIR: &str = "raw";
+const MINUTE_DIR: &str = "1m";
+
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// Raw samples are stored in one file per hour
+const RAW_CHUNK_MS: u64 = HOUR_MS;
+/// Minute aggregates are stored in one file per day
+const MINUTE_CHUNK_MS: u64 = DAY_MS;
+
+/// [`Resolution::Auto`] uses raw samples for ranges up to this length
+const AUTO_RAW_MAX_RANGE_MS: u64 = 6 * HOUR_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// How often the live values of every machine are sampled
+    pub sample_interval_ms: u64,
+    /// How long raw samples are kept
+    pub raw_retention_hours: u64,
+    /// How long minute aggregates are kept
+    pub minute_retention_days: u64,
+    /// Oldest files are deleted once all machines together use more
+    pub max_bytes: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval_ms: 1000,
+            raw_retention_hours: 48,
+            minute_retention_days: 365,
+            max_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Minimum, maximum and mean of a field over some time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Aggregate {
+    const fn new(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+
+    const fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.sum += other.sum;
+        self.count += other.count;
+    }
+
+    fn get(&self, function: AggregateFunction) -> f64 {
+        match function {
+            AggregateFunction::Avg => self.sum / self.count as f64,
+            AggregateFunction::Min => self.min,
+            AggregateFunction::Max => self.max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateFunction {
+    #[default]
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    /// Raw for short ranges which are still in the raw retention, minute otherwise
+    #[default]
+    Auto,
+    Raw,
+    Minute,
+}
+
+/// One line of a raw chunk
+#[derive(Debug, Serialize, Deserialize)]
+struct RawLine {
+    t: u64,
+    v: BTreeMap<String, f64>,
+}
+
+/// One line of a minute chunk, values are `[min, max, sum, count]`
+#[derive(Debug, Serialize, Deserialize)]
+struct MinuteLine {
+    t: u64,
+    v: BTreeMap<String, (f64, f64, f64, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryQuery {
+    /// Milliseconds since the unix epoch, inclusive
+    pub from: u64,
+    /// Milliseconds since the unix epoch, exclusive
+    pub to: u64,
+    /// All fields if `None`
+    pub fields: Option<Vec<String>>,
+    pub resolution: Resolution,
+    /// Merges the points into buckets of this length
+    pub bucket_ms: Option<u64>,
+    pub aggregate: AggregateFunction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryPoint {
+    /// Start of the sample, minute or bucket in milliseconds since the unix epoch
+    pub t: u64,
+    pub values: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryQueryResult {
+    /// [`Resolution::Raw`] or [`Resolution::Minute`], what the points were read from
+    pub resolution: Resolution,
+    pub bucket_ms: Option<u64>,
+    pub aggregate: AggregateFunction,
+    pub points: Vec<HistoryPoint>,
+}
+
+impl HistoryQueryResult {
+    /// Points as CSV, one column per field
+    pub fn to_csv(&self) -> String {
+        let fields: BTreeSet<&String> = self
+            .points
+            .iter()
+            .flat_map(|point| point.values.keys())
+            .collect();
+
+        let mut csv = String::from("timestamp_ms");
+        for field in &fields {
+            csv.push(',');
+            csv.push_str(field);
+        }
+        csv.push('\n');
+        for point in &self.points {
+            csv.push_str(&point.t.to_string());
+            for field in &fields {
+                csv.push(',');
+                if let Some(value) = point.values.get(*field) {
+                    csv.push_str(&value.to_string());
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// The minute which is currently aggregated for a machine
+#[derive(Debug)]
+struct OpenMinute {
+    t: u64,
+    values: BTreeMap<String, Aggregate>,
+}
+
+/// Last recorded values per machine with their timestamp
+type LatestValues = HashMap<MachineIdentificationUnique, (u64, BTreeMap<String, f64>)>;
+
+/// On-disk history of the live values of every machine
+///
+/// Raw samples and minute aggregates are stored as JSON lines in chunks per machine,
+/// retention and the disk budget drop whole chunks, oldest first.
+#[derive(Debug)]
+pub struct HistoryStore {
+    dir: PathBuf,
+    config: HistoryConfig,
+    open_minutes: Mutex<HashMap<MachineIdentificationUnique, OpenMinute>>,
+    latest: Mutex<LatestValues>,
+}
+
+impl HistoryStore {
+    pub fn new(dir: PathBuf) -> Self {
+        let config = read_config(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read history config, using the default\n{:?}", e);
+            HistoryConfig::default()
+        });
+        Self::with_config(dir, config)
+    }
+
+    pub fn with_config(dir: PathBuf, config: HistoryConfig) -> Self {
+        Self {
+            dir,
+            config,
+            open_minutes: Mutex::new(HashMap::new()),
+            latest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `$QITECH_HISTORY_DIR`, `$HOME/.qitech/history` or `./history`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var(HISTORY_DIR_ENV) {
+            return PathBuf::from(dir);
+        }
+        std::env::var("HOME").map_or_else(
+            |_| PathBuf::from("history"),
+            |home| Path::new(&home).join(".qitech").join("history"),
+        )
+    }
+
+    pub const fn config(&self) -> &HistoryConfig {
+        &self.config
+    }
+
+    /// Appends a raw sample and aggregates it into its minute
+    ///
+    /// A minute is written once the first sample of the next minute arrives.
+    pub fn record(
+        &self,
+        machine: &MachineIdentificationUnique,
+        t: u64,
+        values: BTreeMap<String, f64>,
+    ) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        self.latest
+            .lock()
+            .expect("history lock poisoned")
+            .insert(machine.clone(), (t, values.clone()));
+
+        let finished_minute = {
+            let mut open_minutes = self.open_minutes.lock().expect("history lock poisoned");
+            let minute = floor(t, MINUTE_MS);
+            let open = open_minutes.entry(machine.clone()).or_insert(OpenMinute {
+                t: minute,
+                values: BTreeMap::new(),
+            });
+            let finished = match open.t == minute {
+                true => None,
+                false => Some(std::mem::replace(
+                    open,
+                    OpenMinute {
+                        t: minute,
+                        values: BTreeMap::new(),
+                    },
+                )),
+            };
+            for (field, value) in &values {
+                let aggregate = Aggregate::new(*value);
+                open.values
+                    .entry(field.clone())
+                    .and_modify(|open| open.merge(&aggregate))
+                    .or_insert(aggregate);
+            }
+            drop(open_minutes);
+            finished
+        };
+
+        append_line(
+            &self.chunk_path(machine, RAW_DIR, floor(t, RAW_CHUNK_MS)),
+            &RawLine { t, v: values },
+        )?;
+
+        if let Some(minute) = finished_minute {
+            let line = MinuteLine {
+                t: minute.t,
+                v: minute
+                    .values
+                    .into_iter()
+                    .map(|(field, a)| (field, (a.min, a.max, a.sum, a.count)))
+                    .collect(),
+            };
+            append_line(
+                &self.chunk_path(machine, MINUTE_DIR, floor(minute.t, MINUTE_CHUNK_MS)),
+                &line,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Last recorded values of every machine which were recorded after `since`
+    pub fn latest(&self, since: u64) -> Vec<(MachineIdentificationUnique, BTreeMap<String, f64>)> {
+        let mut latest = self.latest.lock().expect("history lock poisoned");
+        // machines which were removed
+        latest.retain(|_, (t, _)| *t >= since);
+        latest
+            .iter()
+            .map(|(machine, (_, values))| (machine.clone(), values.clone()))
+            .collect()
+    }
+
+    pub fn query(
+        &self,
+        machine: &MachineIdentificationUnique,
+        query: &HistoryQuery,
+        now: u64,
+    ) -> Result<HistoryQueryResult> {
+        if query.from >= query.to {
+            bail!("`from` must be before `to`");
+        }
+
+        let raw_oldest = now.saturating_sub(self.config.raw_retention_hours * HOUR_MS);
+        let resolution = match query.resolution {
+            Resolution::Auto
+                if query.to - query.from <= AUTO_RAW_MAX_RANGE_MS && query.from >= raw_oldest =>
+            {
+                Resolution::Raw
+            }
+            Resolution::Auto => Resolution::Minute,
+            resolution => resolution,
+        };
+
+        let keep = |field: &String| {
+            query
+                .fields
+                .as_ref()
+                .is_none_or(|fields| fields.contains(field))
+        };
+        let mut points: Vec<(u64, BTreeMap<String, Aggregate>)> = vec![];
+        match resolution {
+            Resolution::Raw => {
+                for path in self.chunks(machine, RAW_DIR, RAW_CHUNK_MS, query.from, query.to)? {
+                    read_lines(&path, |line: RawLine| {
+                        if (query.from..query.to).contains(&line.t) {
+                            let values = line
+                                .v
+                                .into_iter()
+                                .filter(|(field, _)| keep(field))
+                                .map(|(field, value)| (field, Aggregate::new(value)))
+                                .collect();
+                            points.push((line.t, values));
+                        }
+                    })?;
+                }
+            }
+            _ => {
+                for path in
+                    self.chunks(machine, MINUTE_DIR, MINUTE_CHUNK_MS, query.from, query.to)?
+                {
+                    read_lines(&path, |line: MinuteLine| {
+                        if (floor(query.from, MINUTE_MS)..query.to).contains(&line.t) {
+                            let values = line
+                                .v
+                                .into_iter()
+                                .filter(|(field, _)| keep(field))
+                                .map(|(field, (min, max, sum, count))| {
+                                    (
+                                        field,
+                                        Aggregate {
+                                            min,
+                                            max,
+                                            sum,
+                                            count,
+                                        },
+                                    )
+                                })
+                                .collect();
+                            points.push((line.t, values));
+                        }
+                    })?;
+                }
+            }
+        }
+        points.sort_by_key(|(t, _)| *t);
+
+        if let Some(bucket_ms) = query.bucket_ms {
+            if bucket_ms == 0 {
+                bail!("`bucket` must not be 0");
+            }
+            let mut buckets: Vec<(u64, BTreeMap<String, Aggregate>)> = vec![];
+            for (t, values) in points {
+                let bucket = floor(t, bucket_ms);
+                if buckets.last().is_none_or(|(last, _)| *last != bucket) {
+                    buckets.push((bucket, BTreeMap::new()));
+                }
+                let (_, bucket_values) = buckets.last_mut().expect("bucket was just pushed");
+                for (field, aggregate) in values {
+                    bucket_values
+                        .entry(field)
+                        .and_modify(|open| open.merge(&aggregate))
+                        .or_insert(aggregate);
+                }
+            }
+            points = buckets;
+        }
+
+        Ok(HistoryQueryResult {
+            resolution,
+            bucket_ms: query.bucket_ms,
+            aggregate: query.aggregate,
+            points: points
+                .into_iter()
+                .map(|(t, values)| HistoryPoint {
+                    t,
+                    values: values
+                        .into_iter()
+                        .map(|(field, aggregate)| (field, aggregate.get(query.aggregate)))
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Deletes chunks out of retention, then the oldest chunks until the history fits into `max_bytes`
+    pub fn prune(&self, now: u64) -> Result<()> {
+        let raw_oldest = now.saturating_sub(self.config.raw_retention_hours * HOUR_MS);
+        let minute_oldest = now.saturating_sub(self.config.minute_retention_days * DAY_MS);
+
+        // (chunk start, is raw, size, path)
+        let mut chunks: Vec<(u64, bool, u64, PathBuf)> = vec![];
+        if !self.dir.exists() {
+            return Ok(());
+        }
+        for machine_dir in std::fs::read_dir(&self.dir)? {
+            let machine_dir = machine_dir?.path();
+            if !machine_dir.is_dir() {
+                continue;
+            }
+            for (kind, chunk_ms, oldest) in [
+                (RAW_DIR, RAW_CHUNK_MS, raw_oldest),
+                (MINUTE_DIR, MINUTE_CHUNK_MS, minute_oldest),
+            ] {
+                for (start, path) in list_chunks(&machine_dir.join(kind))? {
+                    if start + chunk_ms <= oldest {
+                        std::fs::remove_file(&path)?;
+                        continue;
+                    }
+                    let size = std::fs::metadata(&path)?.len();
+                    chunks.push((start, kind == RAW_DIR, size, path));
+                }
+            }
+        }
+
+        let mut total: u64 = chunks.iter().map(|(_, _, size, _)| size).sum();
+        if total <= self.config.max_bytes {
+            return Ok(());
+        }
+
+        // raw samples go first, they are also covered by the minutes
+        chunks.sort_by_key(|(start, is_raw, _, _)| (!is_raw, *start));
+        for (_, _, size, path) in chunks {
+            if total <= self.config.max_bytes {
+                break;
+            }
+            std::fs::remove_file(&path)?;
+            total -= size;
+            tracing::info!("Deleted history chunk {} to stay in budget", path.display());
+        }
+        Ok(())
+    }
+
+    fn machine_dir(&self, machine: &MachineIdentificationUnique) -> PathBuf {
+        self.dir.join(format!(
+            "{}_{}_{}",
+            machine.machine_identification.vendor,
+            machine.machine_identification.machine,
+            machine.serial
+        ))
+    }
+
+    fn chunk_path(&self, machine: &MachineIdentificationUnique, kind: &str, start: u64) -> PathBuf {
+        self.machine_dir(machine)
+            .join(kind)
+            .join(format!("{start}.jsonl"))
+    }
+
+    /// Chunks which may contain points of `from..to`, oldest first
+    fn chunks(
+        &self,
+        machine: &MachineIdentificationUnique,
+        kind: &str,
+        chunk_ms: u64,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<PathBuf>> {
+        Ok(list_chunks(&self.machine_dir(machine).join(kind))?
+            .into_iter()
+            .filter(|(start, _)| *start < to && start + chunk_ms > from)
+            .map(|(_, path)| path)
+            .collect())
+    }
+}
+
+/// Numbers of nested live values as `a.b.c`, booleans as 0 and 1
+pub fn flatten_live_values(value: &Value) -> BTreeMap<String, f64> {
+    fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, f64>) {
+        let key = |name: &str| match prefix.is_empty() {
+            true => name.to_string(),
+            false => format!("{prefix}.{name}"),
+        };
+        match value {
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64().filter(|n| n.is_finite()) {
+                    out.insert(prefix.to_string(), number);
+                }
+            }
+            Value::Bool(b) => {
+                out.insert(prefix.to_string(), f64::from(u8::from(*b)));
+            }
+            Value::Object(map) => {
+                for (name, value) in map {
+                    flatten(&key(name), value, out);
+                }
+            }
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    flatten(&key(&i.to_string()), value, out);
+                }
+            }
+            Value::Null | Value::String(_) => (),
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    flatten("", value, &mut out);
+    // a bare number has no name
+    out.remove("");
+    out
+}
+
+const fn floor(t: u64, step: u64) -> u64 {
+    t - t % step
+}
+
+fn read_config(dir: &Path) -> Result<HistoryConfig> {
+    let path = dir.join(CONFIG_FILE);
+    if !path.exists() {
+        return Ok(HistoryConfig::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn append_line<T: Serialize>(path: &Path, line: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(line)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Skips lines which can't be parsed, e.g. cut off by a power loss
+fn read_lines<T: for<'de> Deserialize<'de>>(path: &Path, mut f: impl FnMut(T)) -> Result<()> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    for line in BufReader::new(file).lines() {
+        if let Ok(line) = serde_json::from_str(&line?) {
+            f(line);
+        }
+    }
+    Ok(())
+}
+
+/// Chunk files of a directory with their start, oldest first
+fn list_chunks(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut chunks: Vec<(u64, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let start = path.file_stem()?.to_str()?.parse().ok()?;
+            Some((start, path))
+        })
+        .collect();
+    chunks.sort_by_key(|(start, _)| *start);
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machines::machine_identification::MachineIdentification;
+    use machines::{MACHINE_EXTRUDER_V1, VENDOR_QITECH};
+    use serde_json::json;
+
+    const EXTRUDER: MachineIdentificationUnique = MachineIdentificationUnique {
+        machine_identification: MachineIdentification {
+            vendor: VENDOR_QITECH,
+            machine: MACHINE_EXTRUDER_V1,
+        },
+        serial: 1,
+    };
+
+    /// 2024-01-01T00:00:00Z
+    const START: u64 = 1_704_067_200_000;
+
+    fn temp_store(name: &str, config: HistoryConfig) -> HistoryStore {
+        let dir = std::env::temp_dir().join(format!("history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        HistoryStore::with_config(dir, config)
+    }
+
+    fn sample(value: f64) -> BTreeMap<String, f64> {
+        BTreeMap::from([("nozzle_temperature".to_string(), value)])
+    }
+
+    fn query(from: u64, to: u64, resolution: Resolution) -> HistoryQuery {
+        HistoryQuery {
+            from,
+            to,
+            fields: None,
+            resolution,
+            bucket_ms: None,
+            aggregate: AggregateFunction::Avg,
+        }
+    }
+
+    #[test]
+    fn test_flatten_live_values() {
+        let values = flatten_live_values(&json!({
+            "motor_status": { "rpm": 10.5, "frequency": 50 },
+            "heating": [true, false],
+            "mode": "Extrude",
+            "pressure": null,
+        }));
+        assert_eq!(
+            values,
+            BTreeMap::from([
+                ("heating.0".to_string(), 1.0),
+                ("heating.1".to_string(), 0.0),
+                ("motor_status.frequency".to_string(), 50.0),
+                ("motor_status.rpm".to_string(), 10.5),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_raw_and_minute() {
+        let store = temp_store("query", HistoryConfig::default());
+        // one sample every 10 s for 3 minutes, 0 to 17
+        for i in 0..18 {
+            store
+                .record(&EXTRUDER, START + i * 10_000, sample(i as f64))
+                .unwrap();
+        }
+        let now = START + 3 * MINUTE_MS;
+        assert_eq!(store.latest(START), vec![(EXTRUDER, sample(17.0))]);
+        assert!(store.latest(now).is_empty());
+
+        let raw = store
+            .query(
+                &EXTRUDER,
+                &query(START, START + 30_000, Resolution::Auto),
+                now,
+            )
+            .unwrap();
+        assert_eq!(raw.resolution, Resolution::Raw);
+        assert_eq!(
+            raw.points.iter().map(|p| p.t - START).collect::<Vec<_>>(),
+            vec![0, 10_000, 20_000]
+        );
+
+        // the third minute is still open
+        let minutes = store
+            .query(&EXTRUDER, &query(START, now, Resolution::Minute), now)
+            .unwrap();
+        let avg: Vec<f64> = minutes
+            .points
+            .iter()
+            .map(|p| p.values["nozzle_temperature"])
+            .collect();
+        assert_eq!(avg, vec![2.5, 8.5]);
+
+        let mut max = query(START, now, Resolution::Raw);
+        max.bucket_ms = Some(90_000);
+        max.aggregate = AggregateFunction::Max;
+        let max = store.query(&EXTRUDER, &max, now).unwrap();
+        let max: Vec<f64> = max
+            .points
+            .iter()
+            .map(|p| p.values["nozzle_temperature"])
+            .collect();
+        assert_eq!(max, vec![8.0, 17.0]);
+
+        assert!(
+            store
+                .query(&EXTRUDER, &query(now, START, Resolution::Raw), now)
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_csv() {
+        let result = HistoryQueryResult {
+            resolution: Resolution::Raw,
+            bucket_ms: None,
+            aggregate: AggregateFunction::Avg,
+            points: vec![
+                HistoryPoint {
+                    t: 1000,
+                    values: BTreeMap::from([("a".to_string(), 1.5)]),
+                },
+                HistoryPoint {
+                    t: 2000,
+                    values: BTreeMap::from([("b".to_string(), 2.0)]),
+                },
+            ],
+        };
+        assert_eq!(result.to_csv(), "timestamp_ms,a,b\n1000,1.5,\n2000,,2\n");
+    }
+
+    #[test]
+    fn test_prune() {
+        let store = temp_store(
+            "prune",
+            HistoryConfig {
+                raw_retention_hours: 1,
+                ..Default::default()
+            },
+        );
+        for hour in 0..3 {
+            store
+                .record(&EXTRUDER, START + hour * HOUR_MS, sample(1.0))
+                .unwrap();
+        }
+        let raw_dir = store.machine_dir(&EXTRUDER).join(RAW_DIR);
+        assert_eq!(list_chunks(&raw_dir).unwrap().len(), 3);
+
+        store.prune(START + 2 * HOUR_MS + MINUTE_MS).unwrap();
+        let starts: Vec<u64> = list_chunks(&raw_dir)
+            .unwrap()
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(starts, vec![START + HOUR_MS, START + 2 * HOUR_MS]);
+
+        // the budget deletes raw chunks before minutes
+        let store = HistoryStore::with_config(
+            store.dir,
+            HistoryConfig {
+                max_bytes: 1,
+                ..Default::default()
+            },
+        );
+        store.prune(START + 2 * HOUR_MS).unwrap();
+        assert!(list_chunks(&raw_dir).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use machines::machine_identification::MachineIdentificationUnique;
+use machines::{MachineMessage, MachineValues};
+use smol::channel::Receiver;
+
+use super::flatten_live_values;
+use crate::app_state::SharedState;
+
+/// How often retention and the disk budget are enforced
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Samples the live values of every machine and records them in the history
+///
+/// Machines are polled independently of socket.io subscriptions. A machine has at most one
+/// pending request, the receiver is never dropped before the machine answered.
+pub async fn start_history_recorder(app_state: Arc<SharedState>) {
+    let interval = Duration::from_millis(app_state.history.config().sample_interval_ms.max(100));
+    let pending: Arc<Mutex<HashSet<MachineIdentificationUnique>>> = Arc::default();
+    let mut last_prune: Option<std::time::Instant> = None;
+
+    loop {
+        smol::Timer::after(interval).await;
+
+        let machines: Vec<_> = app_state
+            .api_machines
+            .lock()
+            .await
+            .iter()
+            .map(|(id, sender)| (id.clone(), sender.clone()))
+            .collect();
+
+        for (id, sender) in machines {
+            if !pending
+                .lock()
+                .expect("pending lock poisoned")
+                .insert(id.clone())
+            {
+                continue;
+            }
+            let (values_sender, values_receiver) = smol::channel::bounded(1);
+            if sender
+                .try_send(MachineMessage::RequestValues(values_sender))
+                .is_err()
+            {
+                pending.lock().expect("pending lock poisoned").remove(&id);
+                continue;
+            }
+            smol::spawn(record_values(
+                app_state.clone(),
+                pending.clone(),
+                id,
+                values_receiver,
+            ))
+            .detach();
+        }
+
+        if last_prune.is_none_or(|last| last.elapsed() >= PRUNE_INTERVAL) {
+            last_prune = Some(std::time::Instant::now());
+            let state = app_state.clone();
+            if let Err(e) = smol::unblock(move || state.history.prune(now_ms())).await {
+                tracing::error!("Failed to prune history\n{:?}", e);
+            }
+        }
+    }
+}
+
+async fn record_values(
+    app_state: Arc<SharedState>,
+    pending: Arc<Mutex<HashSet<MachineIdentificationUnique>>>,
+    id: MachineIdentificationUnique,
+    receiver: Receiver<MachineValues>,
+) {
+    // closed without values when the machine was removed
+    if let Ok(values) = receiver.recv().await {
+        let t = now_ms();
+        let values = flatten_live_values(&values.live_values);
+        let state = app_state.clone();
+        let machine = id.clone();
+        let res = smol::unblock(move || state.history.record(&machine, t, values)).await;
+        if let Err(e) = res {
+            tracing::error!("Failed to record history of {}\n{:?}", id, e);
+        }
+    }
+    pending.lock().expect("pending lock poisoned").remove(&id);
+}
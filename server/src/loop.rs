@@ -1,22 +1,44 @@
-use crate::app_state::{EthercatSetup, HotThreadMessage};
+use crate::app_state::{EthercatSetup, HotThreadMessage, SharedState};
+use crate::ethercat::config::MAX_SUBDEVICES;
+use crate::ethercat::dc::DcMonitor;
+use crate::ethercat::diagnosis::collect_diagnosis;
+use crate::ethercat::fault::{
+    MAX_CONSECUTIVE_TRANSIENT_ERRORS, SAFE_STOP_DURATION, is_transient_error, send_ethercat_fault,
+};
+use crate::ethercat::link_quality::LinkMonitor;
+use crate::ethercat::recovery::BusMonitor;
+use crate::ethercat::sdo::execute_sdo_operation;
 use crate::performance_metrics::EthercatPerformanceMetrics;
+use crate::socketio::main_namespace::ethercat_fault_event::EthercatFaultEvent;
+use anyhow::Context;
 use bitvec::prelude::*;
 use control_core::realtime::set_core_affinity;
 #[cfg(not(feature = "development-build"))]
 use control_core::realtime::set_realtime_priority;
+use ethercrab::SubDeviceState;
 use machines::Machine;
+use machines::machine_identification::MachineIdentificationUnique;
 use machines::machine_identification::write_machine_device_identification;
 use smol::channel::Receiver;
 use spin_sleep::SpinSleeper;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
+use crate::metrics::histogram::LOOP_PERIOD;
 use crate::metrics::jitter::record_machines_loop_jitter;
 use crate::metrics::preemption::set_rt_loop_tid;
+use crate::metrics::profiling::{LoopProfiler, Phase};
 pub struct RtLoopInputs<'a> {
     pub machines: &'a mut Vec<Box<dyn Machine>>,
     pub ethercat_setup: Option<Box<EthercatSetup>>,
+    pub bus_monitor: BusMonitor,
+    pub link_monitor: LinkMonitor,
+    /// Reads the diagnosis history of the current setup, dropping it stops the task
+    pub diagnosis_task: Option<smol::Task<()>>,
+    pub dc_monitor: DcMonitor,
     pub ethercat_perf_metrics: Option<&'a mut EthercatPerformanceMetrics>,
+    pub profiler: LoopProfiler,
     pub sleeper: SpinSleeper,
     pub cycle_target: Duration,
 }
@@ -24,6 +46,7 @@ pub struct RtLoopInputs<'a> {
 // 300 us loop cycle target
 // SharedState is mostly read from and rarely locked, but does not contain any machine,ethercat devices etc
 pub fn start_loop_thread(
+    app_state: Arc<SharedState>,
     rt_receiver: Receiver<HotThreadMessage>,
     cycle_target: Duration,
 ) -> Result<std::thread::JoinHandle<()>, std::io::Error> {
@@ -74,12 +97,18 @@ pub fn start_loop_thread(
             let mut rt_loop_inputs = RtLoopInputs {
                 machines: &mut machines,
                 ethercat_setup: None,
+                bus_monitor: BusMonitor::default(),
+                link_monitor: LinkMonitor::default(),
+                diagnosis_task: None,
+                dc_monitor: DcMonitor::default(),
                 sleeper,
                 cycle_target,
                 ethercat_perf_metrics: Some(&mut ethercat_perf),
+                profiler: LoopProfiler::default(),
             };
 
-            loop {
+            let mut transient_errors = 0;
+            let error = loop {
                 let msg = match rt_receiver.try_recv() {
                     Ok(msg) => msg,
                     Err(_) => HotThreadMessage::NoMsg,
@@ -89,21 +118,58 @@ pub fn start_loop_thread(
                     HotThreadMessage::NoMsg => {}
                     HotThreadMessage::AddEtherCatSetup(ethercat_setup) => {
                         println!("EthercatSetup: {:?}", ethercat_setup.devices);
+                        rt_loop_inputs.diagnosis_task = Some(smol::spawn(collect_diagnosis(
+                            app_state.clone(),
+                            ethercat_setup.maindevice,
+                            ethercat_setup.group.clone(),
+                        )));
                         rt_loop_inputs.ethercat_setup = Some(Box::new(ethercat_setup));
+                        rt_loop_inputs.bus_monitor = BusMonitor::default();
+                        rt_loop_inputs.link_monitor = LinkMonitor::default();
+                        rt_loop_inputs.dc_monitor = DcMonitor::default();
                     }
-                    HotThreadMessage::WriteMachineDeviceInfo(info_request) => {
-                        if let Some(ethercat_setup) = &rt_loop_inputs.ethercat_setup {
-                            if let Ok(subdevice) = ethercat_setup.group.subdevice(
-                                &ethercat_setup.maindevice,
-                                info_request
-                                    .hardware_identification_ethercat
-                                    .subdevice_index,
-                            ) {
-                                let _res = smol::block_on(write_machine_device_identification(
-                                    &subdevice,
-                                    &ethercat_setup.maindevice,
-                                    &info_request.device_machine_identification,
-                                ));
+                    HotThreadMessage::WriteMachineDeviceInfo(info_request, result) => {
+                        let res = match &rt_loop_inputs.ethercat_setup {
+                            Some(ethercat_setup) => ethercat_setup
+                                .group
+                                .subdevice(
+                                    ethercat_setup.maindevice,
+                                    info_request
+                                        .hardware_identification_ethercat
+                                        .subdevice_index,
+                                )
+                                .map_err(anyhow::Error::from)
+                                .and_then(|subdevice| {
+                                    smol::block_on(write_machine_device_identification(
+                                        &subdevice,
+                                        ethercat_setup.maindevice,
+                                        &info_request.device_machine_identification,
+                                    ))
+                                }),
+                            None => Err(anyhow::anyhow!("No EtherCAT setup")),
+                        };
+                        let _ = result.try_send(res.map_err(|e| e.to_string()));
+                    }
+                    HotThreadMessage::Sdo(request, result) => {
+                        match &rt_loop_inputs.ethercat_setup {
+                            // mailbox round trips would delay the cycles
+                            Some(ethercat_setup) => {
+                                let maindevice = ethercat_setup.maindevice;
+                                let group = ethercat_setup.group.clone();
+                                smol::spawn(async move {
+                                    let res = execute_sdo_operation(
+                                        maindevice,
+                                        &group,
+                                        request.subdevice_index,
+                                        &request.operation,
+                                    )
+                                    .await;
+                                    let _ = result.send(res.map_err(|e| e.to_string())).await;
+                                })
+                                .detach();
+                            }
+                            None => {
+                                let _ = result.try_send(Err("No EtherCAT setup".to_string()));
                             }
                         }
                     }
@@ -132,25 +198,100 @@ pub fn start_loop_thread(
                         let jitter_ns = period.as_nanos() as i128
                             - rt_loop_inputs.cycle_target.as_nanos() as i128;
                         record_machines_loop_jitter(jitter_ns);
+                        LOOP_PERIOD.observe(period);
                     }
                 }
                 last_iter_start = Some(iter_start);
 
-                if let Err(e) = loop_once(&mut rt_loop_inputs) {
-                    tracing::error!(
-                        "Loop failed\n {:?} \n Last Loop Took: {:?}",
-                        e,
-                        rt_loop_inputs
-                            .ethercat_perf_metrics
-                            .unwrap()
-                            .last_loop_start
-                            .unwrap()
-                            .elapsed()
-                    );
-                    break;
+                let result = loop_once(&mut rt_loop_inputs);
+                if let Err(e) = &result {
+                    rt_loop_inputs.link_monitor.count_error(e);
+                }
+                match result {
+                    Ok(()) => {
+                        transient_errors = 0;
+                        // without EtherCAT the loop sleeps coarsely on purpose
+                        if rt_loop_inputs.ethercat_setup.is_some() {
+                            if let Some(report) = rt_loop_inputs
+                                .profiler
+                                .finish_cycle(rt_loop_inputs.cycle_target)
+                            {
+                                tracing::warn!("RT loop overrun: {}", report);
+                            }
+                        }
+                        if let Some(setup) = rt_loop_inputs.ethercat_setup.as_deref() {
+                            rt_loop_inputs
+                                .bus_monitor
+                                .check(setup, &app_state, iter_start);
+                            rt_loop_inputs
+                                .link_monitor
+                                .poll(setup, &app_state, iter_start);
+                            rt_loop_inputs.dc_monitor.poll(setup, iter_start);
+                        }
+                    }
+                    Err(e)
+                        if is_transient_error(&e)
+                            && transient_errors < MAX_CONSECUTIVE_TRANSIENT_ERRORS =>
+                    {
+                        transient_errors += 1;
+                        tracing::warn!(
+                            "Loop cycle failed, retrying ({}/{})\n {:?}",
+                            transient_errors,
+                            MAX_CONSECUTIVE_TRANSIENT_ERRORS,
+                            e
+                        );
+                    }
+                    // the bus stays unusable, e.g. a cable was pulled, until the recovery gives up
+                    Err(e)
+                        if is_transient_error(&e)
+                            && rt_loop_inputs.ethercat_setup.is_some()
+                            && rt_loop_inputs.bus_monitor.can_recover_bus(iter_start) =>
+                    {
+                        tracing::error!("EtherCAT bus failed, recovering the SubDevices\n {:?}", e);
+                        transient_errors = 0;
+                        if let Some(setup) = rt_loop_inputs.ethercat_setup.as_deref() {
+                            rt_loop_inputs
+                                .bus_monitor
+                                .recover_bus(setup, &app_state, iter_start);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            "Loop failed\n {:?} \n Last Loop Took: {:?}",
+                            e,
+                            rt_loop_inputs
+                                .ethercat_perf_metrics
+                                .as_ref()
+                                .and_then(|metrics| metrics.last_loop_start)
+                                .map(|start| start.elapsed())
+                        );
+                        break e;
+                    }
                 }
+            };
+
+            // Don't leave heaters, pumps and motors in the state of the last cycle
+            let all_machines = rt_loop_inputs
+                .machines
+                .iter()
+                .map(|m| m.get_machine_identification_unique())
+                .collect::<Vec<_>>();
+            let outputs_written = safe_stop_machines(&mut rt_loop_inputs, &all_machines);
+            if outputs_written {
+                tracing::info!("Machines were stopped safely");
+            } else {
+                tracing::error!("Failed to write the safe outputs to the EtherCAT devices");
             }
 
+            smol::block_on(send_ethercat_fault(
+                app_state,
+                EthercatFaultEvent {
+                    error: format!("{:?}", error),
+                    machines: all_machines,
+                    outputs_written,
+                },
+            ));
+
             // Exit the entire program if the Loop fails
             // gets restarted by systemd if running on NixOS, or different distro wtih the same sysd service
             std::process::exit(1);
@@ -158,22 +299,54 @@ pub fn start_loop_thread(
     return res;
 }
 
+/// Result of the tx/rx of one cycle
+#[derive(Debug)]
+pub struct CycleResponse {
+    pub working_counter: u16,
+    pub subdevice_states: heapless::Vec<SubDeviceState, MAX_SUBDEVICES>,
+    /// System time when the frame passed the DC reference, only with distributed clocks
+    pub dc_system_time: Option<u64>,
+}
+
 pub async fn copy_ethercat_inputs(
     ethercat_setup: Option<&EthercatSetup>,
-) -> Result<(), anyhow::Error> {
+) -> Result<Option<CycleResponse>, anyhow::Error> {
     // only if we have an ethercat setup
     // - tx/rx cycle
     // - copy inputs to devices
+    let mut response = None;
     if let Some(ethercat_setup) = ethercat_setup {
-        ethercat_setup
-            .group
-            .tx_rx(&ethercat_setup.maindevice)
-            .await?;
+        response = Some(match &ethercat_setup.dc {
+            // drift compensation of all clocks with the reference in the same frame
+            Some(dc) => {
+                dc.write_host_time(ethercat_setup.maindevice).await?;
+                let response = ethercat_setup
+                    .group
+                    .tx_rx_sync_system_time(ethercat_setup.maindevice)
+                    .await?;
+                CycleResponse {
+                    working_counter: response.working_counter,
+                    subdevice_states: response.subdevice_states,
+                    dc_system_time: response.extra,
+                }
+            }
+            None => {
+                let response = ethercat_setup
+                    .group
+                    .tx_rx(ethercat_setup.maindevice)
+                    .await?;
+                CycleResponse {
+                    working_counter: response.working_counter,
+                    subdevice_states: response.subdevice_states,
+                    dc_system_time: None,
+                }
+            }
+        });
 
         // copy inputs to devices
         for (i, subdevice) in ethercat_setup
             .group
-            .iter(&ethercat_setup.maindevice)
+            .iter(ethercat_setup.maindevice)
             .enumerate()
         {
             // retrieve inputs
@@ -210,7 +383,7 @@ pub async fn copy_ethercat_inputs(
             })?;
         }
     }
-    Ok(())
+    Ok(response)
 }
 
 pub async fn copy_ethercat_outputs(
@@ -220,7 +393,7 @@ pub async fn copy_ethercat_outputs(
         // copy outputs from devices
         for (i, subdevice) in ethercat_setup
             .group
-            .iter(&ethercat_setup.maindevice)
+            .iter(ethercat_setup.maindevice)
             .enumerate()
         {
             // get output buffer for device
@@ -260,49 +433,142 @@ pub async fn copy_ethercat_outputs(
     Ok(())
 }
 
-pub fn execute_machines(machines: &mut Vec<Box<dyn Machine>>) {
+/// Drives `machines_to_stop` into their safe state and writes all outputs for [`SAFE_STOP_DURATION`]
+///
+/// Failing SubDevices are skipped, so the remaining ones still get their safe outputs.
+/// Returns if the outputs reached the devices at least once.
+pub fn safe_stop_machines(
+    inputs: &mut RtLoopInputs<'_>,
+    machines_to_stop: &[MachineIdentificationUnique],
+) -> bool {
+    let mut outputs_written = inputs.ethercat_setup.is_none();
+    let start = Instant::now();
+    while start.elapsed() < SAFE_STOP_DURATION {
+        let cycle_start = Instant::now();
+
+        // machines keep running so queued requests like stopping an inverter still go out,
+        // but safe_stop overrides whatever act did
+        for machine in inputs.machines.iter_mut() {
+            machine.act(cycle_start);
+            if machines_to_stop.contains(&machine.get_machine_identification_unique()) {
+                machine.safe_stop();
+            }
+        }
+
+        if let Some(ethercat_setup) = inputs.ethercat_setup.as_deref() {
+            smol::block_on(copy_ethercat_outputs_unchecked(ethercat_setup));
+            let res = smol::block_on(ethercat_setup.group.tx_rx(ethercat_setup.maindevice));
+            if res.is_ok() {
+                outputs_written = true;
+                smol::block_on(copy_ethercat_inputs_unchecked(ethercat_setup));
+            }
+        }
+
+        inputs
+            .sleeper
+            .sleep_until(cycle_start + inputs.cycle_target);
+    }
+    outputs_written
+}
+
+/// Like [`copy_ethercat_inputs`] without tx/rx, skips devices which fail
+async fn copy_ethercat_inputs_unchecked(ethercat_setup: &EthercatSetup) {
+    for (i, subdevice) in ethercat_setup
+        .group
+        .iter(ethercat_setup.maindevice)
+        .enumerate()
+    {
+        let input = subdevice.inputs_raw();
+        let mut device = ethercat_setup.devices[i].1.as_ref().write().await;
+        if !device.is_used() {
+            continue;
+        }
+        if device.input_checked(input.view_bits::<Lsb0>()).is_ok() {
+            let _ = device.input_post_process();
+        }
+    }
+}
+
+/// Like [`copy_ethercat_outputs`], skips devices which fail
+async fn copy_ethercat_outputs_unchecked(ethercat_setup: &EthercatSetup) {
+    for (i, subdevice) in ethercat_setup
+        .group
+        .iter(ethercat_setup.maindevice)
+        .enumerate()
+    {
+        let mut output = subdevice.outputs_raw_mut();
+        let mut device = ethercat_setup.devices[i].1.as_ref().write().await;
+        if !device.is_used() {
+            continue;
+        }
+        if device.output_pre_process().is_ok() {
+            let _ = device.output_checked(output.view_bits_mut::<Lsb0>());
+        }
+    }
+}
+
+pub fn execute_machines(machines: &mut Vec<Box<dyn Machine>>, profiler: &mut LoopProfiler) {
     let now = Instant::now();
     for machine in machines.iter_mut() {
+        let act_start = Instant::now();
         machine.act(now);
+        profiler.record_machine(
+            &machine.get_machine_identification_unique(),
+            act_start.elapsed(),
+        );
+    }
+    if profiler.machine_count() != machines.len() {
+        let ids: Vec<_> = machines
+            .iter()
+            .map(|machine| machine.get_machine_identification_unique())
+            .collect();
+        profiler.retain_machines(&ids);
     }
 }
 // No more logging in loop_once
 pub fn loop_once<'maindevice>(inputs: &mut RtLoopInputs<'_>) -> Result<(), anyhow::Error> {
     let loop_once_start = std::time::Instant::now();
+    inputs.profiler.start_cycle(loop_once_start);
+    // with a DC reference SubDevice the cycles follow its clock, otherwise the cycle target
+    let mut next_cycle_wait = None;
     if inputs.ethercat_setup.is_some() && inputs.ethercat_perf_metrics.is_some() {
-        inputs
-            .ethercat_perf_metrics
-            .as_deref_mut()
-            .unwrap()
-            .cycle_start();
-
-        let res = smol::block_on(copy_ethercat_inputs(inputs.ethercat_setup.as_deref()));
-        match res {
-            Ok(_) => (),
-            Err(e) => {
-                return Err(anyhow::anyhow!("copy_ethercat_inputs failed: {:?}", e));
+        let perf_metrics = inputs.ethercat_perf_metrics.as_deref_mut().unwrap();
+        perf_metrics.cycle_start();
+
+        let txrx_start = Instant::now();
+        let response = smol::block_on(copy_ethercat_inputs(inputs.ethercat_setup.as_deref()))
+            .context("copy_ethercat_inputs failed")?;
+        perf_metrics.add_txrx_time(txrx_start.elapsed());
+        if let (Some(response), Some(setup)) = (response, inputs.ethercat_setup.as_deref()) {
+            inputs.bus_monitor.set_states(&response.subdevice_states);
+            inputs
+                .link_monitor
+                .check_working_counter(setup, response.working_counter);
+            if let (Some(dc), Some(dc_system_time)) = (&setup.dc, response.dc_system_time) {
+                inputs.dc_monitor.record_cycle(dc, dc_system_time);
+                next_cycle_wait = dc.next_cycle_wait(dc_system_time);
             }
-        };
+        }
     }
 
-    execute_machines(&mut inputs.machines);
+    inputs.profiler.lap(Phase::InputCopy);
+
+    execute_machines(inputs.machines, &mut inputs.profiler);
+    inputs.bus_monitor.stop_machines(inputs.machines);
+    inputs.profiler.lap(Phase::Act);
 
     if inputs.ethercat_setup.is_some() && inputs.ethercat_perf_metrics.is_some() {
-        let res = smol::block_on(copy_ethercat_outputs(inputs.ethercat_setup.as_deref()));
-        match res {
-            Ok(_) => (),
-            Err(e) => {
-                return Err(anyhow::anyhow!("copy_ethercat_outputs failed: {:?}", e));
-            }
-        };
+        smol::block_on(copy_ethercat_outputs(inputs.ethercat_setup.as_deref()))
+            .context("copy_ethercat_outputs failed")?;
     }
+    inputs.profiler.lap(Phase::OutputCopy);
 
     if inputs.ethercat_setup.is_some() {
         // spin_sleep so we have a cycle time of ~300us
         // This does push usage to 100% if completely busy, but provides much better accuracy then thread sleep or async sleep
         inputs
             .sleeper
-            .sleep_until(loop_once_start + inputs.cycle_target);
+            .sleep_until(loop_once_start + next_cycle_wait.unwrap_or(inputs.cycle_target));
     } else {
         // if we dont have an ethercat setup or other rt relevant stuff do the "worse" async sleep or later if we get rid of async thread::sleep or yielding
         // We do this, so that when no rt relevant code runs the cpu doesnt spin at 100% for no reason
@@ -311,6 +577,7 @@ pub fn loop_once<'maindevice>(inputs: &mut RtLoopInputs<'_>) -> Result<(), anyho
             smol::block_on(smol::Timer::after(inputs.cycle_target - loop_duration));
         }
     }
+    inputs.profiler.lap(Phase::Sleep);
 
     Ok(())
 }
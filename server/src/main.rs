@@ -19,6 +19,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use utils::start_dnsmasq;
 
 use app_state::{HotThreadMessage, SharedState};
+use ethercat::config::CYCLE_TARGET_TIME;
 use ethercat::ethercat_discovery_info::send_ethercat_discovering;
 use r#loop::start_loop_thread;
 use metrics::io::set_ethercat_iface;
@@ -41,6 +42,7 @@ use crate::{
         ethercat_discovery_info::send_ethercat_found, init::find_ethercat_interface,
         setup::setup_loop,
     },
+    history::recorder::start_history_recorder,
     modbus_tcp::start_modbus_tcp_discovery,
     socketio::queue::socketio_queue_worker,
 };
@@ -49,13 +51,17 @@ use crate::{
 pub mod mock_init;
 
 pub mod app_state;
+pub mod audit;
+pub mod auth;
 pub mod ethercat;
+pub mod history;
 pub mod logging;
 pub mod r#loop;
 pub mod metrics;
 pub mod modbus_tcp;
 pub mod panic;
 pub mod performance_metrics;
+pub mod recipes;
 pub mod rest;
 pub mod socketio;
 pub mod utils;
@@ -255,8 +261,9 @@ async fn handle_async_requests(recv: Receiver<AsyncThreadMessage>, shared_state:
             }
             AsyncThreadMessage::DisconnectMachines(cross_connection) => {
                 let api_machines_guard = shared_state.api_machines.lock().await;
-                // The Src Connection is from the machine that recvs the request to connect
-                // The Dest Connection the machine to which should be connected
+                // The Src Connection is from the machine that recvs the request to disconnect
+                // The Dest Connection the machine from which should be disconnected,
+                // it drops its connection back to the Src as well
                 let src_ident = cross_connection.src;
                 let dest_ident = cross_connection.dest;
                 let src_sender = match api_machines_guard.get(&src_ident) {
@@ -269,22 +276,34 @@ async fn handle_async_requests(recv: Receiver<AsyncThreadMessage>, shared_state:
                     None => continue,
                 };
 
-                let connection = MachineConnection {
-                    ident: dest_ident.clone(),
-                    connection: dest_sender.clone(),
-                };
-
-                let res = src_sender
-                    .send(machines::MachineMessage::DisconnectMachine(connection))
-                    .await;
-                match res {
-                    Ok(_) => (),
-                    Err(e) => tracing::error!(
-                        "AsyncThreadMessage::DisconnectMachines src:{:?} dest:{:?} error:{:?}",
-                        src_ident,
-                        dest_ident,
-                        e
+                for (sender, connection) in [
+                    (
+                        src_sender,
+                        MachineConnection {
+                            ident: dest_ident.clone(),
+                            connection: dest_sender.clone(),
+                        },
                     ),
+                    (
+                        dest_sender,
+                        MachineConnection {
+                            ident: src_ident.clone(),
+                            connection: src_sender.clone(),
+                        },
+                    ),
+                ] {
+                    let res = sender
+                        .send(machines::MachineMessage::DisconnectMachine(connection))
+                        .await;
+                    match res {
+                        Ok(_) => (),
+                        Err(e) => tracing::error!(
+                            "AsyncThreadMessage::DisconnectMachines src:{:?} dest:{:?} error:{:?}",
+                            src_ident,
+                            dest_ident,
+                            e
+                        ),
+                    }
                 }
             }
         }
@@ -331,14 +350,12 @@ fn main() {
     #[cfg(feature = "development-build")]
     let running = setup_ctrlc_handler();
 
-    const CYCLE_TARGET_TIME: Duration = Duration::from_micros(700);
-
     // for the "hot thread"
     let (sender, receiver) = smol::channel::unbounded();
     let (main_sender, main_receiver) = smol::channel::unbounded();
     let shared_state = SharedState::new(sender.clone(), main_sender);
     let app_state = Arc::new(shared_state);
-    let _loop_thread = start_loop_thread(receiver, CYCLE_TARGET_TIME);
+    let _loop_thread = start_loop_thread(app_state.clone(), receiver, CYCLE_TARGET_TIME);
     let _ = start_api_thread(app_state.clone());
 
     spawn_runtime_metrics_sampler(RuntimeMetricsConfig {
@@ -359,6 +376,8 @@ fn main() {
 
     smol::spawn(start_modbus_tcp_discovery(app_state.clone())).detach();
 
+    smol::spawn(start_history_recorder(app_state.clone())).detach();
+
     smol::block_on(async {
         send_empty_machines_event(app_state.clone()).await;
         send_ethercat_discovering(app_state.clone()).await;
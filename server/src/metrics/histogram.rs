@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets in nanoseconds, 25µs to 50ms.
+///
+/// Dense around the cycle target of the RT loop.
+pub const DURATION_BUCKETS_NS: [u64; 15] = [
+    25_000, 50_000, 100_000, 200_000, 300_000, 400_000, 500_000, 700_000, 1_000_000, 1_500_000,
+    2_000_000, 3_000_000, 5_000_000, 10_000_000, 50_000_000,
+];
+
+/// Histogram of durations which can be written from the RT loop without locking.
+#[derive(Debug)]
+pub struct DurationHistogram {
+    /// Non-cumulative counts per bucket, the last one is `+Inf`
+    buckets: [AtomicU64; DURATION_BUCKETS_NS.len() + 1],
+    sum_ns: AtomicU64,
+}
+
+/// Snapshot of a [`DurationHistogram`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Cumulative counts with their upper bound in nanoseconds, `None` is `+Inf`
+    pub buckets: Vec<(Option<u64>, u64)>,
+    pub sum_ns: u64,
+    pub count: u64,
+}
+
+impl HistogramSnapshot {
+    /// Upper bound of the bucket containing quantile `q` in nanoseconds, `None` if it is `+Inf` or empty
+    pub fn quantile_upper_bound(&self, q: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = (q * self.count as f64).ceil().max(1.0) as u64;
+        self.buckets
+            .iter()
+            .find(|(_, count)| *count >= rank)
+            .and_then(|(bound, _)| *bound)
+    }
+
+    pub const fn mean_ns(&self) -> Option<u64> {
+        self.sum_ns.checked_div(self.count)
+    }
+}
+
+impl DurationHistogram {
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; DURATION_BUCKETS_NS.len() + 1],
+            sum_ns: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let ns = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let index = DURATION_BUCKETS_NS.partition_point(|bound| *bound < ns);
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut count = 0;
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                count += bucket.load(Ordering::Relaxed);
+                (DURATION_BUCKETS_NS.get(i).copied(), count)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+            count,
+        }
+    }
+}
+
+impl Default for DurationHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Period of the RT loop, from one iteration start to the next.
+pub static LOOP_PERIOD: DurationHistogram = DurationHistogram::new();
+
+/// EtherCAT cycle time, recorded by [`crate::performance_metrics::EthercatPerformanceMetrics`].
+pub static ETHERCAT_CYCLE: DurationHistogram = DurationHistogram::new();
+
+/// EtherCAT tx/rx including copying the inputs to the devices.
+pub static ETHERCAT_TXRX: DurationHistogram = DurationHistogram::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_cumulative() {
+        let histogram = DurationHistogram::new();
+        histogram.observe(Duration::from_micros(10));
+        histogram.observe(Duration::from_micros(25));
+        histogram.observe(Duration::from_micros(600));
+        histogram.observe(Duration::from_secs(1));
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 4);
+        assert_eq!(snapshot.sum_ns, 1_000_635_000);
+        assert_eq!(snapshot.buckets[0], (Some(25_000), 2));
+        assert_eq!(snapshot.buckets[6], (Some(500_000), 2));
+        assert_eq!(snapshot.buckets[7], (Some(700_000), 3));
+        assert_eq!(snapshot.buckets.last(), Some(&(None, 4)));
+        assert_eq!(snapshot.quantile_upper_bound(0.5), Some(25_000));
+        assert_eq!(snapshot.quantile_upper_bound(0.75), Some(700_000));
+        assert_eq!(snapshot.quantile_upper_bound(0.99), None);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod collector;
 pub mod csv_writer;
+pub mod histogram;
 pub mod io;
 pub mod jitter;
+pub mod openmetrics;
 pub mod preemption;
 pub mod process;
+pub mod profiling;
 pub mod state;
@@ -0,0 +1,409 @@
+use std::fmt::Write;
+
+use machines::machine_identification::MachineIdentificationUnique;
+use machines::registry::MACHINE_REGISTRY;
+
+use crate::app_state::SharedState;
+use crate::ethercat::dc::{DC_CYCLE_OFFSET, time_differences};
+use crate::history::recorder::now_ms;
+use crate::metrics::histogram::{
+    DurationHistogram, ETHERCAT_CYCLE, ETHERCAT_TXRX, HistogramSnapshot, LOOP_PERIOD,
+};
+use crate::metrics::io::{get_ethercat_iface, read_netdev_counters};
+use crate::metrics::preemption::{get_rt_loop_tid, read_thread_sched_stats};
+use crate::metrics::process::ProcessMetrics;
+use crate::metrics::profiling::{Phase, machine_histograms, overrun_count, phase_histogram};
+use crate::metrics::state::get_latest_runtime_sample;
+
+/// Content type of [`render_openmetrics`].
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Live values which were not updated for this long belong to removed machines.
+const LIVE_VALUES_MAX_AGE_MS: u64 = 10_000;
+
+/// Writes metric families in the OpenMetrics text format.
+#[derive(Debug, Default)]
+pub struct OpenMetricsWriter {
+    out: String,
+}
+
+impl OpenMetricsWriter {
+    fn family(&mut self, name: &str, kind: &str, unit: Option<&str>, help: &str) {
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+        if let Some(unit) = unit {
+            let _ = writeln!(self.out, "# UNIT {name} {unit}");
+        }
+        let _ = writeln!(self.out, "# HELP {name} {}", escape(help));
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{key}=\"{}\"", escape(value));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+
+    /// Gauge family, `unit` has to be the suffix of `name`
+    pub fn gauge(
+        &mut self,
+        name: &str,
+        unit: Option<&str>,
+        help: &str,
+        samples: &[(Vec<(&str, &str)>, f64)],
+    ) {
+        self.family(name, "gauge", unit, help);
+        for (labels, value) in samples {
+            self.sample(name, labels, *value);
+        }
+    }
+
+    /// Counter family, the samples get the `_total` suffix
+    pub fn counter(
+        &mut self,
+        name: &str,
+        unit: Option<&str>,
+        help: &str,
+        samples: &[(Vec<(&str, &str)>, f64)],
+    ) {
+        self.family(name, "counter", unit, help);
+        let total = format!("{name}_total");
+        for (labels, value) in samples {
+            self.sample(&total, labels, *value);
+        }
+    }
+
+    /// Histogram family of durations in seconds
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        series: &[(Vec<(&str, &str)>, HistogramSnapshot)],
+    ) {
+        self.family(name, "histogram", Some("seconds"), help);
+        let (bucket, count, sum) = (
+            format!("{name}_bucket"),
+            format!("{name}_count"),
+            format!("{name}_sum"),
+        );
+        for (labels, snapshot) in series {
+            for (bound, bucket_count) in &snapshot.buckets {
+                let le = bound.map_or_else(|| "+Inf".to_string(), |ns| format_value(ns_to_s(ns)));
+                let mut labels = labels.clone();
+                labels.push(("le", &le));
+                self.sample(&bucket, &labels, *bucket_count as f64);
+            }
+            self.sample(&count, labels, snapshot.count as f64);
+            self.sample(&sum, labels, ns_to_s(snapshot.sum_ns));
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("# EOF\n");
+        self.out
+    }
+}
+
+/// Runtime, EtherCAT and machine metrics for Prometheus.
+pub fn render_openmetrics(app_state: &SharedState) -> String {
+    let mut w = OpenMetricsWriter::default();
+
+    let process = ProcessMetrics::collect();
+    w.gauge(
+        "qitech_process_resident_memory_bytes",
+        Some("bytes"),
+        "Resident set size of the server",
+        &[(vec![], process.rss_bytes as f64)],
+    );
+    w.counter(
+        "qitech_process_cpu_seconds",
+        Some("seconds"),
+        "User and system CPU time of the server",
+        &[(vec![], process.cpu_time_seconds)],
+    );
+    w.counter(
+        "qitech_process_page_faults",
+        None,
+        "Page faults of the server",
+        &[
+            (vec![("kind", "minor")], process.minor_faults as f64),
+            (vec![("kind", "major")], process.major_faults as f64),
+        ],
+    );
+
+    if let Some(stats) = get_rt_loop_tid().and_then(read_thread_sched_stats) {
+        w.counter(
+            "qitech_rt_context_switches",
+            None,
+            "Context switches of the RT loop thread, involuntary ones are preemptions",
+            &[
+                (
+                    vec![("kind", "voluntary")],
+                    stats.nr_voluntary_switches as f64,
+                ),
+                (
+                    vec![("kind", "involuntary")],
+                    stats.nr_involuntary_switches as f64,
+                ),
+            ],
+        );
+    }
+    if let Some(sample) = get_latest_runtime_sample() {
+        w.gauge(
+            "qitech_rt_loop_jitter_seconds",
+            Some("seconds"),
+            "Deviation of the RT loop period from the cycle target over the last 1024 cycles, negative is early",
+            &[
+                (vec![("stat", "min")], sample.jitter_min_ns as f64 / 1e9),
+                (vec![("stat", "avg")], sample.jitter_avg_ns as f64 / 1e9),
+                (vec![("stat", "max")], sample.jitter_max_ns as f64 / 1e9),
+            ],
+        );
+    }
+    histogram(
+        &mut w,
+        "qitech_rt_loop_period_seconds",
+        "Period of the RT loop",
+        &LOOP_PERIOD,
+    );
+    w.counter(
+        "qitech_rt_loop_overruns",
+        None,
+        "Cycles of the RT loop with EtherCAT which took longer than the cycle target",
+        &[(vec![], overrun_count() as f64)],
+    );
+    w.histogram(
+        "qitech_rt_phase_seconds",
+        "Duration of the phases of the RT loop",
+        &Phase::ALL
+            .iter()
+            .map(|phase| {
+                (
+                    vec![("phase", phase.name())],
+                    phase_histogram(*phase).snapshot(),
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    let mut machine_histograms = machine_histograms();
+    machine_histograms.sort_by_key(|(machine, _)| machine.to_string());
+    let labels: Vec<_> = machine_histograms
+        .iter()
+        .map(|(machine, _)| MachineLabels::new(machine))
+        .collect();
+    w.histogram(
+        "qitech_rt_machine_act_seconds",
+        "Duration of `act` per machine",
+        &machine_histograms
+            .iter()
+            .zip(&labels)
+            .map(|((_, histogram), labels)| (labels.as_labels(), histogram.snapshot()))
+            .collect::<Vec<_>>(),
+    );
+
+    histogram(
+        &mut w,
+        "qitech_ethercat_cycle_seconds",
+        "EtherCAT cycle time",
+        &ETHERCAT_CYCLE,
+    );
+    histogram(
+        &mut w,
+        "qitech_ethercat_txrx_seconds",
+        "EtherCAT tx/rx including copying the inputs to the devices",
+        &ETHERCAT_TXRX,
+    );
+    histogram(
+        &mut w,
+        "qitech_ethercat_dc_cycle_offset_seconds",
+        "Time from the last SYNC0 pulse until the frame passed the DC reference",
+        &DC_CYCLE_OFFSET,
+    );
+    let time_differences = time_differences();
+    let labels: Vec<_> = time_differences
+        .iter()
+        .map(|(subdevice_index, name, _)| (subdevice_index.to_string(), name.as_str()))
+        .collect();
+    w.gauge(
+        "qitech_ethercat_dc_system_time_difference_seconds",
+        Some("seconds"),
+        "Deviation of the DC clock of a synchronised SubDevice from the reference, positive is ahead",
+        &time_differences
+            .iter()
+            .zip(&labels)
+            .map(|((_, _, difference), (subdevice_index, name))| {
+                (
+                    vec![("subdevice_index", subdevice_index.as_str()), ("name", *name)],
+                    *difference as f64 / 1e9,
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    if let Some(iface) = get_ethercat_iface() {
+        if let Some(counters) = read_netdev_counters(iface) {
+            w.counter(
+                "qitech_ethercat_network_bytes",
+                Some("bytes"),
+                "Bytes received and transmitted on the EtherCAT interface",
+                &[
+                    (
+                        vec![("interface", iface), ("direction", "rx")],
+                        counters.rx_bytes as f64,
+                    ),
+                    (
+                        vec![("interface", iface), ("direction", "tx")],
+                        counters.tx_bytes as f64,
+                    ),
+                ],
+            );
+        }
+    }
+
+    let since = now_ms().saturating_sub(
+        LIVE_VALUES_MAX_AGE_MS.max(10 * app_state.history.config().sample_interval_ms),
+    );
+    let mut machines = app_state.history.latest(since);
+    machines.sort_by_key(|(machine, _)| machine.to_string());
+    let labels: Vec<_> = machines
+        .iter()
+        .map(|(machine, _)| MachineLabels::new(machine))
+        .collect();
+    let mut samples = vec![];
+    for ((_, values), labels) in machines.iter().zip(&labels) {
+        for (field, value) in values {
+            let mut labels = labels.as_labels();
+            labels.push(("field", field.as_str()));
+            samples.push((labels, *value));
+        }
+    }
+    w.gauge(
+        "qitech_machine_live_value",
+        None,
+        "Numeric live values of the machines, nested fields are joined with a dot",
+        &samples,
+    );
+
+    w.finish()
+}
+
+fn histogram(w: &mut OpenMetricsWriter, name: &str, help: &str, histogram: &DurationHistogram) {
+    w.histogram(name, help, &[(vec![], histogram.snapshot())]);
+}
+
+/// Labels which identify a machine
+struct MachineLabels {
+    vendor: String,
+    machine: String,
+    serial: String,
+    /// Empty if the machine type is not registered
+    slug: String,
+}
+
+impl MachineLabels {
+    fn new(machine: &MachineIdentificationUnique) -> Self {
+        let id = &machine.machine_identification;
+        Self {
+            vendor: id.vendor_str(),
+            machine: id.machine.to_string(),
+            serial: machine.serial.to_string(),
+            slug: MACHINE_REGISTRY
+                .get_descriptor(id)
+                .map_or_else(|_| String::new(), |d| d.slug.to_string()),
+        }
+    }
+
+    fn as_labels(&self) -> Vec<(&str, &str)> {
+        vec![
+            ("vendor", &self.vendor),
+            ("machine", &self.machine),
+            ("serial", &self.serial),
+            ("slug", &self.slug),
+        ]
+    }
+}
+
+fn ns_to_s(ns: u64) -> f64 {
+    ns as f64 / 1e9
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        match value.is_sign_positive() {
+            true => "+Inf".to_string(),
+            false => "-Inf".to_string(),
+        }
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_families() {
+        let mut w = OpenMetricsWriter::default();
+        w.gauge(
+            "qitech_machine_live_value",
+            None,
+            "Live values",
+            &[(vec![("field", "a\"b")], 1.5)],
+        );
+        w.counter(
+            "qitech_process_cpu_seconds",
+            Some("seconds"),
+            "CPU",
+            &[(vec![], 2.0)],
+        );
+        assert_eq!(
+            w.finish(),
+            "# TYPE qitech_machine_live_value gauge\n\
+             # HELP qitech_machine_live_value Live values\n\
+             qitech_machine_live_value{field=\"a\\\"b\"} 1.5\n\
+             # TYPE qitech_process_cpu_seconds counter\n\
+             # UNIT qitech_process_cpu_seconds seconds\n\
+             # HELP qitech_process_cpu_seconds CPU\n\
+             qitech_process_cpu_seconds_total 2\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = DurationHistogram::new();
+        histogram.observe(Duration::from_micros(300));
+        let mut w = OpenMetricsWriter::default();
+        w.histogram(
+            "qitech_test_seconds",
+            "Test",
+            &[(vec![("phase", "act")], histogram.snapshot())],
+        );
+        let text = w.finish();
+
+        assert!(text.contains("qitech_test_seconds_bucket{phase=\"act\",le=\"0.0002\"} 0\n"));
+        assert!(text.contains("qitech_test_seconds_bucket{phase=\"act\",le=\"0.0003\"} 1\n"));
+        assert!(text.contains("qitech_test_seconds_bucket{phase=\"act\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains(
+            "qitech_test_seconds_count{phase=\"act\"} 1\nqitech_test_seconds_sum{phase=\"act\"} 0.0003\n"
+        ));
+    }
+}
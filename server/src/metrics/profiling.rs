@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use machines::machine_identification::MachineIdentificationUnique;
+
+use crate::metrics::histogram::DurationHistogram;
+
+/// Spin sleeping overshoots by a few µs, cycles exceeding the target by more are overruns.
+const OVERRUN_TOLERANCE: Duration = Duration::from_micros(50);
+
+/// Overruns are logged at most this often.
+const OVERRUN_LOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Phases of one RT loop cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// EtherCAT tx/rx and copying the inputs to the devices
+    InputCopy,
+    /// `act` of all machines
+    Act,
+    /// Copying the outputs of the devices to the PDI
+    OutputCopy,
+    /// Waiting for the next cycle
+    Sleep,
+}
+
+impl Phase {
+    pub const ALL: [Self; 4] = [Self::InputCopy, Self::Act, Self::OutputCopy, Self::Sleep];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::InputCopy => "input_copy",
+            Self::Act => "act",
+            Self::OutputCopy => "output_copy",
+            Self::Sleep => "sleep",
+        }
+    }
+}
+
+static PHASES: [DurationHistogram; 4] = [const { DurationHistogram::new() }; 4];
+
+static OVERRUNS: AtomicU64 = AtomicU64::new(0);
+
+/// `act` durations per machine, written by the RT loop through its own `Arc`s.
+static MACHINES: OnceLock<Mutex<MachineHistograms>> = OnceLock::new();
+
+type MachineHistograms = HashMap<MachineIdentificationUnique, Arc<DurationHistogram>>;
+
+fn machines_slot() -> &'static Mutex<MachineHistograms> {
+    MACHINES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Histogram of one phase of the RT loop.
+pub fn phase_histogram(phase: Phase) -> &'static DurationHistogram {
+    &PHASES[phase as usize]
+}
+
+/// `act` histograms of all machines in the RT loop.
+pub fn machine_histograms() -> Vec<(MachineIdentificationUnique, Arc<DurationHistogram>)> {
+    let machines = machines_slot().lock().expect("profiling lock poisoned");
+    machines
+        .iter()
+        .map(|(id, histogram)| (id.clone(), histogram.clone()))
+        .collect()
+}
+
+/// Number of cycles which overran the cycle target.
+pub fn overrun_count() -> u64 {
+    OVERRUNS.load(Ordering::Relaxed)
+}
+
+/// A cycle which took longer than the cycle target.
+#[derive(Debug, Clone)]
+pub struct OverrunReport {
+    pub cycle: Duration,
+    pub target: Duration,
+    pub phases: [Duration; 4],
+    pub slowest_machine: Option<(MachineIdentificationUnique, Duration)>,
+    /// Overruns which were not reported since the last report
+    pub suppressed: u64,
+}
+
+impl OverrunReport {
+    /// The phase or the machine which took the longest, `act` is split into its machines
+    pub fn slowest_contributor(&self) -> String {
+        let (phase, duration) = Phase::ALL
+            .iter()
+            .zip(self.phases)
+            .filter(|(phase, _)| **phase != Phase::Act)
+            .max_by_key(|(_, duration)| *duration)
+            .expect("there are phases besides act");
+        match &self.slowest_machine {
+            Some((machine, machine_duration)) if *machine_duration >= duration => {
+                format!("act of machine {machine} ({machine_duration:?})")
+            }
+            _ => format!("{} ({duration:?})", phase.name()),
+        }
+    }
+}
+
+impl fmt::Display for OverrunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cycle took {:?}, target is {:?}. Slowest: {}. Phases:",
+            self.cycle,
+            self.target,
+            self.slowest_contributor()
+        )?;
+        for (phase, duration) in Phase::ALL.iter().zip(self.phases) {
+            write!(f, " {}={duration:?}", phase.name())?;
+        }
+        if self.suppressed > 0 {
+            write!(
+                f,
+                ". {} more overruns since the last report",
+                self.suppressed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Times the phases of the RT loop and the `act` of every machine.
+///
+/// Owned by the loop thread. Recording only touches atomics, the registry of machines
+/// is locked when a machine shows up or disappears.
+#[derive(Debug)]
+pub struct LoopProfiler {
+    machines: MachineHistograms,
+    cycle_start: Instant,
+    lap_start: Instant,
+    phases: [Duration; 4],
+    slowest_machine: Option<(MachineIdentificationUnique, Duration)>,
+    last_report: Option<Instant>,
+    suppressed: u64,
+}
+
+impl Default for LoopProfiler {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            machines: HashMap::new(),
+            cycle_start: now,
+            lap_start: now,
+            phases: [Duration::ZERO; 4],
+            slowest_machine: None,
+            last_report: None,
+            suppressed: 0,
+        }
+    }
+}
+
+impl LoopProfiler {
+    pub const fn start_cycle(&mut self, now: Instant) {
+        self.cycle_start = now;
+        self.lap_start = now;
+        self.phases = [Duration::ZERO; 4];
+        self.slowest_machine = None;
+    }
+
+    /// Records the time since the last lap as `phase`
+    pub fn lap(&mut self, phase: Phase) {
+        let now = Instant::now();
+        let duration = now.saturating_duration_since(self.lap_start);
+        self.lap_start = now;
+        self.phases[phase as usize] += duration;
+        PHASES[phase as usize].observe(duration);
+    }
+
+    pub fn record_machine(&mut self, machine: &MachineIdentificationUnique, duration: Duration) {
+        match self.machines.get(machine) {
+            Some(histogram) => histogram.observe(duration),
+            None => {
+                let histogram = machines_slot()
+                    .lock()
+                    .expect("profiling lock poisoned")
+                    .entry(machine.clone())
+                    .or_default()
+                    .clone();
+                histogram.observe(duration);
+                self.machines.insert(machine.clone(), histogram);
+            }
+        }
+        if self
+            .slowest_machine
+            .as_ref()
+            .is_none_or(|(_, slowest)| duration > *slowest)
+        {
+            self.slowest_machine = Some((machine.clone(), duration));
+        }
+    }
+
+    /// Number of machines with a histogram
+    pub fn machine_count(&self) -> usize {
+        self.machines.len()
+    }
+
+    /// Drops the histograms of machines which are not in the loop anymore
+    pub fn retain_machines(&mut self, machines: &[MachineIdentificationUnique]) {
+        self.machines.retain(|id, _| machines.contains(id));
+        machines_slot()
+            .lock()
+            .expect("profiling lock poisoned")
+            .retain(|id, _| machines.contains(id));
+    }
+
+    /// Counts an overrun and returns a report at most every [`OVERRUN_LOG_INTERVAL`]
+    pub fn finish_cycle(&mut self, target: Duration) -> Option<OverrunReport> {
+        let cycle = self.cycle_start.elapsed();
+        if cycle <= target + OVERRUN_TOLERANCE {
+            return None;
+        }
+        OVERRUNS.fetch_add(1, Ordering::Relaxed);
+
+        if self
+            .last_report
+            .is_some_and(|last| last.elapsed() < OVERRUN_LOG_INTERVAL)
+        {
+            self.suppressed += 1;
+            return None;
+        }
+        self.last_report = Some(Instant::now());
+        Some(OverrunReport {
+            cycle,
+            target,
+            phases: self.phases,
+            slowest_machine: self.slowest_machine.clone(),
+            suppressed: std::mem::take(&mut self.suppressed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machines::machine_identification::MachineIdentification;
+    use machines::{MACHINE_EXTRUDER_V1, VENDOR_QITECH};
+
+    fn report(phases: [u64; 4], machine: Option<u64>) -> OverrunReport {
+        OverrunReport {
+            cycle: Duration::from_micros(phases.iter().sum()),
+            target: Duration::from_micros(700),
+            phases: phases.map(Duration::from_micros),
+            slowest_machine: machine.map(|us| {
+                (
+                    MachineIdentificationUnique {
+                        machine_identification: MachineIdentification {
+                            vendor: VENDOR_QITECH,
+                            machine: MACHINE_EXTRUDER_V1,
+                        },
+                        serial: 1,
+                    },
+                    Duration::from_micros(us),
+                )
+            }),
+            suppressed: 0,
+        }
+    }
+
+    #[test]
+    fn test_slowest_contributor() {
+        assert_eq!(
+            report([500, 300, 50, 0], Some(250)).slowest_contributor(),
+            "input_copy (500µs)"
+        );
+        assert!(
+            report([100, 600, 50, 0], Some(550))
+                .slowest_contributor()
+                .starts_with("act of machine")
+        );
+    }
+
+    #[test]
+    fn test_finish_cycle() {
+        let mut profiler = LoopProfiler::default();
+        profiler.start_cycle(Instant::now());
+        assert!(profiler.finish_cycle(Duration::from_secs(1)).is_none());
+
+        profiler.start_cycle(Instant::now() - Duration::from_millis(2));
+        profiler.lap(Phase::Act);
+        let report = profiler.finish_cycle(Duration::from_micros(700)).unwrap();
+        assert!(report.phases[Phase::Act as usize] >= Duration::from_millis(2));
+
+        // rate limited
+        profiler.start_cycle(Instant::now() - Duration::from_millis(2));
+        assert!(profiler.finish_cycle(Duration::from_micros(700)).is_none());
+        assert_eq!(profiler.suppressed, 1);
+    }
+}
@@ -29,13 +29,15 @@ pub async fn start_modbus_tcp_discovery(shared_state: Arc<SharedState>) {
         let machines: Vec<Box<dyn Machine>> = addresses
             .into_iter()
             .map(|probe| {
+                let main_sender = shared_state.main_channel.clone();
                 smol::spawn(async move {
                     let machine_identification_unique = MachineIdentificationUnique {
                         machine_identification: WagoPower::MACHINE_IDENTIFICATION,
                         serial: probe.serial,
                     };
 
-                    let channel = MachineChannel::new(machine_identification_unique);
+                    let channel = MachineChannel::new(machine_identification_unique)
+                        .with_main_sender(main_sender);
                     let power = WagoPower::new(channel, probe.addr)
                         .await
                         .expect("Failed to initialize wago power supply");
@@ -58,7 +60,8 @@ pub async fn start_modbus_tcp_discovery(shared_state: Arc<SharedState>) {
         serial: 0xbeef,
     };
 
-    let channel = MachineChannel::new(machine_identification_unique);
+    let channel = MachineChannel::new(machine_identification_unique)
+        .with_main_sender(shared_state.main_channel.clone());
     let power = WagoPower::new(channel)
         .await
         .expect("Failed to initialize wago power supply");
@@ -2,6 +2,8 @@ use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 use tracing::info;
 
+use crate::metrics::histogram::{ETHERCAT_CYCLE, ETHERCAT_TXRX};
+
 /// Configuration for performance metrics collection
 const METRICS_WINDOW_SIZE: usize = 1000 * 30; // Keep last 30k measurements
 const METRICS_LOG_INTERVAL_SECS: u64 = 30; // Log every 30 seconds
@@ -46,6 +48,7 @@ impl EthercatPerformanceMetrics {
 
     /// Adds a tx_rx time measurement
     pub fn add_txrx_time(&mut self, duration: Duration) {
+        ETHERCAT_TXRX.observe(duration);
         if self.txrx_times.len() >= METRICS_WINDOW_SIZE {
             self.txrx_times.pop_front();
         }
@@ -54,6 +57,7 @@ impl EthercatPerformanceMetrics {
 
     /// Adds a cycle time measurement
     fn add_cycle_time(&mut self, duration: Duration) {
+        ETHERCAT_CYCLE.observe(duration);
         if self.loop_times.len() >= METRICS_WINDOW_SIZE {
             self.loop_times.pop_front();
         }
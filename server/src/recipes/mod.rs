@@ -0,0 +1,347 @@
+use anyhow::Result;
+use machines::machine_identification::MachineIdentification;
+use machines::persistence::mutation_key;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable to override where recipes are stored
+pub const RECIPES_DIR_ENV: &str = "QITECH_RECIPES_DIR";
+
+/// A named set of mutations for one machine type
+///
+/// Saving a recipe under an existing name adds a new version, older versions are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub version: u32,
+    pub machine_identification: MachineIdentification,
+    /// Seconds since the unix epoch
+    pub saved_at: u64,
+    pub mutations: Vec<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecipeSummary {
+    pub name: String,
+    pub latest_version: u32,
+    pub saved_at: u64,
+    pub mutation_count: usize,
+}
+
+/// One mutation which is set differently in two recipes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecipeDiffEntry {
+    pub key: String,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecipeFile {
+    recipes: Vec<Recipe>,
+}
+
+/// On-disk store of recipes, one JSON file per [`MachineIdentification`]
+#[derive(Debug)]
+pub struct RecipeStore {
+    dir: PathBuf,
+    /// Serializes read-modify-write cycles on the files
+    lock: Mutex<()>,
+}
+
+impl RecipeStore {
+    pub const fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// `$QITECH_RECIPES_DIR`, `$HOME/.qitech/recipes` or `./recipes`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var(RECIPES_DIR_ENV) {
+            return PathBuf::from(dir);
+        }
+        std::env::var("HOME").map_or_else(
+            |_| PathBuf::from("recipes"),
+            |home| Path::new(&home).join(".qitech").join("recipes"),
+        )
+    }
+
+    /// Latest version of every recipe, sorted by name
+    pub fn list(
+        &self,
+        machine_identification: &MachineIdentification,
+    ) -> Result<Vec<RecipeSummary>> {
+        let _guard = self.lock.lock().expect("recipe lock poisoned");
+        let file = self.read(machine_identification)?;
+
+        let mut summaries: Vec<RecipeSummary> = vec![];
+        for recipe in file.recipes {
+            let summary = RecipeSummary {
+                name: recipe.name.clone(),
+                latest_version: recipe.version,
+                saved_at: recipe.saved_at,
+                mutation_count: recipe.mutations.len(),
+            };
+            match summaries.iter_mut().find(|s| s.name == recipe.name) {
+                Some(existing) if existing.latest_version < recipe.version => *existing = summary,
+                Some(_) => (),
+                None => summaries.push(summary),
+            }
+        }
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(summaries)
+    }
+
+    /// A recipe by name, the latest version if `version` is `None`
+    pub fn get(
+        &self,
+        machine_identification: &MachineIdentification,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<Option<Recipe>> {
+        let _guard = self.lock.lock().expect("recipe lock poisoned");
+        let file = self.read(machine_identification)?;
+
+        Ok(file
+            .recipes
+            .into_iter()
+            .filter(|recipe| recipe.name == name)
+            .filter(|recipe| version.is_none_or(|version| recipe.version == version))
+            .max_by_key(|recipe| recipe.version))
+    }
+
+    /// Stores a new version of a recipe
+    pub fn save(
+        &self,
+        machine_identification: &MachineIdentification,
+        name: &str,
+        mutations: Vec<Value>,
+    ) -> Result<Recipe> {
+        if name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Recipe name must not be empty"));
+        }
+        if mutations.is_empty() {
+            return Err(anyhow::anyhow!("Recipe must contain at least one mutation"));
+        }
+        if let Some(mutation) = mutations.iter().find(|m| mutation_key(m).is_none()) {
+            return Err(anyhow::anyhow!("Not a mutation: {}", mutation));
+        }
+
+        let _guard = self.lock.lock().expect("recipe lock poisoned");
+        let mut file = self.read(machine_identification)?;
+
+        let version = file
+            .recipes
+            .iter()
+            .filter(|recipe| recipe.name == name)
+            .map(|recipe| recipe.version)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let recipe = Recipe {
+            name: name.to_string(),
+            version,
+            machine_identification: machine_identification.clone(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            mutations,
+        };
+        file.recipes.push(recipe.clone());
+        self.write(machine_identification, &file)?;
+        Ok(recipe)
+    }
+
+    fn file_path(&self, machine_identification: &MachineIdentification) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.json",
+            machine_identification.vendor, machine_identification.machine
+        ))
+    }
+
+    fn read(&self, machine_identification: &MachineIdentification) -> Result<RecipeFile> {
+        let path = self.file_path(machine_identification);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(RecipeFile::default()),
+            Err(e) => Err(anyhow::anyhow!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn write(
+        &self,
+        machine_identification: &MachineIdentification,
+        file: &RecipeFile,
+    ) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.file_path(machine_identification);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(file)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+}
+
+/// Compares two sets of mutations by their `Mutation` variant
+///
+/// If a variant appears multiple times the last value counts, like when applying it.
+/// Only variants with different values are returned.
+pub fn diff(left: &[Value], right: &[Value]) -> Vec<RecipeDiffEntry> {
+    let left = last_values(left);
+    let right = last_values(right);
+
+    let mut keys: Vec<&String> = left.iter().map(|(key, _)| key).collect();
+    for (key, _) in right.iter() {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let find = |values: &[(String, Value)]| {
+                values
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.clone())
+            };
+            let left = find(&left);
+            let right = find(&right);
+            (left != right).then(|| RecipeDiffEntry {
+                key: key.clone(),
+                left,
+                right,
+            })
+        })
+        .collect()
+}
+
+fn last_values(mutations: &[Value]) -> Vec<(String, Value)> {
+    let mut values: Vec<(String, Value)> = vec![];
+    for mutation in mutations {
+        let Some(key) = mutation_key(mutation) else {
+            continue;
+        };
+        match values.iter_mut().find(|(k, _)| k == &key) {
+            Some((_, value)) => *value = mutation.clone(),
+            None => values.push((key, mutation.clone())),
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use machines::{MACHINE_WINDER_V1, VENDOR_QITECH};
+    use serde_json::json;
+
+    const WINDER: MachineIdentification = MachineIdentification {
+        vendor: VENDOR_QITECH,
+        machine: MACHINE_WINDER_V1,
+    };
+
+    fn temp_store(name: &str) -> RecipeStore {
+        let dir = std::env::temp_dir().join(format!("recipes-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        RecipeStore::new(dir)
+    }
+
+    #[test]
+    fn test_save_adds_versions() {
+        let store = temp_store("versions");
+
+        let v1 = store
+            .save(
+                &WINDER,
+                "PLA 1.75",
+                vec![json!({ "SetPullerTargetSpeed": 10.0 })],
+            )
+            .unwrap();
+        let v2 = store
+            .save(
+                &WINDER,
+                "PLA 1.75",
+                vec![json!({ "SetPullerTargetSpeed": 12.0 })],
+            )
+            .unwrap();
+        store
+            .save(
+                &WINDER,
+                "ABS",
+                vec![json!({ "SetTraverseLimitOuter": 90.0 })],
+            )
+            .unwrap();
+
+        assert_eq!(v1.version, 1);
+        assert_eq!(v2.version, 2);
+        assert_eq!(store.get(&WINDER, "PLA 1.75", None).unwrap(), Some(v2));
+        assert_eq!(store.get(&WINDER, "PLA 1.75", Some(1)).unwrap(), Some(v1));
+        assert_eq!(store.get(&WINDER, "PLA 1.75", Some(3)).unwrap(), None);
+
+        let names: Vec<(String, u32)> = store
+            .list(&WINDER)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.latest_version))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("ABS".to_string(), 1), ("PLA 1.75".to_string(), 2)]
+        );
+
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+
+    #[test]
+    fn test_save_rejects_invalid_recipes() {
+        let store = temp_store("invalid");
+        assert!(
+            store
+                .save(&WINDER, " ", vec![json!("ResetSpoolProgress")])
+                .is_err()
+        );
+        assert!(store.save(&WINDER, "empty", vec![]).is_err());
+        assert!(store.save(&WINDER, "number", vec![json!(1.0)]).is_err());
+        assert!(store.list(&WINDER).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let left = vec![
+            json!({ "SetPullerTargetSpeed": 10.0 }),
+            json!({ "SetTraverseLimitOuter": 90.0 }),
+            json!({ "SetPullerTargetSpeed": 11.0 }),
+        ];
+        let right = vec![
+            json!({ "SetTraverseLimitOuter": 90.0 }),
+            json!({ "SetPullerTargetSpeed": 12.0 }),
+            json!({ "SetSpoolForward": true }),
+        ];
+
+        assert_eq!(
+            diff(&left, &right),
+            vec![
+                RecipeDiffEntry {
+                    key: "SetPullerTargetSpeed".to_string(),
+                    left: Some(json!({ "SetPullerTargetSpeed": 11.0 })),
+                    right: Some(json!({ "SetPullerTargetSpeed": 12.0 })),
+                },
+                RecipeDiffEntry {
+                    key: "SetSpoolForward".to_string(),
+                    left: None,
+                    right: Some(json!({ "SetSpoolForward": true })),
+                },
+            ]
+        );
+        assert!(diff(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{Response, StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router, debug_handler};
+use serde::Deserialize;
+
+use crate::app_state::SharedState;
+use crate::audit::{AuditQuery, AuditRetention, to_csv};
+use crate::auth::{Principal, Role};
+use crate::rest::response::*;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct ExportFormatQuery {
+    #[serde(default)]
+    pub(crate) format: ExportFormat,
+}
+
+#[debug_handler]
+async fn get_audit_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Query(query): Query<AuditQuery>,
+    Query(format): Query<ExportFormatQuery>,
+) -> std::result::Result<Response<Body>, ApiError> {
+    principal.require(Role::Technician)?;
+
+    let entries = shared_state
+        .audit_log
+        .query(&query)
+        .map_err(internal_error)?;
+
+    Ok(match format.format {
+        ExportFormat::Json => Json(entries).into_response(),
+        ExportFormat::Csv => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+            .header(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"audit.csv\"",
+            )
+            .body(Body::from(to_csv(&entries)))
+            .map_err(internal_error)?,
+    })
+}
+
+#[debug_handler]
+async fn get_retention_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+) -> Result<AuditRetention> {
+    principal.require(Role::Technician)?;
+    json(shared_state.audit_log.retention())
+}
+
+#[debug_handler]
+async fn post_retention_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Json(retention): Json<AuditRetention>,
+) -> Result<AuditRetention> {
+    principal.require(Role::Admin)?;
+    shared_state
+        .audit_log
+        .set_retention(retention)
+        .map_err(internal_error)?;
+    json(retention)
+}
+
+/// Mounted under `/api/v1/audit`
+pub fn audit_router() -> Router<Arc<SharedState>> {
+    Router::new().route("/", get(get_audit_handler)).route(
+        "/retention",
+        get(get_retention_handler).post(post_retention_handler),
+    )
+}
@@ -0,0 +1,69 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::{Router, debug_handler, routing::get};
+
+use crate::app_state::SharedState;
+use crate::auth::{AuthError, Principal, Role};
+use crate::rest::response::*;
+
+/// Token of `Authorization: Bearer <token>`
+pub fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Authenticates every route and requires at least [`Role::Viewer`]
+///
+/// The [`Principal`] is put into the request for handlers which need more.
+pub async fn authenticate(
+    State(shared_state): State<Arc<SharedState>>,
+    request: Request,
+    next: Next,
+) -> std::result::Result<Response, ApiError> {
+    let (mut parts, body) = request.into_parts();
+    let mut principal = shared_state.auth.authenticate(bearer_token(&parts))?;
+    principal.require(Role::Viewer)?;
+    principal.address = parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip().to_string());
+
+    parts.extensions.insert(principal);
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Self>()
+            .cloned()
+            .ok_or_else(|| AuthError::Unauthorized("Not authenticated".to_string()).into())
+    }
+}
+
+#[debug_handler]
+async fn get_me_handler(principal: Principal) -> Result<Principal> {
+    json(principal)
+}
+
+/// Mounted under `/api/v1/auth`
+pub fn auth_router() -> Router<Arc<SharedState>> {
+    Router::new().route("/me", get(get_me_handler))
+}
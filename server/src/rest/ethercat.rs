@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router, debug_handler};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::{HotThreadMessage, SharedState};
+use crate::audit::{AuditAction, AuditResult, NewAuditEntry};
+use crate::auth::{Principal, Role};
+use crate::ethercat::diagnosis::{DiagnosisLogEntry, DiagnosisQuery};
+use crate::ethercat::sdo::{SdoOperation, SdoRequest, SdoRequestStatus};
+use crate::history::recorder::now_ms;
+use crate::rest::response::*;
+use crate::socketio::main_namespace::MainNamespaceEvents;
+use crate::socketio::main_namespace::ethercat_link_quality_event::EthercatLinkQualityEvent;
+use crate::socketio::main_namespace::ethercat_sdo_event::EthercatSdoEvent;
+use control_core::socketio::namespace::NamespaceCacheingLogic;
+
+#[debug_handler]
+async fn get_link_quality_handler(
+    State(shared_state): State<Arc<SharedState>>,
+) -> Result<EthercatLinkQualityEvent> {
+    let link_quality = shared_state.ethercat_link_quality.read().await.clone();
+    link_quality.map_or_else(|| Err(not_found("No EtherCAT bus is running")), json)
+}
+
+#[debug_handler]
+async fn get_diagnosis_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    Query(query): Query<DiagnosisQuery>,
+) -> Result<Vec<DiagnosisLogEntry>> {
+    json(shared_state.ethercat_diagnosis.query(&query))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SdoRequestBody {
+    pub subdevice_index: usize,
+    #[serde(flatten)]
+    pub operation: SdoOperation,
+}
+
+/// Queues the request and answers with it while it is pending.
+/// The result is sent as [`EthercatSdoEvent`] and can be polled with `GET /sdo/{id}`.
+#[debug_handler]
+async fn post_sdo_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Json(body): Json<SdoRequestBody>,
+) -> Result<SdoRequest> {
+    let mut audit_entry = NewAuditEntry {
+        client: principal.name.clone(),
+        client_address: principal.address.clone(),
+        action: AuditAction::Sdo {
+            subdevice_index: body.subdevice_index,
+        },
+        machine: None,
+        payload: serde_json::to_value(&body).unwrap_or_default(),
+        result: AuditResult::Accepted,
+    };
+
+    if let Err(e) = principal.require(Role::Admin) {
+        audit_entry.result = AuditResult::Rejected(e.to_string());
+        shared_state.audit_log.record_or_log(audit_entry);
+        return Err(e.into());
+    }
+
+    if let SdoOperation::Download { value, .. } = &body.operation {
+        if !value.is_expedited() {
+            let message = "Only values of up to 4 bytes can be written";
+            audit_entry.result = AuditResult::Rejected(message.to_string());
+            shared_state.audit_log.record_or_log(audit_entry);
+            return Err(bad_request(message));
+        }
+    }
+
+    let request = shared_state.sdo_requests.add(SdoRequest {
+        id: 0,
+        subdevice_index: body.subdevice_index,
+        operation: body.operation,
+        client: principal.name,
+        requested_ms: now_ms(),
+        finished_ms: None,
+        status: SdoRequestStatus::Pending,
+    });
+
+    let state = shared_state;
+    let pending = request.clone();
+    smol::spawn(async move {
+        let id = pending.id;
+        let (sender, receiver) = smol::channel::bounded(1);
+        let result = match state
+            .rt_machine_creation_channel
+            .send(HotThreadMessage::Sdo(pending, sender))
+            .await
+        {
+            Ok(()) => receiver
+                .recv()
+                .await
+                .unwrap_or_else(|e| Err(format!("No result from the loop thread: {e}"))),
+            Err(e) => {
+                tracing::error!("Failed to send HotThreadMessage::Sdo {}", e);
+                Err(e.to_string())
+            }
+        };
+
+        audit_entry.result = match &result {
+            Ok(_) => AuditResult::Accepted,
+            Err(e) => AuditResult::Rejected(e.clone()),
+        };
+        state.audit_log.record_or_log(audit_entry);
+
+        let status = match result {
+            Ok(result) => SdoRequestStatus::Done { result },
+            Err(error) => SdoRequestStatus::Failed { error },
+        };
+        let Some(request) = state.sdo_requests.finish(id, status, now_ms()) else {
+            return;
+        };
+        let main_namespace = &mut state.socketio_setup.namespaces.write().await.main_namespace;
+        main_namespace.emit(MainNamespaceEvents::EthercatSdoEvent(
+            EthercatSdoEvent { request }.build(),
+        ));
+    })
+    .detach();
+
+    json(request)
+}
+
+#[debug_handler]
+async fn get_sdo_requests_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+) -> Result<Vec<SdoRequest>> {
+    principal.require(Role::Admin)?;
+    json(shared_state.sdo_requests.list())
+}
+
+#[debug_handler]
+async fn get_sdo_request_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Path(id): Path<u64>,
+) -> Result<SdoRequest> {
+    principal.require(Role::Admin)?;
+    shared_state
+        .sdo_requests
+        .get(id)
+        .map_or_else(|| Err(not_found(format!("No SDO request {id}"))), json)
+}
+
+/// Mounted under `/api/v1/ethercat`
+pub fn ethercat_router() -> Router<Arc<SharedState>> {
+    Router::new()
+        .route("/link_quality", get(get_link_quality_handler))
+        .route("/diagnosis", get(get_diagnosis_handler))
+        .route("/sdo", get(get_sdo_requests_handler).post(post_sdo_handler))
+        .route("/sdo/{id}", get(get_sdo_request_handler))
+}
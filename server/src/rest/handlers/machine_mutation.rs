@@ -2,67 +2,123 @@ use super::mutation::MachineMutationBody;
 use super::mutation::MutationResponse;
 use crate::{
     app_state::SharedState,
-    rest::util::{ResponseUtil, ResponseUtilError},
+    audit::{AuditAction, AuditResult, NewAuditEntry},
+    auth::Principal,
+    rest::{
+        response::{ApiError, internal_error, not_found},
+        util::ResponseUtil,
+    },
 };
-use axum::{Json, body::Body, extract::State, http::Response};
+use axum::{Json, body::Body, extract::State, http::Response, response::IntoResponse};
+use machines::machine_identification::MachineIdentificationUnique;
+use machines::{MachineMessage, MutationBatch, RejectedMutation};
 use serde_json::Value;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How long to wait for the machine to apply mutations before their result is recorded as unknown
+const MUTATION_RESULT_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[axum::debug_handler]
 pub async fn post_machine_mutate(
     State(app_state): State<Arc<SharedState>>,
+    principal: Principal,
     Json(body): Json<MachineMutationBody<Value>>,
 ) -> Response<Body> {
-    let result = _post_machine_mutate(State(app_state), Json(body)).await;
-    match result {
-        Ok(_) => ResponseUtil::ok(MutationResponse::success()),
-        Err(e) => ResponseUtilError::Error(e).into(),
-    }
-}
-
-async fn _post_machine_mutate(
-    State(app_state): State<Arc<SharedState>>,
-    Json(body): Json<MachineMutationBody<Value>>,
-) -> Result<(), anyhow::Error> {
     tracing::info!(
         "Mutating machine machine={} data={:?}",
         body.machine_identification_unique,
         body.data,
     );
 
-    let span = tracing::info_span!("machine_mutate", machine = %body.machine_identification_unique);
-    let _span = span.enter();
+    let result = mutate_machine(
+        &app_state,
+        &principal,
+        &body.machine_identification_unique,
+        vec![body.data],
+        AuditAction::MachineMutation,
+    )
+    .await;
+    match result {
+        Ok(rejected) => match rejected.into_iter().next() {
+            Some(rejected) => ResponseUtil::ok(MutationResponse::error(rejected.error)),
+            None => ResponseUtil::ok(MutationResponse::success()),
+        },
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Authorizes, applies and audits mutations
+///
+/// The mutations are sent as one [`MutationBatch`], so every one of them is recorded with its result.
+pub async fn mutate_machine(
+    app_state: &SharedState,
+    principal: &Principal,
+    id: &MachineIdentificationUnique,
+    mutations: Vec<Value>,
+    action: AuditAction,
+) -> Result<Vec<RejectedMutation>, ApiError> {
+    let audit = |mutation: &Value, result: AuditResult| {
+        app_state.audit_log.record_or_log(NewAuditEntry {
+            client: principal.name.clone(),
+            client_address: principal.address.clone(),
+            action: action.clone(),
+            machine: Some(id.clone()),
+            payload: mutation.clone(),
+            result,
+        });
+    };
 
-    match app_state
-        .api_machines
-        .lock()
-        .await
-        .get(&body.machine_identification_unique)
+    if let Err(e) =
+        app_state
+            .auth
+            .authorize_mutations(principal, &id.machine_identification, &mutations)
     {
-        Some(sender) => {
-            let res = sender
-                .clone()
-                .send(machines::MachineMessage::HttpApiJsonRequest(
-                    body.data.clone(),
-                ))
-                .await;
-            match res {
-                Ok(_) => (),
-                Err(e) => tracing::error!(
-                    "[{}::_post_machine_mutate] Sending MachineMessage::HttpApiJsonRequest to {} failed {}",
-                    module_path!(),
-                    body.machine_identification_unique,
-                    e
-                ),
-            };
-            Ok(())
+        for mutation in &mutations {
+            audit(mutation, AuditResult::Rejected(e.to_string()));
+        }
+        return Err(e.into());
+    }
+
+    let (sender, receiver) = smol::channel::bounded(1);
+    let res = app_state
+        .message_machine(
+            id,
+            MachineMessage::HttpApiJsonBatch(MutationBatch {
+                mutations: mutations.clone(),
+                result: sender,
+            }),
+        )
+        .await;
+    if let Err(e) = res {
+        for mutation in &mutations {
+            audit(mutation, AuditResult::Rejected(e.to_string()));
+        }
+        return Err(not_found(e));
+    }
+
+    let rejected = smol::future::or(async { receiver.recv().await.ok() }, async {
+        smol::Timer::after(MUTATION_RESULT_TIMEOUT).await;
+        None
+    })
+    .await;
+    let Some(rejected) = rejected else {
+        let error =
+            format!("Machine {id} didn't report a result, the mutations may still be applied");
+        for mutation in &mutations {
+            audit(mutation, AuditResult::Unknown(error.clone()));
         }
+        return Err(internal_error(error));
+    };
 
-        None => Err(anyhow::anyhow!(
-            "[{}::_post_machine_mutate] Machine api_mutate error {} {}",
-            module_path!(),
-            "No Machine found with id: ",
-            body.machine_identification_unique
-        )),
+    for (index, mutation) in mutations.iter().enumerate() {
+        let result = rejected
+            .iter()
+            .find(|rejected| rejected.index == index)
+            .map_or(AuditResult::Accepted, |rejected| {
+                AuditResult::Rejected(rejected.error.clone())
+            });
+        audit(mutation, result);
     }
+    Ok(rejected)
 }
@@ -1,11 +1,18 @@
 use std::sync::Arc;
 
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
 use axum::{Json, Router, routing::get};
 use serde::Serialize;
 
 use crate::SharedState;
+use crate::metrics::histogram::HistogramSnapshot;
+use crate::metrics::openmetrics::{OPENMETRICS_CONTENT_TYPE, render_openmetrics};
 use crate::metrics::process::ProcessMetrics;
+use crate::metrics::profiling::{Phase, machine_histograms, overrun_count, phase_histogram};
 use crate::metrics::state::get_latest_runtime_sample;
+use machines::machine_identification::MachineIdentificationUnique;
 
 /// Process-level metrics exposed over the REST API.
 ///
@@ -46,6 +53,51 @@ pub struct RuntimeMetricsResponse {
     pub rt_nr_involuntary_switches: Option<u64>,
 }
 
+/// Summary of a timing histogram of the RT loop.
+///
+/// Percentiles are the upper bound of their histogram bucket, `None` above 50ms.
+#[derive(Debug, Serialize)]
+pub struct TimingSummary {
+    pub count: u64,
+    pub mean_us: Option<f64>,
+    pub p99_us: Option<f64>,
+    pub p9999_us: Option<f64>,
+}
+
+impl From<HistogramSnapshot> for TimingSummary {
+    fn from(snapshot: HistogramSnapshot) -> Self {
+        let us = |ns: u64| ns as f64 / 1000.0;
+        Self {
+            count: snapshot.count,
+            mean_us: snapshot.mean_ns().map(us),
+            p99_us: snapshot.quantile_upper_bound(0.99).map(us),
+            p9999_us: snapshot.quantile_upper_bound(0.9999).map(us),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: &'static str,
+    #[serde(flatten)]
+    pub timing: TimingSummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MachineTiming {
+    pub machine_identification_unique: MachineIdentificationUnique,
+    #[serde(flatten)]
+    pub timing: TimingSummary,
+}
+
+/// Timings of the RT loop since the server started.
+#[derive(Debug, Serialize)]
+pub struct RtProfileResponse {
+    pub overruns: u64,
+    pub phases: Vec<PhaseTiming>,
+    pub machines: Vec<MachineTiming>,
+}
+
 async fn get_process_metrics() -> Json<ProcessMetricsResponse> {
     let m = ProcessMetrics::collect();
     Json(ProcessMetricsResponse {
@@ -80,6 +132,39 @@ async fn get_runtime_metrics_latest() -> Json<Option<RuntimeMetricsResponse>> {
     Json(opt)
 }
 
+async fn get_rt_profile() -> Json<RtProfileResponse> {
+    let mut machines: Vec<MachineTiming> = machine_histograms()
+        .into_iter()
+        .map(|(machine, histogram)| MachineTiming {
+            machine_identification_unique: machine,
+            timing: histogram.snapshot().into(),
+        })
+        .collect();
+    machines.sort_by_key(|m| m.machine_identification_unique.to_string());
+
+    Json(RtProfileResponse {
+        overruns: overrun_count(),
+        phases: Phase::ALL
+            .iter()
+            .map(|phase| PhaseTiming {
+                phase: phase.name(),
+                timing: phase_histogram(*phase).snapshot().into(),
+            })
+            .collect(),
+        machines,
+    })
+}
+
+/// Runtime, EtherCAT and machine metrics in the OpenMetrics text format.
+///
+/// Mounted as `/metrics` to be scraped by Prometheus.
+pub async fn get_openmetrics(State(shared_state): State<Arc<SharedState>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)],
+        render_openmetrics(&shared_state),
+    )
+}
+
 /// Router for metrics-related REST endpoints.
 ///
 /// Mounted under `/api/v1/metrics`.
@@ -87,4 +172,5 @@ pub fn metrics_router() -> Router<Arc<SharedState>> {
     Router::new()
         .route("/process/metrics", get(get_process_metrics))
         .route("/runtime/latest", get(get_runtime_metrics_latest))
+        .route("/rt/profile", get(get_rt_profile))
 }
@@ -1,5 +1,10 @@
-use crate::{app_state::SharedState, rest::util::ResponseUtil};
-use axum::{Json, extract::State, http::Response};
+use crate::{
+    app_state::SharedState,
+    audit::{AuditAction, AuditResult, NewAuditEntry},
+    auth::{Principal, Role},
+    rest::{response::ApiError, util::ResponseUtil},
+};
+use axum::{Json, extract::State, http::Response, response::IntoResponse};
 use machines::machine_identification::{
     DeviceHardwareIdentificationEthercat, DeviceMachineIdentification,
 };
@@ -8,7 +13,7 @@ use std::sync::Arc;
 
 use super::mutation::MutationResponse;
 
-#[derive(serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct MachineDeviceInfoRequest {
     pub device_machine_identification: DeviceMachineIdentification,
     pub hardware_identification_ethercat: DeviceHardwareIdentificationEthercat,
@@ -17,22 +22,58 @@ pub struct MachineDeviceInfoRequest {
 #[axum::debug_handler]
 pub async fn post_write_machine_device_identification(
     State(app_state): State<Arc<SharedState>>,
+    principal: Principal,
     Json(body): Json<MachineDeviceInfoRequest>,
 ) -> Response<axum::body::Body> {
+    let mut audit_entry = NewAuditEntry {
+        client: principal.name.clone(),
+        client_address: principal.address.clone(),
+        action: AuditAction::WriteMachineDeviceInfo,
+        machine: Some(
+            body.device_machine_identification
+                .machine_identification_unique
+                .clone(),
+        ),
+        payload: serde_json::to_value(&body).unwrap_or_default(),
+        result: AuditResult::Accepted,
+    };
+
+    if let Err(e) = principal.require(Role::Technician) {
+        audit_entry.result = AuditResult::Rejected(e.to_string());
+        app_state.audit_log.record_or_log(audit_entry);
+        return ApiError::from(e).into_response();
+    }
+
+    let (sender, receiver) = smol::channel::bounded(1);
     let res = app_state
         .rt_machine_creation_channel
         .send(crate::app_state::HotThreadMessage::WriteMachineDeviceInfo(
-            body,
+            body, sender,
         ))
         .await;
 
-    match res {
-        Ok(_) => (),
-        Err(e) => tracing::error!(
-            "Failed to send HotThreadMessage::WriteMachineDeviceInfo {}",
-            e
-        ),
-    }
+    let result = match res {
+        Ok(_) => receiver
+            .recv()
+            .await
+            .unwrap_or_else(|e| Err(format!("No result from the loop thread: {e}"))),
+        Err(e) => {
+            tracing::error!(
+                "Failed to send HotThreadMessage::WriteMachineDeviceInfo {}",
+                e
+            );
+            Err(e.to_string())
+        }
+    };
 
-    ResponseUtil::ok(MutationResponse::success())
+    audit_entry.result = match &result {
+        Ok(()) => AuditResult::Accepted,
+        Err(e) => AuditResult::Rejected(e.clone()),
+    };
+    app_state.audit_log.record_or_log(audit_entry);
+
+    match result {
+        Ok(()) => ResponseUtil::ok(MutationResponse::success()),
+        Err(e) => ResponseUtil::ok(MutationResponse::error(e)),
+    }
 }
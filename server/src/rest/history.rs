@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{Response, StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Json, Router, debug_handler};
+use machines::machine_identification::{MachineIdentification, MachineIdentificationUnique};
+use machines::registry::MachineDescriptor;
+use serde::Deserialize;
+
+use crate::app_state::SharedState;
+use crate::history::recorder::now_ms;
+use crate::history::{AggregateFunction, HistoryQuery, Resolution};
+use crate::rest::audit::{ExportFormat, ExportFormatQuery};
+use crate::rest::response::*;
+
+/// Range of a query without `from`
+const DEFAULT_RANGE_MS: u64 = 60 * 60 * 1000;
+
+/// Upper bound of points in one response
+const MAX_POINTS: usize = 20_000;
+
+#[derive(Deserialize, Debug)]
+struct HistoryQueryParams {
+    /// Milliseconds since the unix epoch, one hour before `to` by default
+    from: Option<u64>,
+    /// Milliseconds since the unix epoch, now by default
+    to: Option<u64>,
+    /// Comma separated field names, e.g. `motor_status.rpm,pressure`
+    fields: Option<String>,
+    #[serde(default)]
+    resolution: Resolution,
+    /// Bucket length in seconds
+    bucket: Option<u64>,
+    #[serde(default)]
+    aggregate: AggregateFunction,
+}
+
+#[debug_handler]
+async fn get_history_handler(
+    State(shared_state): State<Arc<SharedState>>,
+    Extension(id): Extension<MachineIdentification>,
+    Path(serial): Path<u16>,
+    Query(params): Query<HistoryQueryParams>,
+    Query(format): Query<ExportFormatQuery>,
+) -> std::result::Result<Response<Body>, ApiError> {
+    let now = now_ms();
+    let to = params.to.unwrap_or(now);
+    let from = params
+        .from
+        .unwrap_or_else(|| to.saturating_sub(DEFAULT_RANGE_MS));
+    if from >= to {
+        return Err(bad_request("`from` must be before `to`"));
+    }
+    if params.bucket == Some(0) {
+        return Err(bad_request("`bucket` must be at least 1 second"));
+    }
+
+    let query = HistoryQuery {
+        from,
+        to,
+        fields: params.fields.map(|fields| {
+            fields
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect()
+        }),
+        resolution: params.resolution,
+        bucket_ms: params.bucket.map(|bucket| bucket * 1000),
+        aggregate: params.aggregate,
+    };
+    let machine = MachineIdentificationUnique {
+        machine_identification: id,
+        serial,
+    };
+    let result = shared_state
+        .history
+        .query(&machine, &query, now)
+        .map_err(internal_error)?;
+    if result.points.len() > MAX_POINTS {
+        return Err(bad_request(format!(
+            "Query returns {} points, more than {}. Use a shorter range or a larger bucket",
+            result.points.len(),
+            MAX_POINTS
+        )));
+    }
+
+    Ok(match format.format {
+        ExportFormat::Json => Json(result).into_response(),
+        ExportFormat::Csv => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+            .header(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"history.csv\"",
+            )
+            .body(Body::from(result.to_csv()))
+            .map_err(internal_error)?,
+    })
+}
+
+pub fn make_history_router(descriptor: &MachineDescriptor) -> Router<Arc<SharedState>> {
+    Router::new()
+        .route(
+            &format!("/machine/{}/{{serial}}/history", descriptor.slug),
+            get(get_history_handler),
+        )
+        .layer(Extension(descriptor.machine_identification.clone()))
+}
@@ -1,5 +1,7 @@
 use anyhow::Result;
-use axum::routing::post;
+use axum::middleware::from_fn_with_state;
+use axum::routing::{get, post};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::thread;
 use tower_http::cors::CorsLayer;
@@ -9,10 +11,13 @@ use tracing::Level;
 use super::handlers::machine_mutation::post_machine_mutate;
 use super::handlers::write_machine_device_identification::post_write_machine_device_identification;
 use crate::app_state::SharedState;
+use crate::rest::audit::audit_router;
+use crate::rest::auth::{auth_router, authenticate};
+use crate::rest::ethercat::ethercat_router;
 use crate::rest::rest_api::rest_api_router;
 use crate::socketio::init::init_socketio;
 
-use crate::rest::handlers::metrics::metrics_router;
+use crate::rest::handlers::metrics::{get_openmetrics, metrics_router};
 
 async fn init_api(app_state: Arc<SharedState>) -> Result<()> {
     let cors = CorsLayer::permissive();
@@ -30,7 +35,13 @@ async fn init_api(app_state: Arc<SharedState>) -> Result<()> {
         )
         .route("/api/v1/machine/mutate", post(post_machine_mutate))
         .nest("/api/v1/metrics", metrics_router())
+        .route("/metrics", get(get_openmetrics))
+        .nest("/api/v1/auth", auth_router())
+        .nest("/api/v1/audit", audit_router())
+        .nest("/api/v1/ethercat", ethercat_router())
         .nest("/api/v2", rest_api_router())
+        // socket.io authenticates in its connect handler
+        .route_layer(from_fn_with_state(app_state.clone(), authenticate))
         .layer(socketio_layer)
         .layer(cors)
         .layer(trace_layer)
@@ -42,9 +53,12 @@ async fn init_api(app_state: Arc<SharedState>) -> Result<()> {
 
     tracing::info!("HTTP server running on 0.0.0.0:3001");
 
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| anyhow::anyhow!("Server error: {}", e))
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("Server error: {}", e))
 }
 
 /// Starts the API server in its own thread with a single-threaded Tokio runtime
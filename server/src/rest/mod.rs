@@ -1,5 +1,11 @@
+pub mod audit;
+pub mod auth;
+pub mod ethercat;
 pub mod handlers;
+pub mod history;
 pub mod init;
+pub mod openapi;
+pub mod recipes;
 pub mod response;
 pub mod rest_api;
 pub mod util;
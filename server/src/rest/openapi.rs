@@ -0,0 +1,229 @@
+use machines::registry::{MACHINE_REGISTRY, MachineDescriptor};
+use machines::schema::MachineApiSchema;
+use schemars::Schema;
+use serde_json::{Map, Value, json};
+
+/// OpenAPI 3.1 document of the `/api/v2` machine routes
+///
+/// The payload schemas come from the [`MachineApiSchema`] of every registered machine.
+/// Their definitions are moved to `components/schemas` and prefixed with the slug,
+/// so equally named types of different machines don't collide.
+pub fn openapi_document() -> Value {
+    let mut paths = Map::new();
+    let mut components = Map::new();
+
+    paths.insert(
+        "/machine".to_string(),
+        json!({
+            "get": {
+                "summary": "List machines",
+                "responses": {
+                    "200": {
+                        "description": "Machines currently known to the panel",
+                        "content": { "application/json": { "schema": { "type": "object" } } }
+                    }
+                }
+            }
+        }),
+    );
+
+    for descriptor in MACHINE_REGISTRY.descriptors() {
+        let schema = (descriptor.api_schema)();
+        add_machine_paths(descriptor, &schema, &mut paths, &mut components);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "QiTech Control",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/api/v2" }],
+        "paths": paths,
+        "components": {
+            "schemas": components,
+            "securitySchemes": { "bearerAuth": { "type": "http", "scheme": "bearer" } },
+        },
+        "security": [{ "bearerAuth": [] }],
+    })
+}
+
+fn add_machine_paths(
+    descriptor: &MachineDescriptor,
+    schema: &MachineApiSchema,
+    paths: &mut Map<String, Value>,
+    components: &mut Map<String, Value>,
+) {
+    let slug = descriptor.slug;
+    let mutation = add_component(slug, "Mutation", &schema.mutation, components);
+    let state = schema
+        .state
+        .as_ref()
+        .map(|state| add_component(slug, "State", state, components))
+        .unwrap_or_else(|| json!({ "type": "null" }));
+    let live_values = schema
+        .live_values
+        .as_ref()
+        .map(|live_values| add_component(slug, "LiveValues", live_values, components))
+        .unwrap_or_else(|| json!({ "type": "null" }));
+
+    let serial = json!({
+        "name": "serial",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "integer", "minimum": 0, "maximum": 65535 }
+    });
+
+    paths.insert(
+        format!("/machine/{slug}/{{serial}}"),
+        json!({
+            "get": {
+                "summary": format!("State and live values of a {}", descriptor.name),
+                "tags": [slug],
+                "parameters": [serial],
+                "responses": {
+                    "200": {
+                        "description": "Current values",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "machine": { "type": "object" },
+                                        "state": state,
+                                        "live_values": live_values,
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "404": { "description": "Unknown machine" }
+                }
+            },
+            "post": {
+                "summary": format!("Mutate a {}", descriptor.name),
+                "tags": [slug],
+                "parameters": [serial],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": { "type": "array", "items": mutation }
+                        }
+                    }
+                },
+                "responses": {
+                    "200": { "description": "Mutations were sent to the machine" },
+                    "404": { "description": "Unknown machine" }
+                }
+            }
+        }),
+    );
+
+    paths.insert(
+        format!("/machine/{slug}/schema"),
+        json!({
+            "get": {
+                "summary": format!("JSON Schemas of the {} payloads", descriptor.name),
+                "tags": [slug],
+                "responses": {
+                    "200": {
+                        "description": "JSON Schemas (draft 2020-12) of mutation, state and live values",
+                        "content": { "application/json": { "schema": { "type": "object" } } }
+                    }
+                }
+            }
+        }),
+    );
+}
+
+/// Adds a schema and its definitions to the components, returns a `$ref` to it
+fn add_component(
+    slug: &str,
+    name: &str,
+    schema: &Schema,
+    components: &mut Map<String, Value>,
+) -> Value {
+    let mut schema = schema.as_value().clone();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        if let Some(Value::Object(definitions)) = object.remove("$defs") {
+            for (definition_name, mut definition) in definitions {
+                rewrite_refs(slug, &mut definition);
+                components.insert(format!("{slug}.{definition_name}"), definition);
+            }
+        }
+    }
+    rewrite_refs(slug, &mut schema);
+
+    let component_name = format!("{slug}.{name}");
+    components.insert(component_name.clone(), schema);
+    json!({ "$ref": format!("#/components/schemas/{component_name}") })
+}
+
+fn rewrite_refs(slug: &str, value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(reference) if key == "$ref" => {
+                        if let Some(name) = reference.strip_prefix("#/$defs/") {
+                            *reference = format!("#/components/schemas/{slug}.{name}");
+                        }
+                    }
+                    _ => rewrite_refs(slug, value),
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| rewrite_refs(slug, value)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    match value {
+                        Value::String(reference) if key == "$ref" => refs.push(reference.clone()),
+                        _ => collect_refs(value, refs),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+            _ => (),
+        }
+    }
+
+    #[test]
+    fn test_all_refs_resolve() {
+        let document = openapi_document();
+        let schemas = document["components"]["schemas"].as_object().unwrap();
+
+        let mut refs = vec![];
+        collect_refs(&document, &mut refs);
+        assert!(!refs.is_empty());
+
+        for reference in refs {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("{reference} does not point to the components"));
+            assert!(schemas.contains_key(name), "{reference} does not resolve");
+        }
+    }
+
+    #[test]
+    fn test_every_machine_has_paths() {
+        let document = openapi_document();
+        for descriptor in MACHINE_REGISTRY.descriptors() {
+            let path = format!("/machine/{}/{{serial}}", descriptor.slug);
+            assert!(document["paths"][&path]["post"].is_object(), "{path}");
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router, debug_handler};
+use machines::RejectedMutation;
+use machines::machine_identification::{MachineIdentification, MachineIdentificationUnique};
+use machines::persistence::MACHINE_SETTINGS_STORE;
+use machines::registry::MachineDescriptor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app_state::SharedState;
+use crate::audit::AuditAction;
+use crate::auth::{Principal, Role};
+use crate::recipes::{Recipe, RecipeDiffEntry, RecipeSummary, diff};
+use crate::rest::handlers::machine_mutation::mutate_machine;
+use crate::rest::response::*;
+
+#[derive(Serialize, Debug)]
+struct GetRecipesResponse {
+    recipes: Vec<RecipeSummary>,
+}
+
+#[debug_handler]
+async fn get_recipes_handler(
+    Extension(id): Extension<MachineIdentification>,
+    State(shared_state): State<Arc<SharedState>>,
+) -> Result<GetRecipesResponse> {
+    let recipes = shared_state
+        .recipe_store
+        .list(&id)
+        .map_err(internal_error)?;
+    json(GetRecipesResponse { recipes })
+}
+
+#[derive(Deserialize, Debug)]
+struct RecipeVersionQuery {
+    version: Option<u32>,
+}
+
+#[debug_handler]
+async fn get_recipe_handler(
+    Extension(id): Extension<MachineIdentification>,
+    State(shared_state): State<Arc<SharedState>>,
+    Path(name): Path<String>,
+    Query(query): Query<RecipeVersionQuery>,
+) -> Result<Recipe> {
+    json(find_recipe(&shared_state, &id, &name, query.version)?)
+}
+
+/// Either explicit mutations or the current settings of a machine
+#[derive(Deserialize, Debug)]
+struct SaveRecipeRequest {
+    mutations: Option<Vec<Value>>,
+    /// Serial of a machine of this type to take the settings from
+    serial: Option<u16>,
+}
+
+#[debug_handler]
+async fn post_recipe_handler(
+    Extension(id): Extension<MachineIdentification>,
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Path(name): Path<String>,
+    Json(request): Json<SaveRecipeRequest>,
+) -> Result<Recipe> {
+    principal.require(Role::Technician)?;
+
+    let mutations = match (request.mutations, request.serial) {
+        (Some(mutations), None) => mutations,
+        (None, Some(serial)) => current_settings(&id, serial)?,
+        _ => {
+            return Err(bad_request(
+                "Expected either `mutations` or `serial` of a machine",
+            ));
+        }
+    };
+
+    let recipe = shared_state
+        .recipe_store
+        .save(&id, &name, mutations)
+        .map_err(bad_request)?;
+    json(recipe)
+}
+
+#[derive(Deserialize, Debug)]
+struct RecipeDiffQuery {
+    version: Option<u32>,
+    /// Compare with another recipe
+    other: Option<String>,
+    other_version: Option<u32>,
+    /// Compare with the current settings of a machine
+    serial: Option<u16>,
+}
+
+#[derive(Serialize, Debug)]
+struct RecipeDiffResponse {
+    differences: Vec<RecipeDiffEntry>,
+}
+
+#[debug_handler]
+async fn get_recipe_diff_handler(
+    Extension(id): Extension<MachineIdentification>,
+    State(shared_state): State<Arc<SharedState>>,
+    Path(name): Path<String>,
+    Query(query): Query<RecipeDiffQuery>,
+) -> Result<RecipeDiffResponse> {
+    let recipe = find_recipe(&shared_state, &id, &name, query.version)?;
+
+    let other = match (query.other, query.serial) {
+        (Some(other), None) => {
+            find_recipe(&shared_state, &id, &other, query.other_version)?.mutations
+        }
+        (None, Some(serial)) => current_settings(&id, serial)?,
+        _ => {
+            return Err(bad_request(
+                "Expected either `other` recipe or `serial` of a machine",
+            ));
+        }
+    };
+
+    json(RecipeDiffResponse {
+        differences: diff(&recipe.mutations, &other),
+    })
+}
+
+#[derive(Serialize, Debug)]
+struct ApplyRecipeResponse {
+    name: String,
+    version: u32,
+    applied: usize,
+    rejected: Vec<RejectedMutation>,
+}
+
+#[debug_handler]
+async fn post_apply_recipe_handler(
+    Extension(id): Extension<MachineIdentification>,
+    State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
+    Path((serial, name)): Path<(u16, String)>,
+    Query(query): Query<RecipeVersionQuery>,
+) -> Result<ApplyRecipeResponse> {
+    let recipe = find_recipe(&shared_state, &id, &name, query.version)?;
+    let id = MachineIdentificationUnique {
+        machine_identification: id,
+        serial,
+    };
+
+    // the machine applies the whole batch in one cycle and reports back
+    let total = recipe.mutations.len();
+    let rejected = mutate_machine(
+        &shared_state,
+        &principal,
+        &id,
+        recipe.mutations,
+        AuditAction::RecipeApply {
+            name: recipe.name.clone(),
+            version: recipe.version,
+        },
+    )
+    .await?;
+
+    json(ApplyRecipeResponse {
+        name: recipe.name,
+        version: recipe.version,
+        applied: total - rejected.len(),
+        rejected,
+    })
+}
+
+fn find_recipe(
+    shared_state: &SharedState,
+    id: &MachineIdentification,
+    name: &str,
+    version: Option<u32>,
+) -> std::result::Result<Recipe, ApiError> {
+    shared_state
+        .recipe_store
+        .get(id, name, version)
+        .map_err(internal_error)?
+        .ok_or_else(|| not_found(format!("Unknown recipe {name}")))
+}
+
+/// Settings the machine accepted so far, see [`MACHINE_SETTINGS_STORE`]
+fn current_settings(
+    id: &MachineIdentification,
+    serial: u16,
+) -> std::result::Result<Vec<Value>, ApiError> {
+    let id = MachineIdentificationUnique {
+        machine_identification: id.clone(),
+        serial,
+    };
+    let settings = MACHINE_SETTINGS_STORE
+        .get(&id)
+        .ok_or_else(|| not_found(format!("No settings known for machine {id}")))?;
+    Ok(settings
+        .mutations
+        .into_iter()
+        .map(|mutation| mutation.value)
+        .collect())
+}
+
+pub fn make_recipe_router(descriptor: &MachineDescriptor) -> Router<Arc<SharedState>> {
+    let slug = descriptor.slug;
+    let recipes_path = format!("/machine/{slug}/recipes");
+    let recipe_path = format!("{recipes_path}/{{name}}");
+    Router::new()
+        .route(&recipes_path, get(get_recipes_handler))
+        .route(&recipe_path, get(get_recipe_handler))
+        .route(&recipe_path, post(post_recipe_handler))
+        .route(&format!("{recipe_path}/diff"), get(get_recipe_diff_handler))
+        .route(
+            &format!("/machine/{slug}/{{serial}}/recipes/{{name}}/apply"),
+            post(post_apply_recipe_handler),
+        )
+        .layer(Extension(descriptor.machine_identification.clone()))
+}
@@ -1,8 +1,12 @@
 use axum::{Json, body::Body, http::StatusCode};
 use serde_json::json;
 
+use crate::auth::AuthError;
+
 pub enum ApiError {
     ErrBadRequest(String),
+    ErrUnauthorized(String),
+    ErrForbidden(String),
     ErrNotFound(String),
     ErrInternal(String),
 }
@@ -11,6 +15,10 @@ impl axum::response::IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let json = match self {
             Self::ErrBadRequest(ref e) => serde_json::to_string(&json!({ "error_bad_request": e })),
+            Self::ErrUnauthorized(ref e) => {
+                serde_json::to_string(&json!({ "error_unauthorized": e }))
+            }
+            Self::ErrForbidden(ref e) => serde_json::to_string(&json!({ "error_forbidden": e })),
             Self::ErrNotFound(ref e) => serde_json::to_string(&json!({ "error_not_found": e })),
             Self::ErrInternal(ref e) => serde_json::to_string(&json!({ "error_internal": e })),
         };
@@ -22,15 +30,29 @@ impl axum::response::IntoResponse for ApiError {
 
         let status = match self {
             Self::ErrBadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::ErrUnauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::ErrForbidden(_) => StatusCode::FORBIDDEN,
             Self::ErrNotFound(_) => StatusCode::NOT_FOUND,
             Self::ErrInternal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
-        axum::response::Response::builder()
+        let mut builder = axum::response::Response::builder()
             .status(status)
-            .header("Content-Type", "application/json")
-            .body(body)
-            .expect("Failed to build error response")
+            .header("Content-Type", "application/json");
+        if status == StatusCode::UNAUTHORIZED {
+            builder = builder.header("WWW-Authenticate", "Bearer");
+        }
+
+        builder.body(body).expect("Failed to build error response")
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::Unauthorized(e) => Self::ErrUnauthorized(e),
+            AuthError::Forbidden(e) => Self::ErrForbidden(e),
+        }
     }
 }
 
@@ -4,19 +4,18 @@ use axum::extract::{Path, State};
 use axum::routing::{get, post};
 use axum::{Extension, Json, Router, debug_handler};
 use machines::MachineMessage;
-use machines::analog_input_test_machine::AnalogInputTestMachine;
-use machines::aquapath1::AquaPathV1;
-use machines::extruder1::ExtruderV2;
-use machines::ip20_test_machine::IP20TestMachine;
-use machines::laser::LaserMachine;
 use machines::machine_identification::{MachineIdentification, MachineIdentificationUnique};
-use machines::mock::MockMachine;
-use machines::test_machine::TestMachine;
-use machines::wago_power::WagoPower;
-use machines::winder2::Winder2;
+use machines::registry::{MACHINE_REGISTRY, MachineDescriptor};
+use machines::schema::MachineApiSchema;
 use serde::Serialize;
 
 use crate::app_state::SharedState;
+use crate::audit::AuditAction;
+use crate::auth::Principal;
+use crate::rest::handlers::machine_mutation::mutate_machine;
+use crate::rest::history::make_history_router;
+use crate::rest::openapi::openapi_document;
+use crate::rest::recipes::make_recipe_router;
 use crate::rest::response::*;
 
 #[derive(Serialize, Debug, PartialEq)]
@@ -24,7 +23,9 @@ struct MachineResponce {
     legacy_id: MachineIdentificationUnique,
     serial: u16,
     vendor: String,
-    slug: String,
+    /// `None` if the machine type is not registered
+    slug: Option<String>,
+    name: Option<String>,
     error: Option<String>,
 }
 
@@ -33,15 +34,17 @@ impl From<MachineIdentificationUnique> for MachineResponce {
         let vendor = machine_identification_unique
             .machine_identification
             .vendor_str();
-        let slug = machine_identification_unique.machine_identification.slug();
         let serial = machine_identification_unique.serial;
+        let descriptor =
+            MACHINE_REGISTRY.get_descriptor(&machine_identification_unique.machine_identification);
 
         Self {
-            legacy_id: machine_identification_unique,
             serial,
             vendor,
-            slug,
-            error: None,
+            slug: descriptor.as_ref().ok().map(|d| d.slug.to_string()),
+            name: descriptor.as_ref().ok().map(|d| d.name.to_string()),
+            error: descriptor.err().map(|e| e.to_string()),
+            legacy_id: machine_identification_unique,
         }
     }
 }
@@ -60,23 +63,9 @@ async fn get_machines_handler(
         .await
         .into_iter()
         .map(|m| {
-            let vendor = m
-                .machine_identification_unique
-                .machine_identification
-                .vendor_str();
-            let slug = m
-                .machine_identification_unique
-                .machine_identification
-                .slug();
-            let serial = m.machine_identification_unique.serial;
-
-            MachineResponce {
-                legacy_id: m.machine_identification_unique,
-                serial,
-                vendor,
-                slug,
-                error: m.error,
-            }
+            let mut machine = MachineResponce::from(m.machine_identification_unique);
+            machine.error = m.error.or(machine.error);
+            machine
         })
         .collect();
 
@@ -122,6 +111,7 @@ type PostMachineRequest = Vec<serde_json::Value>;
 async fn post_machine_handler(
     Extension(id): Extension<MachineIdentification>,
     State(shared_state): State<Arc<SharedState>>,
+    principal: Principal,
     Path(serial): Path<u16>,
     Json(request): Json<PostMachineRequest>,
 ) -> Result<()> {
@@ -130,37 +120,83 @@ async fn post_machine_handler(
         serial,
     };
 
-    for value in request {
-        shared_state
-            .message_machine(&id, MachineMessage::HttpApiJsonRequest(value))
-            .await
-            .map_err(not_found)?;
+    let rejected = mutate_machine(
+        &shared_state,
+        &principal,
+        &id,
+        request,
+        AuditAction::MachineMutation,
+    )
+    .await?;
+    if !rejected.is_empty() {
+        let errors: Vec<String> = rejected
+            .iter()
+            .map(|rejected| format!("Mutation {}: {}", rejected.index, rejected.error))
+            .collect();
+        return Err(bad_request(errors.join(", ")));
     }
 
     json(())
 }
 
-fn make_machine_router(id: MachineIdentification) -> Router<Arc<SharedState>> {
-    let slug = id.slug();
-    let path = format!("/machine/{slug}/{{serial}}");
+#[debug_handler]
+async fn get_machine_schema_handler(
+    Extension(descriptor): Extension<MachineDescriptor>,
+) -> Result<MachineApiSchema> {
+    json((descriptor.api_schema)())
+}
+
+#[debug_handler]
+async fn get_openapi_handler() -> Result<serde_json::Value> {
+    json(openapi_document())
+}
+
+fn make_machine_router(descriptor: &MachineDescriptor) -> Router<Arc<SharedState>> {
+    let id = descriptor.machine_identification.clone();
+    let path = format!("/machine/{}/{{serial}}", descriptor.slug);
     Router::new()
         .route(&path, get(get_machine_handler))
         .route(&path, post(post_machine_handler))
         .layer(Extension(id))
+        .route(
+            &format!("/machine/{}/schema", descriptor.slug),
+            get(get_machine_schema_handler).layer(Extension(descriptor.clone())),
+        )
+        .merge(make_recipe_router(descriptor))
+        .merge(make_history_router(descriptor))
 }
 
+/// Routes for every machine type in the [`MACHINE_REGISTRY`]
 pub fn rest_api_router() -> Router<Arc<SharedState>> {
-    Router::new()
-        .route("/machine", get(get_machines_handler))
-        .merge(make_machine_router(LaserMachine::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(Winder2::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(MockMachine::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(ExtruderV2::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(AquaPathV1::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(TestMachine::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(WagoPower::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(IP20TestMachine::MACHINE_IDENTIFICATION))
-        .merge(make_machine_router(
-            AnalogInputTestMachine::MACHINE_IDENTIFICATION,
-        ))
+    MACHINE_REGISTRY.descriptors().iter().fold(
+        Router::new()
+            .route("/machine", get(get_machines_handler))
+            .route("/openapi.json", get(get_openapi_handler)),
+        |router, descriptor| router.merge(make_machine_router(descriptor)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_router_for_all_registered_machines() {
+        // axum panics on conflicting routes
+        let _router = rest_api_router();
+
+        let slugs: Vec<&str> = MACHINE_REGISTRY
+            .descriptors()
+            .iter()
+            .map(|d| d.slug)
+            .collect();
+        for slug in [
+            "extruder_v2",
+            "buffer_v1",
+            "digital_input_test_machine",
+            "wago_ai_test_machine",
+        ] {
+            assert!(slugs.contains(&slug), "{slug} is not routed");
+        }
+    }
 }
@@ -1,7 +1,11 @@
 use super::namespace_id::NamespaceId;
 use crate::app_state::SharedState;
+use crate::auth::{AuthError, Role};
+use crate::rest::auth::bearer_token;
+use serde::Deserialize;
 use socketioxide::ParserConfig;
-use socketioxide::extract::SocketRef;
+use socketioxide::extract::{SocketRef, TryData};
+use socketioxide::handler::ConnectHandler;
 use socketioxide::layer::SocketIoLayer;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -17,18 +21,29 @@ pub async fn init_socketio(app_state: Arc<SharedState>) -> SocketIoLayer {
     let app_state_main = app_state.clone();
 
     // set the on connect handler for main namespace
-    io.ns("/main", move |socket: SocketRef| {
-        handle_socket_connection(socket, app_state_main.clone());
-    });
+    let app_state_auth = app_state.clone();
+    io.ns(
+        "/main",
+        (move |socket: SocketRef| {
+            handle_socket_connection(socket, app_state_main.clone());
+        })
+        .with(move |socket: SocketRef, auth: TryData<SocketAuth>| {
+            authenticate_socket(&socket, auth, &app_state_auth)
+        }),
+    );
 
     // Clone app_state for the second handler
     let app_state_machine = app_state.clone();
 
+    let app_state_auth = app_state.clone();
     if let Err(err) = io.dyn_ns(
         "/machine/{vendor}/{machine}/{serial}",
-        move |socket: SocketRef| {
+        (move |socket: SocketRef| {
             handle_socket_connection(socket, app_state_machine.clone());
-        },
+        })
+        .with(move |socket: SocketRef, auth: TryData<SocketAuth>| {
+            authenticate_socket(&socket, auth, &app_state_auth)
+        }),
     ) {
         tracing::error!("Failed to detect machine namespace: {}", err);
     }
@@ -40,6 +55,40 @@ pub async fn init_socketio(app_state: Arc<SharedState>) -> SocketIoLayer {
     socketio_layer
 }
 
+/// Auth payload of the socket.io client, `io(url, { auth: { token } })`
+#[derive(Debug, Deserialize)]
+struct SocketAuth {
+    token: Option<String>,
+}
+
+/// Connect middleware, the namespaces only emit so [`Role::Viewer`] is enough
+///
+/// Browsers can't set headers on websockets, so the token is also taken from the auth payload.
+fn authenticate_socket(
+    socket: &SocketRef,
+    TryData(auth): TryData<SocketAuth>,
+    app_state: &SharedState,
+) -> Result<(), AuthError> {
+    let payload_token = auth.ok().and_then(|auth| auth.token);
+    let token = payload_token
+        .as_deref()
+        .or_else(|| bearer_token(socket.req_parts()));
+
+    let res = app_state
+        .auth
+        .authenticate(token)
+        .and_then(|principal| principal.require(Role::Viewer));
+    if let Err(e) = &res {
+        tracing::info!(
+            "Rejected socket connection socket={:?} namespace={} error={}",
+            socket.id,
+            socket.ns(),
+            e
+        );
+    }
+    res
+}
+
 fn handle_socket_connection(socket: SocketRef, app_state: Arc<SharedState>) {
     let namespace_id = match NamespaceId::from_str(socket.ns()) {
         Ok(namespace_id) => namespace_id,
@@ -0,0 +1,27 @@
+use control_core::socketio::event::Event;
+use serde::{Deserialize, Serialize};
+
+/// A SubDevice which is not in OP
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FaultedSubDevice {
+    pub subdevice_index: usize,
+    pub configured_address: u16,
+    pub name: String,
+    pub state: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum EthercatBusEvent {
+    /// All SubDevices are in OP
+    Ok(bool),
+    /// SubDevices left OP, the machines using them are stopped
+    Degraded(Vec<FaultedSubDevice>),
+    /// The configuration of these SubDevices is written again, their machines stay stopped
+    Recovering(Vec<FaultedSubDevice>),
+}
+
+impl EthercatBusEvent {
+    pub fn build(&self) -> Event<Self> {
+        Event::new("EthercatBusEvent", self.clone())
+    }
+}
@@ -0,0 +1,18 @@
+use control_core::socketio::event::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::ethercat::diagnosis::DiagnosisLogEntry;
+
+/// Diagnosis messages which were read from the SubDevices since the last event, oldest first
+///
+/// The full log is served by `GET /api/v1/ethercat/diagnosis`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EthercatDiagnosisEvent {
+    pub entries: Vec<DiagnosisLogEntry>,
+}
+
+impl EthercatDiagnosisEvent {
+    pub fn build(&self) -> Event<Self> {
+        Event::new("EthercatDiagnosisEvent", self.clone())
+    }
+}
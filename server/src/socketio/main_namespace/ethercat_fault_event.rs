@@ -0,0 +1,20 @@
+use control_core::socketio::event::Event;
+use machines::machine_identification::MachineIdentificationUnique;
+use serde::{Deserialize, Serialize};
+
+/// The EtherCAT loop failed and the server is about to exit
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EthercatFaultEvent {
+    /// Error that made the loop fail
+    pub error: String,
+    /// Machines which were driven into their safe state
+    pub machines: Vec<MachineIdentificationUnique>,
+    /// If the safe outputs reached the devices before the exit
+    pub outputs_written: bool,
+}
+
+impl EthercatFaultEvent {
+    pub fn build(&self) -> Event<Self> {
+        Event::new("EthercatFaultEvent", self.clone())
+    }
+}
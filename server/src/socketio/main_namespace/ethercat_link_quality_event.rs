@@ -0,0 +1,57 @@
+use control_core::socketio::event::Event;
+use serde::{Deserialize, Serialize};
+
+/// ESC error counters of one port, registers `0x0300`-`0x0313`
+///
+/// The counters saturate at 255 and are only reset by a power cycle or by writing them.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortErrorCounters {
+    /// Frames with an invalid CRC or length
+    pub invalid_frames: u8,
+    /// Physical layer errors
+    pub rx_errors: u8,
+    /// Errors detected by a previous SubDevice
+    pub forwarded_rx_errors: u8,
+    pub lost_links: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SubDeviceLinkQuality {
+    pub subdevice_index: usize,
+    pub configured_address: u16,
+    pub name: String,
+    /// AL state like `Op`, `None` until it was read the first time
+    pub al_state: Option<String>,
+    /// AL status code with its description, e.g. `0x001b: Sync manager watchdog`
+    pub al_status_code: Option<String>,
+    /// Ports 0 to 3
+    pub ports: Vec<PortErrorCounters>,
+    /// Errors of the EtherCAT processing unit
+    pub processing_unit_errors: u8,
+    pub pdi_errors: u8,
+    /// Milliseconds since the unix epoch of the last successful read
+    pub last_read_ms: Option<u64>,
+}
+
+/// Health of the EtherCAT bus
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct EthercatLinkQualityEvent {
+    /// Cycles since the bus was initialized
+    pub cycles: u64,
+    /// Working counter of the process data if every SubDevice processed it
+    pub expected_working_counter: u16,
+    pub last_working_counter: u16,
+    /// Cycles in which the working counter differed from the expected one
+    pub working_counter_mismatches: u64,
+    /// Frames without a response in time
+    pub timed_out_frames: u64,
+    /// Frames which were lost or came back broken
+    pub lost_frames: u64,
+    pub subdevices: Vec<SubDeviceLinkQuality>,
+}
+
+impl EthercatLinkQualityEvent {
+    pub fn build(&self) -> Event<Self> {
+        Event::new("EthercatLinkQualityEvent", self.clone())
+    }
+}
@@ -0,0 +1,16 @@
+use control_core::socketio::event::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::ethercat::sdo::SdoRequest;
+
+/// An SDO request queued by `POST /api/v1/ethercat/sdo` was executed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EthercatSdoEvent {
+    pub request: SdoRequest,
+}
+
+impl EthercatSdoEvent {
+    pub fn build(&self) -> Event<Self> {
+        Event::new("EthercatSdoEvent", self.clone())
+    }
+}
@@ -2,17 +2,30 @@ use std::sync::Arc;
 
 use control_core::socketio::{
     event::{Event, GenericEvent},
-    namespace::{CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_one_event},
+    namespace::{
+        CacheFn, CacheableEvents, Namespace, NamespaceCacheingLogic, cache_n_events,
+        cache_one_event,
+    },
 };
+use ethercat_bus_event::EthercatBusEvent;
 use ethercat_devices_event::EthercatDevicesEvent;
+use ethercat_diagnosis_event::EthercatDiagnosisEvent;
+use ethercat_fault_event::EthercatFaultEvent;
 use ethercat_interface_discovery_event::EthercatInterfaceDiscoveryEvent;
+use ethercat_link_quality_event::EthercatLinkQualityEvent;
+use ethercat_sdo_event::EthercatSdoEvent;
 use machines_event::MachinesEvent;
 use smol::channel::Sender;
 use socketioxide::extract::SocketRef;
 use tracing::instrument;
 
+pub mod ethercat_bus_event;
 pub mod ethercat_devices_event;
+pub mod ethercat_diagnosis_event;
+pub mod ethercat_fault_event;
 pub mod ethercat_interface_discovery_event;
+pub mod ethercat_link_quality_event;
+pub mod ethercat_sdo_event;
 pub mod machines_event;
 
 pub struct MainRoom {
@@ -44,6 +57,11 @@ pub enum MainNamespaceEvents {
     MachinesEvent(Event<MachinesEvent>),
     EthercatDevicesEvent(Event<EthercatDevicesEvent>),
     EthercatInterfaceDiscoveryEvent(Event<EthercatInterfaceDiscoveryEvent>),
+    EthercatFaultEvent(Event<EthercatFaultEvent>),
+    EthercatBusEvent(Event<EthercatBusEvent>),
+    EthercatLinkQualityEvent(Event<EthercatLinkQualityEvent>),
+    EthercatDiagnosisEvent(Event<EthercatDiagnosisEvent>),
+    EthercatSdoEvent(Event<EthercatSdoEvent>),
 }
 
 impl CacheableEvents<Self> for MainNamespaceEvents {
@@ -52,6 +70,11 @@ impl CacheableEvents<Self> for MainNamespaceEvents {
             Self::EthercatDevicesEvent(event) => event.into(),
             Self::EthercatInterfaceDiscoveryEvent(event) => event.into(),
             Self::MachinesEvent(event) => event.into(),
+            Self::EthercatFaultEvent(event) => event.into(),
+            Self::EthercatBusEvent(event) => event.into(),
+            Self::EthercatLinkQualityEvent(event) => event.into(),
+            Self::EthercatDiagnosisEvent(event) => event.into(),
+            Self::EthercatSdoEvent(event) => event.into(),
         }
     }
 
@@ -60,6 +83,12 @@ impl CacheableEvents<Self> for MainNamespaceEvents {
             Self::EthercatDevicesEvent(_) => cache_one_event(),
             Self::EthercatInterfaceDiscoveryEvent(_) => cache_one_event(),
             Self::MachinesEvent(_) => cache_one_event(),
+            Self::EthercatFaultEvent(_) => cache_one_event(),
+            Self::EthercatBusEvent(_) => cache_one_event(),
+            Self::EthercatLinkQualityEvent(_) => cache_one_event(),
+            // batches of new entries, late clients get the recent ones
+            Self::EthercatDiagnosisEvent(_) => cache_n_events(20),
+            Self::EthercatSdoEvent(_) => cache_n_events(20),
         }
     }
 }
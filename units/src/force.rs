@@ -0,0 +1,19 @@
+quantity! {
+    /// Force (base unit newton, kg · m · s⁻²).
+    quantity: Force; "force";
+    /// Dimension of force, LMT⁻² (base unit newton, kg · m · s⁻²).
+    dimension: ISQ<
+        P1,  // length
+        P1,  // mass
+        N2,  // time
+        Z0,  // electric current
+        Z0,  // thermodynamic temperature
+        Z0,  // amount of substance
+        Z0>; // luminous intensity
+    units {
+        @newton: 1.0; "N", "newton", "newtons";
+        @kilonewton: 1.0e3; "kN", "kilonewton", "kilonewtons";
+        @gram_force: 9.806_65e-3; "gf", "gram force", "grams force";
+        @kilogram_force: 9.806_65; "kgf", "kilogram force", "kilograms force";
+    }
+}